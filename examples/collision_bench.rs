@@ -0,0 +1,73 @@
+//! Compares the spatial grid broad phase with testing every pair, for 1,000
+//! colliders scattered over a 2000x2000 area. Run with `--release`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use glam::Vec2;
+use kreeda::engine::physics2d::grid::SpatialGrid;
+use kreeda::engine::physics2d::{Aabb, DEFAULT_CELL_SIZE};
+
+const COLLIDERS: usize = 1_000;
+const WORLD_SIZE: f32 = 2000.0;
+const RUNS: u32 = 200;
+
+fn brute_force(boxes: &[Aabb], out: &mut Vec<(u32, u32)>) {
+    out.clear();
+    for (i, a) in boxes.iter().enumerate() {
+        for (j, b) in boxes.iter().enumerate().skip(i + 1) {
+            if a.intersects(b) {
+                out.push((i as u32, j as u32));
+            }
+        }
+    }
+}
+
+fn grid(grid: &mut SpatialGrid, boxes: &[Aabb], out: &mut Vec<(u32, u32)>) {
+    grid.clear();
+    for aabb in boxes {
+        grid.insert(*aabb);
+    }
+    grid.pairs(out);
+}
+
+/// Average time per run of `f`.
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let mut rng = fastrand::Rng::with_seed(7);
+    let boxes: Vec<_> = (0..COLLIDERS)
+        .map(|_| {
+            let center = Vec2::new(rng.f32(), rng.f32()) * WORLD_SIZE;
+            let size = Vec2::new(rng.f32(), rng.f32()) * 40.0 + 8.0;
+            Aabb::from_center_size(center, size)
+        })
+        .collect();
+
+    let (mut expected, mut found) = (Vec::new(), Vec::new());
+    let mut spatial_grid = SpatialGrid::new(DEFAULT_CELL_SIZE);
+    brute_force(&boxes, &mut expected);
+    grid(&mut spatial_grid, &boxes, &mut found);
+    found.sort_unstable();
+    assert_eq!(expected, found, "both phases should find the same pairs");
+
+    let brute_time = time(|| brute_force(black_box(&boxes), &mut expected));
+    let grid_time = time(|| grid(&mut spatial_grid, black_box(&boxes), &mut found));
+    let tests = COLLIDERS * (COLLIDERS - 1) / 2;
+    println!(
+        "{COLLIDERS} colliders, {} overlapping pairs",
+        expected.len()
+    );
+    println!("every pair ({tests} tests): {brute_time:?}");
+    println!("spatial grid ({DEFAULT_CELL_SIZE} unit cells): {grid_time:?}");
+    println!(
+        "speedup: {:.1}x",
+        brute_time.as_secs_f64() / grid_time.as_secs_f64()
+    );
+}
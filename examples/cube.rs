@@ -0,0 +1,69 @@
+//! A spinning cube viewed through the 3D camera. Hold the right mouse
+//! button to look around, move with WASD, Q/E for down/up and Shift to
+//! go faster.
+
+use std::sync::{Arc, Mutex};
+
+use glam::{Mat4, Vec3};
+use kreeda::KreedaError;
+use kreeda::engine::renderer::camera3d::{Camera3D, FlyController};
+use kreeda::engine::renderer::context::GpuContext;
+use kreeda::engine::renderer::mesh::{Mesh, MeshVertex};
+use kreeda::engine::renderer::mesh_renderer::draw_mesh;
+use kreeda::engine::window::Window;
+
+/// Unit cube centered on the origin, four vertices per face so each face
+/// gets a flat normal.
+fn cube_mesh() -> Mesh {
+    let faces = [
+        (Vec3::X, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Y),
+        (Vec3::Y, Vec3::NEG_Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::Y),
+    ];
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (normal, up) in faces {
+        let right = up.cross(normal);
+        let base = vertices.len() as u32;
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let position = (normal + right * (u * 2.0 - 1.0) + up * (v * 2.0 - 1.0)) * 0.5;
+            vertices.push(MeshVertex {
+                position: position.to_array(),
+                normal: normal.to_array(),
+                uv: [u, 1.0 - v],
+            });
+        }
+        // Counter-clockwise seen from outside the cube
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    let gpu = GpuContext::get().expect("the window is running");
+    Mesh::new(&gpu.device, &vertices, &indices)
+}
+
+fn update(dt: f64) {
+    static STATE: Mutex<Option<(Arc<Mesh>, f32)>> = Mutex::new(None);
+
+    let mut state = STATE.lock().unwrap();
+    let (cube, angle) = state.get_or_insert_with(|| (Arc::new(cube_mesh()), 0.0));
+    *angle += dt as f32;
+
+    let model = Mat4::from_rotation_y(*angle) * Mat4::from_rotation_x(*angle * 0.5);
+    draw_mesh(cube.clone(), model, [0.9, 0.4, 0.2, 1.0]);
+}
+
+fn main() -> Result<(), KreedaError> {
+    {
+        let mut camera = Camera3D::get_instance().lock().unwrap();
+        camera.position = Vec3::new(0.0, 1.5, 4.0);
+        camera.look_at(Vec3::ZERO);
+    }
+    FlyController::set_enabled(true);
+
+    Window::add_update(update);
+    Window::run()
+}
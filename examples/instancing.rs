@@ -0,0 +1,95 @@
+//! 10,000 cubes drawn with a single instanced draw call. The instances are
+//! uploaded once and only again when Space recolors them. Hold the right
+//! mouse button to look around and move with WASD.
+
+use std::sync::{Arc, Mutex};
+
+use glam::{Mat4, Vec3};
+use kreeda::KreedaError;
+use kreeda::engine::renderer::camera3d::{Camera3D, FlyController};
+use kreeda::engine::renderer::context::GpuContext;
+use kreeda::engine::renderer::instanced_mesh::{InstanceData, InstancedMesh};
+use kreeda::engine::renderer::mesh::{Mesh, MeshVertex};
+use kreeda::engine::renderer::mesh_renderer::draw_instanced;
+use kreeda::engine::window::Window;
+use kreeda::input::key_listener::KeyInput;
+use winit::keyboard::{Key, NamedKey};
+
+const GRID: usize = 100;
+
+/// Unit cube centered on the origin, four vertices per face so each face
+/// gets a flat normal.
+fn cube_mesh() -> Mesh {
+    let faces = [
+        (Vec3::X, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Y),
+        (Vec3::Y, Vec3::NEG_Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::Y),
+    ];
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (normal, up) in faces {
+        let right = up.cross(normal);
+        let base = vertices.len() as u32;
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let position = (normal + right * (u * 2.0 - 1.0) + up * (v * 2.0 - 1.0)) * 0.5;
+            vertices.push(MeshVertex {
+                position: position.to_array(),
+                normal: normal.to_array(),
+                uv: [u, 1.0 - v],
+            });
+        }
+        // Counter-clockwise seen from outside the cube
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    let gpu = GpuContext::get().expect("the window is running");
+    Mesh::new(&gpu.device, &vertices, &indices)
+}
+
+/// A `GRID`×`GRID` floor of cubes with random colors.
+fn grid() -> Vec<InstanceData> {
+    let offset = (GRID as f32 - 1.0) * 0.5;
+    (0..GRID * GRID)
+        .map(|i| {
+            let x = (i % GRID) as f32 - offset;
+            let z = (i / GRID) as f32 - offset;
+            let model = Mat4::from_translation(Vec3::new(x * 1.5, 0.0, z * 1.5))
+                * Mat4::from_scale(Vec3::splat(0.8));
+            let color = [fastrand::f32(), fastrand::f32(), fastrand::f32(), 1.0];
+            InstanceData::with_color(model, color)
+        })
+        .collect()
+}
+
+fn update(_dt: f64) {
+    static CUBES: Mutex<Option<InstancedMesh>> = Mutex::new(None);
+
+    let gpu = GpuContext::get().expect("the window is running");
+    let mut cubes = CUBES.lock().unwrap();
+    let cubes = cubes.get_or_insert_with(|| {
+        let mut cubes = InstancedMesh::new(&gpu.device, Arc::new(cube_mesh()), 0);
+        cubes.set_instances(&gpu.device, &gpu.queue, &grid());
+        cubes
+    });
+
+    if KeyInput::key_just_pressed(&Key::Named(NamedKey::Space)) {
+        cubes.set_instances(&gpu.device, &gpu.queue, &grid());
+    }
+    draw_instanced(cubes);
+}
+
+fn main() -> Result<(), KreedaError> {
+    {
+        let mut camera = Camera3D::get_instance().lock().unwrap();
+        camera.position = Vec3::new(0.0, 40.0, 90.0);
+        camera.look_at(Vec3::ZERO);
+    }
+    FlyController::set_enabled(true);
+
+    Window::add_update(update);
+    Window::run()
+}
@@ -0,0 +1,67 @@
+//! Loads a Wavefront OBJ model and draws each of its parts with the 3D
+//! camera, colored by material. Pass the path of another model, e.g. a
+//! teapot or Suzanne exported from Blender, to view that instead. Hold the
+//! right mouse button to look around and move with WASD.
+
+use std::sync::{Arc, Mutex};
+
+use glam::{Mat4, Vec3};
+use kreeda::KreedaError;
+use kreeda::engine::renderer::camera3d::{Camera3D, FlyController};
+use kreeda::engine::renderer::context::GpuContext;
+use kreeda::engine::renderer::mesh_renderer::draw_mesh;
+use kreeda::engine::renderer::model::Model;
+use kreeda::engine::window::Window;
+
+const DEFAULT_MODEL: &str = "assets/models/torus.obj";
+
+const PALETTE: [[f32; 4]; 4] = [
+    [0.9, 0.7, 0.3, 1.0],
+    [0.6, 0.6, 0.65, 1.0],
+    [0.3, 0.6, 0.9, 1.0],
+    [0.8, 0.3, 0.3, 1.0],
+];
+
+fn update(dt: f64) {
+    static STATE: Mutex<Option<(Arc<Model>, f32)>> = Mutex::new(None);
+
+    let mut state = STATE.lock().unwrap();
+    let (model, angle) = state.get_or_insert_with(|| {
+        let path = std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| DEFAULT_MODEL.into());
+        let gpu = GpuContext::get().expect("the window is running");
+        let model = Window::get_resource_manager()
+            .lock()
+            .unwrap()
+            .load_model(&gpu.device, &path)
+            .unwrap_or_else(|e| panic!("couldn't load {path}: {e}"));
+        println!(
+            "{path}: {} parts, materials {:?}",
+            model.meshes().len(),
+            model.materials()
+        );
+        (model, 0.0)
+    });
+    *angle += dt as f32 * 0.5;
+
+    let transform = Mat4::from_rotation_y(*angle);
+    for part in model.meshes() {
+        let color = part
+            .material
+            .map_or([1.0; 4], |i| PALETTE[i % PALETTE.len()]);
+        draw_mesh(part.mesh.clone(), transform, color);
+    }
+}
+
+fn main() -> Result<(), KreedaError> {
+    {
+        let mut camera = Camera3D::get_instance().lock().unwrap();
+        camera.position = Vec3::new(0.0, 2.5, 5.0);
+        camera.look_at(Vec3::ZERO);
+    }
+    FlyController::set_enabled(true);
+
+    Window::add_update(update);
+    Window::run()
+}
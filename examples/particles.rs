@@ -0,0 +1,71 @@
+//! Stress test for the 2D particle system: a fountain keeping around 100,000
+//! additively blended particles alive, simulated on the CPU and drawn
+//! through the sprite batcher. Press Space for an extra burst.
+
+use std::sync::{Arc, Mutex};
+
+use glam::Vec2;
+use kreeda::KreedaError;
+use kreeda::engine::math::color::Color;
+use kreeda::engine::renderer::context::GpuContext;
+use kreeda::engine::renderer::particles_2d::{EmitterConfig2D, ParticleEmitter2D};
+use kreeda::engine::renderer::sprite_renderer::SpriteBlend;
+use kreeda::engine::renderer::texture::Texture2D;
+use kreeda::engine::window::Window;
+use kreeda::input::key_listener::KeyInput;
+use winit::keyboard::{Key, NamedKey};
+
+const MAX_PARTICLES: usize = 100_000;
+
+/// A white dot fading out towards its edge.
+fn soft_dot() -> Texture2D {
+    const SIZE: u32 = 32;
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let offset = Vec2::new(x as f32, y as f32) + 0.5 - SIZE as f32 * 0.5;
+            let falloff = (1.0 - offset.length() / (SIZE as f32 * 0.5)).max(0.0);
+            rgba.extend_from_slice(&[255, 255, 255, (falloff * falloff * 255.0) as u8]);
+        }
+    }
+
+    let gpu = GpuContext::get().expect("the window is running");
+    Texture2D::from_rgba8(&gpu.device, &gpu.queue, &rgba, SIZE, SIZE, Some("Dot"))
+}
+
+fn update(dt: f64) {
+    static FOUNTAIN: Mutex<Option<ParticleEmitter2D>> = Mutex::new(None);
+
+    let mut fountain = FOUNTAIN.lock().unwrap();
+    let fountain = fountain.get_or_insert_with(|| {
+        let config = EmitterConfig2D {
+            // Lifetimes average two seconds, so this keeps the pool full
+            spawn_rate: 50_000.0,
+            lifetime: 1.5..2.5,
+            spread: 0.3,
+            speed: 300.0..600.0,
+            gravity: Vec2::new(0.0, -400.0),
+            start_color: Color::from_rgba(1.0, 0.6, 0.2, 0.5),
+            end_color: Color::from_rgba(0.8, 0.1, 0.4, 0.0),
+            start_size: 6.0,
+            end_size: 2.0,
+            max_particles: MAX_PARTICLES,
+            blend: SpriteBlend::Additive,
+            ..Default::default()
+        };
+        let mut fountain = ParticleEmitter2D::new(Arc::new(soft_dot()), config);
+        fountain.set_position(Vec2::new(0.0, -250.0));
+        fountain
+    });
+
+    if KeyInput::key_just_pressed(&Key::Named(NamedKey::Space)) {
+        fountain.burst(10_000);
+    }
+    fountain.update(dt as f32);
+    fountain.draw();
+}
+
+fn main() -> Result<(), KreedaError> {
+    Window::add_update(update);
+    Window::run()
+}
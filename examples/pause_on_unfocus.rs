@@ -0,0 +1,21 @@
+//! Prints the frame delta once a second. Click another window and the
+//! output stops; focus this one again and it resumes without a large jump.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use kreeda::KreedaError;
+use kreeda::engine::window::Window;
+
+fn update(dt: f64) {
+    static FRAMES: AtomicU32 = AtomicU32::new(0);
+    if FRAMES.fetch_add(1, Ordering::Relaxed).is_multiple_of(60) {
+        println!("focused: {}, dt: {:.4}s", Window::is_focused(), dt);
+    }
+}
+
+fn main() -> Result<(), KreedaError> {
+    Window::set_pause_on_unfocus(true);
+
+    Window::add_update(update);
+    Window::run()
+}
@@ -0,0 +1,117 @@
+//! Mouse picking in 3D: a grid of cubes, with the one under the cursor
+//! highlighted and a marker where the ray hits it. The floor is on its own
+//! layer, which the pick ignores. Hold the right mouse button to look
+//! around and move with WASD.
+
+use std::sync::{Arc, Mutex};
+
+use glam::{Mat4, Quat, Vec3};
+use kreeda::KreedaError;
+use kreeda::engine::debug::debug_draw::DebugDraw;
+use kreeda::engine::math::ray::{Ray, RayShape, RayTarget, raycast};
+use kreeda::engine::physics::aabb::Aabb3D;
+use kreeda::engine::renderer::camera3d::{Camera3D, FlyController};
+use kreeda::engine::renderer::context::GpuContext;
+use kreeda::engine::renderer::mesh::{Mesh, MeshVertex};
+use kreeda::engine::renderer::mesh_renderer::draw_mesh;
+use kreeda::engine::window::Window;
+
+const CUBE_LAYER: u32 = 1 << 0;
+const FLOOR_LAYER: u32 = 1 << 1;
+
+/// Unit cube centered on the origin, four vertices per face so each face
+/// gets a flat normal.
+fn cube_mesh() -> Mesh {
+    let faces = [
+        (Vec3::X, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Y),
+        (Vec3::Y, Vec3::NEG_Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::Y),
+    ];
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (normal, up) in faces {
+        let right = up.cross(normal);
+        let base = vertices.len() as u32;
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let position = (normal + right * (u * 2.0 - 1.0) + up * (v * 2.0 - 1.0)) * 0.5;
+            vertices.push(MeshVertex {
+                position: position.to_array(),
+                normal: normal.to_array(),
+                uv: [u, 1.0 - v],
+            });
+        }
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    let gpu = GpuContext::get().expect("the window is running");
+    Mesh::new(&gpu.device, &vertices, &indices)
+}
+
+/// Centers of a 5x5 grid of cubes.
+fn cube_positions() -> Vec<Vec3> {
+    (0..25)
+        .map(|i| Vec3::new((i % 5) as f32 * 2.0 - 4.0, 0.5, (i / 5) as f32 * 2.0 - 4.0))
+        .collect()
+}
+
+fn update(_dt: f64) {
+    static CUBE: Mutex<Option<Arc<Mesh>>> = Mutex::new(None);
+
+    let cube = CUBE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| Arc::new(cube_mesh()))
+        .clone();
+    let positions = cube_positions();
+
+    let floor = Aabb3D::new(Vec3::new(-6.0, -0.1, -6.0), Vec3::new(6.0, 0.0, 6.0));
+    let mut targets: Vec<_> = positions
+        .iter()
+        .enumerate()
+        .map(|(i, &center)| {
+            let bounds = Aabb3D::from_center_size(center, Vec3::ONE);
+            RayTarget::new(Some(i), RayShape::Aabb(bounds)).with_layers(CUBE_LAYER)
+        })
+        .collect();
+    targets.push(RayTarget::new(None, RayShape::Aabb(floor)).with_layers(FLOOR_LAYER));
+
+    let hit = raycast(&Ray::from_mouse(), &targets, CUBE_LAYER);
+    let picked = hit.and_then(|(id, _)| id);
+    for (i, &center) in positions.iter().enumerate() {
+        let color = if picked == Some(i) {
+            [1.0, 0.9, 0.2, 1.0]
+        } else {
+            [0.3, 0.5, 0.9, 1.0]
+        };
+        draw_mesh(cube.clone(), Mat4::from_translation(center), color);
+    }
+    draw_mesh(
+        cube,
+        Mat4::from_scale_rotation_translation(floor.size(), Quat::IDENTITY, floor.center()),
+        [0.8, 0.8, 0.8, 1.0],
+    );
+
+    if let Some((_, hit)) = hit {
+        DebugDraw::line_3d(
+            hit.point,
+            hit.point + hit.normal * 0.5,
+            [1.0, 0.0, 0.0, 1.0],
+        );
+    }
+}
+
+fn main() -> Result<(), KreedaError> {
+    {
+        let mut camera = Camera3D::get_instance().lock().unwrap();
+        camera.position = Vec3::new(0.0, 7.0, 9.0);
+        camera.look_at(Vec3::ZERO);
+    }
+    FlyController::set_enabled(true);
+
+    Window::add_update(update);
+    Window::run()
+}
@@ -0,0 +1,24 @@
+//! Fades the background between two colors using the engine clock. Shaders
+//! read the same clock through `GlobalsUniform`, so the frame-to-frame
+//! values match what an animated shader would see.
+
+use kreeda::KreedaError;
+use kreeda::engine::window::Window;
+
+const FROM: [f32; 3] = [0.1, 0.1, 0.3];
+const TO: [f32; 3] = [0.9, 0.4, 0.2];
+
+/// Seconds for a full fade there and back.
+const PERIOD: f64 = 4.0;
+
+fn update(_dt: f64) {
+    let phase = Window::elapsed_time() / PERIOD * std::f64::consts::TAU;
+    let t = (0.5 - 0.5 * phase.cos()) as f32;
+    let [r, g, b] = std::array::from_fn(|i| FROM[i] + (TO[i] - FROM[i]) * t);
+    Window::set_clear_color([r, g, b, 1.0]);
+}
+
+fn main() -> Result<(), KreedaError> {
+    Window::add_update(update);
+    Window::run()
+}
@@ -0,0 +1,11 @@
+//! An empty window that closes when Escape is pressed.
+
+use kreeda::KreedaError;
+use kreeda::engine::window::Window;
+
+fn main() -> Result<(), KreedaError> {
+    Window::set_quit_on_escape(true);
+
+    Window::add_shutdown_hook(|| println!("Shutting down."));
+    Window::run()
+}
@@ -0,0 +1,50 @@
+//! Keeps a label pinned to the bottom-right corner. Resize the window, or
+//! drag it to a display with a different scale factor, and the label
+//! follows.
+
+use std::sync::Mutex;
+
+use kreeda::KreedaError;
+use kreeda::engine::text::{Text, measure_text};
+use kreeda::engine::window::{ResizeEvent, Window};
+
+const LABEL: &str = "bottom-right";
+const MARGIN: f32 = 16.0;
+
+/// Font size in logical units, so the label keeps its apparent size on
+/// high-DPI displays.
+const LOGICAL_FONT_SIZE: f32 = 24.0;
+
+/// Where the label is drawn and at what pixel size, updated on resize.
+static LAYOUT: Mutex<([f32; 2], f32)> = Mutex::new(([0.0, 0.0], LOGICAL_FONT_SIZE));
+
+fn on_resize(event: ResizeEvent) {
+    println!(
+        "resized: {}x{} px, {:.0}x{:.0} logical, scale {}",
+        event.physical.width,
+        event.physical.height,
+        event.logical.width,
+        event.logical.height,
+        event.scale_factor
+    );
+
+    let scale = event.scale_factor as f32;
+    let size = LOGICAL_FONT_SIZE * scale;
+    let (width, height) = measure_text(LABEL, size);
+    let position = [
+        event.physical.width as f32 - width - MARGIN * scale,
+        event.physical.height as f32 - height - MARGIN * scale,
+    ];
+    *LAYOUT.lock().unwrap() = (position, size);
+}
+
+fn update(_dt: f64) {
+    let (position, size) = *LAYOUT.lock().unwrap();
+    Text::draw(LABEL, position, size, [1.0, 1.0, 1.0, 1.0]);
+}
+
+fn main() -> Result<(), KreedaError> {
+    Window::add_resize_callback(on_resize);
+    Window::add_update(update);
+    Window::run()
+}
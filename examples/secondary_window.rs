@@ -0,0 +1,34 @@
+//! Opens a second window showing the same scene, like an editor's preview.
+//! Press Tab to close it or open it again.
+
+use std::sync::Mutex;
+
+use kreeda::KreedaError;
+use kreeda::engine::window::{Window, WindowConfig, WindowId};
+use kreeda::input::key_listener::KeyInput;
+use winit::keyboard::{Key, NamedKey};
+
+static PREVIEW: Mutex<Option<WindowId>> = Mutex::new(None);
+
+fn open_preview() -> WindowId {
+    Window::open_secondary(WindowConfig::new("Preview", 480, 270))
+}
+
+fn update(_dt: f64) {
+    let phase = Window::elapsed_time().sin() as f32 * 0.5 + 0.5;
+    Window::set_clear_color([0.2, 0.3 + phase * 0.4, 0.5, 1.0]);
+
+    if KeyInput::key_just_pressed(&Key::Named(NamedKey::Tab)) {
+        let mut preview = PREVIEW.lock().unwrap();
+        match preview.take() {
+            Some(id) if Window::is_open(id) => Window::close(id),
+            _ => *preview = Some(open_preview()),
+        }
+    }
+}
+
+fn main() -> Result<(), KreedaError> {
+    *PREVIEW.lock().unwrap() = Some(open_preview());
+    Window::add_update(update);
+    Window::run()
+}
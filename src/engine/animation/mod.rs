@@ -0,0 +1,2 @@
+pub mod state_machine;
+pub mod tween;
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::engine::window::Window;
+
+/// A sequence of atlas frame indices (as in
+/// [`SpriteAtlas::name_at`](crate::engine::renderer::sprite_atlas::SpriteAtlas::name_at))
+/// played at a fixed rate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    pub frames: Vec<usize>,
+    pub fps: f32,
+    /// Starts over after the last frame; otherwise holds it.
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<usize>, fps: f32, looping: bool) -> Self {
+        Self {
+            frames,
+            fps,
+            looping,
+        }
+    }
+}
+
+/// A guarded edge between two states, taken when `condition` returns true.
+struct Transition {
+    from: String,
+    to: String,
+    condition: Box<dyn Fn() -> bool + Send>,
+}
+
+/// Named [`AnimationClip`]s, e.g. "idle", "walk" and "attack", with the
+/// conditions for moving between them.
+///
+/// ```ignore
+/// let mut sm = AnimationStateMachine::new();
+/// sm.add_clip("idle", AnimationClip::new(vec![0, 1], 4.0, true));
+/// sm.add_clip("walk", AnimationClip::new(vec![2, 3, 4, 5], 10.0, true));
+/// sm.add_transition("idle", "walk", || is_moving());
+/// sm.add_transition("walk", "idle", || !is_moving());
+/// sm.transition_to("idle");
+/// // every frame:
+/// let frame = sm.update(dt);
+/// ```
+#[derive(Default)]
+pub struct AnimationStateMachine {
+    clips: HashMap<String, AnimationClip>,
+    transitions: Vec<Transition>,
+    current: Option<String>,
+    /// Position in the current clip's `frames`.
+    frame: usize,
+    timer: f32,
+    finished: bool,
+}
+
+impl AnimationStateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the clip for `state`.
+    pub fn add_clip(&mut self, state: impl Into<String>, clip: AnimationClip) {
+        self.clips.insert(state.into(), clip);
+    }
+
+    /// Moves from `from` to `to` during [`AnimationStateMachine::update`]
+    /// whenever `condition` holds. Transitions are checked in the order
+    /// they were added and the first match wins.
+    pub fn add_transition(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        condition: impl Fn() -> bool + Send + 'static,
+    ) {
+        self.transitions.push(Transition {
+            from: from.into(),
+            to: to.into(),
+            condition: Box::new(condition),
+        });
+    }
+
+    /// Plays the clip for `state` from its first frame. Staying in the
+    /// current state keeps the clip where it is. Returns false, changing
+    /// nothing, if there's no clip for `state`.
+    pub fn transition_to(&mut self, state: &str) -> bool {
+        if !self.clips.contains_key(state) {
+            return false;
+        }
+        if self.current.as_deref() != Some(state) {
+            self.current = Some(state.to_owned());
+            self.frame = 0;
+            self.timer = 0.0;
+            self.finished = false;
+        }
+        true
+    }
+
+    /// Takes the first transition out of the current state whose condition
+    /// holds, then advances the clip by `delta` seconds. Returns the atlas
+    /// frame to draw, 0 while no clip is playing. While a clip plays it
+    /// keeps an [`OnDemand`](crate::engine::window::RedrawMode::OnDemand)
+    /// window drawing.
+    pub fn update(&mut self, delta: f32) -> usize {
+        let next = self.current.as_deref().and_then(|current| {
+            self.transitions
+                .iter()
+                .find(|t| t.from == current && t.to != current && (t.condition)())
+                .map(|t| t.to.clone())
+        });
+        if let Some(next) = next {
+            self.transition_to(&next);
+        }
+
+        if let Some(clip) = self.current.as_ref().and_then(|c| self.clips.get(c))
+            && clip.fps > 0.0
+            && !clip.frames.is_empty()
+            && !self.finished
+        {
+            let frame_time = 1.0 / clip.fps;
+            self.timer += delta.max(0.0);
+            let steps = (self.timer / frame_time) as usize;
+            self.timer -= steps as f32 * frame_time;
+            let last = clip.frames.len() - 1;
+            if clip.looping {
+                self.frame = (self.frame + steps) % clip.frames.len();
+            } else if self.frame + steps >= last {
+                self.frame = last;
+                self.finished = true;
+            } else {
+                self.frame += steps;
+            }
+            if !self.finished && clip.frames.len() > 1 {
+                Window::invalidate();
+            }
+        }
+        self.current_frame()
+    }
+
+    //--Getters--//
+
+    pub fn current_state(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// The atlas frame to draw, 0 while no clip is playing.
+    pub fn current_frame(&self) -> usize {
+        self.current
+            .as_ref()
+            .and_then(|c| self.clips.get(c))
+            .and_then(|clip| clip.frames.get(self.frame))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Whether a non-looping clip has reached its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn clip(&self, state: &str) -> Option<&AnimationClip> {
+        self.clips.get(state)
+    }
+}
@@ -0,0 +1,612 @@
+use std::sync::Mutex;
+
+use glam::{Quat, Vec2, Vec3, Vec4};
+use once_cell::sync::Lazy;
+
+use crate::engine::math::color::Color;
+use crate::engine::math::transform::{Transform2D, Transform3D};
+use crate::engine::window::Window;
+
+/// Values a [`Tween`] can move between.
+pub trait Lerp: Copy {
+    /// The value `t` of the way from `self` to `other`. `t` may leave
+    /// `0..=1` for easings that overshoot.
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec3::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Vec4 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec4::lerp(self, other, t)
+    }
+}
+
+/// Componentwise, e.g. for RGBA colors.
+impl Lerp for [f32; 4] {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].lerp(other[i], t))
+    }
+}
+
+/// In linear space, as [`Color::lerp`].
+impl Lerp for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Color::lerp(self, other, t)
+    }
+}
+
+/// Along the shorter arc.
+impl Lerp for Quat {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// Each part separately; the rotation angle is interpolated as is, so a
+/// turn from 0 to 2π goes all the way round.
+impl Lerp for Transform2D {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Transform2D {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.lerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+impl Lerp for Transform3D {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Transform3D {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: Lerp::lerp(self.rotation, other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+/// Maps linear progress in `0..=1` to eased progress, with 0 and 1 mapped
+/// to themselves.
+pub type EasingFn = fn(f32) -> f32;
+
+pub fn linear(t: f32) -> f32 {
+    t
+}
+
+pub fn ease_in_quad(t: f32) -> f32 {
+    t * t
+}
+
+pub fn ease_out_quad(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn ease_in_out_quad(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+pub fn ease_in_cubic(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn ease_in_out_cubic(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+/// Winds up with a growing wobble before leaving the start.
+pub fn elastic_in(t: f32) -> f32 {
+    1.0 - elastic_out(1.0 - t)
+}
+
+/// Overshoots the end and wobbles into place, like a spring.
+pub fn elastic_out(t: f32) -> f32 {
+    const PERIOD: f32 = std::f32::consts::TAU / 3.0;
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * PERIOD).sin() + 1.0
+    }
+}
+
+/// [`bounce_out`] backwards: bounces off the start before leaving it.
+pub fn bounce_in(t: f32) -> f32 {
+    1.0 - bounce_out(1.0 - t)
+}
+
+/// Arrives like a dropped ball, bouncing a few times before settling.
+pub fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// What a [`Tween`] does on reaching `end`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TweenMode {
+    /// Stops at `end` and reports finished.
+    #[default]
+    Once,
+    /// Jumps back to `start` and plays again.
+    Loop,
+    /// Plays back to `start`, then forwards again.
+    Yoyo,
+}
+
+/// Moves a value from `start` to `end` over `duration` seconds, shaped by
+/// an easing function.
+///
+/// ```ignore
+/// let mut fade = Tween::new(1.0, 0.0, 0.5).with_easing(ease_out_quad);
+/// // every frame:
+/// let alpha = fade.update(dt);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Tween<T: Lerp> {
+    start: T,
+    end: T,
+    duration: f32,
+    elapsed: f32,
+    easing: EasingFn,
+    mode: TweenMode,
+    /// Heading back to `start` in [`TweenMode::Yoyo`].
+    reversed: bool,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// A [`linear`] tween. A `duration` of zero or less finishes at once.
+    pub fn new(start: T, end: T, duration: f32) -> Self {
+        Self {
+            start,
+            end,
+            duration,
+            elapsed: 0.0,
+            easing: linear,
+            mode: TweenMode::Once,
+            reversed: false,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: EasingFn) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Loops or yoyos instead of stopping at `end`. Either way the tween
+    /// never finishes. Ignored when `duration` is zero or less.
+    pub fn with_mode(mut self, mode: TweenMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Plays `next` once this one finishes.
+    pub fn then(self, next: Tween<T>) -> TweenSequence<T> {
+        TweenSequence::new(self).then(next)
+    }
+
+    /// Advances by `delta` seconds and returns the new value. Stays at
+    /// `end` once finished. Until then it keeps an
+    /// [`OnDemand`](crate::engine::window::RedrawMode::OnDemand) window
+    /// drawing.
+    pub fn update(&mut self, delta: f32) -> T {
+        self.advance(delta);
+        self.value()
+    }
+
+    /// Advances by `delta` seconds and returns the time left over after
+    /// finishing, for whatever plays next.
+    fn advance(&mut self, delta: f32) -> f32 {
+        let elapsed = self.elapsed + delta.max(0.0);
+        let duration = self.duration.max(0.0);
+        if self.mode == TweenMode::Once || duration == 0.0 {
+            self.elapsed = elapsed.min(duration);
+            if !self.is_finished() {
+                Window::invalidate();
+            }
+            return (elapsed - duration).max(0.0);
+        }
+
+        // Skip whole cycles at once, however large `delta` is
+        let cycles = (elapsed / duration).floor();
+        self.elapsed = elapsed - cycles * duration;
+        if self.mode == TweenMode::Yoyo && cycles % 2.0 == 1.0 {
+            self.reversed = !self.reversed;
+        }
+        Window::invalidate();
+        0.0
+    }
+
+    /// The value at the current point, without advancing.
+    pub fn value(&self) -> T {
+        let progress = self.progress();
+        let t = if self.reversed {
+            1.0 - progress
+        } else {
+            progress
+        };
+        self.start.lerp(self.end, (self.easing)(t))
+    }
+
+    /// Starts over from `start`.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.reversed = false;
+    }
+
+    /// Whether a [`TweenMode::Once`] tween has reached `end`.
+    pub fn is_finished(&self) -> bool {
+        (self.mode == TweenMode::Once || self.duration <= 0.0) && self.elapsed >= self.duration
+    }
+
+    /// Linear progress from 0 to 1 through the current run, before easing.
+    /// Going back in [`TweenMode::Yoyo`] it still counts up.
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    //--Getters--//
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn mode(&self) -> TweenMode {
+        self.mode
+    }
+}
+
+/// Tweens played one after another, built with [`Tween::then`]. Time left
+/// over when one finishes goes to the next, so the sequence keeps pace
+/// with a large `delta`. A looping or yoyoing tween never finishes, so
+/// nothing after it plays.
+///
+/// ```ignore
+/// // Slide in, wait, then slide out
+/// let mut slide = Tween::new(-200.0, 0.0, 0.3)
+///     .with_easing(ease_out_cubic)
+///     .then(Tween::new(0.0, 0.0, 2.0))
+///     .then(Tween::new(0.0, -200.0, 0.3).with_easing(ease_in_cubic));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TweenSequence<T: Lerp> {
+    tweens: Vec<Tween<T>>,
+    current: usize,
+}
+
+impl<T: Lerp> TweenSequence<T> {
+    pub fn new(first: Tween<T>) -> Self {
+        Self {
+            tweens: vec![first],
+            current: 0,
+        }
+    }
+
+    pub fn then(mut self, next: Tween<T>) -> Self {
+        self.tweens.push(next);
+        self
+    }
+
+    /// Advances by `delta` seconds and returns the new value, which stays
+    /// at the last tween's `end` once finished.
+    pub fn update(&mut self, delta: f32) -> T {
+        let mut delta = delta;
+        loop {
+            delta = self.tweens[self.current].advance(delta);
+            if !self.tweens[self.current].is_finished() || self.current + 1 == self.tweens.len() {
+                return self.value();
+            }
+            self.current += 1;
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.tweens[self.current].value()
+    }
+
+    /// Starts over from the first tween.
+    pub fn reset(&mut self) {
+        self.tweens.iter_mut().for_each(Tween::reset);
+        self.current = 0;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current + 1 == self.tweens.len() && self.tweens[self.current].is_finished()
+    }
+
+    //--Getters--//
+
+    /// Index of the tween playing now.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn tweens(&self) -> &[Tween<T>] {
+        &self.tweens
+    }
+}
+
+/// Something [`TweenManager`] can play: a [`Tween`] or a
+/// [`TweenSequence`].
+pub trait Tweenable<T>: Send + 'static {
+    fn update(&mut self, delta: f32) -> T;
+    fn is_finished(&self) -> bool;
+}
+
+impl<T: Lerp + Send + 'static> Tweenable<T> for Tween<T> {
+    fn update(&mut self, delta: f32) -> T {
+        Tween::update(self, delta)
+    }
+
+    fn is_finished(&self) -> bool {
+        Tween::is_finished(self)
+    }
+}
+
+impl<T: Lerp + Send + 'static> Tweenable<T> for TweenSequence<T> {
+    fn update(&mut self, delta: f32) -> T {
+        TweenSequence::update(self, delta)
+    }
+
+    fn is_finished(&self) -> bool {
+        TweenSequence::is_finished(self)
+    }
+}
+
+/// Handle to a tween started with [`TweenManager::start`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TweenId(u64);
+
+type TweenStep = Box<dyn FnMut(f32) -> bool + Send>;
+
+struct Running {
+    id: TweenId,
+    /// Advances the tween, hands its value on and says whether it finished.
+    step: TweenStep,
+    on_complete: Option<Box<dyn FnOnce() + Send>>,
+}
+
+/// Plays tweens for you: the window updates them once per frame, before
+/// the update callbacks, and hands each new value to a callback.
+///
+/// ```ignore
+/// let fade = TweenManager::start(Tween::new(1.0, 0.0, 0.5), |alpha| set_alpha(alpha));
+/// TweenManager::on_complete(fade, || hide_menu());
+/// ```
+pub struct TweenManager {
+    running: Vec<Running>,
+    next_id: u64,
+    /// The tweens [`TweenManager::tick`] has out, and which of them were
+    /// cancelled meanwhile.
+    ticking: Vec<TweenId>,
+    cancelled: Vec<TweenId>,
+}
+
+impl TweenManager {
+    fn new() -> Self {
+        Self {
+            running: Vec::new(),
+            next_id: 0,
+            ticking: Vec::new(),
+            cancelled: Vec::new(),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<TweenManager> {
+        static INSTANCE: Lazy<Mutex<TweenManager>> = Lazy::new(|| Mutex::new(TweenManager::new()));
+        &INSTANCE
+    }
+
+    /// Plays `tween`, passing its value to `apply` every frame until it
+    /// finishes or is cancelled.
+    pub fn start<T>(
+        mut tween: impl Tweenable<T>,
+        mut apply: impl FnMut(T) + Send + 'static,
+    ) -> TweenId {
+        let step = Box::new(move |delta| {
+            apply(tween.update(delta));
+            tween.is_finished()
+        });
+        let mut manager = Self::get_instance().lock().unwrap();
+        let id = TweenId(manager.next_id);
+        manager.next_id += 1;
+        manager.running.push(Running {
+            id,
+            step,
+            on_complete: None,
+        });
+        id
+    }
+
+    /// Runs `callback` once the tween finishes, after its last value was
+    /// applied. Not run if the tween is cancelled.
+    pub fn on_complete(id: TweenId, callback: impl FnOnce() + Send + 'static) {
+        let mut manager = Self::get_instance().lock().unwrap();
+        if let Some(running) = manager.running.iter_mut().find(|r| r.id == id) {
+            running.on_complete = Some(Box::new(callback));
+        }
+    }
+
+    /// Stops a tween where it is. Returns false if it already finished or
+    /// was cancelled.
+    pub fn cancel(id: TweenId) -> bool {
+        let mut manager = Self::get_instance().lock().unwrap();
+        let len = manager.running.len();
+        manager.running.retain(|running| running.id != id);
+        if manager.running.len() != len {
+            return true;
+        }
+        let ticking = manager.ticking.contains(&id) && !manager.cancelled.contains(&id);
+        if ticking {
+            manager.cancelled.push(id);
+        }
+        ticking
+    }
+
+    /// Advances every tween by `delta` seconds and drops the finished ones.
+    /// The tweens are taken out meanwhile, so their callbacks can start or
+    /// cancel tweens.
+    pub(crate) fn tick(delta: f64) {
+        let mut running = {
+            let mut manager = Self::get_instance().lock().unwrap();
+            let running = std::mem::take(&mut manager.running);
+            manager.ticking = running.iter().map(|tween| tween.id).collect();
+            running
+        };
+        let mut completed = Vec::new();
+        running.retain_mut(|tween| {
+            let finished = (tween.step)(delta as f32);
+            if finished {
+                completed.extend(tween.on_complete.take());
+            }
+            !finished
+        });
+        Self::get_instance().lock().unwrap().ticking =
+            running.iter().map(|tween| tween.id).collect();
+        for callback in completed {
+            callback();
+        }
+
+        let mut manager = Self::get_instance().lock().unwrap();
+        manager.ticking.clear();
+        let cancelled = std::mem::take(&mut manager.cancelled);
+        running.retain(|tween| !cancelled.contains(&tween.id));
+        // Tweens started during the tick go after the ones already running
+        running.append(&mut manager.running);
+        manager.running = running;
+    }
+
+    //--Getters--//
+
+    /// Whether the tween is still playing.
+    pub fn is_running(id: TweenId) -> bool {
+        let manager = Self::get_instance().lock().unwrap();
+        manager.running.iter().any(|running| running.id == id)
+            || manager.ticking.contains(&id) && !manager.cancelled.contains(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn easings_map_the_endpoints_to_themselves() {
+        let easings: [(&str, EasingFn); 11] = [
+            ("linear", linear),
+            ("ease_in_quad", ease_in_quad),
+            ("ease_out_quad", ease_out_quad),
+            ("ease_in_out_quad", ease_in_out_quad),
+            ("ease_in_cubic", ease_in_cubic),
+            ("ease_out_cubic", ease_out_cubic),
+            ("ease_in_out_cubic", ease_in_out_cubic),
+            ("elastic_in", elastic_in),
+            ("elastic_out", elastic_out),
+            ("bounce_in", bounce_in),
+            ("bounce_out", bounce_out),
+        ];
+        for (name, easing) in easings {
+            assert!(easing(0.0).abs() < 1e-5, "{name}(0) = {}", easing(0.0));
+            assert!(
+                (easing(1.0) - 1.0).abs() < 1e-5,
+                "{name}(1) = {}",
+                easing(1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn easings_hit_known_mid_values() {
+        assert_near(linear(0.5), 0.5);
+        assert_near(ease_in_quad(0.5), 0.25);
+        assert_near(ease_out_quad(0.5), 0.75);
+        assert_near(ease_in_out_quad(0.25), 0.125);
+        assert_near(ease_in_out_quad(0.75), 0.875);
+        assert_near(ease_in_cubic(0.5), 0.125);
+        assert_near(ease_out_cubic(0.5), 0.875);
+        assert_near(ease_in_out_cubic(0.25), 0.0625);
+        assert_near(ease_in_out_cubic(0.75), 0.9375);
+        // Overshoots the end by 2^-5 halfway through
+        assert_near(elastic_out(0.5), 1.015625);
+        assert_near(elastic_in(0.5), -0.015625);
+        assert_near(bounce_out(0.5), 0.765625);
+        assert_near(bounce_in(0.5), 0.234375);
+    }
+
+    #[test]
+    fn tweens_apply_their_easing() {
+        let mut tween = Tween::new(10.0, 20.0, 2.0).with_easing(ease_in_quad);
+        assert_near(tween.update(1.0), 12.5);
+        assert_near(tween.update(5.0), 20.0);
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn colors_tween_in_linear_space() {
+        let mut tween = Tween::new(Color::BLACK, Color::from_rgba(1.0, 0.5, 0.0, 0.0), 2.0);
+        assert_eq!(tween.update(1.0), Color::from_rgba(0.5, 0.25, 0.0, 0.5));
+        assert_eq!(tween.update(1.0), Color::from_rgba(1.0, 0.5, 0.0, 0.0));
+    }
+}
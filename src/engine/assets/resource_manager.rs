@@ -0,0 +1,435 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use wgpu::{Device, Queue};
+
+#[cfg(feature = "audio")]
+use crate::engine::audio::{AudioError, Sound};
+use crate::engine::renderer::mesh::Mesh;
+use crate::engine::renderer::model::Model;
+use crate::engine::renderer::obj::ObjError;
+use crate::engine::renderer::skybox::{SkyboxError, SkyboxRenderer};
+use crate::engine::renderer::texture::{Texture2D, TextureError};
+use crate::engine::text::{Font, TextError};
+
+/// Why [`ResourceManager::reload`] couldn't re-read an asset.
+#[derive(Debug)]
+pub enum AssetError {
+    Texture(TextureError),
+    Font(TextError),
+    Model(ObjError),
+    Skybox(SkyboxError),
+    #[cfg(feature = "audio")]
+    Sound(AudioError),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Texture(e) => write!(f, "failed to reload texture: {e}"),
+            AssetError::Font(e) => write!(f, "failed to reload font: {e}"),
+            AssetError::Model(e) => write!(f, "failed to reload model: {e}"),
+            AssetError::Skybox(e) => write!(f, "failed to reload skybox: {e}"),
+            #[cfg(feature = "audio")]
+            AssetError::Sound(e) => write!(f, "failed to reload sound: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+impl From<TextureError> for AssetError {
+    fn from(e: TextureError) -> Self {
+        AssetError::Texture(e)
+    }
+}
+
+impl From<TextError> for AssetError {
+    fn from(e: TextError) -> Self {
+        AssetError::Font(e)
+    }
+}
+
+impl From<ObjError> for AssetError {
+    fn from(e: ObjError) -> Self {
+        AssetError::Model(e)
+    }
+}
+
+impl From<SkyboxError> for AssetError {
+    fn from(e: SkyboxError) -> Self {
+        AssetError::Skybox(e)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<AudioError> for AssetError {
+    fn from(e: AudioError) -> Self {
+        AssetError::Sound(e)
+    }
+}
+
+/// The files a cached skybox was built from, to rebuild it on reload.
+enum SkyboxSource {
+    Panorama,
+    Faces([PathBuf; 6]),
+}
+
+/// Deduplicates loaded assets by their canonical path.
+pub struct ResourceManager {
+    textures: HashMap<PathBuf, Arc<Texture2D>>,
+    meshes: HashMap<PathBuf, Arc<Mesh>>,
+    models: HashMap<PathBuf, Arc<Model>>,
+    fonts: HashMap<PathBuf, Arc<Font>>,
+    skyboxes: HashMap<PathBuf, (SkyboxSource, Arc<SkyboxRenderer>)>,
+    #[cfg(feature = "audio")]
+    sounds: HashMap<PathBuf, Arc<Sound>>,
+}
+
+impl ResourceManager {
+    fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            meshes: HashMap::new(),
+            models: HashMap::new(),
+            fonts: HashMap::new(),
+            skyboxes: HashMap::new(),
+            #[cfg(feature = "audio")]
+            sounds: HashMap::new(),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<ResourceManager> {
+        static INSTANCE: Lazy<Mutex<ResourceManager>> =
+            Lazy::new(|| Mutex::new(ResourceManager::new()));
+
+        &INSTANCE
+    }
+
+    /// Returns the cached texture for `path`, loading it on first use.
+    pub fn load_texture(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<Arc<Texture2D>, TextureError> {
+        let key = cache_key(path.as_ref());
+        if let Some(texture) = self.textures.get(&key) {
+            return Ok(texture.clone());
+        }
+
+        let texture = Arc::new(Texture2D::from_file(device, queue, &key)?);
+        self.textures.insert(key, texture.clone());
+        Ok(texture)
+    }
+
+    /// Returns the cached sound for `path`, loading it on first use.
+    #[cfg(feature = "audio")]
+    pub fn load_sound(&mut self, path: impl AsRef<Path>) -> Result<Arc<Sound>, AudioError> {
+        let key = cache_key(path.as_ref());
+        if let Some(sound) = self.sounds.get(&key) {
+            return Ok(sound.clone());
+        }
+
+        let sound = Arc::new(Sound::from_file(&key)?);
+        self.sounds.insert(key, sound.clone());
+        Ok(sound)
+    }
+
+    /// Returns the cached model for `path`, loading it on first use. Only
+    /// Wavefront OBJ files are supported.
+    pub fn load_model(
+        &mut self,
+        device: &Device,
+        path: impl AsRef<Path>,
+    ) -> Result<Arc<Model>, ObjError> {
+        let key = cache_key(path.as_ref());
+        if let Some(model) = self.models.get(&key) {
+            return Ok(model.clone());
+        }
+
+        let model = Arc::new(Model::from_obj_file(device, &key)?);
+        self.models.insert(key, model.clone());
+        Ok(model)
+    }
+
+    /// Returns the cached font for `path`, loading it on first use.
+    pub fn load_font(&mut self, path: impl AsRef<Path>) -> Result<Arc<Font>, TextError> {
+        let key = cache_key(path.as_ref());
+        if let Some(font) = self.fonts.get(&key) {
+            return Ok(font.clone());
+        }
+
+        let font = Arc::new(Font::from_file(&key)?);
+        self.fonts.insert(key, font.clone());
+        Ok(font)
+    }
+
+    /// Returns the cached skybox for the equirectangular panorama at
+    /// `path`, converting it on first use.
+    pub fn load_skybox(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<Arc<SkyboxRenderer>, SkyboxError> {
+        let key = cache_key(path.as_ref());
+        if let Some((_, skybox)) = self.skyboxes.get(&key) {
+            return Ok(skybox.clone());
+        }
+
+        let skybox = Arc::new(SkyboxRenderer::from_equirectangular_file(
+            device, queue, &key,
+        )?);
+        self.skyboxes
+            .insert(key, (SkyboxSource::Panorama, skybox.clone()));
+        Ok(skybox)
+    }
+
+    /// Returns the cached skybox for six face images, in the order of
+    /// [`SkyboxRenderer::from_faces`], loading it on first use. It's cached
+    /// under the first face's path, for [`ResourceManager::skybox`] and
+    /// [`ResourceManager::reload`].
+    pub fn load_skybox_faces<P: AsRef<Path>>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        paths: &[P; 6],
+    ) -> Result<Arc<SkyboxRenderer>, SkyboxError> {
+        let paths: [PathBuf; 6] = std::array::from_fn(|i| cache_key(paths[i].as_ref()));
+        if let Some((_, skybox)) = self.skyboxes.get(&paths[0]) {
+            return Ok(skybox.clone());
+        }
+
+        let skybox = Arc::new(SkyboxRenderer::from_face_files(device, queue, &paths)?);
+        let key = paths[0].clone();
+        self.skyboxes
+            .insert(key, (SkyboxSource::Faces(paths), skybox.clone()));
+        Ok(skybox)
+    }
+
+    /// Reads every asset cached under `path` from disk again, e.g. after
+    /// editing it. Later loads get the new version; handles already given
+    /// out keep the old one. Returns false if nothing was cached there.
+    /// Meshes added with [`ResourceManager::insert_mesh`] aren't loaded from
+    /// files, so they're left alone.
+    pub fn reload(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<bool, AssetError> {
+        let key = cache_key(path.as_ref());
+        let mut reloaded = false;
+        if self.textures.contains_key(&key) {
+            let texture = Texture2D::from_file(device, queue, &key)?;
+            self.textures.insert(key.clone(), Arc::new(texture));
+            reloaded = true;
+        }
+        if self.models.contains_key(&key) {
+            let model = Model::from_obj_file(device, &key)?;
+            self.models.insert(key.clone(), Arc::new(model));
+            reloaded = true;
+        }
+        if let Some((source, _)) = self.skyboxes.get(&key) {
+            let skybox = match source {
+                SkyboxSource::Panorama => {
+                    SkyboxRenderer::from_equirectangular_file(device, queue, &key)?
+                }
+                SkyboxSource::Faces(paths) => {
+                    SkyboxRenderer::from_face_files(device, queue, paths)?
+                }
+            };
+            self.skyboxes.get_mut(&key).unwrap().1 = Arc::new(skybox);
+            reloaded = true;
+        }
+        if self.fonts.contains_key(&key) {
+            self.fonts
+                .insert(key.clone(), Arc::new(Font::from_file(&key)?));
+            reloaded = true;
+        }
+        #[cfg(feature = "audio")]
+        if self.sounds.contains_key(&key) {
+            self.sounds
+                .insert(key.clone(), Arc::new(Sound::from_file(&key)?));
+            reloaded = true;
+        }
+        Ok(reloaded)
+    }
+
+    /// Caches an already built mesh under `path` and returns the shared handle.
+    pub fn insert_mesh(&mut self, path: impl AsRef<Path>, mesh: Mesh) -> Arc<Mesh> {
+        let mesh = Arc::new(mesh);
+        self.meshes.insert(cache_key(path.as_ref()), mesh.clone());
+        mesh
+    }
+
+    pub fn texture(&self, path: impl AsRef<Path>) -> Option<Arc<Texture2D>> {
+        self.textures.get(&cache_key(path.as_ref())).cloned()
+    }
+
+    pub fn mesh(&self, path: impl AsRef<Path>) -> Option<Arc<Mesh>> {
+        self.meshes.get(&cache_key(path.as_ref())).cloned()
+    }
+
+    pub fn model(&self, path: impl AsRef<Path>) -> Option<Arc<Model>> {
+        self.models.get(&cache_key(path.as_ref())).cloned()
+    }
+
+    pub fn font(&self, path: impl AsRef<Path>) -> Option<Arc<Font>> {
+        self.fonts.get(&cache_key(path.as_ref())).cloned()
+    }
+
+    pub fn skybox(&self, path: impl AsRef<Path>) -> Option<Arc<SkyboxRenderer>> {
+        self.skyboxes
+            .get(&cache_key(path.as_ref()))
+            .map(|(_, skybox)| skybox.clone())
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn sound(&self, path: impl AsRef<Path>) -> Option<Arc<Sound>> {
+        self.sounds.get(&cache_key(path.as_ref())).cloned()
+    }
+
+    /// Drops every asset only the cache still references. Returns how many
+    /// were released.
+    pub fn unload_unused(&mut self) -> usize {
+        let before = self.len();
+        self.textures.retain(|_, t| Arc::strong_count(t) > 1);
+        self.meshes.retain(|_, m| Arc::strong_count(m) > 1);
+        self.models.retain(|_, m| Arc::strong_count(m) > 1);
+        self.fonts.retain(|_, f| Arc::strong_count(f) > 1);
+        self.skyboxes.retain(|_, (_, s)| Arc::strong_count(s) > 1);
+        #[cfg(feature = "audio")]
+        self.sounds.retain(|_, s| Arc::strong_count(s) > 1);
+        before - self.len()
+    }
+
+    /// Number of cached assets of every kind.
+    fn len(&self) -> usize {
+        let len = self.textures.len()
+            + self.meshes.len()
+            + self.models.len()
+            + self.fonts.len()
+            + self.skyboxes.len();
+        #[cfg(feature = "audio")]
+        let len = len + self.sounds.len();
+        len
+    }
+}
+
+/// Canonical form of `path` so `a/../b.png` and `b.png` share an entry.
+/// Falls back to the path as given if it can't be resolved.
+fn cache_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::context::test_device_or_skip;
+
+    const FONT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/fonts/DejaVuSansMono.ttf"
+    );
+
+    #[test]
+    fn loading_a_path_twice_shares_one_font() {
+        let mut cache = ResourceManager::new();
+        let first = cache.load_font(FONT).unwrap();
+        // A different spelling of the same file
+        let dotted = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/../assets/fonts/DejaVuSansMono.ttf"
+        );
+        let second = cache.load_font(dotted).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&first, &cache.font(FONT).unwrap()));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn unused_assets_are_released() {
+        let mut cache = ResourceManager::new();
+        let font = cache.load_font(FONT).unwrap();
+        assert_eq!(cache.unload_unused(), 0);
+
+        drop(font);
+        assert_eq!(cache.unload_unused(), 1);
+        assert!(cache.font(FONT).is_none());
+    }
+
+    #[test]
+    fn missing_files_are_not_cached() {
+        let mut cache = ResourceManager::new();
+        assert!(cache.load_font("no/such/font.ttf").is_err());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn loading_a_path_twice_shares_one_texture_until_reloaded() {
+        let (device, queue) = test_device_or_skip!();
+        let path = std::env::temp_dir().join(format!("kreeda-cache-{}.png", std::process::id()));
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+
+        let mut cache = ResourceManager::new();
+        let first = cache.load_texture(device, queue, &path).unwrap();
+        let second = cache.load_texture(device, queue, &path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // A reload replaces the cached copy; handles already out keep theirs
+        assert!(cache.reload(device, queue, &path).unwrap());
+        let reloaded = cache.texture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!Arc::ptr_eq(&first, &reloaded));
+        assert_eq!(reloaded.width(), 2);
+    }
+
+    #[test]
+    fn skyboxes_are_cached_reloaded_and_unloaded() {
+        let (device, queue) = test_device_or_skip!();
+        let path = std::env::temp_dir().join(format!("kreeda-sky-{}.png", std::process::id()));
+        let panorama = |width| {
+            image::RgbaImage::from_pixel(width, width / 2, image::Rgba([0, 0, 255, 255]))
+                .save(&path)
+                .unwrap();
+        };
+        panorama(8);
+
+        let mut cache = ResourceManager::new();
+        let first = cache.load_skybox(device, queue, &path).unwrap();
+        let second = cache.load_skybox(device, queue, &path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.face_size(), 2);
+
+        panorama(16);
+        assert!(cache.reload(device, queue, &path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        let reloaded = cache.skybox(&path).unwrap();
+        assert_eq!(reloaded.face_size(), 4);
+        assert_eq!(first.face_size(), 2);
+
+        drop((first, second, reloaded));
+        assert_eq!(cache.unload_unused(), 1);
+        assert!(cache.skybox(&path).is_none());
+    }
+
+    #[test]
+    fn missing_skybox_faces_are_an_error() {
+        let (device, queue) = test_device_or_skip!();
+        let mut cache = ResourceManager::new();
+        let faces = ["no/such/face.png"; 6];
+
+        assert!(matches!(
+            cache.load_skybox_faces(device, queue, &faces),
+            Err(SkyboxError::Io(_))
+        ));
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -0,0 +1,609 @@
+use glam::Vec3;
+use once_cell::sync::Lazy;
+use rodio::decoder::DecoderError;
+use rodio::mixer::Mixer;
+use rodio::source::ChannelVolume;
+use rodio::{Decoder, OutputStreamBuilder, Sink, Source, StreamError};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    Decode(DecoderError),
+    Stream(StreamError),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::Io(e) => write!(f, "failed to read audio file: {e}"),
+            AudioError::Decode(e) => write!(f, "failed to decode audio: {e}"),
+            AudioError::Stream(e) => write!(f, "failed to open audio output: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+impl From<std::io::Error> for AudioError {
+    fn from(e: std::io::Error) -> Self {
+        AudioError::Io(e)
+    }
+}
+
+impl From<DecoderError> for AudioError {
+    fn from(e: DecoderError) -> Self {
+        AudioError::Decode(e)
+    }
+}
+
+impl From<StreamError> for AudioError {
+    fn from(e: StreamError) -> Self {
+        AudioError::Stream(e)
+    }
+}
+
+/// An encoded WAV or OGG file held in memory, decoded afresh each time it
+/// is played. Load through
+/// [`ResourceManager::load_sound`](crate::engine::assets::resource_manager::ResourceManager::load_sound)
+/// to share one copy between everything that plays it.
+pub struct Sound {
+    bytes: Arc<[u8]>,
+}
+
+impl Sound {
+    /// Checks that `bytes` decode before accepting them.
+    pub fn from_bytes(bytes: impl Into<Arc<[u8]>>) -> Result<Self, AudioError> {
+        let sound = Self {
+            bytes: bytes.into(),
+        };
+        sound.decoder()?;
+        Ok(sound)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, AudioError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    fn decoder(&self) -> Result<Decoder<Cursor<Arc<[u8]>>>, AudioError> {
+        Ok(Decoder::new(Cursor::new(self.bytes.clone()))?)
+    }
+}
+
+/// How a sound effect is played.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoundParams {
+    /// 1.0 is unchanged; scaled further by the sfx and master volumes.
+    pub volume: f32,
+    /// Playback speed, which also shifts the pitch. 1.0 is unchanged.
+    pub pitch: f32,
+}
+
+impl Default for SoundParams {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+/// How the volume of sounds started with [`play_spatial`] falls off with
+/// distance from the listener.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpatialSettings {
+    /// Closer than this a sound plays at full volume. Further away its
+    /// volume drops with the inverse square of the distance.
+    pub reference_distance: f32,
+    /// Sounds further away than this are silent.
+    pub max_distance: f32,
+}
+
+impl Default for SpatialSettings {
+    fn default() -> Self {
+        Self {
+            reference_distance: 1.0,
+            max_distance: 100.0,
+        }
+    }
+}
+
+/// Identifies one playing sound effect, for [`is_playing`] and
+/// [`stop_sound`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SoundHandle(u64);
+
+/// A playing source and the volume it was started with, before the group
+/// and master volumes are applied.
+struct Voice {
+    sink: Sink,
+    volume: f32,
+    /// Set for sounds started with [`play_spatial`].
+    emitter: Option<Emitter>,
+}
+
+/// Where a positioned sound is, and the left and right ear gains its
+/// source picks up while it plays.
+struct Emitter {
+    position: Vec3,
+    gains: Arc<Mutex<[f32; 2]>>,
+}
+
+/// Where positioned sounds are heard from.
+struct Listener {
+    position: Vec3,
+    forward: Vec3,
+}
+
+/// Output device plus every sound and music track currently playing.
+///
+/// The device is opened on first use. Playback runs on rodio's mixer
+/// thread, so nothing here blocks the render loop beyond decoding headers.
+pub struct AudioManager {
+    mixer: Option<Mixer>,
+    master_volume: f32,
+    music_volume: f32,
+    sfx_volume: f32,
+    music: Option<Voice>,
+    sounds: Vec<(SoundHandle, Voice)>,
+    next_handle: u64,
+    listener: Listener,
+    spatial: SpatialSettings,
+}
+
+impl AudioManager {
+    fn new() -> Self {
+        Self {
+            mixer: None,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            music: None,
+            sounds: Vec::new(),
+            next_handle: 0,
+            listener: Listener {
+                position: Vec3::ZERO,
+                forward: Vec3::NEG_Z,
+            },
+            spatial: SpatialSettings::default(),
+        }
+    }
+
+    pub fn get() -> &'static Mutex<AudioManager> {
+        static INSTANCE: Lazy<Mutex<AudioManager>> = Lazy::new(|| Mutex::new(AudioManager::new()));
+
+        &INSTANCE
+    }
+
+    //--Playback--//
+
+    /// Plays an embedded WAV or OGG sound effect once; see
+    /// [`play_sound_bytes`].
+    pub fn play_sfx(bytes: &'static [u8]) -> Result<SoundHandle, AudioError> {
+        play_sound_bytes(bytes)
+    }
+
+    /// Replaces the music track with an embedded WAV or OGG file, looped
+    /// until [`AudioManager::stop_music`].
+    pub fn play_music(bytes: &'static [u8]) -> Result<(), AudioError> {
+        play_music_bytes(bytes, true)
+    }
+
+    pub fn set_music_volume(volume: f32) {
+        set_music_volume(volume);
+    }
+
+    pub fn stop_music() {
+        stop_music();
+    }
+
+    /// Plays an embedded WAV or OGG sound effect positioned in 3D; see
+    /// [`play_spatial`].
+    pub fn play_spatial(
+        bytes: &'static [u8],
+        source_pos: Vec3,
+        listener_pos: Vec3,
+        listener_forward: Vec3,
+    ) -> Result<SoundHandle, AudioError> {
+        play_spatial(bytes, source_pos, listener_pos, listener_forward)
+    }
+
+    /// Moves a sound started with [`AudioManager::play_spatial`]; see
+    /// [`update_spatial`].
+    pub fn update_spatial(handle: SoundHandle, new_pos: Vec3) {
+        update_spatial(handle, new_pos);
+    }
+
+    fn mixer(&mut self) -> Result<&Mixer, AudioError> {
+        if self.mixer.is_none() {
+            let stream = OutputStreamBuilder::open_default_stream()?;
+            self.mixer = Some(stream.mixer().clone());
+            // Dropping the stream silences everything, and the device is
+            // wanted for the rest of the process anyway.
+            std::mem::forget(stream);
+        }
+        Ok(self.mixer.as_ref().unwrap())
+    }
+
+    /// A new sink at `volume` times `group_volume` and the master volume.
+    fn start(&mut self, volume: f32, group_volume: f32) -> Result<Voice, AudioError> {
+        let sink = Sink::connect_new(self.mixer()?);
+        sink.set_volume(volume * group_volume * self.master_volume);
+        Ok(Voice {
+            sink,
+            volume,
+            emitter: None,
+        })
+    }
+
+    fn play_source<S>(&mut self, source: S, params: SoundParams) -> Result<SoundHandle, AudioError>
+    where
+        S: Source + Send + 'static,
+    {
+        self.sounds.retain(|(_, voice)| !voice.sink.empty());
+        let voice = self.start(params.volume, self.sfx_volume)?;
+        voice.sink.set_speed(params.pitch);
+        voice.sink.append(source);
+
+        let handle = SoundHandle(self.next_handle);
+        self.next_handle += 1;
+        self.sounds.push((handle, voice));
+        Ok(handle)
+    }
+
+    fn voice_mut(&mut self, handle: SoundHandle) -> Option<&mut Voice> {
+        self.sounds
+            .iter_mut()
+            .find(|(h, _)| *h == handle)
+            .map(|(_, voice)| voice)
+    }
+
+    /// Left and right ear gains for a sound at `position`: panned by which
+    /// side of the listener it is on and attenuated by its distance.
+    fn spatial_gains(&self, position: Vec3) -> [f32; 2] {
+        let offset = position - self.listener.position;
+        let distance = offset.length();
+        let SpatialSettings {
+            reference_distance,
+            max_distance,
+        } = self.spatial;
+        if distance > max_distance {
+            return [0.0; 2];
+        }
+        let falloff = (reference_distance / distance.max(reference_distance)).powi(2);
+
+        // Y is up, as for the 3D camera
+        let right = self.listener.forward.cross(Vec3::Y).normalize_or_zero();
+        let pan = offset.normalize_or_zero().dot(right);
+        // Equal-power panning keeps the loudness steady as a sound crosses
+        // from one side to the other
+        let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        [falloff * angle.cos(), falloff * angle.sin()]
+    }
+
+    /// Recomputes the gains of every positioned sound, after the listener
+    /// or the settings changed.
+    fn update_emitters(&self) {
+        for (_, voice) in &self.sounds {
+            if let Some(emitter) = &voice.emitter {
+                *emitter.gains.lock().unwrap() = self.spatial_gains(emitter.position);
+            }
+        }
+    }
+
+    fn apply_volumes(&self) {
+        if let Some(music) = &self.music {
+            music
+                .sink
+                .set_volume(music.volume * self.music_volume * self.master_volume);
+        }
+        for (_, voice) in &self.sounds {
+            voice
+                .sink
+                .set_volume(voice.volume * self.sfx_volume * self.master_volume);
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, AudioError> {
+    Ok(BufReader::new(File::open(path)?))
+}
+
+/// Plays a WAV or OGG file once at full volume.
+pub fn play_sound(path: impl AsRef<Path>) -> Result<SoundHandle, AudioError> {
+    play_sound_with_volume(path, 1.0)
+}
+
+/// Plays a WAV or OGG file once at `volume` (1.0 is unchanged).
+pub fn play_sound_with_volume(
+    path: impl AsRef<Path>,
+    volume: f32,
+) -> Result<SoundHandle, AudioError> {
+    let source = Decoder::new(open(path.as_ref())?)?;
+    let params = SoundParams {
+        volume,
+        ..Default::default()
+    };
+    AudioManager::get()
+        .lock()
+        .unwrap()
+        .play_source(source, params)
+}
+
+/// Plays an embedded WAV or OGG file once at full volume, e.g. one from
+/// `include_bytes!`.
+pub fn play_sound_bytes(bytes: &'static [u8]) -> Result<SoundHandle, AudioError> {
+    let source = Decoder::new(Cursor::new(bytes))?;
+    AudioManager::get()
+        .lock()
+        .unwrap()
+        .play_source(source, SoundParams::default())
+}
+
+/// Plays an embedded WAV or OGG file once as if it came from `source_pos`,
+/// heard by a listener at `listener_pos` facing `listener_forward`. The
+/// sound is panned towards the ear it is closer to and gets quieter with
+/// distance (see [`set_spatial_settings`]).
+///
+/// Move the sound with [`update_spatial`] and the listener with
+/// [`set_listener`], e.g. once per frame from the camera.
+pub fn play_spatial(
+    bytes: &'static [u8],
+    source_pos: Vec3,
+    listener_pos: Vec3,
+    listener_forward: Vec3,
+) -> Result<SoundHandle, AudioError> {
+    let source = Decoder::new(Cursor::new(bytes))?;
+    let mut audio = AudioManager::get().lock().unwrap();
+    audio.listener = Listener {
+        position: listener_pos,
+        forward: listener_forward,
+    };
+    audio.update_emitters();
+
+    let gains = Arc::new(Mutex::new(audio.spatial_gains(source_pos)));
+    let handle = audio.play_source(panned(source, gains.clone()), SoundParams::default())?;
+    if let Some(voice) = audio.voice_mut(handle) {
+        voice.emitter = Some(Emitter {
+            position: source_pos,
+            gains,
+        });
+    }
+    Ok(handle)
+}
+
+/// Mixes `source` down to mono and plays it in stereo at the ear gains in
+/// `gains`, which are read every few milliseconds.
+fn panned<S>(source: S, gains: Arc<Mutex<[f32; 2]>>) -> impl Source + Send + 'static
+where
+    S: Source + Send + 'static,
+{
+    // ChannelVolume sums the input channels rather than averaging them
+    let scale = 1.0 / f32::from(source.channels().max(1));
+    ChannelVolume::new(source, vec![0.0; 2]).periodic_access(
+        Duration::from_millis(5),
+        move |source| {
+            let [left, right] = *gains.lock().unwrap();
+            source.set_volume(0, left * scale);
+            source.set_volume(1, right * scale);
+        },
+    )
+}
+
+/// Moves a sound started with [`play_spatial`]. Does nothing if it
+/// already finished.
+pub fn update_spatial(handle: SoundHandle, new_pos: Vec3) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    let gains = audio.spatial_gains(new_pos);
+    if let Some(emitter) = audio.voice_mut(handle).and_then(|v| v.emitter.as_mut()) {
+        emitter.position = new_pos;
+        *emitter.gains.lock().unwrap() = gains;
+    }
+}
+
+/// Moves the listener every positioned sound is heard from.
+pub fn set_listener(position: Vec3, forward: Vec3) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    audio.listener = Listener { position, forward };
+    audio.update_emitters();
+}
+
+/// Changes how positioned sounds fall off with distance, including ones
+/// already playing.
+pub fn set_spatial_settings(settings: SpatialSettings) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    audio.spatial = settings;
+    audio.update_emitters();
+}
+
+pub fn spatial_settings() -> SpatialSettings {
+    AudioManager::get().lock().unwrap().spatial
+}
+
+/// Plays a loaded sound effect once, fire-and-forget.
+pub fn play(sound: &Sound, params: SoundParams) -> Result<SoundHandle, AudioError> {
+    let source = sound.decoder()?;
+    AudioManager::get()
+        .lock()
+        .unwrap()
+        .play_source(source, params)
+}
+
+/// Whether the sound effect behind `handle` is still playing.
+pub fn is_playing(handle: SoundHandle) -> bool {
+    AudioManager::get()
+        .lock()
+        .unwrap()
+        .sounds
+        .iter()
+        .any(|(h, voice)| *h == handle && !voice.sink.empty())
+}
+
+/// Stops a sound effect early. Does nothing if it already finished.
+pub fn stop_sound(handle: SoundHandle) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    if let Some(i) = audio.sounds.iter().position(|(h, _)| *h == handle) {
+        audio.sounds.swap_remove(i).1.sink.stop();
+    }
+}
+
+/// Replaces the current music track with `path`, streamed from disk.
+pub fn play_music(path: impl AsRef<Path>, looping: bool) -> Result<(), AudioError> {
+    start_music(open(path.as_ref())?, looping)
+}
+
+/// [`play_music`] with an embedded WAV or OGG file, e.g. one from
+/// `include_bytes!`.
+pub fn play_music_bytes(bytes: &'static [u8], looping: bool) -> Result<(), AudioError> {
+    start_music(Cursor::new(bytes), looping)
+}
+
+fn start_music<R>(reader: R, looping: bool) -> Result<(), AudioError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    let mut audio = AudioManager::get().lock().unwrap();
+    let volume = audio.music_volume;
+    let voice = audio.start(1.0, volume)?;
+    if looping {
+        voice.sink.append(Decoder::new_looped(reader)?);
+    } else {
+        voice.sink.append(Decoder::new(reader)?);
+    }
+    audio.music = Some(voice);
+    Ok(())
+}
+
+pub fn stop_music() {
+    if let Some(music) = AudioManager::get().lock().unwrap().music.take() {
+        music.sink.stop();
+    }
+}
+
+/// Pauses the music track where it is; [`resume_music`] continues it.
+pub fn pause_music() {
+    if let Some(music) = &AudioManager::get().lock().unwrap().music {
+        music.sink.pause();
+    }
+}
+
+pub fn resume_music() {
+    if let Some(music) = &AudioManager::get().lock().unwrap().music {
+        music.sink.play();
+    }
+}
+
+/// Whether a music track is playing, i.e. started, not paused and not
+/// finished.
+pub fn is_music_playing() -> bool {
+    AudioManager::get()
+        .lock()
+        .unwrap()
+        .music
+        .as_ref()
+        .is_some_and(|music| !music.sink.is_paused() && !music.sink.empty())
+}
+
+/// Volume of the music track, kept across [`play_music`] calls.
+pub fn set_music_volume(volume: f32) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    audio.music_volume = volume;
+    audio.apply_volumes();
+}
+
+/// Scales every sound effect, including ones already playing.
+pub fn set_sfx_volume(volume: f32) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    audio.sfx_volume = volume;
+    audio.apply_volumes();
+}
+
+/// Scales every sound and the music track, including ones already playing.
+pub fn set_master_volume(volume: f32) {
+    let mut audio = AudioManager::get().lock().unwrap();
+    audio.master_volume = volume;
+    audio.apply_volumes();
+}
+
+pub fn master_volume() -> f32 {
+    AudioManager::get().lock().unwrap().master_volume
+}
+
+pub fn music_volume() -> f32 {
+    AudioManager::get().lock().unwrap().music_volume
+}
+
+pub fn sfx_volume() -> f32 {
+    AudioManager::get().lock().unwrap().sfx_volume
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    /// Overall loudness of a pair of ear gains, undoing the equal-power pan.
+    fn loudness([left, right]: [f32; 2]) -> f32 {
+        left.hypot(right)
+    }
+
+    // The listener starts at the origin facing -Z, so +X is on its right.
+    // None of this opens the output device.
+
+    #[test]
+    fn sounds_are_panned_towards_the_nearer_ear() {
+        let audio = AudioManager::new();
+        let [left, right] = audio.spatial_gains(Vec3::new(0.5, 0.0, 0.0));
+        assert_near(left, 0.0);
+        assert_near(right, 1.0);
+
+        let [left, right] = audio.spatial_gains(Vec3::new(-0.5, 0.0, 0.0));
+        assert_near(left, 1.0);
+        assert_near(right, 0.0);
+
+        let [left, right] = audio.spatial_gains(Vec3::new(0.0, 0.0, -0.5));
+        assert_near(left, FRAC_1_SQRT_2);
+        assert_near(right, FRAC_1_SQRT_2);
+    }
+
+    #[test]
+    fn sounds_within_the_reference_distance_play_at_full_volume() {
+        let mut audio = AudioManager::new();
+        audio.spatial.reference_distance = 2.0;
+        for position in [Vec3::ZERO, Vec3::new(0.0, 0.0, -1.0), Vec3::new(1.2, 1.6, 0.0)] {
+            assert_near(loudness(audio.spatial_gains(position)), 1.0);
+        }
+    }
+
+    #[test]
+    fn volume_falls_off_with_the_inverse_square_of_the_distance() {
+        let audio = AudioManager::new();
+        assert_near(loudness(audio.spatial_gains(Vec3::new(0.0, 0.0, -2.0))), 0.25);
+        assert_near(loudness(audio.spatial_gains(Vec3::new(4.0, 0.0, 0.0))), 0.0625);
+    }
+
+    #[test]
+    fn sounds_past_the_max_distance_are_silent() {
+        let mut audio = AudioManager::new();
+        audio.spatial = SpatialSettings {
+            reference_distance: 1.0,
+            max_distance: 10.0,
+        };
+        assert!(loudness(audio.spatial_gains(Vec3::new(0.0, 0.0, -9.5))) > 0.0);
+        assert_eq!(audio.spatial_gains(Vec3::new(0.0, 0.0, -10.5)), [0.0; 2]);
+        assert_eq!(audio.spatial_gains(Vec3::new(-30.0, 0.0, 0.0)), [0.0; 2]);
+        assert!(audio.mixer.is_none());
+    }
+}
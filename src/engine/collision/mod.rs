@@ -0,0 +1,4 @@
+// The collision primitives live with the physics code; they're re-exported
+// here under their gameplay-facing names.
+pub use crate::engine::physics::aabb::{Aabb2D as Aabb, Hit};
+pub use crate::engine::physics2d::circle::Circle;
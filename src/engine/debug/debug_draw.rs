@@ -0,0 +1,276 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec2, Vec3};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::engine::math::color::Color;
+use crate::engine::physics::aabb::Aabb2D;
+
+/// Line segments used to approximate debug circles.
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// Arrow head length as a fraction of the arrow's length.
+const ARROW_HEAD_FRACTION: f32 = 0.2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl DebugVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Which transform a debug primitive is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugSpace {
+    /// Through the active [`Camera2D`](crate::engine::renderer::camera::Camera2D).
+    World,
+    /// Window pixels, origin top-left, y down.
+    Screen,
+    /// Through the active [`Camera3D`](crate::engine::renderer::camera3d::Camera3D).
+    World3D,
+}
+
+impl DebugSpace {
+    /// Spaces in the order they're drawn, so screen-space lines end up on
+    /// top.
+    pub(crate) const DRAW_ORDER: [DebugSpace; 3] =
+        [DebugSpace::World3D, DebugSpace::World, DebugSpace::Screen];
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Line-list vertices queued this frame, one list per [`DebugSpace`].
+/// Drawn by the renderer on top of the scene and cleared at the end of
+/// every frame.
+///
+/// Queuing is compiled out of release builds, so calls can be left in
+/// gameplay code; [`DebugDraw::set_enabled`] also turns it off at runtime.
+pub struct DebugDraw {
+    lines: [Vec<DebugVertex>; 3],
+    enabled: bool,
+}
+
+impl DebugDraw {
+    fn new() -> Self {
+        Self {
+            lines: Default::default(),
+            enabled: true,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<DebugDraw> {
+        static INSTANCE: Lazy<Mutex<DebugDraw>> = Lazy::new(|| Mutex::new(DebugDraw::new()));
+
+        &INSTANCE
+    }
+
+    #[cfg(debug_assertions)]
+    fn push_line(&mut self, space: DebugSpace, a: Vec3, b: Vec3, color: Color) {
+        if !self.enabled {
+            return;
+        }
+        let color = color.into();
+        let target = &mut self.lines[space.index()];
+        target.push(DebugVertex {
+            position: a.to_array(),
+            color,
+        });
+        target.push(DebugVertex {
+            position: b.to_array(),
+            color,
+        });
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn push_line(&mut self, _space: DebugSpace, _a: Vec3, _b: Vec3, _color: Color) {}
+
+    /// Turns queuing on or off (on by default). While off every call is
+    /// ignored, e.g. to hide gizmos behind a debug key.
+    pub fn set_enabled(enabled: bool) {
+        Self::get_instance().lock().unwrap().enabled = enabled;
+    }
+
+    pub fn is_enabled() -> bool {
+        Self::get_instance().lock().unwrap().enabled
+    }
+
+    /// Line between two points in `space`.
+    pub fn line_in(space: DebugSpace, a: Vec3, b: Vec3, color: impl Into<Color>) {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .push_line(space, a, b, color.into());
+    }
+
+    /// World-space line between two points.
+    pub fn line(a: Vec3, b: Vec3, color: impl Into<Color>) {
+        Self::line_in(DebugSpace::World, a, b, color);
+    }
+
+    /// Line between two points seen through the 3D camera.
+    pub fn line_3d(a: Vec3, b: Vec3, color: impl Into<Color>) {
+        Self::line_in(DebugSpace::World3D, a, b, color);
+    }
+
+    /// World-space rectangle outline with corners `min` and `max`.
+    pub fn rect(min: Vec2, max: Vec2, color: impl Into<Color>) {
+        debug_rect_in(DebugSpace::World, &Aabb2D::new(min, max), color);
+    }
+
+    /// World-space circle outline in the XY plane, made of `segments`
+    /// lines (at least 3).
+    pub fn circle(center: Vec2, radius: f32, segments: usize, color: impl Into<Color>) {
+        push_circle(DebugSpace::World, center, radius, segments, color.into());
+    }
+
+    /// Axis-aligned cross marking `point` in `space`, `size` across. In
+    /// [`DebugSpace::World3D`] it also has a z arm.
+    pub fn cross_in(space: DebugSpace, point: Vec3, size: f32, color: impl Into<Color>) {
+        let color = color.into();
+        let half = size * 0.5;
+        let axes: &[Vec3] = match space {
+            DebugSpace::World3D => &[Vec3::X, Vec3::Y, Vec3::Z],
+            DebugSpace::World | DebugSpace::Screen => &[Vec3::X, Vec3::Y],
+        };
+        let mut d = Self::get_instance().lock().unwrap();
+        for axis in axes {
+            d.push_line(space, point - *axis * half, point + *axis * half, color);
+        }
+    }
+
+    /// World-space cross marking `point`.
+    pub fn cross(point: Vec2, size: f32, color: impl Into<Color>) {
+        Self::cross_in(DebugSpace::World, point.extend(0.0), size, color);
+    }
+
+    /// Cross marking `point` seen through the 3D camera.
+    pub fn cross_3d(point: Vec3, size: f32, color: impl Into<Color>) {
+        Self::cross_in(DebugSpace::World3D, point, size, color);
+    }
+
+    /// World-space wireframe box; a flat rectangle when `min.z == max.z`.
+    pub fn aabb(min: Vec3, max: Vec3, color: impl Into<Color>) {
+        let color = color.into();
+        let corner = |i: usize| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        let mut d = Self::get_instance().lock().unwrap();
+        // Each edge joins two corners whose indices differ in exactly one bit
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    d.push_line(DebugSpace::World, corner(i), corner(i | bit), color);
+                }
+            }
+        }
+    }
+
+    /// World-space arrow from `origin` to `origin + direction`.
+    pub fn arrow(origin: Vec3, direction: Vec3, color: impl Into<Color>) {
+        let color = color.into();
+        let length = direction.length();
+        if length == 0.0 {
+            return;
+        }
+        let tip = origin + direction;
+        let back = direction / length;
+        // Any vector not parallel to the shaft works for building the head
+        let up = if back.z.abs() < 0.99 { Vec3::Z } else { Vec3::X };
+        let side = back.cross(up).normalize();
+        let head = length * ARROW_HEAD_FRACTION;
+
+        let mut d = Self::get_instance().lock().unwrap();
+        d.push_line(DebugSpace::World, origin, tip, color);
+        d.push_line(DebugSpace::World, tip, tip - (back + side * 0.5) * head, color);
+        d.push_line(DebugSpace::World, tip, tip - (back - side * 0.5) * head, color);
+    }
+
+    /// Drops everything queued so far. Called by the engine at the end of
+    /// every frame, including frames that weren't rendered.
+    pub fn flush() {
+        let mut d = Self::get_instance().lock().unwrap();
+        for lines in &mut d.lines {
+            lines.clear();
+        }
+    }
+
+    /// Swaps the queued vertices with the renderer's (already cleared)
+    /// vectors, indexed by [`DebugSpace::index`], so both sides keep their
+    /// allocations across frames.
+    pub(crate) fn swap_buffers(lines: &mut [Vec<DebugVertex>; 3]) {
+        let mut d = Self::get_instance().lock().unwrap();
+        std::mem::swap(&mut d.lines, lines);
+    }
+}
+
+pub fn debug_line_in(space: DebugSpace, p0: Vec2, p1: Vec2, color: impl Into<Color>) {
+    DebugDraw::get_instance()
+        .lock()
+        .unwrap()
+        .push_line(space, p0.extend(0.0), p1.extend(0.0), color.into());
+}
+
+pub fn debug_rect_in(space: DebugSpace, rect: &Aabb2D, color: impl Into<Color>) {
+    let color = color.into();
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+    let mut d = DebugDraw::get_instance().lock().unwrap();
+    for i in 0..corners.len() {
+        let (a, b) = (corners[i], corners[(i + 1) % corners.len()]);
+        d.push_line(space, a.extend(0.0), b.extend(0.0), color);
+    }
+}
+
+pub fn debug_circle_in(space: DebugSpace, center: Vec2, r: f32, color: impl Into<Color>) {
+    push_circle(space, center, r, CIRCLE_SEGMENTS, color.into());
+}
+
+fn push_circle(space: DebugSpace, center: Vec2, r: f32, segments: usize, color: Color) {
+    let segments = segments.max(3);
+    let point = |i: usize| {
+        let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+        (center + Vec2::from_angle(angle) * r).extend(0.0)
+    };
+    let mut d = DebugDraw::get_instance().lock().unwrap();
+    for i in 0..segments {
+        d.push_line(space, point(i), point(i + 1), color);
+    }
+}
+
+/// Draws a world-space line for this frame.
+pub fn debug_line(p0: Vec2, p1: Vec2, color: impl Into<Color>) {
+    debug_line_in(DebugSpace::World, p0, p1, color);
+}
+
+/// Draws a world-space rectangle outline for this frame.
+pub fn debug_rect(rect: &Aabb2D, color: impl Into<Color>) {
+    debug_rect_in(DebugSpace::World, rect, color);
+}
+
+/// Draws a world-space circle outline for this frame.
+pub fn debug_circle(center: Vec2, r: f32, color: impl Into<Color>) {
+    debug_circle_in(DebugSpace::World, center, r, color);
+}
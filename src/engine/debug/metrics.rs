@@ -0,0 +1,106 @@
+use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Number of recent frames the averages are taken over.
+const WINDOW_FRAMES: usize = 60;
+
+/// Frame timing over the last [`WINDOW_FRAMES`] frames, updated by the
+/// window once per frame.
+pub struct PerformanceMetrics {
+    frame_times_ms: VecDeque<f32>,
+    frame_number: u64,
+    last_frame: Option<Instant>,
+}
+
+impl PerformanceMetrics {
+    fn new() -> Self {
+        Self {
+            frame_times_ms: VecDeque::with_capacity(WINDOW_FRAMES),
+            frame_number: 0,
+            last_frame: None,
+        }
+    }
+
+    pub fn get() -> MutexGuard<'static, PerformanceMetrics> {
+        static INSTANCE: OnceCell<Mutex<PerformanceMetrics>> = OnceCell::new();
+        INSTANCE
+            .get_or_init(|| Mutex::new(PerformanceMetrics::new()))
+            .lock()
+            .expect("Failed to lock the PerformanceMetrics instance")
+    }
+
+    /// Marks the end of a frame, timing it from the previous call.
+    pub(crate) fn record_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            if self.frame_times_ms.len() == WINDOW_FRAMES {
+                self.frame_times_ms.pop_front();
+            }
+            let ms = now.duration_since(last_frame).as_secs_f32() * 1000.0;
+            self.frame_times_ms.push_back(ms);
+        }
+        self.last_frame = Some(now);
+        self.frame_number += 1;
+    }
+
+    /// Stops timing until the next frame, so time spent paused doesn't
+    /// show up as one very long frame.
+    pub(crate) fn pause(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// One-line summary, e.g. for the window title or a debug overlay.
+    pub fn report_string(&self) -> String {
+        format!(
+            "{:.1} FPS | {:.2} ms (min {:.2}, max {:.2}) | frame {}",
+            self.avg_fps(),
+            self.avg_frame_ms(),
+            self.min_frame_ms(),
+            self.max_frame_ms(),
+            self.frame_number
+        )
+    }
+
+    //--Getters--//
+
+    /// Duration of the most recent frame.
+    pub fn frame_time_ms(&self) -> f32 {
+        self.frame_times_ms.back().copied().unwrap_or(0.0)
+    }
+
+    pub fn avg_frame_ms(&self) -> f32 {
+        if self.frame_times_ms.is_empty() {
+            return 0.0;
+        }
+        self.frame_times_ms.iter().sum::<f32>() / self.frame_times_ms.len() as f32
+    }
+
+    /// Frames per second over the window, 0 until two frames have passed.
+    pub fn avg_fps(&self) -> f32 {
+        let avg = self.avg_frame_ms();
+        if avg > 0.0 { 1000.0 / avg } else { 0.0 }
+    }
+
+    pub fn min_frame_ms(&self) -> f32 {
+        self.frame_times_ms
+            .iter()
+            .copied()
+            .reduce(f32::min)
+            .unwrap_or(0.0)
+    }
+
+    pub fn max_frame_ms(&self) -> f32 {
+        self.frame_times_ms
+            .iter()
+            .copied()
+            .reduce(f32::max)
+            .unwrap_or(0.0)
+    }
+
+    /// Frames completed since the window started.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+}
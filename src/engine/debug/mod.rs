@@ -0,0 +1,5 @@
+pub mod debug_draw;
+pub mod metrics;
+pub(crate) mod renderer;
+#[cfg(feature = "egui")]
+pub mod ui;
@@ -0,0 +1,183 @@
+use glam::{Mat4, Vec2};
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
+
+use super::debug_draw::{DebugDraw, DebugSpace, DebugVertex};
+use crate::engine::renderer::camera::{Camera2D, screen_projection};
+use crate::engine::renderer::camera3d::Camera3D;
+use crate::engine::renderer::depth::overlay_depth_state;
+
+const INITIAL_VERTEX_CAPACITY: usize = 1024;
+
+struct SpaceUniform {
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+/// Line-list pipeline that flushes [`DebugDraw`] once per frame.
+pub(crate) struct DebugRenderer {
+    pipeline: RenderPipeline,
+    /// Indexed by [`DebugSpace::index`], like `vertices`.
+    spaces: [SpaceUniform; 3],
+    vertex_buffer: Buffer,
+    vertex_capacity: usize,
+    vertices: [Vec<DebugVertex>; 3],
+}
+
+impl DebugRenderer {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/debug_line.wgsl").into()),
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug Line Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let space_uniform = |label| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            SpaceUniform { buffer, bind_group }
+        };
+        let spaces = [
+            space_uniform("Debug World Uniform"),
+            space_uniform("Debug Screen Uniform"),
+            space_uniform("Debug World 3D Uniform"),
+        ];
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Line Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Line Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[DebugVertex::layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // Drawn over the scene without testing against it
+            depth_stencil: Some(overlay_depth_state()),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            spaces,
+            vertex_buffer: Self::create_vertex_buffer(device, INITIAL_VERTEX_CAPACITY),
+            vertex_capacity: INITIAL_VERTEX_CAPACITY,
+            vertices: Default::default(),
+        }
+    }
+
+    fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Line Vertex Buffer"),
+            size: (capacity * std::mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Takes this frame's primitives and uploads them with each space's
+    /// transform, in draw order.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, screen_size: Vec2) {
+        // Hand last frame's vectors back to be refilled, keeping capacity
+        for vertices in &mut self.vertices {
+            vertices.clear();
+        }
+        DebugDraw::swap_buffers(&mut self.vertices);
+
+        let world_vp = Camera2D::get_instance()
+            .lock()
+            .unwrap()
+            .view_projection(screen_size);
+        let world_3d_vp = Camera3D::get_instance().lock().unwrap().view_projection();
+        for (space, transform) in [
+            (DebugSpace::World, world_vp),
+            (DebugSpace::Screen, screen_projection(screen_size)),
+            (DebugSpace::World3D, world_3d_vp),
+        ] {
+            queue.write_buffer(
+                &self.spaces[space.index()].buffer,
+                0,
+                bytemuck::cast_slice(&transform.to_cols_array()),
+            );
+        }
+
+        let total = self.vertices.iter().map(Vec::len).sum::<usize>();
+        if total > self.vertex_capacity {
+            self.vertex_capacity = total.next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+        let mut offset = 0;
+        for space in DebugSpace::DRAW_ORDER {
+            let vertices = &self.vertices[space.index()];
+            queue.write_buffer(
+                &self.vertex_buffer,
+                (offset * std::mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+                bytemuck::cast_slice(vertices),
+            );
+            offset += vertices.len();
+        }
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        if self.vertices.iter().all(Vec::is_empty) {
+            return;
+        }
+
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        let mut start = 0;
+        for space in DebugSpace::DRAW_ORDER {
+            let count = self.vertices[space.index()].len() as u32;
+            if count > 0 {
+                rpass.set_bind_group(0, &self.spaces[space.index()].bind_group, &[]);
+                rpass.draw(start..start + count, 0..1);
+            }
+            start += count;
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::Instant;
+use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, ModifiersState, PhysicalKey};
+
+use crate::input::key_listener::KeyInput;
+use crate::input::mouse_listener::MouseInput;
+
+/// Builds this frame's debug UI. Call it from an update callback, as often
+/// as you like; everything shown is drawn on top of the frame.
+///
+/// ```ignore
+/// ui_frame(|ctx| {
+///     egui::Window::new("Stats").show(ctx, |ui| {
+///         ui.label(format!("{} entities", world.len()));
+///     });
+/// });
+/// ```
+///
+/// Does nothing before the window has started its first frame.
+pub fn ui_frame(build: impl FnOnce(&egui::Context)) {
+    let ctx = {
+        let ui = DebugUi::get_instance().lock().unwrap();
+        if !ui.frame_open {
+            return;
+        }
+        ui.ctx.clone()
+    };
+    build(&ctx);
+}
+
+/// The egui context and the input gathered for its next frame.
+struct DebugUi {
+    ctx: egui::Context,
+    events: Vec<egui::Event>,
+    modifiers: egui::Modifiers,
+    pointer: egui::Pos2,
+    focused: bool,
+    /// Scale factor of the last event, to turn physical pixels into points.
+    scale_factor: f64,
+    start: Instant,
+    frame_open: bool,
+    /// Output of a frame that was never rendered, e.g. because the surface
+    /// was lost. Its texture updates still have to reach the GPU.
+    pending: Option<egui::FullOutput>,
+}
+
+impl DebugUi {
+    fn new() -> Self {
+        Self {
+            ctx: egui::Context::default(),
+            events: Vec::new(),
+            modifiers: egui::Modifiers::default(),
+            pointer: egui::Pos2::ZERO,
+            focused: true,
+            scale_factor: 1.0,
+            start: Instant::now(),
+            frame_open: false,
+            pending: None,
+        }
+    }
+
+    fn get_instance() -> &'static Mutex<DebugUi> {
+        static INSTANCE: Lazy<Mutex<DebugUi>> = Lazy::new(|| Mutex::new(DebugUi::new()));
+
+        &INSTANCE
+    }
+
+    fn to_points(&self, x: f64, y: f64) -> egui::Pos2 {
+        egui::pos2(
+            (x / self.scale_factor) as f32,
+            (y / self.scale_factor) as f32,
+        )
+    }
+}
+
+/// Translates a window event into egui input for the next frame.
+pub(crate) fn handle_event(event: &WindowEvent, scale_factor: f64) {
+    let mut ui = DebugUi::get_instance().lock().unwrap();
+    ui.scale_factor = scale_factor;
+    let modifiers = ui.modifiers;
+
+    match event {
+        WindowEvent::CursorMoved { position, .. } => {
+            ui.pointer = ui.to_points(position.x, position.y);
+            let pos = ui.pointer;
+            ui.events.push(egui::Event::PointerMoved(pos));
+        }
+        WindowEvent::CursorLeft { .. } => ui.events.push(egui::Event::PointerGone),
+        WindowEvent::MouseInput { state, button, .. } => {
+            let Some(button) = pointer_button(*button) else {
+                return;
+            };
+            let pos = ui.pointer;
+            ui.events.push(egui::Event::PointerButton {
+                pos,
+                button,
+                pressed: *state == ElementState::Pressed,
+                modifiers,
+            });
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (unit, delta) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => {
+                    (egui::MouseWheelUnit::Line, egui::vec2(*x, *y))
+                }
+                MouseScrollDelta::PixelDelta(position) => (
+                    egui::MouseWheelUnit::Point,
+                    ui.to_points(position.x, position.y).to_vec2(),
+                ),
+            };
+            ui.events.push(egui::Event::MouseWheel {
+                unit,
+                delta,
+                modifiers,
+            });
+        }
+        WindowEvent::ModifiersChanged(state) => ui.modifiers = to_egui_modifiers(state.state()),
+        WindowEvent::KeyboardInput { event, .. } => {
+            let pressed = event.state == ElementState::Pressed;
+            if let Some(key) = egui_key(&event.logical_key) {
+                let physical_key = match event.physical_key {
+                    PhysicalKey::Code(code) => {
+                        let name = format!("{code:?}");
+                        egui::Key::from_name(name.strip_prefix("Key").unwrap_or(&name))
+                    }
+                    PhysicalKey::Unidentified(_) => None,
+                };
+                ui.events.push(egui::Event::Key {
+                    key,
+                    physical_key,
+                    pressed,
+                    repeat: event.repeat,
+                    modifiers,
+                });
+            }
+            // Shortcuts like Ctrl+C are keys, not text
+            let text = event.text.as_deref().filter(|text| {
+                !text.chars().any(char::is_control) && !modifiers.ctrl && !modifiers.mac_cmd
+            });
+            if let (true, Some(text)) = (pressed, text) {
+                ui.events.push(egui::Event::Text(text.to_owned()));
+            }
+        }
+        WindowEvent::Focused(focused) => {
+            ui.focused = *focused;
+            ui.events.push(egui::Event::WindowFocused(*focused));
+        }
+        _ => {}
+    }
+}
+
+/// Starts a UI frame with the input gathered since the last one. Called by
+/// the window before the update callbacks run.
+pub(crate) fn begin_frame(size: PhysicalSize<u32>, scale_factor: f64) {
+    let mut ui = DebugUi::get_instance().lock().unwrap();
+    if ui.frame_open {
+        let output = ui.ctx.end_frame();
+        match &mut ui.pending {
+            Some(pending) => pending.append(output),
+            None => ui.pending = Some(output),
+        }
+    }
+
+    let mut input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(
+                (f64::from(size.width) / scale_factor) as f32,
+                (f64::from(size.height) / scale_factor) as f32,
+            ),
+        )),
+        time: Some(ui.start.elapsed().as_secs_f64()),
+        modifiers: ui.modifiers,
+        events: std::mem::take(&mut ui.events),
+        focused: ui.focused,
+        ..Default::default()
+    };
+    // egui picks its pixels-per-point from here, which keeps text sharp
+    // across scale factor changes
+    input
+        .viewports
+        .entry(egui::ViewportId::ROOT)
+        .or_default()
+        .native_pixels_per_point = Some(scale_factor as f32);
+
+    ui.scale_factor = scale_factor;
+    ui.ctx.begin_frame(input);
+    ui.frame_open = true;
+}
+
+/// Ends the current UI frame, including the output of any frame that never
+/// made it to the screen. Afterwards game input is captured for as long as
+/// egui wants the pointer or keyboard.
+fn end_frame() -> Option<(egui::Context, egui::FullOutput)> {
+    let mut ui = DebugUi::get_instance().lock().unwrap();
+    if !ui.frame_open {
+        return None;
+    }
+    ui.frame_open = false;
+    let output = ui.ctx.end_frame();
+    let output = match ui.pending.take() {
+        Some(mut pending) => {
+            pending.append(output);
+            pending
+        }
+        None => output,
+    };
+
+    MouseInput::set_captured(ui.ctx.wants_pointer_input());
+    KeyInput::set_captured(ui.ctx.wants_keyboard_input());
+    Some((ui.ctx.clone(), output))
+}
+
+/// Draws the debug UI over a finished frame.
+pub(crate) struct UiRenderer {
+    renderer: egui_wgpu::Renderer,
+}
+
+impl UiRenderer {
+    pub(crate) fn new(device: &Device, format: TextureFormat) -> Self {
+        Self {
+            renderer: egui_wgpu::Renderer::new(device, format, None, 1),
+        }
+    }
+
+    /// Ends the UI frame and records its pass into `encoder`, loading
+    /// whatever is already in `view`.
+    pub(crate) fn draw(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        size: PhysicalSize<u32>,
+    ) {
+        let Some((ctx, output)) = end_frame() else {
+            return;
+        };
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let screen = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [size.width, size.height],
+            pixels_per_point: output.pixels_per_point,
+        };
+
+        for (id, delta) in &output.textures_delta.set {
+            self.renderer.update_texture(device, queue, *id, delta);
+        }
+        let commands = self
+            .renderer
+            .update_buffers(device, queue, encoder, &primitives, &screen);
+        queue.submit(commands);
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Debug UI Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            self.renderer.render(&mut rpass, &primitives, &screen);
+        }
+
+        for id in &output.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+    }
+}
+
+fn pointer_button(button: MouseButton) -> Option<egui::PointerButton> {
+    match button {
+        MouseButton::Left => Some(egui::PointerButton::Primary),
+        MouseButton::Right => Some(egui::PointerButton::Secondary),
+        MouseButton::Middle => Some(egui::PointerButton::Middle),
+        MouseButton::Back => Some(egui::PointerButton::Extra1),
+        MouseButton::Forward => Some(egui::PointerButton::Extra2),
+        MouseButton::Other(_) => None,
+    }
+}
+
+fn egui_key(key: &Key) -> Option<egui::Key> {
+    match key {
+        Key::Named(named) => egui::Key::from_name(&format!("{named:?}")),
+        Key::Character(text) => egui::Key::from_name(text),
+        _ => None,
+    }
+}
+
+fn to_egui_modifiers(state: ModifiersState) -> egui::Modifiers {
+    let mac = cfg!(target_os = "macos");
+    egui::Modifiers {
+        alt: state.alt_key(),
+        ctrl: state.control_key(),
+        shift: state.shift_key(),
+        mac_cmd: mac && state.super_key(),
+        command: if mac {
+            state.super_key()
+        } else {
+            state.control_key()
+        },
+    }
+}
@@ -0,0 +1,528 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+pub mod query;
+
+use query::Query;
+
+/// Handle to a game object in a [`World`]. The generation tells apart
+/// entities that reuse the slot of a despawned one, so stale handles simply
+/// stop matching.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    //--Getters--//
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Anything stored on an entity. Implemented for every `Send + 'static`
+/// type.
+pub trait Component: Send + 'static {}
+
+impl<T: Send + 'static> Component for T {}
+
+/// Components of one type, indexed by entity slot.
+struct Storage<T> {
+    components: Vec<Option<T>>,
+}
+
+impl<T> Storage<T> {
+    fn get(&self, index: u32) -> Option<&T> {
+        self.components.get(index as usize)?.as_ref()
+    }
+
+    fn get_mut(&mut self, index: u32) -> Option<&mut T> {
+        self.components.get_mut(index as usize)?.as_mut()
+    }
+}
+
+trait AnyStorage: Send {
+    fn clear_slot(&mut self, index: u32);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> AnyStorage for Storage<T> {
+    fn clear_slot(&mut self, index: u32) {
+        if let Some(slot) = self.components.get_mut(index as usize) {
+            *slot = None;
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Entities and their components, one storage per component type. Each
+/// [`Scene`](crate::engine::scene::Scene) owns one.
+///
+/// ```ignore
+/// let mut world = World::new();
+/// let player = world.spawn();
+/// world.insert(player, Position(Vec2::ZERO));
+/// world.insert(player, Velocity(Vec2::X));
+///
+/// for (_, (position, velocity)) in world.query_many::<(&mut Position, &Velocity)>() {
+///     position.0 += velocity.0 * dt;
+/// }
+/// ```
+///
+/// Queries borrow the world, so entities can't be spawned or despawned
+/// while iterating. Record the changes in [`Commands`] instead and
+/// [`World::apply`] them afterwards.
+pub struct World {
+    /// Current generation of every slot, live or free.
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            storages: HashMap::new(),
+        }
+    }
+
+    /// A new entity without components.
+    pub fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            return Entity {
+                index,
+                generation: self.generations[index as usize],
+            };
+        }
+        let index = self.generations.len() as u32;
+        self.generations.push(0);
+        self.alive.push(true);
+        Entity {
+            index,
+            generation: 0,
+        }
+    }
+
+    /// Removes `entity` and all its components. Returns false if it was
+    /// already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.clear_slot(entity.index);
+        }
+        let slot = entity.index as usize;
+        self.alive[slot] = false;
+        self.generations[slot] = self.generations[slot].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let slot = entity.index as usize;
+        self.alive.get(slot).copied().unwrap_or(false)
+            && self.generations[slot] == entity.generation
+    }
+
+    fn storage<T: Component>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: Component>(&mut self) -> &mut Storage<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Storage::<T> {
+                    components: Vec::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut()
+            .expect("storages are keyed by their component type")
+    }
+
+    /// Attaches `component` to `entity`, returning the `T` it replaced.
+    /// Does nothing if the entity is gone.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        let slot = entity.index as usize;
+        let components = &mut self.storage_mut::<T>().components;
+        if components.len() <= slot {
+            components.resize_with(slot + 1, || None);
+        }
+        components[slot].replace(component)
+    }
+
+    /// Detaches and returns `entity`'s `T`.
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()
+            .components
+            .get_mut(entity.index as usize)?
+            .take()
+    }
+
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<&T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage::<T>()?.get(entity.index)
+    }
+
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>().get_mut(entity.index)
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    fn entity_at(&self, index: usize) -> Entity {
+        Entity {
+            index: index as u32,
+            generation: self.generations[index],
+        }
+    }
+
+    /// Every entity with a `T`, in slot order.
+    pub fn query<T: Component>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.components.iter().enumerate())
+            .filter_map(|(i, component)| Some((self.entity_at(i), component.as_ref()?)))
+    }
+
+    /// [`World::query`] with mutable access.
+    pub fn query_mut<T: Component>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        let generations = &self.generations;
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<Storage<T>>())
+            .into_iter()
+            .flat_map(|storage| storage.components.iter_mut().enumerate())
+            .filter_map(move |(i, component)| {
+                let entity = Entity {
+                    index: i as u32,
+                    generation: generations[i],
+                };
+                Some((entity, component.as_mut()?))
+            })
+    }
+
+    /// Every entity with both an `A` and a `B`.
+    pub fn query2<A: Component, B: Component>(&self) -> impl Iterator<Item = (Entity, &A, &B)> {
+        let b = self.storage::<B>();
+        self.query::<A>()
+            .filter_map(move |(entity, a)| Some((entity, a, b?.get(entity.index)?)))
+    }
+
+    /// [`World::query2`] with mutable access to `A`, e.g. moving every
+    /// position by its velocity.
+    ///
+    /// # Panics
+    ///
+    /// If `A` and `B` are the same type.
+    pub fn query2_mut<A: Component, B: Component>(
+        &mut self,
+    ) -> impl Iterator<Item = (Entity, &mut A, &B)> {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "query2_mut needs two different component types"
+        );
+        let generations = &self.generations;
+        let [a, b] = self
+            .storages
+            .get_disjoint_mut([&TypeId::of::<A>(), &TypeId::of::<B>()]);
+        let a = a.and_then(|storage| storage.as_any_mut().downcast_mut::<Storage<A>>());
+        let b = b.and_then(|storage| storage.as_any().downcast_ref::<Storage<B>>());
+        a.into_iter()
+            .flat_map(|storage| storage.components.iter_mut().enumerate())
+            .filter_map(move |(i, component)| {
+                let other = b?.get(i as u32)?;
+                let entity = Entity {
+                    index: i as u32,
+                    generation: generations[i],
+                };
+                Some((entity, component.as_mut()?, other))
+            })
+    }
+
+    /// Every entity with all the components in `Q`, a tuple of `&T` and
+    /// `&mut T`, in slot order. Doesn't allocate per entity.
+    ///
+    /// # Panics
+    ///
+    /// If a component type appears twice in `Q`.
+    pub fn query_many<Q: Query>(&mut self) -> impl Iterator<Item = (Entity, Q::Item<'_>)> {
+        Q::iter(self).into_iter().flatten()
+    }
+
+    /// Runs the changes recorded in `commands`, in order, leaving it empty
+    /// for reuse. Changes to entities despawned in the meantime are
+    /// skipped.
+    pub fn apply(&mut self, commands: &mut Commands) {
+        for command in commands.queue.drain(..) {
+            command(self);
+        }
+    }
+
+    //--Getters--//
+
+    /// Number of live entities.
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Changes to a [`World`] recorded while it's borrowed, typically by a
+/// query, and run later by [`World::apply`].
+///
+/// ```ignore
+/// let mut commands = Commands::new();
+/// for (entity, (health,)) in world.query_many::<(&Health,)>() {
+///     if health.0 <= 0 {
+///         commands.despawn(entity);
+///     }
+/// }
+/// world.apply(&mut commands);
+/// ```
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns an entity and hands it to `build` to add its components.
+    pub fn spawn(&mut self, build: impl FnOnce(&mut World, Entity) + Send + 'static) {
+        self.push(move |world| {
+            let entity = world.spawn();
+            build(world, entity);
+        });
+    }
+
+    pub fn despawn(&mut self, entity: Entity) {
+        self.push(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        self.push(move |world| {
+            world.insert(entity, component);
+        });
+    }
+
+    pub fn remove<T: Component>(&mut self, entity: Entity) {
+        self.push(move |world| {
+            world.remove::<T>(entity);
+        });
+    }
+
+    /// Records any other change.
+    pub fn push(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.queue.push(Box::new(command));
+    }
+
+    //--Getters--//
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32, f32);
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32, f32);
+
+    #[test]
+    fn query_visits_the_intersection_of_two_components() {
+        let mut world = World::new();
+        let moving = world.spawn();
+        let still = world.spawn();
+        let ghost = world.spawn();
+        let also_moving = world.spawn();
+        world.insert(moving, Position(0.0, 0.0));
+        world.insert(moving, Velocity(1.0, 2.0));
+        world.insert(still, Position(5.0, 5.0));
+        world.insert(ghost, Velocity(3.0, 3.0));
+        world.insert(also_moving, Position(1.0, 1.0));
+        world.insert(also_moving, Velocity(-1.0, 0.0));
+
+        let both: Vec<_> = world
+            .query2::<Position, Velocity>()
+            .map(|(entity, _, _)| entity)
+            .collect();
+        assert_eq!(both, vec![moving, also_moving]);
+        assert_eq!(world.query::<Position>().count(), 3);
+        assert_eq!(world.query::<Velocity>().count(), 3);
+
+        for (_, position, velocity) in world.query2_mut::<Position, Velocity>() {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        }
+        assert_eq!(world.get::<Position>(moving), Some(&Position(1.0, 2.0)));
+        assert_eq!(
+            world.get::<Position>(also_moving),
+            Some(&Position(0.0, 1.0))
+        );
+        assert_eq!(world.get::<Position>(still), Some(&Position(5.0, 5.0)));
+    }
+
+    #[test]
+    fn insert_get_and_remove() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        assert_eq!(world.len(), 1);
+        assert!(!world.has::<Position>(entity));
+
+        assert_eq!(world.insert(entity, Position(1.0, 1.0)), None);
+        assert_eq!(
+            world.insert(entity, Position(2.0, 2.0)),
+            Some(Position(1.0, 1.0))
+        );
+        world.get_mut::<Position>(entity).unwrap().0 = 3.0;
+        assert_eq!(world.get::<Position>(entity), Some(&Position(3.0, 2.0)));
+
+        assert_eq!(world.remove::<Position>(entity), Some(Position(3.0, 2.0)));
+        assert!(!world.has::<Position>(entity));
+        assert_eq!(world.query2::<Position, Velocity>().count(), 0);
+    }
+
+    #[test]
+    fn despawned_handles_go_stale() {
+        let mut world = World::new();
+        let first = world.spawn();
+        world.insert(first, Position(1.0, 1.0));
+        assert!(world.despawn(first));
+        assert!(!world.despawn(first));
+
+        // The slot is reused under a new generation
+        let second = world.spawn();
+        assert_eq!(second.index(), first.index());
+        assert_ne!(second.generation(), first.generation());
+        assert!(!world.is_alive(first) && world.is_alive(second));
+        assert!(!world.has::<Position>(second));
+        assert_eq!(world.insert(first, Position(9.0, 9.0)), None);
+        assert_eq!(world.query::<Position>().count(), 0);
+    }
+
+    #[test]
+    fn tuple_queries_mix_shared_and_mutable_components() {
+        let mut world = World::new();
+        let moving = world.spawn();
+        let still = world.spawn();
+        world.insert(moving, Position(0.0, 0.0));
+        world.insert(moving, Velocity(1.0, 2.0));
+        world.insert(still, Position(5.0, 5.0));
+
+        for (_, (position, velocity)) in world.query_many::<(&mut Position, &Velocity)>() {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        }
+        assert_eq!(world.get::<Position>(moving), Some(&Position(1.0, 2.0)));
+        assert_eq!(world.get::<Position>(still), Some(&Position(5.0, 5.0)));
+
+        let positions: Vec<_> = world
+            .query_many::<(&Position,)>()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(positions, vec![moving, still]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_type_named_twice_in_a_query_panics() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, Position(0.0, 0.0));
+        let _ = world.query_many::<(&mut Position, &Position)>().count();
+    }
+
+    #[test]
+    fn commands_recorded_during_a_query_apply_afterwards() {
+        let mut world = World::new();
+        let doomed = world.spawn();
+        let survivor = world.spawn();
+        world.insert(doomed, Position(-1.0, 0.0));
+        world.insert(survivor, Position(1.0, 0.0));
+
+        let mut commands = Commands::new();
+        for (entity, (position,)) in world.query_many::<(&Position,)>() {
+            if position.0 < 0.0 {
+                commands.despawn(entity);
+                // Aimed at an entity that is gone by then, so skipped
+                commands.insert(entity, Velocity(0.0, 0.0));
+            } else {
+                commands.insert(entity, Velocity(0.0, 1.0));
+            }
+        }
+        commands.spawn(|world, entity| {
+            world.insert(entity, Position(9.0, 9.0));
+        });
+        assert_eq!(commands.len(), 4);
+        assert_eq!(world.len(), 2);
+
+        world.apply(&mut commands);
+        assert!(commands.is_empty());
+        assert!(!world.is_alive(doomed));
+        assert_eq!(world.get::<Velocity>(survivor), Some(&Velocity(0.0, 1.0)));
+        assert_eq!(world.query::<Velocity>().count(), 1);
+        assert_eq!(world.query::<Position>().count(), 2);
+    }
+}
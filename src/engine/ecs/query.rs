@@ -0,0 +1,102 @@
+use std::any::{Any, TypeId};
+use std::iter::Map;
+use std::slice;
+
+use super::{Component, Entity, Storage, World};
+
+/// One component in a [`World::query_many`] tuple: `&T` to read it or
+/// `&mut T` to change it.
+pub trait QueryTerm: 'static {
+    type Item<'w>;
+    type Slots<'w>: Iterator<Item = Option<Self::Item<'w>>>;
+
+    fn type_id() -> TypeId;
+
+    /// The component of every slot of `storage`, a `Storage<T>`.
+    fn slots(storage: &mut dyn Any) -> Self::Slots<'_>;
+}
+
+fn downcast<T: Component>(storage: &mut dyn Any) -> &mut Storage<T> {
+    storage
+        .downcast_mut()
+        .expect("storages are keyed by their component type")
+}
+
+impl<T: Component> QueryTerm for &'static T {
+    type Item<'w> = &'w T;
+    type Slots<'w> = Map<slice::Iter<'w, Option<T>>, fn(&'w Option<T>) -> Option<&'w T>>;
+
+    fn type_id() -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn slots(storage: &mut dyn Any) -> Self::Slots<'_> {
+        downcast::<T>(storage).components.iter().map(Option::as_ref)
+    }
+}
+
+impl<T: Component> QueryTerm for &'static mut T {
+    type Item<'w> = &'w mut T;
+    type Slots<'w> = Map<slice::IterMut<'w, Option<T>>, fn(&'w mut Option<T>) -> Option<&'w mut T>>;
+
+    fn type_id() -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn slots(storage: &mut dyn Any) -> Self::Slots<'_> {
+        downcast::<T>(storage)
+            .components
+            .iter_mut()
+            .map(Option::as_mut)
+    }
+}
+
+type QueryIter<'w, Item> = Box<dyn Iterator<Item = (Entity, Item)> + 'w>;
+
+/// A tuple of up to four [`QueryTerm`]s, e.g. `(&Transform, &mut Velocity)`.
+pub trait Query {
+    type Item<'w>;
+
+    /// Every entity with all the components, or `None` if a component type
+    /// was never inserted.
+    fn iter(world: &mut World) -> Option<QueryIter<'_, Self::Item<'_>>>;
+}
+
+macro_rules! impl_query {
+    ($($term:ident $slots:ident),+) => {
+        impl<$($term: QueryTerm),+> Query for ($($term,)+) {
+            type Item<'w> = ($($term::Item<'w>,)+);
+
+            fn iter(world: &mut World) -> Option<QueryIter<'_, Self::Item<'_>>> {
+                let World {
+                    generations,
+                    storages,
+                    ..
+                } = world;
+                // Panics if a type repeats, which would alias a `&mut`
+                let [$($slots),+] = storages.get_disjoint_mut([$(&$term::type_id()),+]);
+                let ($(mut $slots,)+) = ($($term::slots($slots?.as_any_mut()),)+);
+
+                // Storages are walked in step, ending with the shortest
+                let mut index = 0;
+                let entities = std::iter::from_fn(move || {
+                    let ($($slots,)+) = ($($slots.next()?,)+);
+                    let entity = Entity {
+                        index: index as u32,
+                        generation: generations[index],
+                    };
+                    index += 1;
+                    Some((entity, $($slots,)+))
+                });
+                Some(Box::new(entities.filter_map(|(entity, $($slots,)+)| {
+                    Some((entity, ($($slots?,)+)))
+                })))
+            }
+        }
+    };
+}
+
+impl_query!(A a);
+impl_query!(A a, B b);
+impl_query!(A a, B b, C c);
+impl_query!(A a, B b, C c, D d);
@@ -0,0 +1,183 @@
+use once_cell::sync::Lazy;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::engine::ecs::Entity;
+use crate::input::input_device::InputDevice;
+
+/// The window's drawable area changed size, in device pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+/// The window gained (`true`) or lost keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FocusChanged(pub bool);
+
+/// A file was dropped onto the window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDropped(pub PathBuf);
+
+/// The user asked to close the window. Whether the engine exits depends on
+/// the [`ClosePolicy`](crate::engine::window::ClosePolicy) and any
+/// [`Window::on_close_requested`](crate::engine::window::Window::on_close_requested)
+/// handler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CloseRequested;
+
+/// The player switched between keyboard and mouse and a gamepad; see
+/// [`last_input_device`](crate::input::input_device::last_input_device).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputDeviceChanged(pub InputDevice);
+
+/// Two entities' colliders started touching; see
+/// [`Physics2D`](crate::engine::physics2d::Physics2D). The smaller entity
+/// comes first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionBegan(pub Entity, pub Entity);
+
+/// Two entities' colliders stopped touching, or one of them lost its
+/// collider or was despawned. The smaller entity comes first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionEnded(pub Entity, pub Entity);
+
+/// Events of one type: those sent since the last frame started, and those
+/// readable during the current frame.
+struct Channel<T> {
+    pending: Vec<T>,
+    current: Vec<T>,
+    handlers: Vec<fn(&T)>,
+}
+
+trait AnyChannel: Send {
+    /// Makes the pending events current. Returns a call to the handlers
+    /// with them, to be run once the bus is unlocked.
+    fn advance(&mut self) -> Option<Box<dyn FnOnce()>>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Clone + Send + 'static> AnyChannel for Channel<T> {
+    fn advance(&mut self) -> Option<Box<dyn FnOnce()>> {
+        self.current.clear();
+        std::mem::swap(&mut self.pending, &mut self.current);
+        if self.current.is_empty() || self.handlers.is_empty() {
+            return None;
+        }
+
+        let events = self.current.clone();
+        let handlers = self.handlers.clone();
+        Some(Box::new(move || {
+            for event in &events {
+                for handler in &handlers {
+                    handler(event);
+                }
+            }
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Typed publish/subscribe between the engine and game code.
+///
+/// Events sent during a frame become readable at the start of the next one,
+/// when subscribed handlers run (before the update callbacks). They stay
+/// readable through [`EventBus::read`] for that whole frame, so any number
+/// of systems can poll them. Any `Clone + Send + 'static` type can be an
+/// event; the engine sends [`WindowResized`], [`FocusChanged`],
+/// [`FileDropped`], [`CloseRequested`], [`InputDeviceChanged`],
+/// [`CollisionBegan`] and [`CollisionEnded`].
+pub struct EventBus {
+    channels: HashMap<TypeId, Box<dyn AnyChannel>>,
+}
+
+impl EventBus {
+    fn new() -> Self {
+        Self {
+            channels: HashMap::new(),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<EventBus> {
+        static INSTANCE: Lazy<Mutex<EventBus>> = Lazy::new(|| Mutex::new(EventBus::new()));
+
+        &INSTANCE
+    }
+
+    fn channel<T: Clone + Send + 'static>(&mut self) -> &mut Channel<T> {
+        self.channels
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Channel::<T> {
+                    pending: Vec::new(),
+                    current: Vec::new(),
+                    handlers: Vec::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut()
+            .expect("channels are keyed by their event type")
+    }
+
+    /// Publishes `event` for the next frame.
+    pub fn send<T: Clone + Send + 'static>(event: T) {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .channel::<T>()
+            .pending
+            .push(event);
+    }
+
+    /// Calls `handler` for every `T` at the start of the frame it becomes
+    /// readable. Handlers may send events; those arrive a frame later.
+    pub fn subscribe<T: Clone + Send + 'static>(handler: fn(&T)) {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .channel::<T>()
+            .handlers
+            .push(handler);
+    }
+
+    /// Starts a new frame: events sent since the last call become current
+    /// and their handlers run.
+    pub(crate) fn update() {
+        let dispatches: Vec<_> = Self::get_instance()
+            .lock()
+            .unwrap()
+            .channels
+            .values_mut()
+            .filter_map(|channel| channel.advance())
+            .collect();
+        for dispatch in dispatches {
+            dispatch();
+        }
+    }
+
+    //--Getters--//
+
+    /// This frame's events of type `T`, oldest first.
+    pub fn read<T: Clone + Send + 'static>() -> Vec<T> {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .channels
+            .get(&TypeId::of::<T>())
+            .and_then(|channel| channel.as_any().downcast_ref::<Channel<T>>())
+            .map(|channel| channel.current.clone())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,75 @@
+// Lighting data as uploaded by the engine, plus Blinn-Phong helpers.
+// Bind it in your own shader with, e.g.:
+//     @group(1) @binding(0) var<uniform> lighting: Lighting;
+
+const MAX_POINT_LIGHTS: u32 = 8u;
+
+struct DirectionalLight {
+    // Direction the light travels in, not necessarily normalized
+    direction: vec3<f32>,
+    intensity: f32,
+    color: vec3<f32>,
+    _padding: f32,
+};
+
+struct PointLight {
+    position: vec3<f32>,
+    // No light reaches past this distance
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+};
+
+struct Lighting {
+    directional: DirectionalLight,
+    points: array<PointLight, 8>,
+    ambient: vec3<f32>,
+    point_count: u32,
+};
+
+// Diffuse plus specular from one light. `to_light` and `normal` must be
+// normalized.
+fn phong_term(
+    to_light: vec3<f32>,
+    radiance: vec3<f32>,
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    shininess: f32,
+) -> vec3<f32> {
+    let diffuse = max(dot(normal, to_light), 0.0);
+    let half_dir = normalize(to_light + view_dir);
+    let specular = select(0.0, pow(max(dot(normal, half_dir), 0.0), shininess), diffuse > 0.0);
+    return radiance * (diffuse + specular);
+}
+
+// Total light reaching a surface at `position` facing `normal`, seen from
+// `view_dir` (pointing from the surface to the eye). Multiply by the surface
+// color.
+fn phong_lighting(
+    lighting: Lighting,
+    position: vec3<f32>,
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    shininess: f32,
+) -> vec3<f32> {
+    let n = normalize(normal);
+    let v = normalize(view_dir);
+    let sun = lighting.directional;
+    var light = lighting.ambient
+        + phong_term(-normalize(sun.direction), sun.color * sun.intensity, n, v, shininess);
+
+    // Copied so the array can be indexed with a loop variable
+    var points = lighting.points;
+    for (var i = 0u; i < min(lighting.point_count, MAX_POINT_LIGHTS); i++) {
+        let point = points[i];
+        let offset = point.position - position;
+        let distance = length(offset);
+        if distance >= point.range || distance == 0.0 {
+            continue;
+        }
+        let falloff = 1.0 - distance / point.range;
+        let radiance = point.color * point.intensity * falloff * falloff;
+        light += phong_term(offset / distance, radiance, n, v, shininess);
+    }
+    return light;
+}
@@ -0,0 +1,231 @@
+use bytemuck::{Pod, Zeroable};
+use once_cell::sync::{Lazy, OnceCell};
+use std::sync::Mutex;
+use wgpu::{BindGroup, BindGroupLayout, Device, Queue};
+
+use crate::engine::renderer::bind_group::BindGroupBuilder;
+use crate::engine::renderer::uniform::UniformBuffer;
+
+/// Most point lights a [`LightingUniform`] holds.
+pub const MAX_POINT_LIGHTS: usize = 8;
+
+/// WGSL for the [`LightingUniform`] layout (`Lighting`, `DirectionalLight`,
+/// `PointLight`) and a `phong_lighting` helper. Prepend it to your own
+/// shader source, e.g. `format!("{LIGHTING_WGSL}\n{my_shader}")`, and bind
+/// [`LightingBuffer::bind_group`].
+pub const LIGHTING_WGSL: &str = include_str!("lighting.wgsl");
+
+/// A light infinitely far away, like the sun.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// Direction the light travels in; needn't be normalized.
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+impl Default for DirectionalLight {
+    /// White key light pointing down and away from the default camera.
+    fn default() -> Self {
+        Self {
+            direction: [-0.4, -1.0, -0.6],
+            color: [1.0; 3],
+            intensity: 0.75,
+        }
+    }
+}
+
+/// A light shining in every direction from `position`, fading out to
+/// nothing at `range`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct GpuDirectionalLight {
+    direction: [f32; 3],
+    intensity: f32,
+    color: [f32; 3],
+    _padding: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct GpuPointLight {
+    position: [f32; 3],
+    range: f32,
+    color: [f32; 3],
+    intensity: f32,
+}
+
+/// The scene's lights, laid out to match the WGSL `Lighting` struct in
+/// [`LIGHTING_WGSL`]. The engine uploads the instance behind
+/// [`LightingUniform::get_instance`] every frame; built-in meshes are lit
+/// by it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct LightingUniform {
+    directional: GpuDirectionalLight,
+    points: [GpuPointLight; MAX_POINT_LIGHTS],
+    ambient: [f32; 3],
+    point_count: u32,
+}
+
+impl LightingUniform {
+    /// The default directional light, a dim white ambient and no point
+    /// lights.
+    pub fn new() -> Self {
+        let mut lighting = Self::zeroed();
+        lighting.set_directional_light(DirectionalLight::default());
+        lighting.set_ambient([0.25; 3]);
+        lighting
+    }
+
+    pub fn get_instance() -> &'static Mutex<LightingUniform> {
+        static INSTANCE: Lazy<Mutex<LightingUniform>> =
+            Lazy::new(|| Mutex::new(LightingUniform::new()));
+
+        &INSTANCE
+    }
+
+    /// An intensity of 0 turns the directional light off. Without it and
+    /// without point lights, lit surfaces get only the ambient light.
+    pub fn set_directional_light(&mut self, light: DirectionalLight) {
+        self.directional = GpuDirectionalLight {
+            direction: light.direction,
+            intensity: light.intensity,
+            color: light.color,
+            _padding: 0.0,
+        };
+    }
+
+    /// Light added to every surface regardless of direction.
+    pub fn set_ambient(&mut self, color: [f32; 3]) {
+        self.ambient = color;
+    }
+
+    /// Adds `light` and returns its index, or `None` if
+    /// [`MAX_POINT_LIGHTS`] are already in use.
+    pub fn add_point_light(&mut self, light: PointLight) -> Option<usize> {
+        let index = self.point_count as usize;
+        if index >= MAX_POINT_LIGHTS {
+            return None;
+        }
+        self.point_count += 1;
+        self.set_point_light(index, light);
+        Some(index)
+    }
+
+    /// Replaces the point light at `index`. Returns false if there is none.
+    pub fn set_point_light(&mut self, index: usize, light: PointLight) -> bool {
+        if index >= self.point_count as usize {
+            return false;
+        }
+        self.points[index] = GpuPointLight {
+            position: light.position,
+            range: light.range.max(0.0),
+            color: light.color,
+            intensity: light.intensity,
+        };
+        true
+    }
+
+    pub fn clear_point_lights(&mut self) {
+        self.points = [GpuPointLight::zeroed(); MAX_POINT_LIGHTS];
+        self.point_count = 0;
+    }
+
+    /// The uniform's bytes, ready for `Queue::write_buffer`.
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+
+    //--Getters--//
+
+    pub fn directional_light(&self) -> DirectionalLight {
+        DirectionalLight {
+            direction: self.directional.direction,
+            color: self.directional.color,
+            intensity: self.directional.intensity,
+        }
+    }
+
+    pub fn point_light(&self, index: usize) -> Option<PointLight> {
+        self.points[..self.point_count as usize]
+            .get(index)
+            .map(|light| PointLight {
+                position: light.position,
+                color: light.color,
+                intensity: light.intensity,
+                range: light.range,
+            })
+    }
+
+    pub fn point_light_count(&self) -> usize {
+        self.point_count as usize
+    }
+
+    pub fn ambient(&self) -> [f32; 3] {
+        self.ambient
+    }
+}
+
+impl Default for LightingUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// GPU copy of the [`LightingUniform`] instance, rewritten every frame.
+/// Pipelines that want lighting put [`LightingBuffer::layout`] in their
+/// pipeline layout and bind [`LightingBuffer::bind_group`].
+pub struct LightingBuffer {
+    buffer: UniformBuffer<LightingUniform>,
+    layout: BindGroupLayout,
+    bind_group: BindGroup,
+}
+
+static LIGHTING_BUFFER: OnceCell<LightingBuffer> = OnceCell::new();
+
+impl LightingBuffer {
+    /// `None` until the window has finished initializing the GPU.
+    pub fn get() -> Option<&'static LightingBuffer> {
+        LIGHTING_BUFFER.get()
+    }
+
+    pub(crate) fn init(device: &Device) -> &'static LightingBuffer {
+        LIGHTING_BUFFER.get_or_init(|| Self::new(device))
+    }
+
+    fn new(device: &Device) -> Self {
+        let buffer = UniformBuffer::new(device, Some("Lighting Buffer"), &LightingUniform::new());
+        let (layout, bind_group) = BindGroupBuilder::new(Some("Lighting"))
+            .uniform(device, &buffer, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+
+        Self {
+            buffer,
+            layout,
+            bind_group,
+        }
+    }
+
+    pub(crate) fn write(&self, queue: &Queue, lighting: &LightingUniform) {
+        queue.write_buffer(self.buffer.buffer(), 0, lighting.as_bytes());
+    }
+
+    //--Getters--//
+
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
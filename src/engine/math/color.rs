@@ -0,0 +1,262 @@
+use glam::Vec4;
+
+/// An RGBA color in linear space, the space shaders and blending work in.
+/// Colors picked in an image editor or given as hex codes are sRGB; build
+/// those with [`Color::from_srgb`], [`Color::from_hex`] or
+/// [`Color::from_hsl`].
+///
+/// The engine takes colors as `impl Into<Color>`, so a plain `[f32; 4]`
+/// works too and is read as linear.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::from_rgb(1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::from_rgb(0.0, 0.0, 0.0);
+    pub const TRANSPARENT: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.0);
+    pub const RED: Color = Color::from_rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Color = Color::from_rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Color = Color::from_rgb(0.0, 0.0, 1.0);
+
+    /// Opaque color from linear components.
+    pub const fn from_rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::from_rgba(r, g, b, 1.0)
+    }
+
+    /// Color from linear components.
+    pub const fn from_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Opaque color from sRGB components in `0..=1`.
+    pub fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::from_srgba(r, g, b, 1.0)
+    }
+
+    /// Color from sRGB components in `0..=1`. Alpha is linear either way.
+    pub fn from_srgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::from_rgba(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a)
+    }
+
+    /// Color from 8-bit sRGB components, as in most image formats.
+    pub fn from_srgba_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        let unit = |c: u8| f32::from(c) / 255.0;
+        Self::from_srgba(unit(r), unit(g), unit(b), unit(a))
+    }
+
+    /// Color from a `0xRRGGBB` hex code, which is sRGB.
+    pub fn from_hex(rgb: u32) -> Self {
+        let [_, r, g, b] = rgb.to_be_bytes();
+        Self::from_srgba_u8(r, g, b, 255)
+    }
+
+    /// Opaque color from hue in degrees, saturation and lightness in
+    /// `0..=1`. Like hex codes, HSL describes sRGB, so this is converted to
+    /// linear.
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = l - chroma / 2.0;
+        Self::from_srgb(r + m, g + m, b + m)
+    }
+
+    pub fn with_alpha(mut self, a: f32) -> Self {
+        self.a = a;
+        self
+    }
+
+    /// The components converted to sRGB, e.g. to show in a color picker.
+    pub fn to_srgba(self) -> [f32; 4] {
+        [
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        ]
+    }
+
+    /// Hue in degrees `0..360`, saturation and lightness in `0..=1`, of
+    /// the sRGB encoded color. Alpha is dropped.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_srgba();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let l = (max + min) / 2.0;
+        if chroma <= f32::EPSILON {
+            return (0.0, 0.0, l);
+        }
+
+        let s = chroma / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            (g - b) / chroma
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        ((h * 60.0).rem_euclid(360.0), s, l)
+    }
+
+    /// Linear interpolation, `t` of the way from `self` to `other`.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        Vec4::from(self).lerp(Vec4::from(other), t).into()
+    }
+}
+
+/// One sRGB encoded component in `0..=1` decoded to linear.
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// One linear component in `0..=1` encoded as sRGB.
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from([r, g, b, a]: [f32; 4]) -> Self {
+        Self::from_rgba(r, g, b, a)
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(c: Color) -> Self {
+        [c.r, c.g, c.b, c.a]
+    }
+}
+
+impl From<Vec4> for Color {
+    fn from(v: Vec4) -> Self {
+        Self::from_rgba(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Color> for Vec4 {
+    fn from(c: Color) -> Self {
+        Vec4::new(c.r, c.g, c.b, c.a)
+    }
+}
+
+/// wgpu colors are linear too, e.g. for clearing an sRGB surface.
+impl From<Color> for wgpu::Color {
+    fn from(c: Color) -> Self {
+        wgpu::Color {
+            r: f64::from(c.r),
+            g: f64::from(c.g),
+            b: f64::from(c.b),
+            a: f64::from(c.a),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn srgb_round_trips_through_linear() {
+        for c in [0.0, 0.02, 0.2, 0.5, 0.8, 1.0] {
+            assert_near(linear_to_srgb(srgb_to_linear(c)), c);
+        }
+        // Mid grey in sRGB is much darker in linear light
+        assert_near(srgb_to_linear(0.5), 0.21404);
+        let [r, g, b, a] = Color::from_srgb(0.5, 0.5, 0.5).to_srgba();
+        assert_near(r, 0.5);
+        assert_near(g, 0.5);
+        assert_near(b, 0.5);
+        assert_eq!(a, 1.0);
+    }
+
+    #[test]
+    fn hex_codes_are_srgb() {
+        assert_eq!(Color::from_hex(0xFF0000), Color::RED);
+        assert_eq!(Color::from_hex(0x000000), Color::BLACK);
+        let orange = Color::from_hex(0xFF8000);
+        assert_near(orange.g, srgb_to_linear(128.0 / 255.0));
+        assert_eq!(
+            Color::from_srgba_u8(255, 255, 255, 0),
+            Color::WHITE.with_alpha(0.0)
+        );
+    }
+
+    #[test]
+    fn lerp_and_conversions_keep_linear_values() {
+        let mid = Color::BLACK.lerp(Color::WHITE, 0.25);
+        assert_eq!(mid, Color::from_rgb(0.25, 0.25, 0.25));
+
+        let c = Color::from_rgba(0.1, 0.2, 0.3, 0.4);
+        assert_eq!(Color::from(<[f32; 4]>::from(c)), c);
+        assert_eq!(Color::from(Vec4::from(c)), c);
+        let wgpu = wgpu::Color::from(c);
+        assert_eq!(wgpu.b, f64::from(0.3f32));
+        assert_eq!(wgpu.a, f64::from(0.4f32));
+    }
+
+    fn assert_hsl_near((h, s, l): (f32, f32, f32), expected: (f32, f32, f32)) {
+        assert_near(h, expected.0);
+        assert_near(s, expected.1);
+        assert_near(l, expected.2);
+    }
+
+    #[test]
+    fn hsl_primaries_match_the_named_colors() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::RED);
+        assert_eq!(Color::from_hsl(120.0, 1.0, 0.5), Color::GREEN);
+        assert_eq!(Color::from_hsl(240.0, 1.0, 0.5), Color::BLUE);
+        assert_eq!(Color::from_hsl(0.0, 0.0, 1.0), Color::WHITE);
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.0), Color::BLACK);
+        // Hue wraps around
+        assert_eq!(Color::from_hsl(360.0, 1.0, 0.5), Color::RED);
+        assert_eq!(Color::from_hsl(-120.0, 1.0, 0.5), Color::BLUE);
+    }
+
+    #[test]
+    fn to_hsl_reads_the_srgb_color() {
+        assert_hsl_near(Color::RED.to_hsl(), (0.0, 1.0, 0.5));
+        assert_hsl_near(Color::BLUE.to_hsl(), (240.0, 1.0, 0.5));
+        // Magenta sits just below red, not at a negative hue
+        assert_hsl_near(Color::from_hex(0xFF00FF).to_hsl(), (300.0, 1.0, 0.5));
+        // Greys have no hue or saturation, and sRGB mid grey is lightness 0.5
+        assert_hsl_near(Color::from_srgb(0.5, 0.5, 0.5).to_hsl(), (0.0, 0.0, 0.5));
+        assert_hsl_near(Color::TRANSPARENT.to_hsl(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn hsl_round_trips() {
+        for hsl in [(30.0, 1.0, 0.5), (200.0, 0.4, 0.3), (330.0, 0.8, 0.9)] {
+            let color = Color::from_hsl(hsl.0, hsl.1, hsl.2);
+            assert_hsl_near(color.to_hsl(), hsl);
+        }
+    }
+}
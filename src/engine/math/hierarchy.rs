@@ -0,0 +1,446 @@
+use glam::Mat4;
+use std::fmt;
+
+use super::transform::Transform3D;
+
+/// Handle to a node in a [`TransformHierarchy`]. Like an ECS entity, the
+/// generation stops handles to removed nodes from matching a node that
+/// later reuses the slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HierarchyError {
+    /// The node was removed, or belongs to another hierarchy.
+    UnknownNode(NodeId),
+    /// Parenting `child` to `parent` would make `child` its own ancestor.
+    Cycle { child: NodeId, parent: NodeId },
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyError::UnknownNode(node) => write!(f, "node {node:?} doesn't exist"),
+            HierarchyError::Cycle { child, parent } => write!(
+                f,
+                "can't parent {child:?} to {parent:?}: {parent:?} is {child:?} or one of its descendants"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+struct Node {
+    local: Transform3D,
+    /// As of the last [`TransformHierarchy::propagate`].
+    world: Mat4,
+    parent: Option<u32>,
+    children: Vec<u32>,
+    /// The local transform or parent changed since the last propagation.
+    dirty: bool,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+/// Transforms arranged in a tree, where each node's world matrix is its
+/// parent's world matrix times its own local transform, so e.g. a weapon
+/// attached to a hand follows it.
+///
+/// Changing a local transform or a parent marks the node dirty;
+/// [`TransformHierarchy::propagate`], run once per frame before drawing,
+/// recomputes the world matrices of the dirty subtrees only. 2D objects
+/// use it through `Transform3D::from(transform_2d)` and
+/// [`Transform3D::to_2d`].
+///
+/// ```ignore
+/// let body = hierarchy.add(Transform3D::default());
+/// let hand = hierarchy.add(Transform3D::from_translation(Vec3::X));
+/// hierarchy.set_parent(hand, body)?;
+/// hierarchy.propagate();
+/// let hand_world = hierarchy.world_matrix(hand);
+/// ```
+#[derive(Default)]
+pub struct TransformHierarchy {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    /// Nodes marked dirty since the last propagation, possibly removed or
+    /// listed twice.
+    dirty: Vec<u32>,
+}
+
+impl TransformHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new root node.
+    pub fn add(&mut self, local: Transform3D) -> NodeId {
+        let node = Node {
+            local,
+            world: local.local_matrix(),
+            parent: None,
+            children: Vec::new(),
+            dirty: false,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].node = Some(node);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+        NodeId {
+            index,
+            generation: self.slots[index as usize].generation,
+        }
+    }
+
+    /// Removes `node` and all its descendants.
+    pub fn remove(&mut self, node: NodeId) -> Result<(), HierarchyError> {
+        self.index(node)?;
+        self.unlink(node.index);
+        let mut stack = vec![node.index];
+        while let Some(index) = stack.pop() {
+            let slot = &mut self.slots[index as usize];
+            if let Some(removed) = slot.node.take() {
+                stack.extend(removed.children);
+            }
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(index);
+        }
+        Ok(())
+    }
+
+    /// Attaches `child` under `parent`, keeping its local transform, so it
+    /// takes up the same offset relative to its new parent.
+    pub fn set_parent(&mut self, child: NodeId, parent: NodeId) -> Result<(), HierarchyError> {
+        let child_index = self.index(child)?;
+        let parent_index = self.index(parent)?;
+        let mut ancestor = Some(parent_index);
+        while let Some(index) = ancestor {
+            if index == child_index {
+                return Err(HierarchyError::Cycle { child, parent });
+            }
+            ancestor = self.node(index).parent;
+        }
+
+        self.unlink(child_index);
+        self.node_mut(child_index).parent = Some(parent_index);
+        self.node_mut(parent_index).children.push(child_index);
+        self.mark_dirty(child_index);
+        Ok(())
+    }
+
+    /// Makes `child` a root again. Its local transform becomes its current
+    /// world transform, so it stays where it is.
+    pub fn detach(&mut self, child: NodeId) -> Result<(), HierarchyError> {
+        let index = self.index(child)?;
+        if self.node(index).parent.is_none() {
+            return Ok(());
+        }
+        let world = self.compute_world(index);
+        self.unlink(index);
+        self.node_mut(index).local = Transform3D::from_matrix(world);
+        self.mark_dirty(index);
+        Ok(())
+    }
+
+    pub fn set_local(&mut self, node: NodeId, local: Transform3D) -> Result<(), HierarchyError> {
+        let index = self.index(node)?;
+        self.node_mut(index).local = local;
+        self.mark_dirty(index);
+        Ok(())
+    }
+
+    /// Recomputes the world matrices of every node changed since the last
+    /// call, and of their descendants. Untouched subtrees are skipped.
+    pub fn propagate(&mut self) {
+        let dirty = std::mem::take(&mut self.dirty);
+        for &index in &dirty {
+            // Removed since, already handled, or covered by a dirty ancestor
+            let Some(node) = self.slots[index as usize].node.as_ref() else {
+                continue;
+            };
+            if !node.dirty || self.has_dirty_ancestor(index) {
+                continue;
+            }
+            let parent_world = node
+                .parent
+                .map_or(Mat4::IDENTITY, |parent| self.node(parent).world);
+
+            let mut stack = vec![(index, parent_world)];
+            while let Some((index, parent_world)) = stack.pop() {
+                let node = self.node_mut(index);
+                node.world = parent_world * node.local.local_matrix();
+                node.dirty = false;
+                let world = node.world;
+                stack.extend(node.children.iter().map(|&child| (child, world)));
+            }
+        }
+        // Keep the allocation for next frame
+        self.dirty = dirty;
+        self.dirty.clear();
+    }
+
+    fn index(&self, node: NodeId) -> Result<u32, HierarchyError> {
+        match self.slots.get(node.index as usize) {
+            Some(slot) if slot.generation == node.generation && slot.node.is_some() => {
+                Ok(node.index)
+            }
+            _ => Err(HierarchyError::UnknownNode(node)),
+        }
+    }
+
+    fn node(&self, index: u32) -> &Node {
+        self.slots[index as usize]
+            .node
+            .as_ref()
+            .expect("links only point at live nodes")
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut Node {
+        self.slots[index as usize]
+            .node
+            .as_mut()
+            .expect("links only point at live nodes")
+    }
+
+    fn mark_dirty(&mut self, index: u32) {
+        let node = self.node_mut(index);
+        if !node.dirty {
+            node.dirty = true;
+            self.dirty.push(index);
+        }
+    }
+
+    fn has_dirty_ancestor(&self, index: u32) -> bool {
+        let mut ancestor = self.node(index).parent;
+        while let Some(index) = ancestor {
+            let node = self.node(index);
+            if node.dirty {
+                return true;
+            }
+            ancestor = node.parent;
+        }
+        false
+    }
+
+    /// Detaches `index` from its parent, if it has one.
+    fn unlink(&mut self, index: u32) {
+        if let Some(parent) = self.node_mut(index).parent.take() {
+            self.node_mut(parent)
+                .children
+                .retain(|&child| child != index);
+        }
+    }
+
+    /// The world matrix from the current local transforms, whether or not
+    /// they've been propagated.
+    fn compute_world(&self, index: u32) -> Mat4 {
+        let mut world = self.node(index).local.local_matrix();
+        let mut ancestor = self.node(index).parent;
+        while let Some(index) = ancestor {
+            let node = self.node(index);
+            world = node.local.local_matrix() * world;
+            ancestor = node.parent;
+        }
+        world
+    }
+
+    //--Getters--//
+
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.index(node).is_ok()
+    }
+
+    pub fn local(&self, node: NodeId) -> Option<Transform3D> {
+        Some(self.node(self.index(node).ok()?).local)
+    }
+
+    /// The world matrix as of the last [`TransformHierarchy::propagate`].
+    pub fn world_matrix(&self, node: NodeId) -> Option<Mat4> {
+        Some(self.node(self.index(node).ok()?).world)
+    }
+
+    /// [`TransformHierarchy::world_matrix`] decomposed.
+    pub fn world_transform(&self, node: NodeId) -> Option<Transform3D> {
+        self.world_matrix(node).map(Transform3D::from_matrix)
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        let parent = self.node(self.index(node).ok()?).parent?;
+        Some(NodeId {
+            index: parent,
+            generation: self.slots[parent as usize].generation,
+        })
+    }
+
+    pub fn children(&self, node: NodeId) -> Vec<NodeId> {
+        let Ok(index) = self.index(node) else {
+            return Vec::new();
+        };
+        self.node(index)
+            .children
+            .iter()
+            .map(|&child| NodeId {
+                index: child,
+                generation: self.slots[child as usize].generation,
+            })
+            .collect()
+    }
+
+    /// Number of nodes.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::math::transform::Transform2D;
+    use glam::{Quat, Vec2, Vec3};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn translation(hierarchy: &TransformHierarchy, node: NodeId) -> Vec3 {
+        hierarchy.world_transform(node).unwrap().translation
+    }
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-5),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    /// A body at (10, 0, 0) turned a quarter about Z, with a hand one unit
+    /// along its X axis.
+    fn body_and_hand() -> (TransformHierarchy, NodeId, NodeId) {
+        let mut hierarchy = TransformHierarchy::new();
+        let body = hierarchy.add(Transform3D::new(
+            Vec3::new(10.0, 0.0, 0.0),
+            Quat::from_rotation_z(FRAC_PI_2),
+            Vec3::ONE,
+        ));
+        let hand = hierarchy.add(Transform3D::from_translation(Vec3::X));
+        hierarchy.set_parent(hand, body).unwrap();
+        hierarchy.propagate();
+        (hierarchy, body, hand)
+    }
+
+    #[test]
+    fn children_follow_their_parent_after_propagation() {
+        let (mut hierarchy, body, hand) = body_and_hand();
+        assert_near(translation(&hierarchy, hand), Vec3::new(10.0, 1.0, 0.0));
+        assert_eq!(hierarchy.parent(hand), Some(body));
+        assert_eq!(hierarchy.children(body), vec![hand]);
+
+        hierarchy
+            .set_local(body, Transform3D::from_translation(Vec3::Y))
+            .unwrap();
+        // World matrices only change on propagation
+        assert_near(translation(&hierarchy, hand), Vec3::new(10.0, 1.0, 0.0));
+        hierarchy.propagate();
+        assert_near(translation(&hierarchy, hand), Vec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn grandchildren_update_when_a_middle_node_moves() {
+        let (mut hierarchy, _, hand) = body_and_hand();
+        let finger = hierarchy.add(Transform3D::from_translation(Vec3::X));
+        hierarchy.set_parent(finger, hand).unwrap();
+        hierarchy.propagate();
+        assert_near(translation(&hierarchy, finger), Vec3::new(10.0, 2.0, 0.0));
+
+        hierarchy
+            .set_local(hand, Transform3D::from_translation(Vec3::ZERO))
+            .unwrap();
+        hierarchy.propagate();
+        assert_near(translation(&hierarchy, finger), Vec3::new(10.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let (mut hierarchy, body, hand) = body_and_hand();
+        assert_eq!(
+            hierarchy.set_parent(body, hand),
+            Err(HierarchyError::Cycle {
+                child: body,
+                parent: hand
+            })
+        );
+        assert_eq!(
+            hierarchy.set_parent(body, body),
+            Err(HierarchyError::Cycle {
+                child: body,
+                parent: body
+            })
+        );
+        assert_eq!(hierarchy.parent(body), None);
+    }
+
+    #[test]
+    fn detached_nodes_stay_where_they_are() {
+        let (mut hierarchy, body, hand) = body_and_hand();
+        hierarchy.detach(hand).unwrap();
+        assert_eq!(hierarchy.parent(hand), None);
+        assert!(hierarchy.children(body).is_empty());
+
+        hierarchy
+            .set_local(body, Transform3D::from_translation(Vec3::ZERO))
+            .unwrap();
+        hierarchy.propagate();
+        assert_near(translation(&hierarchy, hand), Vec3::new(10.0, 1.0, 0.0));
+        assert_near(
+            hierarchy.local(hand).unwrap().translation,
+            Vec3::new(10.0, 1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn removing_a_node_removes_its_descendants() {
+        let (mut hierarchy, body, hand) = body_and_hand();
+        let other = hierarchy.add(Transform3D::IDENTITY);
+        hierarchy.remove(body).unwrap();
+        assert_eq!(hierarchy.len(), 1);
+        assert!(!hierarchy.contains(hand));
+        assert_eq!(hierarchy.world_matrix(hand), None);
+        assert_eq!(
+            hierarchy.set_local(body, Transform3D::IDENTITY),
+            Err(HierarchyError::UnknownNode(body))
+        );
+
+        // The reused slot doesn't answer to the old handle
+        let reused = hierarchy.add(Transform3D::IDENTITY);
+        assert_ne!(reused, body);
+        assert_ne!(reused, hand);
+        assert!(!hierarchy.contains(body) && hierarchy.contains(other));
+        hierarchy.propagate();
+    }
+
+    #[test]
+    fn transform_2d_round_trips_through_3d() {
+        let transform = Transform2D::new(Vec2::new(3.0, -4.0), 0.5, Vec2::new(2.0, 1.5));
+        let back = Transform3D::from(transform).to_2d();
+        assert!(back.translation.abs_diff_eq(transform.translation, 1e-5));
+        assert!((back.rotation - transform.rotation).abs() < 1e-5);
+        assert!(back.scale.abs_diff_eq(transform.scale, 1e-5));
+    }
+}
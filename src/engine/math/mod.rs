@@ -0,0 +1,10 @@
+pub mod color;
+pub mod hierarchy;
+pub mod projection;
+pub mod ray;
+pub mod rect;
+pub mod transform;
+pub mod util;
+
+// The engine's vector and matrix types, so games needn't depend on glam
+pub use glam::{Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
@@ -0,0 +1,98 @@
+use glam::camera::rh::proj::directx;
+use glam::camera::rh::view;
+use glam::{Mat4, Vec3};
+
+// Right-handed, with the 0..1 clip depth range wgpu uses
+
+/// Orthographic projection of the box between the planes, with `near`
+/// mapped to depth 0 and `far` to 1.
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    directx::orthographic(left, right, bottom, top, near, far)
+}
+
+/// Perspective projection with `near` mapped to depth 0 and `far` to 1.
+/// `fov_y` is the vertical field of view in radians.
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    directx::perspective(fov_y, aspect, near, far)
+}
+
+/// Perspective projection with depth reversed: 1 at `near` and 0 at `far`,
+/// which spreads depth precision far more evenly. An infinite `far` never
+/// clips distant geometry. The 3D renderer uses this, with
+/// [`DEPTH_CLEAR`](crate::engine::renderer::depth::DEPTH_CLEAR) clearing
+/// to the far plane.
+pub fn perspective_reverse_z(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    if far.is_finite() {
+        // Swapping the planes flips the depth range
+        directx::perspective(fov_y, aspect, far, near)
+    } else {
+        directx::perspective_infinite_reverse(fov_y, aspect, near)
+    }
+}
+
+/// View matrix of a camera at `eye` looking at `target`, with `up` roughly
+/// towards the top of the view.
+pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+    view::look_at_mat4(eye, target, up)
+}
+
+/// View matrix of a camera at `eye` looking along `direction`, which
+/// needn't be normalized.
+pub fn look_to(eye: Vec3, direction: Vec3, up: Vec3) -> Mat4 {
+    view::look_to_mat4(eye, direction.normalize(), up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec4;
+
+    /// Depth of the point `distance` in front of a camera at the origin.
+    fn depth(projection: Mat4, distance: f32) -> f32 {
+        let clip = projection * Vec4::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn projections_map_near_and_far_to_wgpu_depth() {
+        let ortho = orthographic(-1.0, 1.0, -1.0, 1.0, 0.1, 100.0);
+        assert_near(depth(ortho, 0.1), 0.0);
+        assert_near(depth(ortho, 100.0), 1.0);
+
+        let perspective = perspective(1.0, 1.5, 0.1, 100.0);
+        assert_near(depth(perspective, 0.1), 0.0);
+        assert_near(depth(perspective, 100.0), 1.0);
+    }
+
+    #[test]
+    fn reverse_z_puts_near_at_one() {
+        let finite = perspective_reverse_z(1.0, 1.5, 0.1, 100.0);
+        assert_near(depth(finite, 0.1), 1.0);
+        assert_near(depth(finite, 100.0), 0.0);
+
+        let infinite = perspective_reverse_z(1.0, 1.5, 0.1, f32::INFINITY);
+        assert_near(depth(infinite, 0.1), 1.0);
+        assert!(depth(infinite, 1.0e6) > 0.0);
+        assert!(depth(infinite, 1.0e6) < 1.0e-6);
+    }
+
+    #[test]
+    fn look_at_puts_the_target_straight_ahead() {
+        let eye = Vec3::new(3.0, 2.0, 5.0);
+        let target = Vec3::new(3.0, 2.0, -5.0);
+        let view = look_at(eye, target, Vec3::Y);
+        assert!(view.transform_point3(eye).length() < 1e-5);
+        let ahead = view.transform_point3(target);
+        assert!(ahead.truncate().length() < 1e-5);
+        assert_near(ahead.z, -10.0);
+
+        assert!(view.abs_diff_eq(look_to(eye, target - eye, Vec3::Y), 1e-6));
+    }
+}
@@ -0,0 +1,493 @@
+use glam::{Mat4, Vec2, Vec3};
+use std::sync::Arc;
+
+use crate::engine::physics::aabb::Aabb3D;
+use crate::engine::renderer::camera3d::Camera3D;
+use crate::engine::window::Window;
+use crate::input::mouse_listener::MouseInput;
+
+/// Layer mask matching every [`RayTarget`].
+pub const ALL_LAYERS: u32 = u32::MAX;
+
+/// Where a ray hit a shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// In multiples of the ray's direction, like [`Ray::at`].
+    pub distance: f32,
+    pub point: Vec3,
+    /// Unit normal of the surface hit, facing back along the ray. Rays
+    /// starting inside a shape get the reversed ray direction.
+    pub normal: Vec3,
+}
+
+/// A half-line from `origin` along `direction`. Hit distances are in
+/// multiples of `direction`, so they're world units when it's normalized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction }
+    }
+
+    /// The ray from `camera`'s near plane through the window pixel
+    /// `screen_pos` (origin top-left, y down), with a normalized direction.
+    /// The camera's viewport should match `screen_size`, which the engine
+    /// keeps true for the [`Camera3D`] it renders with.
+    pub fn from_screen(screen_pos: Vec2, screen_size: Vec2, camera: &Camera3D) -> Ray {
+        let ndc = Vec2::new(
+            screen_pos.x / screen_size.x * 2.0 - 1.0,
+            1.0 - screen_pos.y / screen_size.y * 2.0,
+        );
+        let inverse = camera.view_projection().inverse();
+        // Reverse-Z puts the near plane at depth 1. The far plane may be at
+        // infinity, so aim at a point in between instead.
+        let near = inverse.project_point3(ndc.extend(1.0));
+        let toward = inverse.project_point3(ndc.extend(0.5));
+        Ray {
+            origin: near,
+            direction: (toward - near).normalize_or_zero(),
+        }
+    }
+
+    /// The ray through the cursor from the shared [`Camera3D`], for
+    /// picking what's under the mouse.
+    pub fn from_mouse() -> Ray {
+        let size = Window::size();
+        let screen_size = Vec2::new(size.width.max(1) as f32, size.height.max(1) as f32);
+        let camera = *Camera3D::get_instance().lock().unwrap();
+        Self::from_screen(MouseInput::position(), screen_size, &camera)
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// The hit for `distance`, with `normal` turned to face the ray.
+    fn hit(&self, distance: f32, normal: Vec3) -> RayHit {
+        let normal = if distance == 0.0 {
+            -self.direction.normalize_or_zero()
+        } else if normal.dot(self.direction) > 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        RayHit {
+            distance,
+            point: self.at(distance),
+            normal,
+        }
+    }
+
+    /// Distance to where the ray enters `aabb`, `None` if it misses. A ray
+    /// that only grazes an edge or face hits; one starting inside hits at
+    /// `0`.
+    pub fn intersect_aabb(&self, aabb: &Aabb3D) -> Option<f32> {
+        let mut entry = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        for axis in 0..3 {
+            let (origin, direction) = (self.origin[axis], self.direction[axis]);
+            let (min, max) = (aabb.min[axis], aabb.max[axis]);
+            if direction == 0.0 {
+                // Parallel to this pair of faces: must already be between them
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min - origin) / direction;
+            let t1 = (max - origin) / direction;
+            entry = entry.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+        }
+
+        if entry > exit || exit < 0.0 {
+            return None;
+        }
+        Some(entry.max(0.0))
+    }
+
+    /// [`Ray::intersect_aabb`] with the hit point and the normal of the
+    /// face entered.
+    pub fn cast_aabb(&self, aabb: &Aabb3D) -> Option<RayHit> {
+        let distance = self.intersect_aabb(aabb)?;
+        // The face entered is the one the hit point lies closest to,
+        // relative to the box's size
+        let offset = (self.at(distance) - aabb.center())
+            / (aabb.size() * 0.5).max(Vec3::splat(f32::EPSILON));
+        let axis = offset.abs().max_position();
+        let mut normal = Vec3::ZERO;
+        normal[axis] = offset[axis].signum();
+        Some(self.hit(distance, normal))
+    }
+
+    /// Distance to where the ray enters the sphere, `None` if it misses. A
+    /// tangent ray hits; one starting inside hits at `0`.
+    pub fn intersect_sphere(&self, center: Vec3, radius: f32) -> Option<f32> {
+        let to_origin = self.origin - center;
+        let a = self.direction.length_squared();
+        let c = to_origin.length_squared() - radius * radius;
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        if a == 0.0 {
+            return None;
+        }
+
+        let half_b = to_origin.dot(self.direction);
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        // Outside the sphere both roots share a sign; the nearer one is
+        // the entry point
+        let t = (-half_b - discriminant.sqrt()) / a;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// [`Ray::intersect_sphere`] with the hit point and normal.
+    pub fn cast_sphere(&self, center: Vec3, radius: f32) -> Option<RayHit> {
+        let distance = self.intersect_sphere(center, radius)?;
+        Some(self.hit(distance, (self.at(distance) - center).normalize_or_zero()))
+    }
+
+    /// Distance to where the ray crosses the triangle `a`, `b`, `c` from
+    /// either side, `None` if it misses or runs parallel to it.
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        // Möller–Trumbore: solve for the distance and the barycentric
+        // coordinates of the crossing at once
+        let (edge1, edge2) = (b - a, c - a);
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = to_origin.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(q) * inverse;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// [`Ray::intersect_triangle`] with the hit point and normal.
+    pub fn cast_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<RayHit> {
+        let distance = self.intersect_triangle(a, b, c)?;
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        // Only a ray starting on the triangle hits at 0; keep its normal
+        Some(RayHit {
+            distance,
+            point: self.at(distance),
+            normal: if normal.dot(self.direction) > 0.0 {
+                -normal
+            } else {
+                normal
+            },
+        })
+    }
+
+    /// Nearest hit on a triangle mesh: `indices` lists three `positions`
+    /// per triangle, placed in the world by `transform`.
+    pub fn cast_mesh(
+        &self,
+        positions: &[Vec3],
+        indices: &[u32],
+        transform: Mat4,
+    ) -> Option<RayHit> {
+        indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let [a, b, c] =
+                    [0, 1, 2].map(|i| transform.transform_point3(positions[triangle[i] as usize]));
+                self.cast_triangle(a, b, c)
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+/// Something [`raycast`] can hit.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RayShape {
+    Aabb(Aabb3D),
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    Triangle([Vec3; 3]),
+    /// See [`Ray::cast_mesh`]. The arrays are shared, so one model's data
+    /// can back many targets.
+    Mesh {
+        positions: Arc<[Vec3]>,
+        indices: Arc<[u32]>,
+        transform: Mat4,
+    },
+}
+
+impl RayShape {
+    pub fn cast(&self, ray: &Ray) -> Option<RayHit> {
+        match self {
+            RayShape::Aabb(aabb) => ray.cast_aabb(aabb),
+            RayShape::Sphere { center, radius } => ray.cast_sphere(*center, *radius),
+            RayShape::Triangle([a, b, c]) => ray.cast_triangle(*a, *b, *c),
+            RayShape::Mesh {
+                positions,
+                indices,
+                transform,
+            } => ray.cast_mesh(positions, indices, *transform),
+        }
+    }
+}
+
+/// A shape tagged with whatever identifies the object it belongs to, e.g.
+/// an [`Entity`](crate::engine::ecs::Entity), and the layers it's on, one
+/// bit each.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RayTarget<T> {
+    pub id: T,
+    pub shape: RayShape,
+    pub layers: u32,
+}
+
+impl<T> RayTarget<T> {
+    /// A target on every layer.
+    pub fn new(id: T, shape: RayShape) -> Self {
+        Self {
+            id,
+            shape,
+            layers: ALL_LAYERS,
+        }
+    }
+
+    pub fn with_layers(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+}
+
+/// Every target sharing a layer with `mask` that `ray` hits, nearest
+/// first.
+///
+/// ```ignore
+/// if let Some((cube, hit)) = raycast(&Ray::from_mouse(), &targets, ALL_LAYERS) {
+///     highlight(cube, hit.point);
+/// }
+/// ```
+pub fn raycast_all<T: Clone>(ray: &Ray, targets: &[RayTarget<T>], mask: u32) -> Vec<(T, RayHit)> {
+    let mut hits: Vec<_> = targets
+        .iter()
+        .filter(|target| target.layers & mask != 0)
+        .filter_map(|target| Some((target.id.clone(), target.shape.cast(ray)?)))
+        .collect();
+    hits.sort_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance));
+    hits
+}
+
+/// The nearest hit of [`raycast_all`].
+pub fn raycast<T: Clone>(ray: &Ray, targets: &[RayTarget<T>], mask: u32) -> Option<(T, RayHit)> {
+    targets
+        .iter()
+        .filter(|target| target.layers & mask != 0)
+        .filter_map(|target| Some((target, target.shape.cast(ray)?)))
+        .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+        .map(|(target, hit)| (target.id.clone(), hit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb3D {
+        Aabb3D::new(Vec3::splat(-1.0), Vec3::splat(1.0))
+    }
+
+    #[test]
+    fn ray_enters_the_box_at_the_near_face() {
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::X);
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(4.0));
+        let hit = ray.cast_aabb(&unit_box()).unwrap();
+        assert_eq!(hit.point, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(hit.normal, Vec3::NEG_X);
+    }
+
+    #[test]
+    fn rays_past_or_away_from_the_box_miss() {
+        // Passes above it
+        let above = Ray::new(Vec3::new(-5.0, 2.0, 0.0), Vec3::X);
+        assert_eq!(above.intersect_aabb(&unit_box()), None);
+
+        // Diagonal that clears the corner
+        let diagonal = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(diagonal.intersect_aabb(&unit_box()), None);
+
+        // Box is behind the origin
+        let away = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::NEG_X);
+        assert_eq!(away.intersect_aabb(&unit_box()), None);
+    }
+
+    #[test]
+    fn grazing_a_face_or_edge_hits() {
+        let along_face = Ray::new(Vec3::new(-5.0, 1.0, 0.0), Vec3::X);
+        assert_eq!(along_face.intersect_aabb(&unit_box()), Some(4.0));
+
+        let along_edge = Ray::new(Vec3::new(-5.0, 1.0, 1.0), Vec3::X);
+        assert_eq!(along_edge.intersect_aabb(&unit_box()), Some(4.0));
+
+        let through_corner = Ray::new(Vec3::new(-2.0, -2.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(through_corner.intersect_aabb(&unit_box()), Some(1.0));
+    }
+
+    #[test]
+    fn ray_starting_inside_the_box_hits_at_zero() {
+        let ray = Ray::new(Vec3::new(0.5, 0.0, 0.0), Vec3::Y);
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(0.0));
+        let hit = ray.cast_aabb(&unit_box()).unwrap();
+        assert_eq!(hit.point, ray.origin);
+        assert_eq!(hit.normal, Vec3::NEG_Y);
+    }
+
+    #[test]
+    fn sphere_hits_misses_and_tangents() {
+        let center = Vec3::new(0.0, 0.0, -10.0);
+        let forward = Ray::new(Vec3::ZERO, Vec3::NEG_Z);
+
+        assert_eq!(forward.intersect_sphere(center, 2.0), Some(8.0));
+        assert_eq!(forward.cast_sphere(center, 2.0).unwrap().normal, Vec3::Z);
+
+        let beside = Ray::new(Vec3::new(3.0, 0.0, 0.0), Vec3::NEG_Z);
+        assert_eq!(beside.intersect_sphere(center, 2.0), None);
+
+        let tangent = Ray::new(Vec3::new(2.0, 0.0, 0.0), Vec3::NEG_Z);
+        assert_eq!(tangent.intersect_sphere(center, 2.0), Some(10.0));
+
+        let behind = Ray::new(Vec3::ZERO, Vec3::Z);
+        assert_eq!(behind.intersect_sphere(center, 2.0), None);
+
+        let inside = Ray::new(center, Vec3::X);
+        assert_eq!(inside.intersect_sphere(center, 2.0), Some(0.0));
+    }
+
+    #[test]
+    fn screen_center_looks_down_the_camera_axis() {
+        let camera = Camera3D::default();
+        let size = Vec2::new(800.0, 600.0);
+        let ray = Ray::from_screen(size * 0.5, size, &camera);
+
+        assert!(ray.direction.abs_diff_eq(camera.forward(), 1e-4));
+    }
+
+    /// The triangle (0,0,0), (2,0,0), (0,2,0) in the XY plane.
+    fn triangle() -> [Vec3; 3] {
+        [
+            Vec3::ZERO,
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn triangles_are_hit_from_either_side() {
+        let [a, b, c] = triangle();
+        let front = Ray::new(Vec3::new(0.5, 0.5, 3.0), Vec3::NEG_Z);
+        let hit = front.cast_triangle(a, b, c).unwrap();
+        assert_eq!(hit.distance, 3.0);
+        assert_eq!(hit.point, Vec3::new(0.5, 0.5, 0.0));
+        assert_eq!(hit.normal, Vec3::Z);
+
+        let back = Ray::new(Vec3::new(0.5, 0.5, -2.0), Vec3::Z);
+        let hit = back.cast_triangle(a, b, c).unwrap();
+        assert_eq!(hit.distance, 2.0);
+        assert_eq!(hit.normal, Vec3::NEG_Z);
+    }
+
+    #[test]
+    fn triangle_misses() {
+        let [a, b, c] = triangle();
+        // Past the hypotenuse
+        let outside = Ray::new(Vec3::new(1.5, 1.5, 3.0), Vec3::NEG_Z);
+        assert_eq!(outside.intersect_triangle(a, b, c), None);
+        // Behind the origin
+        let away = Ray::new(Vec3::new(0.5, 0.5, 3.0), Vec3::Z);
+        assert_eq!(away.intersect_triangle(a, b, c), None);
+        // In the triangle's plane
+        let parallel = Ray::new(Vec3::new(-1.0, 0.5, 0.0), Vec3::X);
+        assert_eq!(parallel.intersect_triangle(a, b, c), None);
+    }
+
+    #[test]
+    fn meshes_report_the_nearest_transformed_triangle() {
+        // Two quads, at z = 0 and z = 1, moved 10 along X
+        let positions = [
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(1.0, -1.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(-1.0, 1.0, 1.0),
+        ];
+        let indices = [0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7];
+        let transform = Mat4::from_translation(Vec3::new(10.0, 0.0, 0.0));
+
+        let ray = Ray::new(Vec3::new(10.5, -0.5, 5.0), Vec3::NEG_Z);
+        let hit = ray.cast_mesh(&positions, &indices, transform).unwrap();
+        assert_eq!(hit.distance, 4.0);
+        assert_eq!(hit.point, Vec3::new(10.5, -0.5, 1.0));
+
+        let untransformed = Ray::new(Vec3::new(0.5, -0.5, 5.0), Vec3::NEG_Z);
+        assert_eq!(
+            untransformed.cast_mesh(&positions, &indices, transform),
+            None
+        );
+    }
+
+    #[test]
+    fn raycast_filters_by_layer_and_sorts_by_distance() {
+        let near = RayTarget::new("near", RayShape::Aabb(unit_box())).with_layers(0b01);
+        let far = RayTarget::new(
+            "far",
+            RayShape::Sphere {
+                center: Vec3::new(10.0, 0.0, 0.0),
+                radius: 1.0,
+            },
+        )
+        .with_layers(0b10);
+        // Facing the ray at x = 5, on every layer
+        let wall = RayTarget::new(
+            "wall",
+            RayShape::Triangle([
+                Vec3::new(5.0, -1.0, -1.0),
+                Vec3::new(5.0, 2.0, -1.0),
+                Vec3::new(5.0, -1.0, 2.0),
+            ]),
+        );
+        let targets = [far, near, wall];
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::X);
+
+        let hits: Vec<_> = raycast_all(&ray, &targets, ALL_LAYERS)
+            .into_iter()
+            .map(|(id, hit)| (id, hit.distance))
+            .collect();
+        assert_eq!(hits, [("near", 4.0), ("wall", 10.0), ("far", 14.0)]);
+
+        let nearest = |mask| raycast(&ray, &targets, mask).map(|(id, _)| id);
+        assert_eq!(nearest(ALL_LAYERS), Some("near"));
+        assert_eq!(nearest(0b10), Some("wall"));
+        assert_eq!(nearest(0), None);
+
+        let above = Ray::new(Vec3::new(-5.0, 5.0, 0.0), Vec3::X);
+        assert!(raycast_all(&above, &targets, ALL_LAYERS).is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use glam::Vec2;
+
+/// Axis-aligned rectangle given by its top-left corner and size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn min(&self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+
+    pub fn max(&self) -> Vec2 {
+        Vec2::new(self.x + self.width, self.y + self.height)
+    }
+
+    /// The rectangle spanning `min` to `max`.
+    pub fn from_min_max(min: Vec2, max: Vec2) -> Self {
+        Self::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        Self::from_min_max(center - size * 0.5, center + size * 0.5)
+    }
+
+    /// Whether `point` is inside or on the edge.
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.cmpge(self.min()).all() && point.cmple(self.max()).all()
+    }
+
+    /// Whether `other` lies entirely inside.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        self.contains_point(other.min()) && self.contains_point(other.max())
+    }
+
+    /// Whether the rectangles overlap. Touching edges don't count.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.min().cmplt(other.max()).all() && other.min().cmplt(self.max()).all()
+    }
+
+    /// The overlapping part, or `None` if they don't overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        self.intersects(other)
+            .then(|| Rect::from_min_max(self.min().max(other.min()), self.max().min(other.max())))
+    }
+
+    /// The smallest rectangle covering both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::from_min_max(self.min().min(other.min()), self.max().max(other.max()))
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.min() + self.size() * 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containment_includes_the_edges() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 5.0);
+        assert!(rect.contains_point(Vec2::new(10.0, 5.0)));
+        assert!(!rect.contains_point(Vec2::new(10.1, 2.0)));
+        assert!(rect.contains_rect(&Rect::new(2.0, 1.0, 8.0, 4.0)));
+        assert!(!rect.contains_rect(&Rect::new(2.0, 1.0, 9.0, 4.0)));
+    }
+
+    #[test]
+    fn touching_rects_do_not_intersect() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        assert!(!a.intersects(&Rect::new(10.0, 0.0, 5.0, 5.0)));
+        assert_eq!(a.intersection(&Rect::new(10.0, 0.0, 5.0, 5.0)), None);
+
+        let b = Rect::new(5.0, -5.0, 10.0, 10.0);
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Some(Rect::new(5.0, 0.0, 5.0, 5.0)));
+        assert_eq!(a.union(&b), Rect::new(0.0, -5.0, 15.0, 15.0));
+    }
+
+    #[test]
+    fn center_and_size_round_trip() {
+        let rect = Rect::from_center_size(Vec2::new(4.0, 2.0), Vec2::new(6.0, 2.0));
+        assert_eq!(rect, Rect::new(1.0, 1.0, 6.0, 2.0));
+        assert_eq!(rect.center(), Vec2::new(4.0, 2.0));
+        assert_eq!(rect.max(), Vec2::new(7.0, 3.0));
+    }
+}
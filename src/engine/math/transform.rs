@@ -0,0 +1,241 @@
+use glam::{Mat4, Quat, Vec2, Vec3};
+
+/// Position, rotation and scale of a 2D object relative to its parent (or
+/// the world if it has none). Applied as scale, then rotation, then
+/// translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub translation: Vec2,
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Transform2D {
+    pub const IDENTITY: Self = Self {
+        translation: Vec2::ZERO,
+        rotation: 0.0,
+        scale: Vec2::ONE,
+    };
+
+    pub fn new(translation: Vec2, rotation: f32, scale: Vec2) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Maps this object's local space into its parent's space.
+    pub fn local_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            self.scale.extend(1.0),
+            Quat::from_rotation_z(self.rotation),
+            self.translation.extend(0.0),
+        )
+    }
+
+    /// Maps local space into world space, given the parent's world matrix
+    /// (`Mat4::IDENTITY` for a root object). Pass the result on as the
+    /// `parent_world` of this object's children.
+    pub fn world_matrix(&self, parent_world: Mat4) -> Mat4 {
+        parent_world * self.local_matrix()
+    }
+
+    /// Converts a point in this object's local space to world space.
+    pub fn local_to_world(&self, parent_world: Mat4, point: Vec2) -> Vec2 {
+        self.world_matrix(parent_world)
+            .transform_point3(point.extend(0.0))
+            .truncate()
+    }
+
+    /// Converts a world-space point into this object's local space. A zero
+    /// scale anywhere in the chain makes this undefined.
+    pub fn world_to_local(&self, parent_world: Mat4, point: Vec2) -> Vec2 {
+        self.world_matrix(parent_world)
+            .inverse()
+            .transform_point3(point.extend(0.0))
+            .truncate()
+    }
+
+    /// Decomposes a world matrix built from `Transform2D`s back into one,
+    /// e.g. to read where a child ended up. Non-uniform scale combined with
+    /// a rotated parent introduces shear, which is lost.
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        let (axis, angle) = rotation.to_axis_angle();
+        Self {
+            translation: translation.truncate(),
+            rotation: if axis.z < 0.0 { -angle } else { angle },
+            scale: scale.truncate(),
+        }
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Position, rotation and scale of a 3D object relative to its parent (or
+/// the world if it has none). Applied as scale, then rotation, then
+/// translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform3D {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform3D {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Maps this object's local space into its parent's space.
+    pub fn local_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    /// Decomposes a matrix built from `Transform3D`s back into one. Like
+    /// [`Transform2D::from_matrix`], shear from non-uniform scale under a
+    /// rotated parent is lost.
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// The 2D part: translation and scale in XY and the rotation about Z.
+    pub fn to_2d(&self) -> Transform2D {
+        Transform2D::from_matrix(self.local_matrix())
+    }
+}
+
+impl Default for Transform3D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl From<Transform2D> for Transform3D {
+    /// The same transform in the XY plane, with a scale of 1 along Z.
+    fn from(transform: Transform2D) -> Self {
+        Self {
+            translation: transform.translation.extend(0.0),
+            rotation: Quat::from_rotation_z(transform.rotation),
+            scale: transform.scale.extend(1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-5),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    /// A tank at (10, 0), turned a quarter counter-clockwise and drawn at
+    /// twice the size, with a turret one unit along its barrel axis.
+    fn tank_and_turret() -> (Transform2D, Transform2D) {
+        let tank = Transform2D::new(Vec2::new(10.0, 0.0), FRAC_PI_2, Vec2::splat(2.0));
+        let turret = Transform2D::from_translation(Vec2::new(1.0, 0.0));
+        (tank, turret)
+    }
+
+    #[test]
+    fn local_matrix_scales_then_rotates_then_translates() {
+        let transform = Transform2D::new(Vec2::new(5.0, 0.0), FRAC_PI_2, Vec2::new(2.0, 3.0));
+        let point = transform.local_to_world(Mat4::IDENTITY, Vec2::new(1.0, 1.0));
+
+        // (1, 1) scales to (2, 3), turns to (-3, 2) and moves to (2, 2)
+        assert_near(point, Vec2::new(2.0, 2.0));
+    }
+
+    #[test]
+    fn child_world_is_parent_world_times_local() {
+        let (tank, turret) = tank_and_turret();
+        let tank_world = tank.world_matrix(Mat4::IDENTITY);
+        let turret_world = turret.world_matrix(tank_world);
+
+        assert_eq!(turret_world, tank_world * turret.local_matrix());
+        assert_near(
+            turret.local_to_world(tank_world, Vec2::ZERO),
+            Vec2::new(10.0, 2.0),
+        );
+        assert_near(
+            turret.local_to_world(tank_world, Vec2::new(1.0, 0.0)),
+            Vec2::new(10.0, 4.0),
+        );
+    }
+
+    #[test]
+    fn world_to_local_undoes_local_to_world() {
+        let (tank, turret) = tank_and_turret();
+        let tank_world = tank.world_matrix(Mat4::IDENTITY);
+        let turret = Transform2D {
+            rotation: 0.3,
+            scale: Vec2::new(0.5, 1.5),
+            ..turret
+        };
+
+        let point = Vec2::new(-2.0, 7.0);
+        let world = turret.local_to_world(tank_world, point);
+        assert_near(turret.world_to_local(tank_world, world), point);
+    }
+
+    #[test]
+    fn from_matrix_recovers_the_composed_chain() {
+        let (tank, turret) = tank_and_turret();
+        let turret = Transform2D {
+            rotation: FRAC_PI_2,
+            ..turret
+        };
+        let world = Transform2D::from_matrix(turret.world_matrix(tank.local_matrix()));
+
+        assert_near(world.translation, Vec2::new(10.0, 2.0));
+        assert!((world.rotation - 2.0 * FRAC_PI_2).abs() < 1e-5);
+        assert_near(world.scale, Vec2::splat(2.0));
+    }
+
+    #[test]
+    fn clockwise_rotation_survives_from_matrix() {
+        let transform = Transform2D::new(Vec2::ZERO, -0.5, Vec2::ONE);
+        let back = Transform2D::from_matrix(transform.local_matrix());
+        assert!((back.rotation + 0.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,81 @@
+use std::f32::consts::{PI, TAU};
+
+/// `angle` in radians brought into `-π..=π`.
+pub fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    // rem_euclid gives -π for π itself
+    if wrapped == -PI { PI } else { wrapped }
+}
+
+/// The shortest turn from `from` to `to`, in radians, within `-π..=π`.
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    wrap_angle(to - from)
+}
+
+/// `current` moved towards `target` by at most `max_delta`, without
+/// overshooting it. Vectors have `Vec2::move_towards` and
+/// `Vec3::move_towards` from glam.
+///
+/// ```ignore
+/// speed = move_towards(speed, max_speed, acceleration * dt);
+/// ```
+pub fn move_towards(current: f32, target: f32, max_delta: f32) -> f32 {
+    let delta = target - current;
+    if delta.abs() <= max_delta {
+        target
+    } else {
+        current + delta.signum() * max_delta
+    }
+}
+
+/// [`move_towards`] for angles: turns the short way round and returns a
+/// wrapped angle.
+pub fn move_towards_angle(current: f32, target: f32, max_delta: f32) -> f32 {
+    let delta = angle_difference(current, target);
+    wrap_angle(current + move_towards(0.0, delta, max_delta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn angles_wrap_into_minus_pi_to_pi() {
+        assert_near(wrap_angle(0.5), 0.5);
+        assert_near(wrap_angle(TAU + 0.5), 0.5);
+        assert_near(wrap_angle(-TAU - 0.5), -0.5);
+        assert_near(wrap_angle(1.5 * PI), -0.5 * PI);
+        assert_eq!(wrap_angle(PI), PI);
+        assert_eq!(wrap_angle(-PI), PI);
+    }
+
+    #[test]
+    fn angle_difference_takes_the_short_way_round() {
+        assert_near(angle_difference(0.1, -0.1), -0.2);
+        // Across the ±π seam
+        assert_near(angle_difference(PI - 0.1, -PI + 0.1), 0.2);
+        assert_near(angle_difference(0.0, TAU), 0.0);
+    }
+
+    #[test]
+    fn move_towards_stops_at_the_target() {
+        assert_eq!(move_towards(0.0, 10.0, 3.0), 3.0);
+        assert_eq!(move_towards(10.0, 0.0, 3.0), 7.0);
+        assert_eq!(move_towards(9.0, 10.0, 3.0), 10.0);
+        assert_eq!(move_towards(10.0, 10.0, 3.0), 10.0);
+    }
+
+    #[test]
+    fn move_towards_angle_turns_across_the_seam() {
+        assert_near(move_towards_angle(PI - 0.1, -PI + 0.1, 0.05), PI - 0.05);
+        assert_near(move_towards_angle(PI - 0.1, -PI + 0.1, 0.15), -PI + 0.05);
+        assert_near(move_towards_angle(PI - 0.1, -PI + 0.1, 1.0), -PI + 0.1);
+    }
+}
@@ -1 +1,17 @@
+pub mod animation;
+pub mod assets;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod collision;
+pub mod debug;
+pub mod ecs;
+pub mod events;
+pub mod lighting;
+pub mod math;
+pub mod physics;
+pub mod physics2d;
+pub mod renderer;
+pub mod scene;
+pub mod text;
+pub mod timer;
 pub mod window;
@@ -0,0 +1,375 @@
+use glam::{Vec2, Vec3};
+
+use crate::engine::physics2d::circle::Circle;
+
+/// Axis-aligned bounding box in 2D.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb2D {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb2D {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        let half = size.abs() * 0.5;
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// True if the boxes overlap or touch along an edge.
+    pub fn intersects(&self, other: &Aabb2D) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+    }
+
+    /// True if the box overlaps or touches `circle`.
+    pub fn intersects_circle(&self, circle: &Circle) -> bool {
+        circle.intersects_aabb(self)
+    }
+
+    /// True if `p` lies inside the box or on its boundary.
+    pub fn contains_point(&self, p: Vec2) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    /// Minimum translation that moves `self` out of `other`, or `None` if
+    /// they don't overlap (touching edges need no correction).
+    pub fn overlap(&self, other: &Aabb2D) -> Option<Vec2> {
+        let x = axis_push(self.min.x, self.max.x, other.min.x, other.max.x)?;
+        let y = axis_push(self.min.y, self.max.y, other.min.y, other.max.y)?;
+
+        // Push out along the axis that needs the shorter move
+        if x.abs() < y.abs() {
+            Some(Vec2::new(x, 0.0))
+        } else {
+            Some(Vec2::new(0.0, y))
+        }
+    }
+
+    /// Time of impact in `[0, 1]` when `self` moves by `velocity` towards a
+    /// stationary `other`, or `None` if they don't meet this step. Boxes
+    /// already overlapping hit at `0`; sliding along a shared edge is not
+    /// a hit.
+    pub fn swept_intersects(&self, velocity: Vec2, other: &Aabb2D) -> Option<f32> {
+        self.sweep(velocity, other).map(|hit| hit.time)
+    }
+
+    /// Like [`Aabb2D::swept_intersects`], also reporting the normal of the
+    /// face of `other` that was hit.
+    pub fn sweep(&self, velocity: Vec2, other: &Aabb2D) -> Option<Hit> {
+        let (entry_x, exit_x) =
+            axis_interval(self.min.x, self.max.x, other.min.x, other.max.x, velocity.x)?;
+        let (entry_y, exit_y) =
+            axis_interval(self.min.y, self.max.y, other.min.y, other.max.y, velocity.y)?;
+
+        let entry = entry_x.max(entry_y);
+        let exit = exit_x.min(exit_y);
+        if entry >= exit || exit <= 0.0 || entry > 1.0 {
+            return None;
+        }
+
+        // The axis entered last is the one whose face was struck. Boxes that
+        // overlap without moving have no meaningful face.
+        let normal = if entry == f32::NEG_INFINITY {
+            Vec2::ZERO
+        } else if entry_x > entry_y {
+            Vec2::new(-velocity.x.signum(), 0.0)
+        } else {
+            Vec2::new(0.0, -velocity.y.signum())
+        };
+        Some(Hit {
+            time: entry.max(0.0),
+            normal,
+        })
+    }
+}
+
+/// Axis-aligned bounding box in 3D.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb3D {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb3D {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    pub fn from_center_size(center: Vec3, size: Vec3) -> Self {
+        let half = size.abs() * 0.5;
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// True if the boxes overlap or touch along a face.
+    pub fn intersects(&self, other: &Aabb3D) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+
+    /// True if `p` lies inside the box or on its boundary.
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        p.cmpge(self.min).all() && p.cmple(self.max).all()
+    }
+}
+
+/// Result of [`Aabb2D::sweep`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    /// Fraction of the velocity travelled before contact, in `[0, 1]`.
+    pub time: f32,
+    /// Unit normal of the struck face, pointing back towards the mover.
+    pub normal: Vec2,
+}
+
+/// Shortest signed move along one axis that takes an interval out of
+/// another, or `None` if they don't overlap. Contained intervals leave by
+/// the nearer end.
+fn axis_push(min: f32, max: f32, other_min: f32, other_max: f32) -> Option<f32> {
+    let forward = other_max - min;
+    let backward = max - other_min;
+    if forward <= 0.0 || backward <= 0.0 {
+        return None;
+    }
+    Some(if backward < forward {
+        -backward
+    } else {
+        forward
+    })
+}
+
+/// Times at which a moving interval starts and stops overlapping a static one.
+fn axis_interval(min: f32, max: f32, other_min: f32, other_max: f32, v: f32) -> Option<(f32, f32)> {
+    if v == 0.0 {
+        // No motion on this axis: they must already overlap on it. Merely
+        // touching doesn't count, unless both are the same single point.
+        let coincide = min == other_min && max == other_max;
+        if !coincide && (max <= other_min || min >= other_max) {
+            return None;
+        }
+        return Some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+
+    let t0 = (other_min - max) / v;
+    let t1 = (other_max - min) / v;
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(min: (f32, f32), max: (f32, f32)) -> Aabb2D {
+        Aabb2D::new(Vec2::new(min.0, min.1), Vec2::new(max.0, max.1))
+    }
+
+    fn point(x: f32, y: f32) -> Aabb2D {
+        aabb((x, y), (x, y))
+    }
+
+    #[test]
+    fn touching_edges_intersect_without_overlap() {
+        let a = aabb((0.0, 0.0), (1.0, 1.0));
+        let right = aabb((1.0, 0.0), (2.0, 1.0));
+        let corner = aabb((1.0, 1.0), (2.0, 2.0));
+
+        assert!(a.intersects(&right) && right.intersects(&a));
+        assert!(a.intersects(&corner));
+        assert_eq!(a.overlap(&right), None);
+        assert_eq!(a.overlap(&corner), None);
+        assert!(a.contains_point(Vec2::new(1.0, 0.5)));
+        assert!(!a.contains_point(Vec2::new(1.01, 0.5)));
+    }
+
+    #[test]
+    fn separated_boxes_do_not_intersect() {
+        let a = aabb((0.0, 0.0), (1.0, 1.0));
+        let b = aabb((1.5, 0.0), (2.5, 1.0));
+
+        assert!(!a.intersects(&b));
+        assert_eq!(a.overlap(&b), None);
+    }
+
+    #[test]
+    fn partial_overlap_pushes_out_along_the_shallow_axis() {
+        let a = aabb((0.0, 0.0), (2.0, 2.0));
+        let b = aabb((1.5, 0.5), (3.5, 1.5));
+
+        assert_eq!(a.overlap(&b), Some(Vec2::new(-0.5, 0.0)));
+        assert_eq!(b.overlap(&a), Some(Vec2::new(0.5, 0.0)));
+    }
+
+    #[test]
+    fn contained_box_leaves_by_the_nearest_edge() {
+        let outer = aabb((0.0, 0.0), (10.0, 10.0));
+        let inner = aabb((1.0, 4.0), (3.0, 6.0));
+
+        assert!(outer.intersects(&inner) && inner.intersects(&outer));
+        let push = inner.overlap(&outer).unwrap();
+        assert_eq!(push, Vec2::new(-3.0, 0.0));
+        let moved = Aabb2D::new(inner.min + push, inner.max + push);
+        assert_eq!(moved.overlap(&outer), None);
+
+        // The outer box is pushed the other way, clear of the inner one
+        assert_eq!(outer.overlap(&inner), Some(Vec2::new(3.0, 0.0)));
+    }
+
+    #[test]
+    fn zero_size_boxes() {
+        let a = aabb((0.0, 0.0), (2.0, 2.0));
+
+        assert!(a.intersects(&point(1.0, 1.0)));
+        assert!(a.intersects(&point(2.0, 1.0)));
+        assert!(!a.intersects(&point(2.5, 1.0)));
+        assert_eq!(point(0.5, 1.0).overlap(&a), Some(Vec2::new(-0.5, 0.0)));
+        assert_eq!(point(2.0, 1.0).overlap(&a), None);
+        assert!(point(1.0, 1.0).intersects(&point(1.0, 1.0)));
+        assert_eq!(
+            Aabb2D::from_center_size(Vec2::ONE, Vec2::ZERO),
+            point(1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn sweep_reports_time_and_face() {
+        let mover = aabb((0.0, 0.0), (1.0, 1.0));
+        let wall = aabb((3.0, 0.0), (4.0, 1.0));
+
+        let hit = mover.sweep(Vec2::new(4.0, 0.0), &wall).unwrap();
+        assert_eq!(hit.time, 0.5);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        assert_eq!(
+            mover.swept_intersects(Vec2::new(4.0, 0.0), &wall),
+            Some(0.5)
+        );
+
+        let floor = aabb((0.0, -3.0), (1.0, -2.0));
+        let hit = mover.sweep(Vec2::new(0.0, -8.0), &floor).unwrap();
+        assert_eq!(hit.time, 0.25);
+        assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn sweep_normals_point_back_at_the_mover() {
+        let target = aabb((0.0, 0.0), (1.0, 1.0));
+        let cases = [
+            ((-3.0, 0.0), (4.0, 0.0), (-1.0, 0.0)),
+            ((3.0, 0.0), (-4.0, 0.0), (1.0, 0.0)),
+            ((0.0, -3.0), (0.0, 4.0), (0.0, -1.0)),
+            ((0.0, 3.0), (0.0, -4.0), (0.0, 1.0)),
+        ];
+        for (start, velocity, normal) in cases {
+            let mover = aabb(start, (start.0 + 1.0, start.1 + 1.0));
+            let hit = mover.sweep(Vec2::from(velocity), &target).unwrap();
+            assert_eq!(hit.time, 0.5);
+            assert_eq!(hit.normal, Vec2::from(normal));
+        }
+    }
+
+    #[test]
+    fn contained_points_and_corners() {
+        let a = aabb((-1.0, -1.0), (1.0, 1.0));
+
+        assert!(a.contains_point(Vec2::ZERO));
+        assert!(a.contains_point(Vec2::new(-1.0, 1.0)));
+        assert!(!a.contains_point(Vec2::new(-1.0, 1.5)));
+        assert!(a.intersects(&a));
+        assert!(a.intersects(&Aabb2D::from_center_size(Vec2::ZERO, Vec2::splat(0.5))));
+    }
+
+    #[test]
+    fn sweep_misses() {
+        let mover = aabb((0.0, 0.0), (1.0, 1.0));
+        let wall = aabb((3.0, 0.0), (4.0, 1.0));
+
+        // Too short, moving away, and passing above
+        assert_eq!(mover.swept_intersects(Vec2::new(1.5, 0.0), &wall), None);
+        assert_eq!(mover.swept_intersects(Vec2::new(-4.0, 0.0), &wall), None);
+        assert_eq!(mover.swept_intersects(Vec2::new(4.0, 4.0), &wall), None);
+    }
+
+    #[test]
+    fn parallel_sweeps() {
+        let mover = aabb((0.0, 0.0), (1.0, 1.0));
+
+        // Sliding along a shared edge isn't a hit
+        let ground = aabb((-5.0, -1.0), (5.0, 0.0));
+        assert_eq!(mover.swept_intersects(Vec2::new(3.0, 0.0), &ground), None);
+
+        // Nor is moving in a lane beside the other box
+        let beside = aabb((3.0, 2.0), (4.0, 3.0));
+        assert_eq!(mover.swept_intersects(Vec2::new(4.0, 0.0), &beside), None);
+
+        // Moving parallel to the face it runs into is
+        let in_lane = aabb((3.0, 0.5), (4.0, 1.5));
+        let hit = mover.sweep(Vec2::new(4.0, 0.0), &in_lane).unwrap();
+        assert_eq!(hit.time, 0.5);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn sweep_starting_inside_hits_at_zero() {
+        let a = aabb((0.0, 0.0), (2.0, 2.0));
+        let b = aabb((1.0, 1.0), (3.0, 3.0));
+
+        let hit = a.sweep(Vec2::ZERO, &b).unwrap();
+        assert_eq!(hit.time, 0.0);
+        assert_eq!(hit.normal, Vec2::ZERO);
+        assert_eq!(a.swept_intersects(Vec2::new(1.0, 0.0), &b), Some(0.0));
+    }
+
+    #[test]
+    fn zero_size_sweeps() {
+        let wall = aabb((3.0, 0.0), (4.0, 2.0));
+
+        // A point runs into a box, and a box into a point
+        assert_eq!(
+            point(0.0, 1.0).swept_intersects(Vec2::new(6.0, 0.0), &wall),
+            Some(0.5)
+        );
+        let mover = aabb((0.0, 0.0), (1.0, 1.0));
+        assert_eq!(
+            mover.swept_intersects(Vec2::new(4.0, 0.0), &point(3.0, 0.5)),
+            Some(0.5)
+        );
+
+        // Points that coincide already overlap; touching ones don't
+        assert_eq!(
+            point(1.0, 1.0).swept_intersects(Vec2::ZERO, &point(1.0, 1.0)),
+            Some(0.0)
+        );
+        assert_eq!(point(3.0, 2.0).swept_intersects(Vec2::ZERO, &wall), None);
+    }
+}
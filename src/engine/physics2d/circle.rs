@@ -0,0 +1,130 @@
+use glam::Vec2;
+
+use crate::engine::physics::aabb::Aabb2D;
+
+/// Circle collider in 2D.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self {
+            center,
+            radius: radius.abs(),
+        }
+    }
+
+    /// Smallest box containing the circle.
+    pub fn bounds(&self) -> Aabb2D {
+        Aabb2D::from_center_size(self.center, Vec2::splat(self.radius * 2.0))
+    }
+
+    /// True if the circles overlap or touch.
+    pub fn intersects(&self, other: &Circle) -> bool {
+        let reach = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= reach * reach
+    }
+
+    /// True if the circle overlaps or touches `aabb`.
+    pub fn intersects_aabb(&self, aabb: &Aabb2D) -> bool {
+        let closest = self.center.clamp(aabb.min, aabb.max);
+        self.center.distance_squared(closest) <= self.radius * self.radius
+    }
+
+    /// True if `p` lies inside the circle or on its boundary.
+    pub fn contains_point(&self, p: Vec2) -> bool {
+        self.center.distance_squared(p) <= self.radius * self.radius
+    }
+}
+
+/// Either collider shape, for code handling both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collider2D {
+    Aabb(Aabb2D),
+    Circle(Circle),
+}
+
+impl Collider2D {
+    pub fn bounds(&self) -> Aabb2D {
+        match self {
+            Collider2D::Aabb(aabb) => *aabb,
+            Collider2D::Circle(circle) => circle.bounds(),
+        }
+    }
+
+    pub fn intersects(&self, other: &Collider2D) -> bool {
+        match (self, other) {
+            (Collider2D::Aabb(a), Collider2D::Aabb(b)) => a.intersects(b),
+            (Collider2D::Aabb(a), Collider2D::Circle(b)) => b.intersects_aabb(a),
+            (Collider2D::Circle(a), Collider2D::Aabb(b)) => a.intersects_aabb(b),
+            (Collider2D::Circle(a), Collider2D::Circle(b)) => a.intersects(b),
+        }
+    }
+
+    pub fn contains_point(&self, p: Vec2) -> bool {
+        match self {
+            Collider2D::Aabb(aabb) => aabb.contains_point(p),
+            Collider2D::Circle(circle) => circle.contains_point(p),
+        }
+    }
+}
+
+impl From<Aabb2D> for Collider2D {
+    fn from(aabb: Aabb2D) -> Self {
+        Collider2D::Aabb(aabb)
+    }
+}
+
+impl From<Circle> for Collider2D {
+    fn from(circle: Circle) -> Self {
+        Collider2D::Circle(circle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circles_touching_at_one_point_intersect() {
+        let a = Circle::new(Vec2::ZERO, 1.0);
+        assert!(a.intersects(&Circle::new(Vec2::new(3.0, 0.0), 2.0)));
+        assert!(!a.intersects(&Circle::new(Vec2::new(3.1, 0.0), 2.0)));
+        // Negative radii are taken as positive
+        assert_eq!(Circle::new(Vec2::ZERO, -1.0), a);
+    }
+
+    #[test]
+    fn circles_miss_a_box_corner_their_bounds_overlap() {
+        let aabb = Aabb2D::new(Vec2::ZERO, Vec2::ONE);
+        let circle = Circle::new(Vec2::new(1.8, 1.8), 1.0);
+        assert!(circle.bounds().intersects(&aabb));
+        assert!(!circle.intersects_aabb(&aabb));
+        assert!(!aabb.intersects_circle(&circle));
+
+        let closer = Circle::new(Vec2::new(1.5, 1.5), 1.0);
+        assert!(closer.intersects_aabb(&aabb));
+        assert!(aabb.intersects_circle(&closer));
+        // Centered inside the box
+        assert!(Circle::new(Vec2::splat(0.5), 0.1).intersects_aabb(&aabb));
+    }
+
+    #[test]
+    fn colliders_dispatch_on_both_shapes() {
+        let aabb = Collider2D::from(Aabb2D::new(Vec2::ZERO, Vec2::ONE));
+        let circle = Collider2D::from(Circle::new(Vec2::new(2.0, 0.5), 1.0));
+        let far = Collider2D::from(Circle::new(Vec2::new(5.0, 5.0), 1.0));
+
+        assert!(aabb.intersects(&circle) && circle.intersects(&aabb));
+        assert!(!aabb.intersects(&far) && !far.intersects(&circle));
+        assert!(circle.contains_point(Vec2::new(2.5, 0.5)));
+        assert!(!circle.contains_point(Vec2::new(2.9, 1.4)));
+        assert_eq!(
+            circle.bounds(),
+            Aabb2D::new(Vec2::new(1.0, -0.5), Vec2::new(3.0, 1.5))
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use glam::Vec2;
+use std::collections::HashMap;
+
+use crate::engine::physics::aabb::Aabb2D;
+
+/// Uniform grid bucketing boxes by the cells they cover, so finding
+/// overlaps only compares boxes sharing a cell instead of every pair.
+///
+/// Rebuilt every frame: [`SpatialGrid::clear`], then
+/// [`SpatialGrid::insert`] every box. Cells should be around the size of
+/// a typical box; much smaller ones make big boxes cover many cells, much
+/// larger ones put many boxes in each.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<u32>>,
+    bounds: Vec<Aabb2D>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: Vec::new(),
+        }
+    }
+
+    /// Removes every box. Cells used since the last clear keep their
+    /// allocation, since moving objects mostly stay in the same ones.
+    pub fn clear(&mut self) {
+        self.cells.retain(|_, ids| {
+            let used = !ids.is_empty();
+            ids.clear();
+            used
+        });
+        self.bounds.clear();
+    }
+
+    /// Adds `bounds`, returning its id: the number of boxes inserted before
+    /// it since the last clear.
+    pub fn insert(&mut self, bounds: Aabb2D) -> u32 {
+        let id = self.bounds.len() as u32;
+        let (min, max) = (self.cell(bounds.min), self.cell(bounds.max));
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                self.cells.entry((x, y)).or_default().push(id);
+            }
+        }
+        self.bounds.push(bounds);
+        id
+    }
+
+    /// Fills `out` with every pair of ids whose boxes overlap or touch,
+    /// each once, the smaller id first.
+    pub fn pairs(&self, out: &mut Vec<(u32, u32)>) {
+        out.clear();
+        for (&cell, ids) in &self.cells {
+            for (i, &a) in ids.iter().enumerate() {
+                for &b in &ids[i + 1..] {
+                    let (bounds_a, bounds_b) = (&self.bounds[a as usize], &self.bounds[b as usize]);
+                    // Overlapping boxes share every cell their overlap
+                    // covers; only the one holding its min corner reports
+                    // them
+                    if bounds_a.intersects(bounds_b)
+                        && self.cell(bounds_a.min.max(bounds_b.min)) == cell
+                    {
+                        out.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fills `out` with the ids of every box overlapping or touching
+    /// `area`, in ascending order.
+    pub fn query(&self, area: &Aabb2D, out: &mut Vec<u32>) {
+        out.clear();
+        let (min, max) = (self.cell(area.min), self.cell(area.max));
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                let Some(ids) = self.cells.get(&(x, y)) else {
+                    continue;
+                };
+                out.extend(
+                    ids.iter()
+                        .filter(|&&id| self.bounds[id as usize].intersects(area)),
+                );
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    fn cell(&self, p: Vec2) -> (i32, i32) {
+        let cell = (p / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    //--Getters--//
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// The box inserted as `id`.
+    pub fn bounds(&self, id: u32) -> Option<&Aabb2D> {
+        self.bounds.get(id as usize)
+    }
+
+    /// Number of boxes inserted since the last clear.
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A loose diagonal of overlapping boxes of mixed sizes, some spanning
+    /// several cells, and the pairs found by testing every one.
+    fn boxes() -> (Vec<Aabb2D>, Vec<(u32, u32)>) {
+        let boxes: Vec<_> = (0..40)
+            .map(|i| {
+                let i = i as f32;
+                let min = Vec2::new(i * 7.0 % 90.0, i * 13.0 % 70.0);
+                Aabb2D::new(min, min + Vec2::splat(5.0 + i % 4.0 * 8.0))
+            })
+            .collect();
+        let mut expected = Vec::new();
+        for a in 0..boxes.len() {
+            for b in a + 1..boxes.len() {
+                if boxes[a].intersects(&boxes[b]) {
+                    expected.push((a as u32, b as u32));
+                }
+            }
+        }
+        (boxes, expected)
+    }
+
+    #[test]
+    fn pairs_match_testing_every_pair_once() {
+        let (boxes, expected) = boxes();
+        assert!(!expected.is_empty());
+        let mut grid = SpatialGrid::new(10.0);
+        for bounds in &boxes {
+            grid.insert(*bounds);
+        }
+
+        let mut pairs = Vec::new();
+        grid.pairs(&mut pairs);
+        pairs.sort_unstable();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn query_finds_boxes_touching_the_area() {
+        let mut grid = SpatialGrid::new(4.0);
+        let small = grid.insert(Aabb2D::new(Vec2::ZERO, Vec2::ONE));
+        let wide = grid.insert(Aabb2D::new(Vec2::new(-20.0, 0.0), Vec2::new(20.0, 1.0)));
+        grid.insert(Aabb2D::new(Vec2::splat(50.0), Vec2::splat(51.0)));
+
+        let mut ids = Vec::new();
+        grid.query(&Aabb2D::new(Vec2::splat(0.5), Vec2::splat(0.5)), &mut ids);
+        assert_eq!(ids, vec![small, wide]);
+        grid.query(
+            &Aabb2D::new(Vec2::new(15.0, 0.0), Vec2::new(16.0, 2.0)),
+            &mut ids,
+        );
+        assert_eq!(ids, vec![wide]);
+
+        grid.clear();
+        assert!(grid.is_empty());
+        grid.query(&Aabb2D::new(Vec2::splat(0.5), Vec2::splat(0.5)), &mut ids);
+        assert!(ids.is_empty());
+    }
+}
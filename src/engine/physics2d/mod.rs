@@ -0,0 +1,318 @@
+use glam::Vec2;
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+pub mod circle;
+pub mod grid;
+
+// The box collider is physics::aabb's Aabb2D, under its component name
+pub use crate::engine::physics::aabb::{Aabb2D as Aabb, Hit};
+pub use circle::{Circle, Collider2D};
+
+use crate::engine::ecs::{Entity, World};
+use crate::engine::events::{CollisionBegan, CollisionEnded, EventBus};
+use grid::SpatialGrid;
+
+/// Default [`Physics2D::set_cell_size`], a few sprites across.
+pub const DEFAULT_CELL_SIZE: f32 = 64.0;
+
+/// Collision detection between [`Aabb`] and [`Circle`] components.
+///
+/// Colliders are plain components in world space, kept in sync with their
+/// sprites by game code. Once per frame, after the update callbacks and
+/// physics steps, the window runs [`Physics2D::detect`] on the active
+/// scene's [`World`], which records the touching pairs and sends
+/// [`CollisionBegan`] and [`CollisionEnded`]. A [`SpatialGrid`] keeps it
+/// to comparing nearby colliders.
+pub struct Physics2D {
+    grid: SpatialGrid,
+    /// The collider behind each grid id.
+    colliders: Vec<(Entity, Collider2D)>,
+    candidates: Vec<(u32, u32)>,
+    /// Sorted, as of the last detection.
+    pairs: Vec<(Entity, Entity)>,
+    touching: HashSet<(Entity, Entity)>,
+}
+
+impl Physics2D {
+    fn new() -> Self {
+        Self {
+            grid: SpatialGrid::new(DEFAULT_CELL_SIZE),
+            colliders: Vec::new(),
+            candidates: Vec::new(),
+            pairs: Vec::new(),
+            touching: HashSet::new(),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<Physics2D> {
+        static INSTANCE: Lazy<Mutex<Physics2D>> = Lazy::new(|| Mutex::new(Physics2D::new()));
+
+        &INSTANCE
+    }
+
+    /// Sets the broad phase's cell size in world units. Around the size of
+    /// a typical collider works best.
+    pub fn set_cell_size(cell_size: f32) {
+        let mut guard = Self::get_instance().lock().unwrap();
+        let physics = &mut *guard;
+        physics.grid = SpatialGrid::new(cell_size);
+        for (_, collider) in &physics.colliders {
+            physics.grid.insert(collider.bounds());
+        }
+    }
+
+    /// Tests every [`Aabb`] and [`Circle`] in `world` against each other,
+    /// replacing the touching pairs and sending [`CollisionBegan`] and
+    /// [`CollisionEnded`] for the pairs that changed since the last call.
+    /// An entity with both kinds of collider counts once per pair.
+    ///
+    /// The window already calls this for the active [`Scene`]'s world
+    /// every frame.
+    ///
+    /// [`Scene`]: crate::engine::scene::Scene
+    pub fn detect(world: &World) {
+        let mut guard = Self::get_instance().lock().unwrap();
+        let physics = &mut *guard;
+
+        physics.colliders.clear();
+        physics.colliders.extend(
+            world
+                .query::<Aabb>()
+                .map(|(e, aabb)| (e, Collider2D::Aabb(*aabb))),
+        );
+        physics.colliders.extend(
+            world
+                .query::<Circle>()
+                .map(|(e, circle)| (e, Collider2D::Circle(*circle))),
+        );
+        physics.grid.clear();
+        for (_, collider) in &physics.colliders {
+            physics.grid.insert(collider.bounds());
+        }
+
+        physics.grid.pairs(&mut physics.candidates);
+        physics.pairs.clear();
+        for &(a, b) in &physics.candidates {
+            let (entity_a, collider_a) = &physics.colliders[a as usize];
+            let (entity_b, collider_b) = &physics.colliders[b as usize];
+            if entity_a != entity_b && collider_a.intersects(collider_b) {
+                physics
+                    .pairs
+                    .push((*entity_a.min(entity_b), *entity_a.max(entity_b)));
+            }
+        }
+        physics.pairs.sort_unstable();
+        physics.pairs.dedup();
+
+        let touching: HashSet<_> = physics.pairs.iter().copied().collect();
+        for pair in &physics.pairs {
+            if !physics.touching.contains(pair) {
+                EventBus::send(CollisionBegan(pair.0, pair.1));
+            }
+        }
+        let mut ended: Vec<_> = physics.touching.difference(&touching).collect();
+        ended.sort_unstable();
+        for pair in ended {
+            EventBus::send(CollisionEnded(pair.0, pair.1));
+        }
+        physics.touching = touching;
+    }
+
+    /// Forgets every collider and touching pair without sending
+    /// [`CollisionEnded`]. The [`SceneManager`] calls this when the active
+    /// scene changes, as entities of different worlds can't be compared.
+    ///
+    /// [`SceneManager`]: crate::engine::scene::SceneManager
+    pub fn clear() {
+        let mut physics = Self::get_instance().lock().unwrap();
+        physics.grid.clear();
+        physics.colliders.clear();
+        physics.pairs.clear();
+        physics.touching.clear();
+    }
+
+    /// First collider hit by `aabb` moving by `velocity`, and where, so
+    /// fast movers like bullets can't pass through thin walls between two
+    /// frames. `ignore` is usually the mover itself. Circles are swept
+    /// against as their bounding boxes. Uses the colliders as of the last
+    /// [`Physics2D::detect`].
+    pub fn sweep(aabb: &Aabb, velocity: Vec2, ignore: Option<Entity>) -> Option<(Entity, Hit)> {
+        let physics = Self::get_instance().lock().unwrap();
+        let moved = Aabb::new(aabb.min + velocity, aabb.max + velocity);
+        let area = Aabb::new(aabb.min.min(moved.min), aabb.max.max(moved.max));
+        let mut ids = Vec::new();
+        physics.grid.query(&area, &mut ids);
+
+        ids.into_iter()
+            .filter_map(|id| {
+                let (entity, collider) = &physics.colliders[id as usize];
+                if Some(*entity) == ignore {
+                    return None;
+                }
+                Some((*entity, aabb.sweep(velocity, &collider.bounds())?))
+            })
+            .min_by(|(_, a), (_, b)| a.time.total_cmp(&b.time))
+    }
+
+    //--Getters--//
+
+    /// Every pair of entities whose colliders touched at the last
+    /// [`Physics2D::detect`], sorted, the smaller entity first.
+    pub fn pairs() -> Vec<(Entity, Entity)> {
+        Self::get_instance().lock().unwrap().pairs.clone()
+    }
+
+    pub fn is_touching(a: Entity, b: Entity) -> bool {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .touching
+            .contains(&(a.min(b), a.max(b)))
+    }
+
+    /// Entities with a collider containing `point`, e.g. the mouse in
+    /// world space, sorted and as of the last [`Physics2D::detect`].
+    pub fn entities_at(point: Vec2) -> Vec<Entity> {
+        let physics = Self::get_instance().lock().unwrap();
+        let mut ids = Vec::new();
+        physics.grid.query(&Aabb::new(point, point), &mut ids);
+
+        let mut entities: Vec<_> = ids
+            .into_iter()
+            .map(|id| &physics.colliders[id as usize])
+            .filter(|(_, collider)| collider.contains_point(point))
+            .map(|(entity, _)| *entity)
+            .collect();
+        entities.sort_unstable();
+        entities.dedup();
+        entities
+    }
+}
+
+/// Held by tests that use [`Physics2D`], directly or through scene
+/// changes, which every test in the crate shares. Starts from a clean one.
+#[cfg(test)]
+pub(crate) fn test_guard() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let guard = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    *Physics2D::get_instance().lock().unwrap() = Physics2D::new();
+    guard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(world: &mut World, collider: impl Into<Collider2D>) -> Entity {
+        let entity = world.spawn();
+        match collider.into() {
+            Collider2D::Aabb(aabb) => {
+                world.insert(entity, aabb);
+            }
+            Collider2D::Circle(circle) => {
+                world.insert(entity, circle);
+            }
+        }
+        entity
+    }
+
+    /// Runs detection and returns the collision events it sent.
+    fn detect(world: &World) -> (Vec<CollisionBegan>, Vec<CollisionEnded>) {
+        // Leftovers from earlier tests become current and are dropped
+        EventBus::update();
+        Physics2D::detect(world);
+        EventBus::update();
+        (EventBus::read(), EventBus::read())
+    }
+
+    #[test]
+    fn touching_pairs_begin_and_end_once() {
+        let _guard = test_guard();
+        let mut world = World::new();
+        let wall = spawn(&mut world, Aabb::new(Vec2::ZERO, Vec2::new(10.0, 10.0)));
+        let ball = spawn(&mut world, Circle::new(Vec2::new(12.0, 5.0), 1.0));
+        let far = spawn(&mut world, Circle::new(Vec2::new(100.0, 5.0), 1.0));
+
+        assert_eq!(detect(&world), (vec![], vec![]));
+
+        world.insert(ball, Circle::new(Vec2::new(10.5, 5.0), 1.0));
+        assert_eq!(detect(&world), (vec![CollisionBegan(wall, ball)], vec![]));
+        assert_eq!(Physics2D::pairs(), vec![(wall, ball)]);
+        assert!(Physics2D::is_touching(ball, wall));
+        assert!(!Physics2D::is_touching(wall, far));
+
+        // Still touching: no new events
+        assert_eq!(detect(&world), (vec![], vec![]));
+
+        world.despawn(ball);
+        assert_eq!(detect(&world), (vec![], vec![CollisionEnded(wall, ball)]));
+        assert!(Physics2D::pairs().is_empty());
+    }
+
+    #[test]
+    fn an_entity_with_both_colliders_counts_once_per_pair() {
+        let _guard = test_guard();
+        let mut world = World::new();
+        let both = spawn(&mut world, Aabb::new(Vec2::ZERO, Vec2::ONE));
+        world.insert(both, Circle::new(Vec2::splat(0.5), 1.0));
+        let other = spawn(&mut world, Circle::new(Vec2::new(1.5, 0.5), 1.0));
+
+        let (began, _) = detect(&world);
+        assert_eq!(began, vec![CollisionBegan(both, other)]);
+        assert_eq!(Physics2D::pairs(), vec![(both, other)]);
+    }
+
+    #[test]
+    fn entities_at_picks_every_collider_under_a_point() {
+        let _guard = test_guard();
+        let mut world = World::new();
+        let big = spawn(&mut world, Aabb::new(Vec2::ZERO, Vec2::splat(100.0)));
+        let round = spawn(&mut world, Circle::new(Vec2::splat(10.0), 5.0));
+        Physics2D::detect(&world);
+
+        assert_eq!(Physics2D::entities_at(Vec2::splat(10.0)), vec![big, round]);
+        // Inside the circle's bounds but not the circle
+        assert_eq!(Physics2D::entities_at(Vec2::splat(14.5)), vec![big]);
+        assert!(Physics2D::entities_at(Vec2::splat(-1.0)).is_empty());
+
+        Physics2D::set_cell_size(8.0);
+        assert_eq!(Physics2D::entities_at(Vec2::splat(10.0)), vec![big, round]);
+    }
+
+    #[test]
+    fn sweeps_stop_fast_movers_at_thin_walls() {
+        let _guard = test_guard();
+        let mut world = World::new();
+        let bullet = spawn(&mut world, Aabb::new(Vec2::ZERO, Vec2::ONE));
+        let thin = spawn(
+            &mut world,
+            Aabb::new(Vec2::new(50.0, 0.0), Vec2::new(51.0, 1.0)),
+        );
+        let thick = spawn(
+            &mut world,
+            Aabb::new(Vec2::new(80.0, 0.0), Vec2::new(90.0, 1.0)),
+        );
+        Physics2D::detect(&world);
+
+        // Jumps past the thin wall in one step; the sweep still finds it
+        let aabb = *world.get::<Aabb>(bullet).unwrap();
+        let (hit, result) = Physics2D::sweep(&aabb, Vec2::new(98.0, 0.0), Some(bullet)).unwrap();
+        assert_eq!(hit, thin);
+        assert!((result.time - 0.5).abs() < 1e-6);
+        assert_eq!(result.normal, Vec2::NEG_X);
+
+        // Without ignoring it, the mover hits itself straight away
+        let (hit, result) = Physics2D::sweep(&aabb, Vec2::new(98.0, 0.0), None).unwrap();
+        assert_eq!((hit, result.time), (bullet, 0.0));
+
+        let past_thin = Aabb::new(Vec2::new(60.0, 0.0), Vec2::new(61.0, 1.0));
+        let (hit, result) = Physics2D::sweep(&past_thin, Vec2::new(38.0, 0.0), None).unwrap();
+        assert_eq!(hit, thick);
+        assert!((result.time - 0.5).abs() < 1e-6);
+        assert!(Physics2D::sweep(&aabb, Vec2::new(0.0, 50.0), Some(bullet)).is_none());
+    }
+}
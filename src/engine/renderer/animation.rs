@@ -0,0 +1,370 @@
+use std::sync::Arc;
+
+use super::sprite_atlas::SpriteAtlas;
+use crate::engine::window::Window;
+
+/// What an [`AnimationPlayer`] does after the last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Starts over from the first frame.
+    Loop,
+    /// Stops on the last frame and reports finished.
+    Once,
+    /// Plays backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+/// One frame of an [`Animation`]: an atlas region shown for `duration`
+/// seconds.
+#[derive(Clone, Debug)]
+pub struct AnimationFrame {
+    pub region: String,
+    pub duration: f64,
+}
+
+/// An ordered list of atlas regions with per-frame durations.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    mode: LoopMode,
+}
+
+impl Animation {
+    /// An animation with no frames yet; add them with
+    /// [`Animation::with_frame`].
+    pub fn new(mode: LoopMode) -> Self {
+        Self {
+            frames: Vec::new(),
+            mode,
+        }
+    }
+
+    /// Every region in `regions` shown for the same `frame_duration`.
+    pub fn from_regions(regions: &[&str], frame_duration: f64, mode: LoopMode) -> Self {
+        regions.iter().fold(Self::new(mode), |animation, region| {
+            animation.with_frame(*region, frame_duration)
+        })
+    }
+
+    /// The atlas regions at `indices` (source order, as in
+    /// [`SpriteAtlas::name_at`]), e.g. one row of a grid sheet. Indices past
+    /// the end of the atlas are skipped.
+    pub fn from_atlas_range(
+        atlas: &SpriteAtlas,
+        indices: std::ops::Range<usize>,
+        frame_duration: f64,
+        mode: LoopMode,
+    ) -> Self {
+        indices
+            .filter_map(|i| atlas.name_at(i))
+            .fold(Self::new(mode), |animation, region| {
+                animation.with_frame(region, frame_duration)
+            })
+    }
+
+    pub fn with_frame(mut self, region: impl Into<String>, duration: f64) -> Self {
+        self.frames.push(AnimationFrame {
+            region: region.into(),
+            duration: duration.max(0.0),
+        });
+        self
+    }
+
+    /// Time for the playback to return to the same frame going the same
+    /// way. For `Once` this is simply the total duration.
+    fn cycle_duration(&self) -> f64 {
+        let total: f64 = self.frames.iter().map(|f| f.duration).sum();
+        match self.mode {
+            LoopMode::Loop | LoopMode::Once => total,
+            // The end frames are shown once per cycle, the rest twice
+            LoopMode::PingPong if self.frames.len() > 2 => {
+                let inner = &self.frames[1..self.frames.len() - 1];
+                total + inner.iter().map(|f| f.duration).sum::<f64>()
+            }
+            LoopMode::PingPong => total,
+        }
+    }
+
+    //--Getters--//
+
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    pub fn mode(&self) -> LoopMode {
+        self.mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Sum of all frame durations.
+    pub fn duration(&self) -> f64 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+/// Steps through an [`Animation`] with the frame delta. Draw the current
+/// frame with [`SpriteRenderer::draw_animation`](super::sprite_renderer::SpriteRenderer::draw_animation).
+pub struct AnimationPlayer {
+    animation: Arc<Animation>,
+    frame: usize,
+    timer: f64,
+    speed: f64,
+    playing: bool,
+    finished: bool,
+    /// Playing backwards through a `PingPong` animation.
+    reversed: bool,
+}
+
+impl AnimationPlayer {
+    /// Starts playing `animation` from its first frame.
+    pub fn new(animation: Arc<Animation>) -> Self {
+        Self {
+            animation,
+            frame: 0,
+            timer: 0.0,
+            speed: 1.0,
+            playing: true,
+            finished: false,
+            reversed: false,
+        }
+    }
+
+    /// Resumes playback, restarting a finished animation.
+    pub fn play(&mut self) {
+        if self.finished {
+            self.rewind();
+        }
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Pauses and goes back to the first frame.
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.rewind();
+    }
+
+    fn rewind(&mut self) {
+        self.frame = 0;
+        self.timer = 0.0;
+        self.finished = false;
+        self.reversed = false;
+    }
+
+    /// Multiplies the delta passed to [`AnimationPlayer::update`]. Negative
+    /// values are treated as 0.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Switches to `animation` and plays it. With `reset` it starts from the
+    /// first frame; otherwise the current frame index and the time spent in
+    /// it carry over (clamped to the new animation), which keeps e.g. a walk
+    /// cycle in step when swapping to a different facing. Switching to the
+    /// animation already playing without `reset` does nothing.
+    pub fn set_animation(&mut self, animation: Arc<Animation>, reset: bool) {
+        if !reset && Arc::ptr_eq(&self.animation, &animation) {
+            return;
+        }
+        self.animation = animation;
+        if reset {
+            self.rewind();
+        } else {
+            self.frame = self.frame.min(self.animation.len().saturating_sub(1));
+            self.finished = false;
+        }
+        self.playing = true;
+    }
+
+    /// Advances playback by `dt` seconds. Returns true on the update in which
+    /// a `Once` animation reaches its end, however large `dt` is; after that
+    /// [`AnimationPlayer::finished`] stays true until the player is
+    /// restarted. An animation without frames finishes immediately. While
+    /// playing it keeps an
+    /// [`OnDemand`](crate::engine::window::RedrawMode::OnDemand) window
+    /// drawing.
+    pub fn update(&mut self, dt: f64) -> bool {
+        if !self.playing || self.finished {
+            return false;
+        }
+        let animation = self.animation.clone();
+        let frames = animation.frames();
+        if frames.is_empty() {
+            self.finished = true;
+            return true;
+        }
+        Window::invalidate();
+
+        self.timer += dt * self.speed;
+        if animation.mode != LoopMode::Once {
+            let cycle = animation.cycle_duration();
+            if cycle <= 0.0 {
+                // Every frame is zero-length, so there is nothing to time
+                self.timer = 0.0;
+                return false;
+            }
+            // Skip whole cycles rather than stepping through them one frame
+            // at a time
+            self.timer %= cycle;
+        }
+
+        while self.timer >= frames[self.frame].duration {
+            self.timer -= frames[self.frame].duration;
+            if !self.advance(frames.len(), animation.mode) {
+                self.timer = 0.0;
+                self.finished = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Moves to the next frame. Returns false at the end of a `Once`
+    /// animation.
+    fn advance(&mut self, len: usize, mode: LoopMode) -> bool {
+        let last = len - 1;
+        match mode {
+            LoopMode::Once if self.frame == last => return false,
+            LoopMode::Once => self.frame += 1,
+            LoopMode::Loop => self.frame = (self.frame + 1) % len,
+            LoopMode::PingPong if last == 0 => {}
+            LoopMode::PingPong => {
+                if self.reversed && self.frame == 0 || !self.reversed && self.frame == last {
+                    self.reversed = !self.reversed;
+                }
+                if self.reversed {
+                    self.frame -= 1;
+                } else {
+                    self.frame += 1;
+                }
+            }
+        }
+        true
+    }
+
+    //--Getters--//
+
+    pub fn animation(&self) -> &Arc<Animation> {
+        &self.animation
+    }
+
+    /// Atlas region of the current frame, `None` for an animation without
+    /// frames.
+    pub fn current_region(&self) -> Option<&str> {
+        self.animation
+            .frames()
+            .get(self.frame)
+            .map(|f| f.region.as_str())
+    }
+
+    /// `[u0, v0, u1, v1]` to draw for the current frame, `None` if the
+    /// region is missing from `atlas` or the animation has no frames.
+    pub fn current_uv_rect(&self, atlas: &SpriteAtlas) -> Option<[f32; 4]> {
+        self.current_region()
+            .and_then(|region| atlas.uv_rect(region))
+    }
+
+    /// Index of the current frame in the animation.
+    pub fn current_frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Whether a `Once` animation has reached its end.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing && !self.finished
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(mode: LoopMode) -> AnimationPlayer {
+        AnimationPlayer::new(Arc::new(Animation::from_regions(
+            &["a", "b", "c"],
+            0.1,
+            mode,
+        )))
+    }
+
+    #[test]
+    fn frames_advance_once_their_duration_passes() {
+        let mut player = player(LoopMode::Loop);
+        player.update(0.05);
+        assert_eq!(player.current_frame(), 0);
+        player.update(0.06);
+        assert_eq!(player.current_frame(), 1);
+        assert_eq!(player.current_region(), Some("b"));
+        player.update(0.1);
+        assert_eq!(player.current_frame(), 2);
+    }
+
+    #[test]
+    fn looping_wraps_to_the_first_frame() {
+        let mut player = player(LoopMode::Loop);
+        player.update(0.25);
+        assert_eq!(player.current_frame(), 2);
+        player.update(0.1);
+        assert_eq!(player.current_frame(), 0);
+        // Several cycles in one update land on the same frame as stepping
+        player.update(0.65);
+        assert_eq!(player.current_frame(), 0);
+        assert!(!player.finished());
+    }
+
+    #[test]
+    fn speed_scales_the_frame_rate() {
+        let mut player = player(LoopMode::Loop);
+        player.set_speed(2.0);
+        player.update(0.06);
+        assert_eq!(player.current_frame(), 1);
+
+        player.set_speed(-1.0);
+        assert_eq!(player.speed(), 0.0);
+        player.update(1.0);
+        assert_eq!(player.current_frame(), 1);
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let mut player = player(LoopMode::Once);
+        assert!(!player.update(0.25));
+        assert!(player.update(10.0));
+        assert_eq!(player.current_frame(), 2);
+        assert!(player.finished());
+        assert!(!player.update(0.1));
+
+        player.play();
+        assert_eq!(player.current_frame(), 0);
+        assert!(player.is_playing());
+    }
+
+    #[test]
+    fn ping_pong_turns_around_at_the_ends() {
+        let mut player = player(LoopMode::PingPong);
+        let mut frames = Vec::new();
+        for _ in 0..6 {
+            player.update(0.1);
+            frames.push(player.current_frame());
+        }
+        assert_eq!(frames, [1, 2, 1, 0, 1, 2]);
+    }
+}
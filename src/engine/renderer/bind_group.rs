@@ -0,0 +1,285 @@
+use bytemuck::Pod;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, Device, Sampler, ShaderStages, TextureView,
+};
+
+use super::texture::Texture2D;
+use super::uniform::UniformBuffer;
+
+/// Collects resources for one bind group, numbering bindings from 0 in the
+/// order they're added (or at the index given to the `add_*` methods), and
+/// creates the matching layout alongside the group.
+///
+/// ```ignore
+/// let (layout, group) = BindGroupBuilder::new(Some("Material"))
+///     .uniform(device, &params, ShaderStages::FRAGMENT)
+///     .texture(texture.view(), ShaderStages::FRAGMENT)
+///     .sampler(texture.sampler(), ShaderStages::FRAGMENT)
+///     .build(device);
+/// ```
+pub struct BindGroupBuilder<'a> {
+    label: Option<&'a str>,
+    layout_entries: Vec<wgpu::BindGroupLayoutEntry>,
+    resources: Vec<(u32, BindingResource<'a>)>,
+}
+
+impl<'a> BindGroupBuilder<'a> {
+    pub fn new(label: Option<&'a str>) -> Self {
+        Self {
+            label,
+            layout_entries: Vec::new(),
+            resources: Vec::new(),
+        }
+    }
+
+    /// One past the highest binding so far, so bindings added without an
+    /// index follow on from explicit ones.
+    fn next_binding(&self) -> u32 {
+        self.layout_entries
+            .iter()
+            .map(|entry| entry.binding + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn push(
+        mut self,
+        binding: u32,
+        ty: wgpu::BindingType,
+        visibility: ShaderStages,
+        resource: BindingResource<'a>,
+    ) -> Self {
+        assert!(
+            self.layout_entries
+                .iter()
+                .all(|entry| entry.binding != binding),
+            "binding {binding} is used twice"
+        );
+        self.layout_entries.push(wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty,
+            count: None,
+        });
+        self.resources.push((binding, resource));
+        self
+    }
+
+    /// Binds a uniform buffer. Dynamic buffers bind one element at a time
+    /// and take their offset in `set_bind_group`.
+    ///
+    /// # Panics
+    ///
+    /// If a dynamic buffer's stride isn't a multiple of the device's
+    /// `min_uniform_buffer_offset_alignment`.
+    pub fn uniform<T: Pod>(
+        self,
+        device: &Device,
+        buffer: &'a UniformBuffer<T>,
+        visibility: ShaderStages,
+    ) -> Self {
+        let binding = self.next_binding();
+        self.add_uniform_buffer(device, binding, buffer, visibility)
+    }
+
+    /// [`BindGroupBuilder::uniform`] at an explicit `binding`, for matching
+    /// a shader whose bindings have gaps or come in another order.
+    ///
+    /// # Panics
+    ///
+    /// If `binding` is already used, or as for `uniform`.
+    pub fn add_uniform_buffer<T: Pod>(
+        self,
+        device: &Device,
+        binding: u32,
+        buffer: &'a UniformBuffer<T>,
+        visibility: ShaderStages,
+    ) -> Self {
+        let dynamic = buffer.is_dynamic();
+        if dynamic {
+            let alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+            assert!(
+                buffer.stride().is_multiple_of(alignment),
+                "uniform stride {} is not a multiple of min_uniform_buffer_offset_alignment ({alignment})",
+                buffer.stride(),
+            );
+        }
+
+        let resource = BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: buffer.buffer(),
+            offset: 0,
+            size: dynamic.then(|| buffer.binding_size()),
+        });
+        self.push(
+            binding,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: dynamic,
+                min_binding_size: Some(buffer.binding_size()),
+            },
+            visibility,
+            resource,
+        )
+    }
+
+    /// Binds a filterable 2D float texture.
+    pub fn texture(self, view: &'a TextureView, visibility: ShaderStages) -> Self {
+        let binding = self.next_binding();
+        self.add_texture(binding, view, visibility)
+    }
+
+    /// [`BindGroupBuilder::texture`] at an explicit `binding`.
+    ///
+    /// # Panics
+    ///
+    /// If `binding` is already used.
+    pub fn add_texture(
+        self,
+        binding: u32,
+        view: &'a TextureView,
+        visibility: ShaderStages,
+    ) -> Self {
+        self.push(
+            binding,
+            wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            visibility,
+            BindingResource::TextureView(view),
+        )
+    }
+
+    /// Binds a filtering sampler.
+    pub fn sampler(self, sampler: &'a Sampler, visibility: ShaderStages) -> Self {
+        let binding = self.next_binding();
+        self.add_sampler(binding, sampler, visibility)
+    }
+
+    /// [`BindGroupBuilder::sampler`] at an explicit `binding`.
+    ///
+    /// # Panics
+    ///
+    /// If `binding` is already used.
+    pub fn add_sampler(self, binding: u32, sampler: &'a Sampler, visibility: ShaderStages) -> Self {
+        self.push(
+            binding,
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            visibility,
+            BindingResource::Sampler(sampler),
+        )
+    }
+
+    /// Just the layout, e.g. for a pipeline created before its resources.
+    pub fn build_layout(&self, device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: self.label,
+            entries: &self.layout_entries,
+        })
+    }
+
+    /// A group for an existing layout built from the same kinds of bindings.
+    pub fn build_group(self, device: &Device, layout: &BindGroupLayout) -> BindGroup {
+        let entries: Vec<_> = self
+            .resources
+            .into_iter()
+            .map(|(binding, resource)| wgpu::BindGroupEntry { binding, resource })
+            .collect();
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: self.label,
+            layout,
+            entries: &entries,
+        })
+    }
+
+    pub fn build(self, device: &Device) -> (BindGroupLayout, BindGroup) {
+        let layout = self.build_layout(device);
+        let group = self.build_group(device, &layout);
+        (layout, group)
+    }
+}
+
+/// Layout of a fragment-stage filterable 2D texture at binding 0 and a
+/// filtering sampler at binding 1, as `BindGroupBuilder::texture` and
+/// `sampler` would produce.
+pub(crate) fn texture_sampler_layout(device: &Device, label: Option<&str>) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label,
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// One bind group per [`Texture2D`] (view at binding 0, sampler at 1),
+/// created the first time the texture is drawn and dropped along with it.
+pub(crate) struct TextureBindGroups {
+    label: &'static str,
+    layout: BindGroupLayout,
+    /// Keyed by texture address; the weak handle spots reused addresses.
+    groups: HashMap<usize, (Weak<Texture2D>, BindGroup)>,
+}
+
+impl TextureBindGroups {
+    pub fn new(device: &Device, label: &'static str) -> Self {
+        let layout = texture_sampler_layout(device, Some(label));
+        Self {
+            label,
+            layout,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Forgets textures that no longer exist and creates groups for any new
+    /// ones in `textures`.
+    pub fn prepare<'t>(
+        &mut self,
+        device: &Device,
+        textures: impl IntoIterator<Item = &'t Arc<Texture2D>>,
+    ) {
+        self.groups
+            .retain(|_, (texture, _)| texture.strong_count() > 0);
+        for texture in textures {
+            let key = Arc::as_ptr(texture) as usize;
+            let stale = self
+                .groups
+                .get(&key)
+                .is_none_or(|(cached, _)| !cached.ptr_eq(&Arc::downgrade(texture)));
+            if stale {
+                let group = BindGroupBuilder::new(Some(self.label))
+                    .texture(texture.view(), ShaderStages::FRAGMENT)
+                    .sampler(texture.sampler(), ShaderStages::FRAGMENT)
+                    .build_group(device, &self.layout);
+                self.groups.insert(key, (Arc::downgrade(texture), group));
+            }
+        }
+    }
+
+    /// The group for `texture`, which must have gone through
+    /// [`TextureBindGroups::prepare`].
+    pub fn get(&self, texture: &Arc<Texture2D>) -> &BindGroup {
+        &self.groups[&(Arc::as_ptr(texture) as usize)].1
+    }
+
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+}
@@ -0,0 +1,90 @@
+use glam::{Mat4, Vec2, Vec3};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::engine::math::projection;
+use crate::engine::window::Window;
+use crate::input::mouse_listener::MouseInput;
+
+/// Orthographic 2D camera. World space is y-up with one unit per pixel at
+/// `zoom == 1`; `position` is the world point shown at the window center.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera2D {
+    pub position: Vec2,
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f32,
+    pub zoom: f32,
+}
+
+impl Camera2D {
+    pub fn new() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            zoom: 1.0,
+        }
+    }
+
+    /// The camera the engine renders world-space content with.
+    pub fn get_instance() -> &'static Mutex<Camera2D> {
+        static INSTANCE: Lazy<Mutex<Camera2D>> = Lazy::new(|| Mutex::new(Camera2D::new()));
+
+        &INSTANCE
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::from_scale(Vec3::new(self.zoom, self.zoom, 1.0))
+            * Mat4::from_rotation_z(-self.rotation)
+            * Mat4::from_translation(-self.position.extend(0.0))
+    }
+
+    pub fn projection(screen_size: Vec2) -> Mat4 {
+        let half = screen_size * 0.5;
+        projection::orthographic(-half.x, half.x, -half.y, half.y, -1.0, 1.0)
+    }
+
+    /// World to clip space for a window of `screen_size` pixels.
+    pub fn view_projection(&self, screen_size: Vec2) -> Mat4 {
+        Self::projection(screen_size) * self.view()
+    }
+
+    /// Converts window pixels (origin top-left, y down) to world space.
+    pub fn screen_to_world(&self, screen_pos: Vec2, screen_size: Vec2) -> Vec2 {
+        let centered = Vec2::new(
+            screen_pos.x - screen_size.x * 0.5,
+            screen_size.y * 0.5 - screen_pos.y,
+        );
+        self.view()
+            .inverse()
+            .transform_point3(centered.extend(0.0))
+            .truncate()
+    }
+
+    /// The world point under the cursor, e.g. to pick what's there with
+    /// [`Physics2D::entities_at`](crate::engine::physics2d::Physics2D::entities_at).
+    pub fn mouse_world_position(&self) -> Vec2 {
+        let size = Window::size();
+        let screen_size = Vec2::new(size.width.max(1) as f32, size.height.max(1) as f32);
+        self.screen_to_world(MouseInput::position(), screen_size)
+    }
+
+    /// Converts a world position to window pixels (origin top-left, y down).
+    pub fn world_to_screen(&self, world_pos: Vec2, screen_size: Vec2) -> Vec2 {
+        let centered = self.view().transform_point3(world_pos.extend(0.0));
+        Vec2::new(
+            centered.x + screen_size.x * 0.5,
+            screen_size.y * 0.5 - centered.y,
+        )
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Projection for window pixels (origin top-left, y down).
+pub fn screen_projection(screen_size: Vec2) -> Mat4 {
+    projection::orthographic(0.0, screen_size.x, screen_size.y, 0.0, -1.0, 1.0)
+}
@@ -0,0 +1,263 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use once_cell::sync::{Lazy, OnceCell};
+use std::f32::consts::FRAC_PI_2;
+use std::sync::Mutex;
+use wgpu::{BindGroup, BindGroupLayout, Device, Queue};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use super::bind_group::BindGroupBuilder;
+use super::uniform::UniformBuffer;
+use crate::engine::math::projection;
+use crate::input::key_listener::KeyInput;
+use crate::input::mouse_listener::MouseInput;
+
+/// Pitch stays just short of straight up/down so the view never flips.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Perspective camera. World space is right-handed and y-up; with zero yaw
+/// and pitch the camera looks down -z.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera3D {
+    pub position: Vec3,
+    /// Rotation about +y in radians; positive turns right.
+    pub yaw: f32,
+    /// Rotation above the horizon in radians.
+    pub pitch: f32,
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+    pub near: f32,
+    /// May be `f32::INFINITY` (the default) for an infinite far plane.
+    pub far: f32,
+    aspect: f32,
+}
+
+impl Camera3D {
+    pub fn new() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 60f32.to_radians(),
+            near: 0.1,
+            far: f32::INFINITY,
+            aspect: 1.0,
+        }
+    }
+
+    /// The camera the engine renders 3D content with.
+    pub fn get_instance() -> &'static Mutex<Camera3D> {
+        static INSTANCE: Lazy<Mutex<Camera3D>> = Lazy::new(|| Mutex::new(Camera3D::new()));
+
+        &INSTANCE
+    }
+
+    /// Updates the aspect ratio; the engine calls this whenever the window
+    /// is resized.
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    /// Turns the camera to face `target`.
+    pub fn look_at(&mut self, target: Vec3) {
+        let dir = (target - self.position).normalize_or_zero();
+        if dir != Vec3::ZERO {
+            self.yaw = dir.x.atan2(-dir.z);
+            self.pitch = dir.y.asin().clamp(-MAX_PITCH, MAX_PITCH);
+        }
+    }
+
+    /// Unit vector the camera looks along.
+    pub fn forward(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        Vec3::new(cos_pitch * sin_yaw, sin_pitch, -cos_pitch * cos_yaw)
+    }
+
+    /// Unit vector to the camera's right, always horizontal.
+    pub fn right(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        Vec3::new(cos_yaw, 0.0, sin_yaw)
+    }
+
+    pub fn view(&self) -> Mat4 {
+        projection::look_to(self.position, self.forward(), Vec3::Y)
+    }
+
+    /// Reverse-Z projection: depth is 1 at `near` and 0 at `far`.
+    pub fn projection(&self) -> Mat4 {
+        projection::perspective_reverse_z(self.fov_y, self.aspect, self.near, self.far)
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection() * self.view()
+    }
+
+    //--Getters--//
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect
+    }
+}
+
+impl Default for Camera3D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Free-flying controls for [`Camera3D`]: WASD to move, Q/E down/up, Shift
+/// to go faster, and mouse look while `look_button` is held. Disabled until
+/// [`FlyController::set_enabled`] is called.
+#[derive(Clone, Copy, Debug)]
+pub struct FlyController {
+    pub enabled: bool,
+    /// Units per second.
+    pub speed: f32,
+    /// Speed multiplier while Shift is held.
+    pub fast_multiplier: f32,
+    /// Radians per unit of raw mouse motion.
+    pub sensitivity: f32,
+    /// Mouse button that must be held to look around, or `None` to always
+    /// follow the mouse.
+    pub look_button: Option<usize>,
+}
+
+impl FlyController {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            speed: 5.0,
+            fast_multiplier: 4.0,
+            sensitivity: 0.003,
+            look_button: Some(1),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<FlyController> {
+        static INSTANCE: Lazy<Mutex<FlyController>> =
+            Lazy::new(|| Mutex::new(FlyController::new()));
+
+        &INSTANCE
+    }
+
+    pub fn set_enabled(enabled: bool) {
+        Self::get_instance().lock().unwrap().enabled = enabled;
+    }
+
+    /// Moves and turns `camera` from this frame's input.
+    pub fn update(&self, camera: &mut Camera3D, dt: f32) {
+        let down = |code| KeyInput::raw_key_down(&PhysicalKey::Code(code));
+        let axis = |negative, positive| f32::from(down(positive)) - f32::from(down(negative));
+
+        let mut speed = self.speed;
+        if down(KeyCode::ShiftLeft) || down(KeyCode::ShiftRight) {
+            speed *= self.fast_multiplier;
+        }
+        let movement = camera.forward() * axis(KeyCode::KeyS, KeyCode::KeyW)
+            + camera.right() * axis(KeyCode::KeyA, KeyCode::KeyD)
+            + Vec3::Y * axis(KeyCode::KeyQ, KeyCode::KeyE);
+        camera.position += movement.normalize_or_zero() * speed * dt;
+
+        if self.look_button.is_none_or(MouseInput::mouse_button_down) {
+            camera.yaw += MouseInput::get_raw_dx() as f32 * self.sensitivity;
+            // Raw mouse y grows downwards
+            camera.pitch -= MouseInput::get_raw_dy() as f32 * self.sensitivity;
+            camera.pitch = camera.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        }
+    }
+
+    /// Applies the controller to the active camera if it's enabled.
+    pub(crate) fn tick(dt: f64) {
+        let controller = *Self::get_instance().lock().unwrap();
+        if controller.enabled {
+            controller.update(&mut Camera3D::get_instance().lock().unwrap(), dt as f32);
+        }
+    }
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct CameraUniformData {
+    view_projection: [f32; 16],
+    position: [f32; 4],
+}
+
+/// GPU copy of the active [`Camera3D`], rewritten every frame. Pipelines
+/// that draw in 3D put [`CameraUniform::layout`] in their pipeline layout
+/// and bind [`CameraUniform::bind_group`]. In WGSL:
+///
+/// ```wgsl
+/// struct Camera {
+///     view_projection: mat4x4<f32>,
+///     position: vec4<f32>,
+/// };
+/// ```
+pub struct CameraUniform {
+    buffer: UniformBuffer<CameraUniformData>,
+    layout: BindGroupLayout,
+    bind_group: BindGroup,
+}
+
+static CAMERA_UNIFORM: OnceCell<CameraUniform> = OnceCell::new();
+
+impl CameraUniform {
+    /// `None` until the window has finished initializing the GPU.
+    pub fn get() -> Option<&'static CameraUniform> {
+        CAMERA_UNIFORM.get()
+    }
+
+    pub(crate) fn init(device: &Device) -> &'static CameraUniform {
+        CAMERA_UNIFORM.get_or_init(|| Self::new(device))
+    }
+
+    fn new(device: &Device) -> Self {
+        let buffer = UniformBuffer::new(
+            device,
+            Some("Camera Buffer"),
+            &CameraUniformData {
+                view_projection: Mat4::IDENTITY.to_cols_array(),
+                position: [0.0; 4],
+            },
+        );
+        let (layout, bind_group) = BindGroupBuilder::new(Some("Camera"))
+            .uniform(
+                device,
+                &buffer,
+                wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            )
+            .build(device);
+
+        Self {
+            buffer,
+            layout,
+            bind_group,
+        }
+    }
+
+    pub(crate) fn write(&self, queue: &Queue, camera: &Camera3D) {
+        let data = CameraUniformData {
+            view_projection: camera.view_projection().to_cols_array(),
+            position: camera.position.extend(1.0).to_array(),
+        };
+        self.buffer.write(queue, &data);
+    }
+
+    //--Getters--//
+
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
@@ -0,0 +1,62 @@
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+use wgpu::{Device, Queue};
+
+/// The device and queue the engine renders with, shared so game code can
+/// create GPU resources.
+pub struct GpuContext {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+}
+
+static INSTANCE: OnceCell<GpuContext> = OnceCell::new();
+
+impl GpuContext {
+    /// `None` until the window has finished initializing the GPU.
+    pub fn get() -> Option<&'static GpuContext> {
+        INSTANCE.get()
+    }
+
+    pub(crate) fn init(device: Arc<Device>, queue: Arc<Queue>) {
+        // Only the first window's device is published
+        let _ = INSTANCE.set(GpuContext { device, queue });
+    }
+}
+
+/// A device on whatever adapter is around, software ones included, for
+/// tests that need the GPU. `None` on machines without any.
+///
+/// Every test gets the same device, as a running engine only ever has one
+/// and caches like [`RenderTarget::layout`](super::render_target::RenderTarget::layout)
+/// rely on that.
+#[cfg(test)]
+pub(crate) fn test_device() -> Option<&'static (Device, Queue)> {
+    static DEVICE: once_cell::sync::Lazy<Option<(Device, Queue)>> =
+        once_cell::sync::Lazy::new(|| {
+            let instance = wgpu::Instance::default();
+            let adapter = pollster::block_on(
+                instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+            )?;
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .ok()
+        });
+
+    DEVICE.as_ref()
+}
+
+/// The [`test_device`] as `(device, queue)`, or returns from the calling
+/// test, noting that it was skipped, on machines without any adapter.
+#[cfg(test)]
+macro_rules! test_device_or_skip {
+    () => {
+        match $crate::engine::renderer::context::test_device() {
+            Some((device, queue)) => (device, queue),
+            None => {
+                eprintln!("no GPU adapter, skipping");
+                return;
+            }
+        }
+    };
+}
+#[cfg(test)]
+pub(crate) use test_device_or_skip;
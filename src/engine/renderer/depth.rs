@@ -0,0 +1,42 @@
+use wgpu::{Device, TextureFormat, TextureView};
+
+/// Format of the main pass depth buffer. Every pipeline drawn in that pass
+/// must declare it in its `depth_stencil` state.
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Depth cleared at the start of each frame. The engine uses reverse-Z
+/// (near = 1, far = 0), which keeps float precision where it's needed.
+pub const DEPTH_CLEAR: f32 = 0.0;
+
+/// Depth test for reverse-Z: closer fragments have larger depth.
+pub const DEPTH_COMPARE: wgpu::CompareFunction = wgpu::CompareFunction::Greater;
+
+pub(crate) fn create_depth_view(device: &Device, width: u32, height: u32) -> TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Depth state for overlays drawn in the main pass that should neither test
+/// against nor occlude the scene.
+pub(crate) fn overlay_depth_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
@@ -0,0 +1,87 @@
+use bytemuck::{Pod, Zeroable};
+use once_cell::sync::OnceCell;
+use wgpu::{BindGroup, BindGroupLayout, Device, Queue};
+
+use super::bind_group::BindGroupBuilder;
+use super::uniform::UniformBuffer;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct GlobalsData {
+    resolution: [f32; 2],
+    time: f32,
+    _padding: f32,
+}
+
+/// Per-frame values for animated shaders, rewritten before every frame is
+/// rendered. Pipelines put [`GlobalsUniform::layout`] in their pipeline
+/// layout and bind [`GlobalsUniform::bind_group`] at whatever group index
+/// they like. The group has one uniform buffer at binding 0, visible to
+/// the vertex and fragment stages. In WGSL:
+///
+/// ```wgsl
+/// struct Globals {
+///     // Surface size in pixels
+///     resolution: vec2<f32>,
+///     // Seconds since the window opened, stopping while paused and
+///     // wrapping back to 0 every hour
+///     time: f32,
+/// };
+///
+/// @group(0) @binding(0) var<uniform> globals: Globals;
+/// ```
+pub struct GlobalsUniform {
+    buffer: UniformBuffer<GlobalsData>,
+    layout: BindGroupLayout,
+    bind_group: BindGroup,
+}
+
+static GLOBALS_UNIFORM: OnceCell<GlobalsUniform> = OnceCell::new();
+
+impl GlobalsUniform {
+    /// `None` until the window has finished initializing the GPU.
+    pub fn get() -> Option<&'static GlobalsUniform> {
+        GLOBALS_UNIFORM.get()
+    }
+
+    pub(crate) fn init(device: &Device) -> &'static GlobalsUniform {
+        GLOBALS_UNIFORM.get_or_init(|| Self::new(device))
+    }
+
+    fn new(device: &Device) -> Self {
+        let buffer = UniformBuffer::new(device, Some("Globals Buffer"), &GlobalsData::zeroed());
+        let (layout, bind_group) = BindGroupBuilder::new(Some("Globals"))
+            .uniform(
+                device,
+                &buffer,
+                wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            )
+            .build(device);
+
+        Self {
+            buffer,
+            layout,
+            bind_group,
+        }
+    }
+
+    pub(crate) fn write(&self, queue: &Queue, time: f64, resolution: [f32; 2]) {
+        let data = GlobalsData {
+            resolution,
+            // Wrapped so f32 keeps enough precision in long sessions
+            time: (time % 3600.0) as f32,
+            _padding: 0.0,
+        };
+        self.buffer.write(queue, &data);
+    }
+
+    //--Getters--//
+
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
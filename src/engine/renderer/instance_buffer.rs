@@ -0,0 +1,97 @@
+use bytemuck::Pod;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use wgpu::{Buffer, Device, Queue};
+
+/// A vertex buffer of per-instance `T`s, bound alongside a mesh's vertex
+/// buffer with `step_mode: Instance`.
+pub struct InstanceBuffer<T: Pod> {
+    buffer: Arc<Buffer>,
+    label: Option<String>,
+    capacity: u32,
+    len: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> InstanceBuffer<T> {
+    /// Room for `capacity` instances; starts out empty.
+    pub fn new(device: &Device, label: Option<&str>, capacity: u32) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            buffer: Arc::new(Self::create_buffer(device, label, capacity)),
+            label: label.map(String::from),
+            capacity,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn create_buffer(device: &Device, label: Option<&str>, capacity: u32) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label,
+            size: u64::from(capacity) * std::mem::size_of::<T>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Grows the buffer to hold at least `capacity` instances. The current
+    /// contents are discarded if it has to be reallocated.
+    pub fn reserve(&mut self, device: &Device, capacity: u32) {
+        if capacity > self.capacity {
+            self.capacity = capacity.next_power_of_two();
+            self.buffer = Arc::new(Self::create_buffer(
+                device,
+                self.label.as_deref(),
+                self.capacity,
+            ));
+            self.len = 0;
+        }
+    }
+
+    /// Replaces the contents with `instances`, which must fit in the
+    /// capacity (see [`InstanceBuffer::reserve`]).
+    pub fn write(&mut self, queue: &Queue, instances: &[T]) {
+        assert!(
+            instances.len() <= self.capacity as usize,
+            "{} instances exceed the buffer capacity ({})",
+            instances.len(),
+            self.capacity
+        );
+        if !instances.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        }
+        self.len = instances.len() as u32;
+    }
+
+    /// The part of the buffer holding the written instances.
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        let size = u64::from(self.len.max(1)) * std::mem::size_of::<T>() as u64;
+        self.buffer.slice(..size)
+    }
+
+    //--Getters--//
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// A handle to the buffer that stays valid after a reallocation, for
+    /// drawing it once this `InstanceBuffer` is out of reach.
+    pub fn shared_buffer(&self) -> Arc<Buffer> {
+        self.buffer.clone()
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Number of instances from the last [`InstanceBuffer::write`].
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
@@ -0,0 +1,198 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Mat4;
+use std::sync::Arc;
+use wgpu::{Device, Queue, RenderPass};
+
+use super::instance_buffer::InstanceBuffer;
+use super::mesh::Mesh;
+use crate::engine::math::color::Color;
+
+/// Per-instance data, read by the vertex shader after
+/// [`MeshVertex`](super::mesh::MeshVertex)'s locations 0–2:
+///
+/// | location | field             |
+/// |----------|-------------------|
+/// | 3–6      | `model`, by column |
+/// | 7        | `color`           |
+///
+/// This is the layout the engine's mesh pipeline expects, so an
+/// [`InstancedMesh`] can be queued with
+/// [`draw_instanced`](super::mesh_renderer::draw_instanced).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct InstanceData {
+    pub model: [f32; 16],
+    /// Multiplied with the mesh's material color.
+    pub color: [f32; 4],
+}
+
+impl InstanceData {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4, 7 => Float32x4
+    ];
+
+    /// An untinted (white) instance.
+    pub fn new(model: Mat4) -> Self {
+        Self::with_color(model, Color::WHITE)
+    }
+
+    pub fn with_color(model: Mat4, color: impl Into<Color>) -> Self {
+        Self {
+            model: model.to_cols_array(),
+            color: color.into().into(),
+        }
+    }
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+impl From<Mat4> for InstanceData {
+    fn from(model: Mat4) -> Self {
+        Self::new(model)
+    }
+}
+
+/// A mesh drawn many times in one call, once per [`InstanceData`]. Queue
+/// it for the engine's pass with
+/// [`draw_instanced`](super::mesh_renderer::draw_instanced), or draw it in
+/// your own pass from a pipeline whose vertex buffers are
+/// `[MeshVertex::layout(), InstanceData::layout()]`.
+pub struct InstancedMesh {
+    mesh: Arc<Mesh>,
+    instances: InstanceBuffer<InstanceData>,
+    /// What's in the buffer, to skip uploads that wouldn't change it.
+    uploaded: Vec<InstanceData>,
+}
+
+impl InstancedMesh {
+    /// Starts with room for `capacity` instances; the buffer grows as
+    /// needed.
+    pub fn new(device: &Device, mesh: Arc<Mesh>, capacity: u32) -> Self {
+        Self {
+            mesh,
+            instances: InstanceBuffer::new(device, Some("Instanced Mesh Buffer"), capacity),
+            uploaded: Vec::new(),
+        }
+    }
+
+    /// Makes room for at least `capacity` instances.
+    pub fn reserve(&mut self, device: &Device, capacity: u32) {
+        let before = self.instances.capacity();
+        self.instances.reserve(device, capacity);
+        if self.instances.capacity() != before {
+            // The new buffer starts out empty
+            self.uploaded.clear();
+        }
+    }
+
+    /// Replaces the instances that get drawn, growing the buffer if they
+    /// don't fit. Nothing is uploaded when `instances` matches the last
+    /// call, so it's cheap to call every frame with static data.
+    pub fn set_instances(&mut self, device: &Device, queue: &Queue, instances: &[InstanceData]) {
+        if instances == self.uploaded.as_slice() {
+            return;
+        }
+        self.reserve(device, instances.len() as u32);
+        self.instances.write(queue, instances);
+        self.uploaded.clear();
+        self.uploaded.extend_from_slice(instances);
+    }
+
+    /// Binds the mesh to slot 0 and the instances to slot 1, then draws every
+    /// instance.
+    pub fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        if self.instances.is_empty() {
+            return;
+        }
+        rpass.set_vertex_buffer(0, self.mesh.vertex_buffer().slice(..));
+        rpass.set_vertex_buffer(1, self.instances.slice());
+        rpass.set_index_buffer(
+            self.mesh.index_buffer().slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        rpass.draw_indexed(0..self.mesh.index_count(), 0, 0..self.instances.len());
+    }
+
+    //--Getters--//
+
+    pub fn mesh(&self) -> &Arc<Mesh> {
+        &self.mesh
+    }
+
+    pub fn instances(&self) -> &InstanceBuffer<InstanceData> {
+        &self.instances
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.instances.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::context::test_device_or_skip;
+    use crate::engine::renderer::mesh::MeshVertex;
+
+    fn triangle(device: &Device) -> Arc<Mesh> {
+        let vertex = |x: f32, y: f32| MeshVertex {
+            position: [x, y, 0.0],
+            normal: [0.0, 0.0, 1.0],
+            uv: [0.0, 0.0],
+        };
+        let vertices = [vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(0.0, 1.0)];
+        Arc::new(Mesh::new(device, &vertices, &[0, 1, 2]))
+    }
+
+    fn instances(count: usize) -> Vec<InstanceData> {
+        (0..count)
+            .map(|i| InstanceData::new(Mat4::from_translation(glam::Vec3::X * i as f32)))
+            .collect()
+    }
+
+    #[test]
+    fn buffer_grows_and_is_reused() {
+        let (device, queue) = test_device_or_skip!();
+        let mut mesh = InstancedMesh::new(device, triangle(device), 0);
+        assert_eq!(mesh.instances().capacity(), 1);
+
+        mesh.set_instances(device, queue, &instances(3));
+        assert_eq!(mesh.instance_count(), 3);
+        assert_eq!(mesh.instances().capacity(), 4);
+        let buffer = mesh.instances().shared_buffer();
+
+        // Shrinking keeps the buffer
+        mesh.set_instances(device, queue, &instances(2));
+        assert_eq!(mesh.instance_count(), 2);
+        assert_eq!(mesh.instances().capacity(), 4);
+        assert!(Arc::ptr_eq(&buffer, &mesh.instances().shared_buffer()));
+
+        mesh.set_instances(device, queue, &instances(5));
+        assert_eq!(mesh.instance_count(), 5);
+        assert_eq!(mesh.instances().capacity(), 8);
+        assert!(!Arc::ptr_eq(&buffer, &mesh.instances().shared_buffer()));
+    }
+
+    #[test]
+    fn unchanged_instances_are_not_uploaded_again() {
+        let (device, queue) = test_device_or_skip!();
+        let mut mesh = InstancedMesh::new(device, triangle(device), 4);
+
+        mesh.set_instances(device, queue, &instances(3));
+        assert_eq!(mesh.uploaded, instances(3));
+
+        // Clearing the buffer's length shows whether the next call writes
+        mesh.instances.write(queue, &[]);
+        mesh.set_instances(device, queue, &instances(3));
+        assert_eq!(mesh.instance_count(), 0);
+
+        mesh.set_instances(device, queue, &instances(1));
+        assert_eq!(mesh.instance_count(), 1);
+    }
+}
@@ -0,0 +1,235 @@
+use bytemuck::{Pod, Zeroable};
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+use wgpu::{
+    BindGroup, BindGroupLayout, Device, RenderPipeline, Sampler, ShaderStages, TextureView,
+};
+
+use super::bind_group::BindGroupBuilder;
+use super::texture::Texture2D;
+use super::uniform::UniformBuffer;
+use crate::engine::math::color::Color;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct MaterialParams {
+    diffuse_color: [f32; 4],
+    has_texture: u32,
+    lit: u32,
+    _padding: [u32; 2],
+}
+
+/// Stands in for a missing diffuse texture so every material binds the
+/// same layout. Never sampled from: the shader checks `has_texture`.
+struct FallbackTexture {
+    view: TextureView,
+    sampler: Sampler,
+}
+
+static FALLBACK_TEXTURE: OnceCell<FallbackTexture> = OnceCell::new();
+static LAYOUT: OnceCell<BindGroupLayout> = OnceCell::new();
+
+fn fallback_texture(device: &Device) -> &'static FallbackTexture {
+    FALLBACK_TEXTURE.get_or_init(|| {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Material Fallback Texture"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        FallbackTexture {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor::default()),
+        }
+    })
+}
+
+/// How a mesh's surface looks: a diffuse color, optionally multiplied by a
+/// texture, and optionally a pipeline of its own. Build one with
+/// [`MaterialBuilder`] and attach it with
+/// [`Mesh::with_material`](super::mesh::Mesh::with_material).
+///
+/// Its bind group goes in group 2 (the camera is group 0, lighting group
+/// 1). In WGSL:
+///
+/// ```wgsl
+/// struct Material {
+///     diffuse_color: vec4<f32>,
+///     has_texture: u32,
+///     // Nonzero if the mesh should be lit, otherwise drawn at full color
+///     lit: u32,
+/// };
+///
+/// @group(2) @binding(0) var<uniform> material: Material;
+/// @group(2) @binding(1) var diffuse_texture: texture_2d<f32>;
+/// @group(2) @binding(2) var diffuse_sampler: sampler;
+/// ```
+pub struct Material {
+    diffuse_color: [f32; 4],
+    diffuse_texture: Option<Arc<Texture2D>>,
+    lit: bool,
+    pipeline: Option<Arc<RenderPipeline>>,
+    /// Read through `bind_group`.
+    _params: UniformBuffer<MaterialParams>,
+    bind_group: BindGroup,
+}
+
+impl Material {
+    /// Layout of every material's bind group, for pipelines passed to
+    /// [`MaterialBuilder::pipeline`].
+    pub fn layout(device: &Device) -> &'static BindGroupLayout {
+        LAYOUT.get_or_init(|| {
+            let fragment = ShaderStages::FRAGMENT;
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Material"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: fragment,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<
+                                MaterialParams,
+                            >()
+                                as u64),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: fragment,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: fragment,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            })
+        })
+    }
+
+    //--Getters--//
+
+    pub fn diffuse_color(&self) -> Color {
+        self.diffuse_color.into()
+    }
+
+    pub fn diffuse_texture(&self) -> Option<&Arc<Texture2D>> {
+        self.diffuse_texture.as_ref()
+    }
+
+    /// Whether the scene's lights shade the mesh.
+    pub fn lit(&self) -> bool {
+        self.lit
+    }
+
+    /// `None` when meshes with this material use the engine's mesh pipeline.
+    pub fn pipeline(&self) -> Option<&Arc<RenderPipeline>> {
+        self.pipeline.as_ref()
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
+
+/// Collects a [`Material`]'s settings. Anything left unset defaults to
+/// opaque white, no texture, lit, and the engine's mesh pipeline.
+#[derive(Default)]
+pub struct MaterialBuilder {
+    diffuse_color: Option<Color>,
+    diffuse_texture: Option<Arc<Texture2D>>,
+    lit: Option<bool>,
+    pipeline: Option<Arc<RenderPipeline>>,
+}
+
+impl MaterialBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Multiplies the texture and the per-instance color of
+    /// [`draw_mesh`](super::mesh_renderer::draw_mesh).
+    pub fn diffuse_color(mut self, color: impl Into<Color>) -> Self {
+        self.diffuse_color = Some(color.into());
+        self
+    }
+
+    /// Sampled with the mesh's UVs.
+    pub fn diffuse_texture(mut self, texture: Arc<Texture2D>) -> Self {
+        self.diffuse_texture = Some(texture);
+        self
+    }
+
+    /// With `true` (the default) the mesh is shaded by the
+    /// [`LightingUniform`](crate::engine::lighting::LightingUniform)'s
+    /// lights with Blinn-Phong; with `false` it is drawn at its full
+    /// color, e.g. for skies, glowing objects or UI in the world.
+    pub fn lit(mut self, lit: bool) -> Self {
+        self.lit = Some(lit);
+        self
+    }
+
+    /// Draws meshes with `pipeline` instead of the engine's own. It must
+    /// target the main pass (surface format, [`DEPTH_FORMAT`](super::depth::DEPTH_FORMAT))
+    /// and take the same inputs: [`MeshVertex`](super::mesh::MeshVertex) in
+    /// buffer 0, the instance model matrix (locations 3-6) and color
+    /// (location 7) in buffer 1, and the camera, lighting and
+    /// [`Material::layout`] bind groups.
+    pub fn pipeline(mut self, pipeline: Arc<RenderPipeline>) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    pub fn build(self, device: &Device) -> Material {
+        let diffuse_color = self.diffuse_color.unwrap_or(Color::WHITE).into();
+        let lit = self.lit.unwrap_or(true);
+        let params = UniformBuffer::new(
+            device,
+            Some("Material Buffer"),
+            &MaterialParams {
+                diffuse_color,
+                has_texture: self.diffuse_texture.is_some() as u32,
+                lit: lit as u32,
+                _padding: [0; 2],
+            },
+        );
+
+        let fallback = fallback_texture(device);
+        let (view, sampler) = match &self.diffuse_texture {
+            Some(texture) => (texture.view(), texture.sampler()),
+            None => (&fallback.view, &fallback.sampler),
+        };
+        let bind_group = BindGroupBuilder::new(Some("Material"))
+            .uniform(device, &params, ShaderStages::FRAGMENT)
+            .texture(view, ShaderStages::FRAGMENT)
+            .sampler(sampler, ShaderStages::FRAGMENT)
+            .build_group(device, Material::layout(device));
+
+        Material {
+            diffuse_color,
+            diffuse_texture: self.diffuse_texture,
+            lit,
+            pipeline: self.pipeline,
+            _params: params,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use glam::Mat4;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wgpu::{
+    Buffer, Device, PipelineLayout, Queue, RenderPass, RenderPipeline, ShaderModule, TextureFormat,
+};
+
+use super::camera3d::CameraUniform;
+use super::depth::{DEPTH_COMPARE, DEPTH_FORMAT};
+use super::instanced_mesh::{InstanceData, InstancedMesh};
+use super::material::{Material, MaterialBuilder};
+use super::mesh::{Mesh, MeshVertex};
+use crate::engine::lighting::{LIGHTING_WGSL, LightingBuffer};
+use crate::engine::math::color::Color;
+
+const INITIAL_INSTANCE_CAPACITY: usize = 64;
+
+/// Meshes queued for this frame, drawn by the engine and then cleared.
+struct MeshQueue {
+    meshes: Vec<Arc<Mesh>>,
+    instances: Vec<InstanceData>,
+    batches: Vec<InstancedBatch>,
+}
+
+/// An [`InstancedMesh`] as it was when queued. The buffer is shared, so it
+/// is not copied or uploaded again.
+struct InstancedBatch {
+    mesh: Arc<Mesh>,
+    buffer: Arc<Buffer>,
+    count: u32,
+}
+
+/// How meshes are shaded, for inspecting geometry. Only 3D meshes are
+/// affected; sprites, tilemaps and text draw as usual.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DebugView {
+    /// Normal shading (the default).
+    #[default]
+    None,
+    /// Triangle edges only. Needs `Features::POLYGON_MODE_LINE`, which the
+    /// window enables when the adapter has it; without it meshes are drawn
+    /// as usual.
+    Wireframe,
+    /// World-space normals as colors, x in red, y in green and z in blue.
+    Normals,
+    /// Texture coordinates as red and green, repeating outside 0..1.
+    Uvs,
+    /// Distance from the camera, white up close and darker further away.
+    Depth,
+}
+
+impl DebugView {
+    /// The view after this one, wrapping back to `None`, for cycling
+    /// through them with a key.
+    pub fn next(self) -> DebugView {
+        match self {
+            DebugView::None => DebugView::Wireframe,
+            DebugView::Wireframe => DebugView::Normals,
+            DebugView::Normals => DebugView::Uvs,
+            DebugView::Uvs => DebugView::Depth,
+            DebugView::Depth => DebugView::None,
+        }
+    }
+}
+
+fn debug_view_state() -> &'static Mutex<DebugView> {
+    static INSTANCE: Lazy<Mutex<DebugView>> = Lazy::new(|| Mutex::new(DebugView::None));
+
+    &INSTANCE
+}
+
+/// Shades every mesh with `view` from the next frame on, ignoring their
+/// materials' shading and pipelines.
+pub fn set_debug_view(view: DebugView) {
+    *debug_view_state().lock().unwrap() = view;
+}
+
+pub fn debug_view() -> DebugView {
+    *debug_view_state().lock().unwrap()
+}
+
+fn mesh_queue() -> &'static Mutex<MeshQueue> {
+    static INSTANCE: Lazy<Mutex<MeshQueue>> = Lazy::new(|| {
+        Mutex::new(MeshQueue {
+            meshes: Vec::new(),
+            instances: Vec::new(),
+            batches: Vec::new(),
+        })
+    });
+
+    &INSTANCE
+}
+
+/// Draws `mesh` this frame with the active
+/// [`Camera3D`](super::camera3d::Camera3D), transformed by `model` and lit
+/// by the [`LightingUniform`](crate::engine::lighting::LightingUniform).
+/// The mesh's [`Material`] is applied on top of `color`.
+pub fn draw_mesh(mesh: Arc<Mesh>, model: Mat4, color: impl Into<Color>) {
+    let mut q = mesh_queue().lock().unwrap();
+    q.meshes.push(mesh);
+    q.instances.push(InstanceData::with_color(model, color));
+}
+
+/// Draws every instance of `mesh` this frame, lit and shaded like
+/// [`draw_mesh`]. Only the instance buffer's handle is queued, so call
+/// this every frame and [`InstancedMesh::set_instances`] only when the
+/// instances change.
+pub fn draw_instanced(mesh: &InstancedMesh) {
+    if mesh.instance_count() == 0 {
+        return;
+    }
+    mesh_queue().lock().unwrap().batches.push(InstancedBatch {
+        mesh: mesh.mesh().clone(),
+        buffer: mesh.instances().shared_buffer(),
+        count: mesh.instance_count(),
+    });
+}
+
+/// Depth-tested pipeline for the meshes queued with [`draw_mesh`] and
+/// [`draw_instanced`].
+pub(crate) struct MeshRenderer {
+    pipeline: RenderPipeline,
+    shader: ShaderModule,
+    layout: PipelineLayout,
+    format: TextureFormat,
+    /// Created the first time each view is used.
+    debug_pipelines: HashMap<DebugView, RenderPipeline>,
+    /// The view this frame, if it has a pipeline.
+    debug_view: Option<DebugView>,
+    warned_no_wireframe: bool,
+    /// For meshes without a material of their own.
+    default_material: Material,
+    instance_buffer: Buffer,
+    instance_capacity: usize,
+    meshes: Vec<Arc<Mesh>>,
+    instances: Vec<InstanceData>,
+    batches: Vec<InstancedBatch>,
+}
+
+impl MeshRenderer {
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        camera: &CameraUniform,
+        lighting: &LightingBuffer,
+    ) -> Self {
+        let source = format!("{LIGHTING_WGSL}\n{}", include_str!("shaders/mesh.wgsl"));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &[camera.layout(), lighting.layout(), Material::layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = Self::create_pipeline(
+            device,
+            &shader,
+            &layout,
+            format,
+            "fs_main",
+            wgpu::PolygonMode::Fill,
+        );
+
+        Self {
+            pipeline,
+            shader,
+            layout,
+            format,
+            debug_pipelines: HashMap::new(),
+            debug_view: None,
+            warned_no_wireframe: false,
+            default_material: MaterialBuilder::new().build(device),
+            instance_buffer: Self::create_instance_buffer(device, INITIAL_INSTANCE_CAPACITY),
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            meshes: Vec::new(),
+            instances: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        shader: &ShaderModule,
+        layout: &PipelineLayout,
+        format: TextureFormat,
+        fragment_entry: &str,
+        polygon_mode: wgpu::PolygonMode,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[MeshVertex::layout(), InstanceData::layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: DEPTH_COMPARE,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: fragment_entry,
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+
+    /// Picks up the current [`DebugView`], creating its pipeline on first
+    /// use.
+    fn update_debug_view(&mut self, device: &Device) {
+        let view = debug_view();
+        let (fragment_entry, polygon_mode) = match view {
+            DebugView::None => {
+                self.debug_view = None;
+                return;
+            }
+            DebugView::Wireframe => {
+                if !device
+                    .features()
+                    .contains(wgpu::Features::POLYGON_MODE_LINE)
+                {
+                    if !std::mem::replace(&mut self.warned_no_wireframe, true) {
+                        log::warn!("The GPU can't draw wireframes; meshes are drawn as usual.");
+                    }
+                    self.debug_view = None;
+                    return;
+                }
+                ("fs_main", wgpu::PolygonMode::Line)
+            }
+            DebugView::Normals => ("fs_normals", wgpu::PolygonMode::Fill),
+            DebugView::Uvs => ("fs_uvs", wgpu::PolygonMode::Fill),
+            DebugView::Depth => ("fs_depth", wgpu::PolygonMode::Fill),
+        };
+        self.debug_pipelines.entry(view).or_insert_with(|| {
+            Self::create_pipeline(
+                device,
+                &self.shader,
+                &self.layout,
+                self.format,
+                fragment_entry,
+                polygon_mode,
+            )
+        });
+        self.debug_view = Some(view);
+    }
+
+    fn create_instance_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Instance Buffer"),
+            size: (capacity * std::mem::size_of::<InstanceData>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Takes this frame's meshes and uploads their instance data.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue) {
+        self.update_debug_view(device);
+        self.meshes.clear();
+        self.instances.clear();
+        self.batches.clear();
+        {
+            let mut q = mesh_queue().lock().unwrap();
+            std::mem::swap(&mut q.meshes, &mut self.meshes);
+            std::mem::swap(&mut q.instances, &mut self.instances);
+            std::mem::swap(&mut q.batches, &mut self.batches);
+        }
+
+        if self.instances.len() > self.instance_capacity {
+            self.instance_capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, self.instance_capacity);
+        }
+        if !self.instances.is_empty() {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&self.instances));
+        }
+    }
+
+    pub fn draw<'a>(
+        &'a self,
+        rpass: &mut RenderPass<'a>,
+        camera: &'a CameraUniform,
+        lighting: &'a LightingBuffer,
+    ) {
+        if self.meshes.is_empty() && self.batches.is_empty() {
+            return;
+        }
+        rpass.set_bind_group(0, camera.bind_group(), &[]);
+        rpass.set_bind_group(1, lighting.bind_group(), &[]);
+        let mut bound: Option<&RenderPipeline> = None;
+        if !self.meshes.is_empty() {
+            rpass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        }
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let instance = i as u32;
+            self.bind_mesh(rpass, mesh, &mut bound);
+            rpass.draw_indexed(0..mesh.index_count(), 0, instance..instance + 1);
+        }
+        for batch in &self.batches {
+            self.bind_mesh(rpass, &batch.mesh, &mut bound);
+            rpass.set_vertex_buffer(1, batch.buffer.slice(..));
+            rpass.draw_indexed(0..batch.mesh.index_count(), 0, 0..batch.count);
+        }
+    }
+
+    /// Binds `mesh`'s buffers and material, switching pipeline only if the
+    /// material needs a different one from `bound`.
+    fn bind_mesh<'a>(
+        &'a self,
+        rpass: &mut RenderPass<'a>,
+        mesh: &'a Mesh,
+        bound: &mut Option<&'a RenderPipeline>,
+    ) {
+        let material = mesh.material().map_or(&self.default_material, Arc::as_ref);
+        let debug = self
+            .debug_view
+            .and_then(|view| self.debug_pipelines.get(&view));
+        let pipeline =
+            debug.unwrap_or_else(|| material.pipeline().map_or(&self.pipeline, Arc::as_ref));
+        if !bound.is_some_and(|p| std::ptr::eq(p, pipeline)) {
+            rpass.set_pipeline(pipeline);
+            *bound = Some(pipeline);
+        }
+        rpass.set_bind_group(2, material.bind_group(), &[]);
+        rpass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+        rpass.set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+    }
+}
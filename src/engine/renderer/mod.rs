@@ -0,0 +1,32 @@
+pub mod animation;
+pub mod bind_group;
+pub mod camera;
+pub mod camera3d;
+pub mod context;
+pub mod depth;
+pub mod globals;
+pub mod instance_buffer;
+pub mod instanced_mesh;
+pub mod material;
+pub mod mesh;
+pub mod mesh_renderer;
+pub mod model;
+pub mod obj;
+pub mod particles;
+pub mod particles_2d;
+pub mod post_process;
+pub mod quad;
+pub mod render_graph;
+pub mod render_target;
+pub mod screenshot;
+pub mod shader;
+pub mod shader_preprocessor;
+pub mod skybox;
+pub mod sprite_atlas;
+pub mod sprite_renderer;
+pub mod text;
+pub mod texture;
+pub mod tilemap;
+pub(crate) mod tilemap_renderer;
+pub mod uniform;
+pub mod viewport;
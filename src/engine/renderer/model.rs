@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::sync::Arc;
+use wgpu::Device;
+
+use super::mesh::Mesh;
+use super::obj::{ObjData, ObjError};
+
+/// One part of a [`Model`], drawn with a single material.
+pub struct ModelMesh {
+    pub name: String,
+    /// Index into [`Model::materials`], if the part names a material.
+    pub material: Option<usize>,
+    pub mesh: Arc<Mesh>,
+}
+
+/// The meshes of a model file, uploaded to the GPU. Load through
+/// [`ResourceManager::load_model`](crate::engine::assets::resource_manager::ResourceManager::load_model)
+/// to share one copy between everything that draws it.
+pub struct Model {
+    meshes: Vec<ModelMesh>,
+    materials: Vec<String>,
+}
+
+impl Model {
+    /// Loads a Wavefront OBJ file; see [`ObjData`] for what is read.
+    pub fn from_obj_file(device: &Device, path: impl AsRef<Path>) -> Result<Self, ObjError> {
+        Ok(Self::from_obj(device, &ObjData::from_file(path)?))
+    }
+
+    pub fn from_obj(device: &Device, data: &ObjData) -> Self {
+        let meshes = data
+            .meshes
+            .iter()
+            .map(|part| ModelMesh {
+                name: part.name.clone(),
+                material: part.material,
+                mesh: Arc::new(Mesh::new(device, &part.vertices, &part.indices)),
+            })
+            .collect();
+        Self {
+            meshes,
+            materials: data.materials.clone(),
+        }
+    }
+
+    //--Getters--//
+
+    pub fn meshes(&self) -> &[ModelMesh] {
+        &self.meshes
+    }
+
+    /// Material names the meshes refer to by index.
+    pub fn materials(&self) -> &[String] {
+        &self.materials
+    }
+}
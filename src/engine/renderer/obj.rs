@@ -0,0 +1,382 @@
+use glam::Vec3;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use super::mesh::MeshVertex;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    /// A malformed statement, with its 1-based line number.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "failed to read OBJ file: {e}"),
+            ObjError::Parse { line, message } => write!(f, "OBJ line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+    fn from(e: std::io::Error) -> Self {
+        ObjError::Io(e)
+    }
+}
+
+/// One object or group of an OBJ file, with a single material.
+#[derive(Clone, Debug, Default)]
+pub struct ObjMesh {
+    /// From the `o` or `g` statement, empty before the first one.
+    pub name: String,
+    /// Index into [`ObjData::materials`], from the `usemtl` in effect.
+    pub material: Option<usize>,
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
+
+/// The triangles of a Wavefront OBJ file, ready for [`Mesh::new`].
+///
+/// OBJ indexes positions, UVs and normals separately; each distinct
+/// combination used by a face becomes one vertex, shared by every face
+/// that uses it. Polygons are split into triangle fans. Faces without
+/// normals get smooth ones averaged from the faces around each position.
+/// Material libraries (`mtllib`) aren't read, only the names faces refer to.
+///
+/// [`Mesh::new`]: super::mesh::Mesh::new
+#[derive(Clone, Debug, Default)]
+pub struct ObjData {
+    /// Every object, group and material change with at least one face.
+    pub meshes: Vec<ObjMesh>,
+    /// Material names in the order `usemtl` first names them.
+    pub materials: Vec<String>,
+}
+
+/// Which position, UV and normal a face corner uses, as 0-based indices.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// The sub-mesh being filled in, and how its corners map to vertices.
+#[derive(Default)]
+struct Builder {
+    mesh: ObjMesh,
+    vertices: HashMap<Corner, u32>,
+    /// Area-weighted normals of the faces without normals, summed per
+    /// position.
+    face_normals: HashMap<usize, Vec3>,
+    /// Vertices whose normal comes from `face_normals`.
+    computed: Vec<(u32, usize)>,
+}
+
+impl Builder {
+    fn vertex(&mut self, corner: Corner, attributes: &Attributes) -> u32 {
+        if let Some(&index) = self.vertices.get(&corner) {
+            return index;
+        }
+        let (position, uv, normal) = corner;
+        let index = self.mesh.vertices.len() as u32;
+        self.mesh.vertices.push(MeshVertex {
+            position: attributes.positions[position].to_array(),
+            normal: normal.map_or([0.0; 3], |n| attributes.normals[n].to_array()),
+            // OBJ puts v = 0 at the bottom of the image, wgpu at the top
+            uv: uv.map_or([0.0; 2], |t| {
+                let [u, v] = attributes.uvs[t];
+                [u, 1.0 - v]
+            }),
+        });
+        if normal.is_none() {
+            self.computed.push((index, position));
+        }
+        self.vertices.insert(corner, index);
+        index
+    }
+
+    fn finish(mut self) -> ObjMesh {
+        for (index, position) in self.computed {
+            let normal = self.face_normals.get(&position).copied().unwrap_or(Vec3::Y);
+            self.mesh.vertices[index as usize].normal =
+                normal.try_normalize().unwrap_or(Vec3::Y).to_array();
+        }
+        self.mesh
+    }
+}
+
+#[derive(Default)]
+struct Attributes {
+    positions: Vec<Vec3>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<Vec3>,
+}
+
+impl ObjData {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ObjError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ObjError> {
+        let mut data = ObjData::default();
+        let mut attributes = Attributes::default();
+        let mut builder = Builder::default();
+
+        for (i, line) in source.lines().enumerate() {
+            let error = |message: String| ObjError::Parse {
+                line: i + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let args: Vec<&str> = words.collect();
+
+            match keyword {
+                "v" => attributes
+                    .positions
+                    .push(Vec3::from(floats::<3>(&args, "v").map_err(error)?)),
+                "vt" => {
+                    // The v coordinate is optional, and a 3D texture
+                    // coordinate's w is ignored
+                    let u = float(args.first(), "vt").map_err(error)?;
+                    let v = match args.get(1) {
+                        Some(_) => float(args.get(1), "vt").map_err(error)?,
+                        None => 0.0,
+                    };
+                    attributes.uvs.push([u, v]);
+                }
+                "vn" => attributes
+                    .normals
+                    .push(Vec3::from(floats::<3>(&args, "vn").map_err(error)?)),
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(error(format!("face with {} vertices", args.len())));
+                    }
+                    let corners = args
+                        .iter()
+                        .map(|arg| corner(arg, &attributes))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(error)?;
+                    add_face(&mut builder, &corners, &attributes);
+                }
+                "o" | "g" => {
+                    let name = args.join(" ");
+                    let material = builder.mesh.material;
+                    push_mesh(&mut data, std::mem::take(&mut builder));
+                    builder.mesh.name = name;
+                    builder.mesh.material = material;
+                }
+                "usemtl" => {
+                    let name = args.join(" ");
+                    let material = match data.materials.iter().position(|m| *m == name) {
+                        Some(index) => index,
+                        None => {
+                            data.materials.push(name);
+                            data.materials.len() - 1
+                        }
+                    };
+                    if builder.mesh.material != Some(material) {
+                        let name = builder.mesh.name.clone();
+                        push_mesh(&mut data, std::mem::take(&mut builder));
+                        builder.mesh.name = name;
+                        builder.mesh.material = Some(material);
+                    }
+                }
+                // Smoothing groups, material libraries, lines and points
+                _ => {}
+            }
+        }
+        push_mesh(&mut data, builder);
+        Ok(data)
+    }
+}
+
+/// Keeps the sub-mesh if it has any faces.
+fn push_mesh(data: &mut ObjData, builder: Builder) {
+    if !builder.mesh.indices.is_empty() {
+        data.meshes.push(builder.finish());
+    }
+}
+
+fn add_face(builder: &mut Builder, corners: &[Corner], attributes: &Attributes) {
+    if corners.iter().any(|(_, _, normal)| normal.is_none()) {
+        // Twice the polygon's area along its normal (Newell's method), so
+        // big faces count for more than slivers
+        let mut normal = Vec3::ZERO;
+        for (i, &(a, _, _)) in corners.iter().enumerate() {
+            let (b, _, _) = corners[(i + 1) % corners.len()];
+            normal += attributes.positions[a].cross(attributes.positions[b]);
+        }
+        for &(position, _, _) in corners {
+            *builder.face_normals.entry(position).or_default() += normal;
+        }
+    }
+
+    let first = builder.vertex(corners[0], attributes);
+    for pair in corners[1..].windows(2) {
+        let b = builder.vertex(pair[0], attributes);
+        let c = builder.vertex(pair[1], attributes);
+        builder.mesh.indices.extend_from_slice(&[first, b, c]);
+    }
+}
+
+/// Parses a face corner such as `3`, `3/1`, `3//2` or `3/1/2`.
+fn corner(arg: &str, attributes: &Attributes) -> Result<Corner, String> {
+    let mut parts = arg.split('/');
+    let position = index(parts.next(), attributes.positions.len(), arg)?
+        .ok_or_else(|| format!("face vertex {arg:?} has no position"))?;
+    let uv = index(parts.next(), attributes.uvs.len(), arg)?;
+    let normal = index(parts.next(), attributes.normals.len(), arg)?;
+    Ok((position, uv, normal))
+}
+
+/// Resolves a 1-based index, or a negative one counting back from the end,
+/// against the `len` elements defined so far.
+fn index(part: Option<&str>, len: usize, arg: &str) -> Result<Option<usize>, String> {
+    let Some(part) = part.filter(|part| !part.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = part
+        .parse()
+        .map_err(|_| format!("bad index in face vertex {arg:?}"))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..0 => len as i64 + index,
+        0 => -1,
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!("face vertex {arg:?} refers to a missing element"));
+    }
+    Ok(Some(resolved as usize))
+}
+
+fn float(arg: Option<&&str>, keyword: &str) -> Result<f32, String> {
+    let arg = arg.ok_or_else(|| format!("{keyword} is missing a coordinate"))?;
+    arg.parse()
+        .map_err(|_| format!("{keyword} has a bad coordinate {arg:?}"))
+}
+
+fn floats<const N: usize>(args: &[&str], keyword: &str) -> Result<[f32; N], String> {
+    let mut values = [0.0; N];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = float(args.get(i), keyword)?;
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+f 1/1 2/1 3/2 4/2
+";
+
+    #[test]
+    fn polygons_become_fans_of_shared_vertices() {
+        let data = ObjData::parse(QUAD).unwrap();
+
+        assert_eq!(data.meshes.len(), 1);
+        let mesh = &data.meshes[0];
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        // V is flipped so the top of the image is at 0
+        assert_eq!(mesh.vertices[0].uv, [0.0, 1.0]);
+        assert_eq!(mesh.vertices[2].uv, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn missing_normals_are_computed_from_the_winding() {
+        let data = ObjData::parse(QUAD).unwrap();
+
+        for vertex in &data.meshes[0].vertices {
+            assert_eq!(vertex.normal, [0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn objects_and_materials_split_the_meshes() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vn 0 0 -1
+o first
+usemtl red
+f 1//1 2//1 3//1
+usemtl blue
+f -3//-1 -2//-1 -1//-1
+o second
+f 1//1 2//1 3//1
+usemtl red
+f 1//1 2//1 3//1
+";
+        let data = ObjData::parse(source).unwrap();
+
+        assert_eq!(data.materials, vec!["red", "blue"]);
+        let parts: Vec<_> = data
+            .meshes
+            .iter()
+            .map(|m| (m.name.as_str(), m.material))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("first", Some(0)),
+                ("first", Some(1)),
+                ("second", Some(1)),
+                ("second", Some(0)),
+            ]
+        );
+        // Given normals are kept
+        assert_eq!(data.meshes[1].vertices[0].normal, [0.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn errors_report_the_line() {
+        let bad_index = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        assert!(matches!(
+            ObjData::parse(bad_index),
+            Err(ObjError::Parse { line: 3, .. })
+        ));
+
+        let bad_float = "v 0 zero 0\n";
+        assert!(matches!(
+            ObjData::parse(bad_float),
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+
+        let short_face = "v 0 0 0\nv 1 0 0\nf 1 2\n";
+        assert!(matches!(
+            ObjData::parse(short_face),
+            Err(ObjError::Parse { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn bundled_torus_loads() {
+        let data = ObjData::from_file("assets/models/torus.obj").unwrap();
+
+        assert!(!data.meshes.is_empty());
+        for mesh in &data.meshes {
+            assert_eq!(mesh.indices.len() % 3, 0);
+            assert!(
+                mesh.indices
+                    .iter()
+                    .all(|&i| (i as usize) < mesh.vertices.len())
+            );
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use std::ops::Range;
+use wgpu::{Buffer, Device, Queue};
+
+use crate::engine::math::color::Color;
+use crate::engine::window::Window;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub lifetime: f32,
+    pub age: f32,
+    pub color: [f32; 4],
+    pub size: f32,
+    start_alpha: f32,
+}
+
+/// Parameters for one call to [`ParticleEmitter::emit`].
+#[derive(Clone, Debug)]
+pub struct EmitConfig {
+    pub count: usize,
+    pub position: Vec3,
+    /// Center of the emission cone; doesn't need to be normalized.
+    pub direction: Vec3,
+    /// Half-angle of the cone in radians. `PI` emits in every direction.
+    pub spread: f32,
+    pub speed: Range<f32>,
+    pub lifetime: Range<f32>,
+    pub color: Color,
+    pub size: f32,
+}
+
+impl Default for EmitConfig {
+    fn default() -> Self {
+        Self {
+            count: 32,
+            position: Vec3::ZERO,
+            direction: Vec3::Y,
+            spread: std::f32::consts::FRAC_PI_4,
+            speed: 1.0..2.0,
+            lifetime: 0.5..1.0,
+            color: Color::WHITE,
+            size: 0.1,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ParticleVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl ParticleVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x4];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticleVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// CPU-simulated particles uploaded as camera-facing quads each frame.
+pub struct ParticleEmitter {
+    particles: Vec<Particle>,
+    billboard_right: Vec3,
+    billboard_up: Vec3,
+    vertices: Vec<ParticleVertex>,
+    vertex_buffer: Option<Buffer>,
+    vertex_capacity: usize,
+}
+
+impl ParticleEmitter {
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+            billboard_right: Vec3::X,
+            billboard_up: Vec3::Y,
+            vertices: Vec::new(),
+            vertex_buffer: None,
+            vertex_capacity: 0,
+        }
+    }
+
+    /// Spawns `config.count` particles with velocities inside the cone.
+    pub fn emit(&mut self, config: EmitConfig) {
+        let axis = config.direction.try_normalize().unwrap_or(Vec3::Y);
+        let (tangent, bitangent) = axis.any_orthonormal_pair();
+        let cos_spread = config.spread.clamp(0.0, std::f32::consts::PI).cos();
+
+        self.particles.reserve(config.count);
+        for _ in 0..config.count {
+            // Uniform over the spherical cap around `axis`
+            let cos_theta = lerp(cos_spread, 1.0, fastrand::f32());
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = fastrand::f32() * std::f32::consts::TAU;
+            let dir = axis * cos_theta
+                + (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta;
+
+            let speed = lerp(config.speed.start, config.speed.end, fastrand::f32());
+            let lifetime = lerp(config.lifetime.start, config.lifetime.end, fastrand::f32());
+
+            self.particles.push(Particle {
+                position: config.position,
+                velocity: dir * speed,
+                lifetime: lifetime.max(f32::EPSILON),
+                age: 0.0,
+                color: config.color.into(),
+                size: config.size,
+                start_alpha: config.color.a,
+            });
+        }
+    }
+
+    /// Integrates positions, fades alpha towards zero over each particle's
+    /// lifetime and drops the ones that have expired. While any are alive
+    /// it keeps an [`OnDemand`](crate::engine::window::RedrawMode::OnDemand)
+    /// window drawing.
+    pub fn update(&mut self, delta: f32) {
+        self.particles.retain_mut(|p| {
+            p.age += delta;
+            if p.age >= p.lifetime {
+                return false;
+            }
+            p.position += p.velocity * delta;
+            p.color[3] = p.start_alpha * (1.0 - p.age / p.lifetime);
+            true
+        });
+        if !self.particles.is_empty() {
+            Window::invalidate();
+        }
+    }
+
+    /// Sets the camera right/up vectors the quads are expanded along.
+    /// Defaults to the XY plane.
+    pub fn set_billboard_axes(&mut self, right: Vec3, up: Vec3) {
+        self.billboard_right = right;
+        self.billboard_up = up;
+    }
+
+    /// Rebuilds the quads for the live particles and uploads them, growing
+    /// the GPU buffer when needed.
+    pub fn build_vertex_buffer(&mut self, device: &Device, queue: &Queue) {
+        self.vertices.clear();
+        for p in &self.particles {
+            let right = self.billboard_right * (p.size * 0.5);
+            let up = self.billboard_up * (p.size * 0.5);
+            let v = |offset: Vec3, uv: [f32; 2]| ParticleVertex {
+                position: (p.position + offset).to_array(),
+                uv,
+                color: p.color,
+            };
+            let (bl, br) = (v(-right - up, [0.0, 1.0]), v(right - up, [1.0, 1.0]));
+            let (tl, tr) = (v(-right + up, [0.0, 0.0]), v(right + up, [1.0, 0.0]));
+            self.vertices.extend_from_slice(&[bl, br, tr, bl, tr, tl]);
+        }
+
+        if self.vertices.is_empty() {
+            return;
+        }
+        if self.vertex_buffer.is_none() || self.vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = self.vertices.len().next_power_of_two();
+            self.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Particle Vertex Buffer"),
+                size: (self.vertex_capacity * std::mem::size_of::<ParticleVertex>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let Some(buffer) = &self.vertex_buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.vertices));
+        }
+    }
+
+    //--Getters--//
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn vertex_buffer(&self) -> Option<&Buffer> {
+        self.vertex_buffer.as_ref()
+    }
+
+    /// Vertices written by the last [`ParticleEmitter::build_vertex_buffer`].
+    pub fn vertex_count(&self) -> u32 {
+        self.vertices.len() as u32
+    }
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
@@ -0,0 +1,381 @@
+use glam::{FloatExt, Vec2};
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::sprite_atlas::SpriteAtlas;
+use super::sprite_renderer::{self, SpriteBlend, SpriteInstance};
+use super::texture::Texture2D;
+use crate::engine::math::color::Color;
+use crate::engine::window::Window;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Particle2D {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+/// `count` particles spawned at once, `time` seconds into each cycle.
+#[derive(Clone, Copy, Debug)]
+pub struct Burst {
+    pub time: f32,
+    pub count: usize,
+}
+
+/// How a [`ParticleEmitter2D`] spawns, moves and draws its particles.
+#[derive(Clone, Debug)]
+pub struct EmitterConfig2D {
+    /// Particles spawned per second while emitting.
+    pub spawn_rate: f32,
+    pub bursts: Vec<Burst>,
+    pub lifetime: Range<f32>,
+    /// Center of the emission cone; doesn't need to be normalized.
+    pub direction: Vec2,
+    /// Half-angle of the cone in radians. `PI` emits in every direction.
+    pub spread: f32,
+    pub speed: Range<f32>,
+    /// Acceleration in world units per second squared.
+    pub gravity: Vec2,
+    pub start_color: Color,
+    pub end_color: Color,
+    /// Width and height in world units at birth.
+    pub start_size: f32,
+    pub end_size: f32,
+    /// Seconds a cycle emits for.
+    pub duration: f32,
+    /// Starts a new cycle, firing its bursts again, when one ends.
+    pub looping: bool,
+    /// Spawns beyond this many live particles are dropped.
+    pub max_particles: usize,
+    pub blend: SpriteBlend,
+}
+
+impl Default for EmitterConfig2D {
+    fn default() -> Self {
+        Self {
+            spawn_rate: 50.0,
+            bursts: Vec::new(),
+            lifetime: 1.0..2.0,
+            direction: Vec2::Y,
+            spread: std::f32::consts::FRAC_PI_8,
+            speed: 100.0..200.0,
+            gravity: Vec2::new(0.0, -200.0),
+            start_color: Color::WHITE,
+            end_color: Color::WHITE.with_alpha(0.0),
+            start_size: 8.0,
+            end_size: 2.0,
+            duration: 1.0,
+            looping: true,
+            max_particles: 1000,
+            blend: SpriteBlend::Alpha,
+        }
+    }
+}
+
+/// CPU-simulated 2D particles drawn through the sprite batcher, textured
+/// with a whole texture or an atlas region.
+pub struct ParticleEmitter2D {
+    simulation: Simulation2D,
+    texture: Arc<Texture2D>,
+    uv_rect: [f32; 4],
+}
+
+impl ParticleEmitter2D {
+    pub fn new(texture: Arc<Texture2D>, config: EmitterConfig2D) -> Self {
+        Self {
+            simulation: Simulation2D::new(config),
+            texture,
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+
+    /// An emitter drawing the atlas region `name`, or `None` if the atlas
+    /// has no such region.
+    pub fn from_atlas(atlas: &SpriteAtlas, name: &str, config: EmitterConfig2D) -> Option<Self> {
+        let uv_rect = atlas.uv_rect(name)?;
+        let mut emitter = Self::new(atlas.texture().clone(), config);
+        emitter.uv_rect = uv_rect;
+        Some(emitter)
+    }
+
+    /// Ages and moves the particles, drops expired ones and spawns new ones
+    /// for the time passed. While it isn't [`finished`](Self::finished) it
+    /// keeps an [`OnDemand`](crate::engine::window::RedrawMode::OnDemand)
+    /// window drawing.
+    pub fn update(&mut self, delta: f32) {
+        self.simulation.update(delta);
+        if !self.finished() {
+            Window::invalidate();
+        }
+    }
+
+    /// Spawns `count` particles at once, on top of the configured rate and
+    /// bursts.
+    pub fn burst(&mut self, count: usize) {
+        self.simulation.spawn(count);
+        Window::invalidate();
+    }
+
+    /// Queues every live particle to draw this frame, with color and size
+    /// interpolated over its life.
+    pub fn draw(&self) {
+        let config = &self.simulation.config;
+        let instances = self.simulation.particles.iter().map(|p| {
+            let t = p.age / p.lifetime;
+            let size = config.start_size.lerp(config.end_size, t);
+            let color = config.start_color.lerp(config.end_color, t);
+            SpriteInstance::new(p.position, Vec2::splat(size), self.uv_rect, color.into())
+        });
+        sprite_renderer::queue_instances(&self.texture, config.blend, instances);
+    }
+
+    /// Removes every particle and starts emitting from the beginning.
+    pub fn restart(&mut self) {
+        self.simulation.restart();
+        Window::invalidate();
+    }
+
+    /// Where new particles spawn; existing ones keep moving on their own.
+    pub fn set_position(&mut self, position: Vec2) {
+        self.simulation.position = position;
+    }
+
+    //--Getters--//
+
+    /// True once a non-looping emitter has stopped emitting and its last
+    /// particle has expired.
+    pub fn finished(&self) -> bool {
+        self.simulation.finished()
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.simulation.position
+    }
+
+    pub fn particles(&self) -> &[Particle2D] {
+        &self.simulation.particles
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.simulation.particles.len()
+    }
+
+    pub fn config(&self) -> &EmitterConfig2D {
+        &self.simulation.config
+    }
+}
+
+/// The part of a [`ParticleEmitter2D`] that runs on the CPU: spawning,
+/// moving and expiring particles, with no GPU resources involved.
+struct Simulation2D {
+    config: EmitterConfig2D,
+    position: Vec2,
+    /// Live particles, allocated once for `max_particles`; dead ones are
+    /// swapped out so the storage is reused.
+    particles: Vec<Particle2D>,
+    /// Seconds into the current cycle, or since the start without looping.
+    elapsed: f32,
+    /// Fractional particles carried over between updates.
+    spawn_debt: f32,
+}
+
+impl Simulation2D {
+    fn new(config: EmitterConfig2D) -> Self {
+        Self {
+            particles: Vec::with_capacity(config.max_particles),
+            config,
+            position: Vec2::ZERO,
+            elapsed: 0.0,
+            spawn_debt: 0.0,
+        }
+    }
+
+    fn update(&mut self, delta: f32) {
+        let gravity = self.config.gravity * delta;
+        let mut i = 0;
+        while i < self.particles.len() {
+            let p = &mut self.particles[i];
+            p.age += delta;
+            if p.age >= p.lifetime {
+                self.particles.swap_remove(i);
+                continue;
+            }
+            p.velocity += gravity;
+            p.position += p.velocity * delta;
+            i += 1;
+        }
+
+        let duration = self.config.duration;
+        let mut from = self.elapsed;
+        let mut to = from + delta;
+        let emitting = if self.config.looping {
+            delta
+        } else {
+            (duration - from).clamp(0.0, delta)
+        };
+        if self.config.looping && duration > 0.0 {
+            while to >= duration {
+                self.fire_bursts(from, duration);
+                from = 0.0;
+                to -= duration;
+            }
+        }
+        self.fire_bursts(from, to);
+        self.elapsed = to;
+
+        self.spawn_debt += self.config.spawn_rate * emitting;
+        let count = self.spawn_debt.floor();
+        self.spawn_debt -= count;
+        self.spawn(count as usize);
+    }
+
+    /// Fires the bursts timed within `from..to` of a cycle.
+    fn fire_bursts(&mut self, from: f32, to: f32) {
+        let to = to.min(self.config.duration);
+        for i in 0..self.config.bursts.len() {
+            let burst = self.config.bursts[i];
+            if (from..to).contains(&burst.time) {
+                self.spawn(burst.count);
+            }
+        }
+    }
+
+    fn spawn(&mut self, count: usize) {
+        let room = self
+            .config
+            .max_particles
+            .saturating_sub(self.particles.len());
+        let count = count.min(room);
+        let axis = self
+            .config
+            .direction
+            .try_normalize()
+            .unwrap_or(Vec2::Y)
+            .to_angle();
+        let spread = self.config.spread.clamp(0.0, std::f32::consts::PI);
+        let (speed, lifetime) = (&self.config.speed, &self.config.lifetime);
+        for _ in 0..count {
+            let angle = axis + spread * (fastrand::f32() * 2.0 - 1.0);
+            let speed = speed.start.lerp(speed.end, fastrand::f32());
+            self.particles.push(Particle2D {
+                position: self.position,
+                velocity: Vec2::from_angle(angle) * speed,
+                age: 0.0,
+                lifetime: lifetime
+                    .start
+                    .lerp(lifetime.end, fastrand::f32())
+                    .max(f32::EPSILON),
+            });
+        }
+    }
+
+    fn restart(&mut self) {
+        self.particles.clear();
+        self.elapsed = 0.0;
+        self.spawn_debt = 0.0;
+    }
+
+    fn finished(&self) -> bool {
+        !self.config.looping && self.elapsed >= self.config.duration && self.particles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> EmitterConfig2D {
+        EmitterConfig2D {
+            spawn_rate: 10.0,
+            lifetime: 10.0..10.0,
+            looping: false,
+            duration: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn the_spawn_rate_carries_fractional_particles_over() {
+        let mut simulation = Simulation2D::new(config());
+        simulation.update(0.15);
+        assert_eq!(simulation.particles.len(), 1);
+        simulation.update(0.15);
+        assert_eq!(simulation.particles.len(), 3);
+        // Emission stops after the duration.
+        simulation.update(5.0);
+        assert_eq!(simulation.particles.len(), 10);
+    }
+
+    #[test]
+    fn spawns_beyond_max_particles_are_dropped() {
+        let mut simulation = Simulation2D::new(EmitterConfig2D {
+            max_particles: 4,
+            ..config()
+        });
+        simulation.spawn(10);
+        assert_eq!(simulation.particles.len(), 4);
+        assert!(
+            simulation
+                .particles
+                .iter()
+                .all(|p| p.position == Vec2::ZERO)
+        );
+    }
+
+    #[test]
+    fn bursts_fire_once_per_cycle() {
+        let mut simulation = Simulation2D::new(EmitterConfig2D {
+            spawn_rate: 0.0,
+            bursts: vec![Burst {
+                time: 0.5,
+                count: 5,
+            }],
+            looping: true,
+            ..config()
+        });
+        simulation.update(0.4);
+        assert_eq!(simulation.particles.len(), 0);
+        simulation.update(0.2);
+        assert_eq!(simulation.particles.len(), 5);
+        simulation.update(0.2);
+        assert_eq!(simulation.particles.len(), 5);
+        // Wraps into the second cycle and past its burst.
+        simulation.update(0.8);
+        assert_eq!(simulation.particles.len(), 10);
+    }
+
+    #[test]
+    fn a_one_shot_emitter_finishes_when_its_particles_expire() {
+        let mut simulation = Simulation2D::new(EmitterConfig2D {
+            lifetime: 0.5..0.5,
+            ..config()
+        });
+        simulation.update(1.0);
+        assert!(!simulation.finished());
+        simulation.update(0.6);
+        assert_eq!(simulation.particles.len(), 0);
+        assert!(simulation.finished());
+
+        simulation.restart();
+        assert!(!simulation.finished());
+    }
+
+    #[test]
+    fn particles_move_under_gravity_from_the_spawn_position() {
+        let mut simulation = Simulation2D::new(EmitterConfig2D {
+            spawn_rate: 0.0,
+            speed: 0.0..0.0,
+            gravity: Vec2::new(0.0, -10.0),
+            ..config()
+        });
+        simulation.position = Vec2::new(3.0, 4.0);
+        simulation.spawn(2);
+        simulation.update(0.5);
+        for p in &simulation.particles {
+            assert_eq!(p.velocity, Vec2::new(0.0, -5.0));
+            assert_eq!(p.position, Vec2::new(3.0, 1.5));
+            assert_eq!(p.age, 0.5);
+        }
+    }
+}
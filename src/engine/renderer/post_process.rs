@@ -0,0 +1,433 @@
+use bytemuck::{Pod, Zeroable};
+use once_cell::sync::Lazy;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wgpu::{
+    BindGroup, BindGroupLayout, CommandEncoder, Device, Queue, RenderPipeline, ShaderModule,
+    TextureFormat, TextureView,
+};
+
+use super::bind_group::{BindGroupBuilder, texture_sampler_layout};
+use super::render_target::RenderTarget;
+use super::uniform::UniformBuffer;
+
+/// Format of the images passed between effects. Values above 1 survive
+/// until an effect such as [`PostEffect::tonemap`] maps them back.
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// One fullscreen effect: the caller's fragment shader run over every pixel
+/// of the output.
+///
+/// The shader's entry point must be `fs_main`, taking the texture
+/// coordinate at location 0:
+///
+/// ```wgsl
+/// @fragment
+/// fn fs_main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> { ... }
+/// ```
+pub struct PostProcessPass {
+    pipeline: RenderPipeline,
+}
+
+impl PostProcessPass {
+    /// `bind_group_layout` is group 0 of `shader`; the matching group is
+    /// passed to [`PostProcessPass::draw`].
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        shader: &ShaderModule,
+        bind_group_layout: &BindGroupLayout,
+    ) -> Self {
+        Self::with_layouts(device, format, shader, &[bind_group_layout])
+    }
+
+    fn with_layouts(
+        device: &Device,
+        format: TextureFormat,
+        shader: &ShaderModule,
+        bind_group_layouts: &[&BindGroupLayout],
+    ) -> Self {
+        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Fullscreen Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/fullscreen.wgsl").into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Process Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &vertex,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self { pipeline }
+    }
+
+    /// A layout for the input image like [`RenderTarget::layout`]: the
+    /// image as `texture_2d<f32>` at binding 0 and a filtering sampler at
+    /// binding 1.
+    pub fn input_layout(device: &Device) -> BindGroupLayout {
+        texture_sampler_layout(device, Some("Post Process Input"))
+    }
+
+    /// Records a pass that overwrites all of `output_view`.
+    pub fn draw(
+        &self,
+        encoder: &mut CommandEncoder,
+        input_bind_group: &BindGroup,
+        output_view: &TextureView,
+    ) {
+        self.draw_with(encoder, &[input_bind_group], output_view);
+    }
+
+    /// [`PostProcessPass::draw`] with `bind_groups` in groups 0, 1, ...
+    fn draw_with(
+        &self,
+        encoder: &mut CommandEncoder,
+        bind_groups: &[&BindGroup],
+        output_view: &TextureView,
+    ) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Process Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    // Every pixel is written, so there's nothing to load
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        for (index, bind_group) in bind_groups.iter().enumerate() {
+            rpass.set_bind_group(index as u32, bind_group, &[]);
+        }
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+/// Parameters of [`PostEffect::tonemap`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct TonemapParams {
+    /// Multiplies the image before the curve is applied.
+    pub exposure: f32,
+    /// Extra gamma applied after the curve. Leave at 1 for sRGB surfaces,
+    /// which encode gamma themselves.
+    pub gamma: f32,
+}
+
+impl Default for TonemapParams {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+/// Parameters of [`PostEffect::vignette`]. Distances are from the center of
+/// the screen, scaled so the corners are at 1.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct VignetteParams {
+    /// How much of the color is taken away at full darkening, from 0 to 1.
+    pub strength: f32,
+    /// Distance where the darkening starts.
+    pub radius: f32,
+    /// Distance over which it ramps up to `strength`.
+    pub softness: f32,
+}
+
+impl Default for VignetteParams {
+    fn default() -> Self {
+        Self {
+            strength: 0.5,
+            radius: 0.5,
+            softness: 0.5,
+        }
+    }
+}
+
+/// An effect's own uniform buffer, typed by whatever was passed to
+/// [`PostEffect::with_uniform`].
+struct EffectUniform {
+    buffer: Box<dyn Any + Send + Sync>,
+    layout: BindGroupLayout,
+    bind_group: BindGroup,
+}
+
+/// A fullscreen effect for a [`PostProcessChain`], optionally with a
+/// uniform block of its own. Its shader is written as for
+/// [`PostProcessPass`], with the input image in group 0 and the uniform,
+/// if any, in group 1:
+///
+/// ```wgsl
+/// @group(0) @binding(0) var input_texture: texture_2d<f32>;
+/// @group(0) @binding(1) var input_sampler: sampler;
+/// @group(1) @binding(0) var<uniform> params: Params;
+/// ```
+pub struct PostEffect {
+    shader: ShaderModule,
+    uniform: Option<EffectUniform>,
+    /// Pipelines by output format, built the first time they're needed.
+    passes: Mutex<HashMap<TextureFormat, Arc<PostProcessPass>>>,
+}
+
+impl PostEffect {
+    pub fn new(shader: ShaderModule) -> Self {
+        Self {
+            shader,
+            uniform: None,
+            passes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// An effect whose shader reads `value` from the uniform in group 1.
+    /// Change it later with [`PostEffect::write_uniform`].
+    pub fn with_uniform<T: Pod + Send + Sync>(
+        device: &Device,
+        shader: ShaderModule,
+        value: &T,
+    ) -> Self {
+        let buffer = UniformBuffer::new(device, Some("Post Effect Uniform"), value);
+        let (layout, bind_group) = BindGroupBuilder::new(Some("Post Effect Uniform"))
+            .uniform(device, &buffer, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+
+        Self {
+            uniform: Some(EffectUniform {
+                buffer: Box::new(buffer),
+                layout,
+                bind_group,
+            }),
+            ..Self::new(shader)
+        }
+    }
+
+    /// ACES filmic tonemapping, mapping HDR colors into the displayable
+    /// range. Usually the last effect in a chain.
+    pub fn tonemap(device: &Device, params: TonemapParams) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Tonemap Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/tonemap.wgsl").into()),
+        });
+        Self::with_uniform(device, shader, &params)
+    }
+
+    /// Darkens the image towards its corners.
+    pub fn vignette(device: &Device, params: VignetteParams) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Vignette Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/vignette.wgsl").into()),
+        });
+        Self::with_uniform(device, shader, &params)
+    }
+
+    /// Replaces the value of the effect's uniform.
+    ///
+    /// # Panics
+    ///
+    /// If the effect has no uniform or it was created with another type.
+    pub fn write_uniform<T: Pod + Send + Sync>(&self, queue: &Queue, value: &T) {
+        let buffer = self
+            .uniform
+            .as_ref()
+            .and_then(|uniform| uniform.buffer.downcast_ref::<UniformBuffer<T>>())
+            .expect("the effect's uniform has a different type");
+        buffer.write(queue, value);
+    }
+
+    fn pass(&self, device: &Device, format: TextureFormat) -> Arc<PostProcessPass> {
+        let mut passes = self.passes.lock().unwrap();
+        passes
+            .entry(format)
+            .or_insert_with(|| {
+                let mut layouts = vec![RenderTarget::layout(device)];
+                if let Some(uniform) = &self.uniform {
+                    layouts.push(&uniform.layout);
+                }
+                Arc::new(PostProcessPass::with_layouts(
+                    device,
+                    format,
+                    &self.shader,
+                    &layouts,
+                ))
+            })
+            .clone()
+    }
+
+    pub(crate) fn draw(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        input: &RenderTarget,
+        output_view: &TextureView,
+        format: TextureFormat,
+    ) {
+        let mut bind_groups = vec![input.bind_group()];
+        if let Some(uniform) = &self.uniform {
+            bind_groups.push(&uniform.bind_group);
+        }
+        self.pass(device, format)
+            .draw_with(encoder, &bind_groups, output_view);
+    }
+}
+
+/// Effects applied one after another: the scene is rendered into
+/// [`PostProcessChain::input`], each effect samples the previous one's
+/// result, and the last writes to the final output. In between, images
+/// alternate between two [`HDR_FORMAT`] targets.
+pub struct PostProcessChain {
+    format: TextureFormat,
+    scene: RenderTarget,
+    ping_pong: [RenderTarget; 2],
+    effects: Vec<Arc<PostEffect>>,
+}
+
+impl PostProcessChain {
+    /// An empty chain for a `width * height` output in `format`. The scene
+    /// is rendered in `format` too, so pipelines made for the output can
+    /// draw it.
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        Self {
+            format,
+            scene: RenderTarget::new(device, width, height, format),
+            ping_pong: [
+                RenderTarget::new(device, width, height, HDR_FORMAT),
+                RenderTarget::new(device, width, height, HDR_FORMAT),
+            ],
+            effects: Vec::new(),
+        }
+    }
+
+    pub fn add_effect(&mut self, effect: Arc<PostEffect>) {
+        self.effects.push(effect);
+    }
+
+    pub fn set_effects(&mut self, effects: Vec<Arc<PostEffect>>) {
+        self.effects = effects;
+    }
+
+    /// Resizes every intermediate image, e.g. along with the window.
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.scene.resize(device, width, height);
+        for target in &mut self.ping_pong {
+            target.resize(device, width, height);
+        }
+    }
+
+    /// Records every effect, ending with a write to `output_view`. With no
+    /// effects this records nothing.
+    pub fn run(&self, device: &Device, encoder: &mut CommandEncoder, output_view: &TextureView) {
+        let mut input = &self.scene;
+        for (i, effect) in self.effects.iter().enumerate() {
+            if i + 1 == self.effects.len() {
+                effect.draw(device, encoder, input, output_view, self.format);
+            } else {
+                let output = &self.ping_pong[i % 2];
+                effect.draw(device, encoder, input, output.texture_view(), HDR_FORMAT);
+                input = output;
+            }
+        }
+    }
+
+    //--Getters--//
+
+    /// Where the scene should be rendered for the chain to process it.
+    pub fn input(&self) -> &RenderTarget {
+        &self.scene
+    }
+
+    pub fn effects(&self) -> &[Arc<PostEffect>] {
+        &self.effects
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+/// Effects the window runs over every frame, and a count of changes so it
+/// knows when to pick them up again.
+struct Registry {
+    effects: Vec<Arc<PostEffect>>,
+    version: u64,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| {
+        Mutex::new(Registry {
+            effects: Vec::new(),
+            version: 0,
+        })
+    });
+
+    &REGISTRY
+}
+
+/// Runs `effect` over every frame the window draws, after the effects
+/// added before it. Keep the handle to change its uniform or remove it.
+pub fn add_effect(effect: Arc<PostEffect>) {
+    let mut registry = registry().lock().unwrap();
+    registry.effects.push(effect);
+    registry.version += 1;
+}
+
+/// Returns false if `effect` wasn't added.
+pub fn remove_effect(effect: &Arc<PostEffect>) -> bool {
+    let mut registry = registry().lock().unwrap();
+    let before = registry.effects.len();
+    registry.effects.retain(|e| !Arc::ptr_eq(e, effect));
+    let removed = registry.effects.len() != before;
+    if removed {
+        registry.version += 1;
+    }
+    removed
+}
+
+/// Removes every effect; frames are drawn straight to the window again.
+pub fn clear_effects() {
+    let mut registry = registry().lock().unwrap();
+    registry.effects.clear();
+    registry.version += 1;
+}
+
+/// The effects in order, if they changed since `version`, with the version
+/// to pass next time.
+pub(crate) fn effects_since(version: u64) -> Option<(u64, Vec<Arc<PostEffect>>)> {
+    let registry = registry().lock().unwrap();
+    (registry.version != version).then(|| (registry.version, registry.effects.clone()))
+}
@@ -0,0 +1,245 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline, Sampler,
+    TextureFormat, TextureView,
+};
+
+/// Initial vertex capacity; the buffer doubles whenever a frame needs more.
+const INITIAL_VERTEX_CAPACITY: usize = 6 * 256;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct QuadVertex {
+    /// Pixel coordinates, origin at the top-left of the window.
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl QuadVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<QuadVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// The two triangles of an axis-aligned quad.
+///
+/// `rect` and `uv` are `[x0, y0, x1, y1]`.
+pub fn quad_vertices(rect: [f32; 4], uv: [f32; 4], color: [f32; 4]) -> [QuadVertex; 6] {
+    let [x0, y0, x1, y1] = rect;
+    let [u0, v0, u1, v1] = uv;
+    let v = |x, y, u, v| QuadVertex {
+        position: [x, y],
+        uv: [u, v],
+        color,
+    };
+    [
+        v(x0, y0, u0, v0),
+        v(x0, y1, u0, v1),
+        v(x1, y1, u1, v1),
+        v(x0, y0, u0, v0),
+        v(x1, y1, u1, v1),
+        v(x1, y0, u1, v0),
+    ]
+}
+
+/// Appends the two triangles of an axis-aligned quad to `out`.
+///
+/// `rect` and `uv` are `[x0, y0, x1, y1]`.
+pub fn push_quad(out: &mut Vec<QuadVertex>, rect: [f32; 4], uv: [f32; 4], color: [f32; 4]) {
+    out.extend_from_slice(&quad_vertices(rect, uv, color));
+}
+
+/// Alpha-blended pipeline for textured, tinted quads in screen space.
+pub struct QuadPipeline {
+    pipeline: RenderPipeline,
+    screen_buffer: Buffer,
+    screen_bind_group: BindGroup,
+    texture_layout: BindGroupLayout,
+    vertex_buffer: Buffer,
+    vertex_capacity: usize,
+    vertex_count: u32,
+}
+
+impl QuadPipeline {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Quad Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/quad.wgsl").into()),
+        });
+
+        let screen_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Quad Screen Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Quad Texture Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let screen_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Quad Screen Buffer"),
+            contents: bytemuck::cast_slice(&[1.0f32, 1.0]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let screen_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Quad Screen Bind Group"),
+            layout: &screen_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: screen_buffer.as_entire_binding(),
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Quad Pipeline Layout"),
+            bind_group_layouts: &[&screen_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Quad Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[QuadVertex::layout()],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        let vertex_buffer = Self::create_vertex_buffer(device, INITIAL_VERTEX_CAPACITY);
+
+        Self {
+            pipeline,
+            screen_buffer,
+            screen_bind_group,
+            texture_layout,
+            vertex_buffer,
+            vertex_capacity: INITIAL_VERTEX_CAPACITY,
+            vertex_count: 0,
+        }
+    }
+
+    fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Quad Vertex Buffer"),
+            size: (capacity * std::mem::size_of::<QuadVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn texture_layout(&self) -> &BindGroupLayout {
+        &self.texture_layout
+    }
+
+    pub fn create_texture_bind_group(
+        &self,
+        device: &Device,
+        view: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Quad Texture Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Uploads this frame's vertices, growing the buffer if needed.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        screen_size: [f32; 2],
+        vertices: &[QuadVertex],
+    ) {
+        self.set_screen_size(queue, screen_size);
+
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+        }
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    /// Maps the prepared vertices, which are in pixels, onto a target of
+    /// `screen_size` from the next submission on.
+    pub fn set_screen_size(&self, queue: &Queue, screen_size: [f32; 2]) {
+        queue.write_buffer(&self.screen_buffer, 0, bytemuck::cast_slice(&screen_size));
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>, texture: &'a BindGroup) {
+        if self.vertex_count == 0 {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.screen_bind_group, &[]);
+        rpass.set_bind_group(1, texture, &[]);
+        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        rpass.draw(0..self.vertex_count, 0..1);
+    }
+}
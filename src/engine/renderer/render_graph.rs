@@ -0,0 +1,444 @@
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
+
+use super::post_process::PostEffect;
+use super::render_target::RenderTarget;
+
+/// A texture in a [`RenderGraph`]: the scene, an intermediate image, or the
+/// final frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(usize);
+
+impl TextureHandle {
+    /// What the window draws the scene into before the graph runs.
+    pub const SCENE: TextureHandle = TextureHandle(0);
+    /// The frame the window presents. Passes can write it but not read it.
+    pub const OUTPUT: TextureHandle = TextureHandle(usize::MAX);
+}
+
+#[derive(Debug)]
+pub enum RenderGraphError {
+    /// A pass reads or writes a handle from another graph.
+    UnknownTexture { pass: String },
+    /// A pass reads [`TextureHandle::OUTPUT`].
+    ReadsOutput { pass: String },
+    /// Two passes write the same texture.
+    MultipleWriters { first: String, second: String },
+    /// The passes depend on each other in a loop.
+    Cycle(Vec<String>),
+    /// No pass writes [`TextureHandle::OUTPUT`].
+    NoOutput,
+}
+
+impl fmt::Display for RenderGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderGraphError::UnknownTexture { pass } => {
+                write!(f, "pass {pass:?} uses a texture from another graph")
+            }
+            RenderGraphError::ReadsOutput { pass } => {
+                write!(
+                    f,
+                    "pass {pass:?} reads the output, which can only be written"
+                )
+            }
+            RenderGraphError::MultipleWriters { first, second } => {
+                write!(f, "passes {first:?} and {second:?} write the same texture")
+            }
+            RenderGraphError::Cycle(passes) => {
+                write!(f, "passes depend on each other: {}", passes.join(", "))
+            }
+            RenderGraphError::NoOutput => write!(f, "no pass writes the output"),
+        }
+    }
+}
+
+impl std::error::Error for RenderGraphError {}
+
+/// What a pass gets to record its work with. The fields are public so the
+/// inputs and output can be used while `encoder` is borrowed.
+pub struct PassContext<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pub encoder: &'a mut CommandEncoder,
+    /// In the order given to [`RenderGraph::add_pass`].
+    pub inputs: Vec<&'a RenderTarget>,
+    /// The view to render into. Its previous contents are undefined, so the
+    /// pass should clear it or overwrite every pixel.
+    pub output: &'a TextureView,
+    pub output_format: TextureFormat,
+}
+
+type PassFn = Box<dyn FnMut(&mut PassContext<'_>) + Send>;
+
+struct GraphPass {
+    name: String,
+    inputs: Vec<TextureHandle>,
+    output: TextureHandle,
+    run: PassFn,
+}
+
+/// Render passes connected by the textures they read and write, run each
+/// frame in an order where every pass comes after the passes writing its
+/// inputs.
+///
+/// The window draws the scene into [`TextureHandle::SCENE`] and then runs
+/// the graph, whose last pass writes [`TextureHandle::OUTPUT`]. Every
+/// texture is written by at most one pass per frame; a texture no pass
+/// writes keeps its contents from before. Textures match the window's
+/// size and are resized with it.
+pub struct RenderGraph {
+    output_format: TextureFormat,
+    /// Indexed by handle; the scene is the first.
+    textures: Vec<RenderTarget>,
+    passes: Vec<GraphPass>,
+    /// Pass indices in execution order, worked out on first use after a
+    /// change.
+    order: Option<Vec<usize>>,
+}
+
+impl RenderGraph {
+    /// A graph without passes for a `width * height` output in `format`.
+    /// The scene is rendered in `format` too.
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        Self {
+            output_format: format,
+            textures: vec![RenderTarget::new(device, width, height, format)],
+            passes: Vec::new(),
+            order: None,
+        }
+    }
+
+    /// Adds an intermediate texture, e.g. [`HDR_FORMAT`] for values above 1.
+    ///
+    /// [`HDR_FORMAT`]: super::post_process::HDR_FORMAT
+    pub fn create_texture(&mut self, device: &Device, format: TextureFormat) -> TextureHandle {
+        let scene = &self.textures[0];
+        let target = RenderTarget::new(device, scene.width(), scene.height(), format);
+        self.textures.push(target);
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    /// Adds a pass that reads `inputs` and renders into `output`. `run`
+    /// records its work each frame.
+    pub fn add_pass(
+        &mut self,
+        name: &str,
+        inputs: &[TextureHandle],
+        output: TextureHandle,
+        run: impl FnMut(&mut PassContext<'_>) + Send + 'static,
+    ) {
+        self.passes.push(GraphPass {
+            name: name.to_string(),
+            inputs: inputs.to_vec(),
+            output,
+            run: Box::new(run),
+        });
+        self.order = None;
+    }
+
+    /// Adds a pass running `effect` over `input`.
+    pub fn add_effect(
+        &mut self,
+        name: &str,
+        effect: Arc<PostEffect>,
+        input: TextureHandle,
+        output: TextureHandle,
+    ) {
+        self.add_pass(name, &[input], output, move |ctx| {
+            let (device, format) = (ctx.device, ctx.output_format);
+            effect.draw(device, ctx.encoder, ctx.inputs[0], ctx.output, format);
+        });
+    }
+
+    /// Resizes every texture, e.g. along with the window.
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        for target in &mut self.textures {
+            target.resize(device, width, height);
+        }
+    }
+
+    /// Checks the graph and works out the order its passes run in.
+    pub fn compile(&mut self) -> Result<(), RenderGraphError> {
+        if self.order.is_none() {
+            self.order = Some(self.sort()?);
+        }
+        Ok(())
+    }
+
+    fn sort(&self) -> Result<Vec<usize>, RenderGraphError> {
+        let mut writers: Vec<Option<usize>> = vec![None; self.textures.len()];
+        let mut output_writer = None;
+        for (i, pass) in self.passes.iter().enumerate() {
+            let writer = if pass.output == TextureHandle::OUTPUT {
+                &mut output_writer
+            } else {
+                writers
+                    .get_mut(pass.output.0)
+                    .ok_or_else(|| RenderGraphError::UnknownTexture {
+                        pass: pass.name.clone(),
+                    })?
+            };
+            if let Some(first) = writer.replace(i) {
+                return Err(RenderGraphError::MultipleWriters {
+                    first: self.passes[first].name.clone(),
+                    second: pass.name.clone(),
+                });
+            }
+        }
+        if output_writer.is_none() {
+            return Err(RenderGraphError::NoOutput);
+        }
+
+        // Kahn's algorithm, taking ready passes in the order they were added
+        let mut dependencies = Vec::with_capacity(self.passes.len());
+        for pass in &self.passes {
+            let mut depends_on = Vec::new();
+            for &input in &pass.inputs {
+                if input == TextureHandle::OUTPUT {
+                    return Err(RenderGraphError::ReadsOutput {
+                        pass: pass.name.clone(),
+                    });
+                }
+                let writer =
+                    writers
+                        .get(input.0)
+                        .ok_or_else(|| RenderGraphError::UnknownTexture {
+                            pass: pass.name.clone(),
+                        })?;
+                depends_on.extend(*writer);
+            }
+            dependencies.push(depends_on);
+        }
+
+        let mut order = Vec::with_capacity(self.passes.len());
+        let mut done = vec![false; self.passes.len()];
+        while order.len() < self.passes.len() {
+            let ready = (0..self.passes.len())
+                .find(|&i| !done[i] && dependencies[i].iter().all(|&d| done[d]));
+            let Some(ready) = ready else {
+                let stuck = (0..self.passes.len()).filter(|&i| !done[i]);
+                return Err(RenderGraphError::Cycle(
+                    stuck.map(|i| self.passes[i].name.clone()).collect(),
+                ));
+            };
+            done[ready] = true;
+            order.push(ready);
+        }
+        Ok(order)
+    }
+
+    /// Records every pass, with [`TextureHandle::OUTPUT`] being
+    /// `output_view`.
+    pub fn execute(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        output_view: &TextureView,
+    ) -> Result<(), RenderGraphError> {
+        self.compile()?;
+        let order = self.order.as_deref().unwrap_or_default();
+        for &i in order {
+            let pass = &mut self.passes[i];
+            let (output, output_format) = match pass.output {
+                TextureHandle::OUTPUT => (output_view, self.output_format),
+                handle => {
+                    let target = &self.textures[handle.0];
+                    (target.texture_view(), target.format())
+                }
+            };
+            let mut context = PassContext {
+                device,
+                queue,
+                encoder: &mut *encoder,
+                inputs: pass.inputs.iter().map(|h| &self.textures[h.0]).collect(),
+                output,
+                output_format,
+            };
+            (pass.run)(&mut context);
+        }
+        Ok(())
+    }
+
+    //--Getters--//
+
+    pub fn texture(&self, handle: TextureHandle) -> Option<&RenderTarget> {
+        self.textures.get(handle.0)
+    }
+
+    /// Where the scene should be rendered for the graph to process it.
+    pub fn scene(&self) -> &RenderTarget {
+        &self.textures[0]
+    }
+
+    pub fn output_format(&self) -> TextureFormat {
+        self.output_format
+    }
+
+    pub fn pass_count(&self) -> usize {
+        self.passes.len()
+    }
+}
+
+type GraphBuilder = dyn Fn(&Device, &mut RenderGraph) + Send + Sync;
+
+/// The graph the window renders with, and a count of changes so it knows
+/// when to build it again.
+struct Registry {
+    build: Option<Arc<GraphBuilder>>,
+    version: u64,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| {
+        Mutex::new(Registry {
+            build: None,
+            version: 0,
+        })
+    });
+
+    &REGISTRY
+}
+
+/// Runs the scene through a graph of passes every frame. `build` adds the
+/// passes and textures to an empty graph; the window calls it once the GPU
+/// is ready, before any effects added with
+/// [`post_process::add_effect`](super::post_process::add_effect), which
+/// then process the graph's output.
+pub fn set_graph(build: impl Fn(&Device, &mut RenderGraph) + Send + Sync + 'static) {
+    let mut registry = registry().lock().unwrap();
+    registry.build = Some(Arc::new(build));
+    registry.version += 1;
+}
+
+/// Goes back to drawing the scene straight to the window.
+pub fn clear_graph() {
+    let mut registry = registry().lock().unwrap();
+    registry.build = None;
+    registry.version += 1;
+}
+
+/// The graph builder, if it changed since `version`, with the version to
+/// pass next time.
+pub(crate) fn graph_since(version: u64) -> Option<(u64, Option<Arc<GraphBuilder>>)> {
+    let registry = registry().lock().unwrap();
+    (registry.version != version).then(|| (registry.version, registry.build.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::context::test_device_or_skip;
+
+    const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+    /// Adds a pass that records its name when it runs.
+    fn pass(
+        graph: &mut RenderGraph,
+        log: &Arc<Mutex<Vec<String>>>,
+        name: &str,
+        inputs: &[TextureHandle],
+        output: TextureHandle,
+    ) {
+        let (log, owned) = (log.clone(), name.to_string());
+        graph.add_pass(name, inputs, output, move |_| {
+            log.lock().unwrap().push(owned.clone())
+        });
+    }
+
+    #[test]
+    fn passes_run_after_the_passes_they_read_from() {
+        let (device, queue) = test_device_or_skip!();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph = RenderGraph::new(device, 4, 4, FORMAT);
+        let bright = graph.create_texture(device, FORMAT);
+        let blurred = graph.create_texture(device, FORMAT);
+
+        // Added out of order on purpose
+        pass(
+            &mut graph,
+            &log,
+            "combine",
+            &[TextureHandle::SCENE, blurred],
+            TextureHandle::OUTPUT,
+        );
+        pass(&mut graph, &log, "blur", &[bright], blurred);
+        pass(
+            &mut graph,
+            &log,
+            "threshold",
+            &[TextureHandle::SCENE],
+            bright,
+        );
+
+        let target = RenderTarget::new(device, 4, 4, FORMAT);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        graph
+            .execute(device, queue, &mut encoder, target.texture_view())
+            .unwrap();
+
+        assert_eq!(*log.lock().unwrap(), ["threshold", "blur", "combine"]);
+    }
+
+    #[test]
+    fn invalid_graphs_fail_to_compile() {
+        let (device, _) = test_device_or_skip!();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let graph = || RenderGraph::new(device, 4, 4, FORMAT);
+
+        let mut empty = graph();
+        assert!(matches!(empty.compile(), Err(RenderGraphError::NoOutput)));
+
+        let mut reads_output = graph();
+        pass(
+            &mut reads_output,
+            &log,
+            "a",
+            &[TextureHandle::OUTPUT],
+            TextureHandle::OUTPUT,
+        );
+        assert!(matches!(
+            reads_output.compile(),
+            Err(RenderGraphError::ReadsOutput { .. })
+        ));
+
+        let mut two_writers = graph();
+        pass(&mut two_writers, &log, "a", &[], TextureHandle::OUTPUT);
+        pass(&mut two_writers, &log, "b", &[], TextureHandle::OUTPUT);
+        assert!(matches!(
+            two_writers.compile(),
+            Err(RenderGraphError::MultipleWriters { first, second }) if first == "a" && second == "b"
+        ));
+
+        let mut foreign = graph();
+        pass(
+            &mut foreign,
+            &log,
+            "a",
+            &[TextureHandle(7)],
+            TextureHandle::OUTPUT,
+        );
+        assert!(matches!(
+            foreign.compile(),
+            Err(RenderGraphError::UnknownTexture { .. })
+        ));
+
+        let mut cycle = graph();
+        let (x, y) = (
+            cycle.create_texture(device, FORMAT),
+            cycle.create_texture(device, FORMAT),
+        );
+        pass(&mut cycle, &log, "x", &[y], x);
+        pass(&mut cycle, &log, "y", &[x], y);
+        pass(&mut cycle, &log, "out", &[x], TextureHandle::OUTPUT);
+        assert!(matches!(
+            cycle.compile(),
+            Err(RenderGraphError::Cycle(stuck)) if stuck == ["x", "y", "out"]
+        ));
+
+        assert!(log.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wgpu::{
+    BindGroup, BindGroupLayout, CommandEncoder, Device, RenderPass, Sampler, Texture,
+    TextureFormat, TextureView,
+};
+
+use super::bind_group::{BindGroupBuilder, texture_sampler_layout};
+use super::depth::{DEPTH_CLEAR, create_depth_view};
+use super::post_process::PostProcessPass;
+
+static LAYOUT: OnceCell<BindGroupLayout> = OnceCell::new();
+
+/// An offscreen color texture that can be rendered to and then sampled in
+/// a later pass, optionally with its own depth buffer.
+pub struct RenderTarget {
+    texture: Texture,
+    view: TextureView,
+    sampler: Sampler,
+    depth_view: Option<TextureView>,
+    format: TextureFormat,
+    bind_group: BindGroup,
+}
+
+impl RenderTarget {
+    /// A `width * height` color target. It can be sampled, and copied out
+    /// (e.g. for screenshots).
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        let (texture, view) = Self::create_color(device, width, height, format);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Render Target Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = Self::create_bind_group(device, &view, &sampler);
+
+        Self {
+            texture,
+            view,
+            sampler,
+            depth_view: None,
+            format,
+            bind_group,
+        }
+    }
+
+    /// Layout of [`RenderTarget::bind_group`]: the color texture as
+    /// `texture_2d<f32>` at binding 0 and a filtering sampler at binding 1,
+    /// both visible to the fragment stage.
+    pub fn layout(device: &Device) -> &'static BindGroupLayout {
+        LAYOUT.get_or_init(|| texture_sampler_layout(device, Some("Render Target")))
+    }
+
+    fn create_bind_group(device: &Device, view: &TextureView, sampler: &Sampler) -> BindGroup {
+        BindGroupBuilder::new(Some("Render Target"))
+            .texture(view, wgpu::ShaderStages::FRAGMENT)
+            .sampler(sampler, wgpu::ShaderStages::FRAGMENT)
+            .build_group(device, Self::layout(device))
+    }
+
+    /// Adds a depth buffer in [`DEPTH_FORMAT`](super::depth::DEPTH_FORMAT),
+    /// so pipelines from the main pass can draw into this target.
+    pub fn with_depth(mut self, device: &Device) -> Self {
+        self.depth_view = Some(create_depth_view(device, self.width(), self.height()));
+        self
+    }
+
+    fn create_color(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+    ) -> (Texture, TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Target"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    /// Recreates the textures at the new size; their contents are lost.
+    /// [`RenderTarget::bind_group`] is rebuilt, but bind groups made
+    /// elsewhere from the old view must be too.
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        if (width.max(1), height.max(1)) == (self.width(), self.height()) {
+            return;
+        }
+        (self.texture, self.view) = Self::create_color(device, width, height, self.format);
+        if self.depth_view.is_some() {
+            self.depth_view = Some(create_depth_view(device, self.width(), self.height()));
+        }
+        self.bind_group = Self::create_bind_group(device, &self.view, &self.sampler);
+    }
+
+    /// Starts a pass drawing into this target instead of the surface. The
+    /// color is cleared to `clear`, or kept if `None`; the depth buffer, if
+    /// any, is always cleared.
+    pub fn begin_render_pass<'a>(
+        &'a self,
+        encoder: &'a mut CommandEncoder,
+        clear: Option<wgpu::Color>,
+    ) -> RenderPass<'a> {
+        let load = clear.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear);
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Target Pass"),
+            color_attachments: &[Some(self.as_color_attachment(wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            }))],
+            depth_stencil_attachment: self.as_depth_attachment(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(DEPTH_CLEAR),
+                store: wgpu::StoreOp::Store,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
+
+    pub fn as_color_attachment(
+        &self,
+        ops: wgpu::Operations<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'_> {
+        wgpu::RenderPassColorAttachment {
+            view: &self.view,
+            resolve_target: None,
+            ops,
+        }
+    }
+
+    /// `None` unless created [`RenderTarget::with_depth`].
+    pub fn as_depth_attachment(
+        &self,
+        depth_ops: wgpu::Operations<f32>,
+    ) -> Option<wgpu::RenderPassDepthStencilAttachment<'_>> {
+        self.depth_view
+            .as_ref()
+            .map(|view| wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: Some(depth_ops),
+                stencil_ops: None,
+            })
+    }
+
+    //--Getters--//
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// View for binding the rendered image as a shader input.
+    pub fn texture_view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    /// The color texture and sampler, bound as in [`RenderTarget::layout`].
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    pub fn depth_view(&self) -> Option<&TextureView> {
+        self.depth_view.as_ref()
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+}
+
+/// Records a pass copying `source` over all of `destination`, stretching it
+/// to fit. `format` is the destination's, e.g. the surface format.
+pub fn blit(
+    device: &Device,
+    encoder: &mut CommandEncoder,
+    source: &RenderTarget,
+    destination: &TextureView,
+    format: TextureFormat,
+) {
+    static PASSES: Lazy<Mutex<HashMap<TextureFormat, Arc<PostProcessPass>>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+
+    let pass = PASSES
+        .lock()
+        .unwrap()
+        .entry(format)
+        .or_insert_with(|| {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Blit Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/blit.wgsl").into()),
+            });
+            Arc::new(PostProcessPass::new(
+                device,
+                format,
+                &shader,
+                RenderTarget::layout(device),
+            ))
+        })
+        .clone();
+    pass.draw(encoder, source.bind_group(), destination);
+}
@@ -0,0 +1,151 @@
+use image::{ImageEncoder, RgbaImage};
+use image::codecs::png::PngEncoder;
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use wgpu::{Device, Queue, Texture, TextureFormat};
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    IoError(std::io::Error),
+    ImageError(image::ImageError),
+    GpuMapError(wgpu::BufferAsyncError),
+    /// The surface wasn't created with `COPY_SRC`, so it can't be read back.
+    NotCopyable,
+    /// Only 8-bit RGBA/BGRA surfaces can be saved.
+    UnsupportedFormat(TextureFormat),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::IoError(e) => write!(f, "failed to write screenshot: {e}"),
+            ScreenshotError::ImageError(e) => write!(f, "failed to encode screenshot: {e}"),
+            ScreenshotError::GpuMapError(e) => write!(f, "failed to read back frame: {e}"),
+            ScreenshotError::NotCopyable => write!(f, "the surface does not support copying"),
+            ScreenshotError::UnsupportedFormat(format) => {
+                write!(f, "cannot save a {format:?} surface")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+impl From<std::io::Error> for ScreenshotError {
+    fn from(e: std::io::Error) -> Self {
+        ScreenshotError::IoError(e)
+    }
+}
+
+impl From<image::ImageError> for ScreenshotError {
+    fn from(e: image::ImageError) -> Self {
+        ScreenshotError::ImageError(e)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for ScreenshotError {
+    fn from(e: wgpu::BufferAsyncError) -> Self {
+        ScreenshotError::GpuMapError(e)
+    }
+}
+
+/// Copies `texture` to the CPU, blocking until the GPU is done, and saves it
+/// as a PNG at `path`.
+pub(crate) fn save_texture_png(
+    device: &Device,
+    queue: &Queue,
+    texture: &Texture,
+    path: &Path,
+) -> Result<(), ScreenshotError> {
+    let image = read_texture_rgba(device, queue, texture)?;
+    let writer = BufWriter::new(File::create(path)?);
+    PngEncoder::new(writer).write_image(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        image::ExtendedColorType::Rgba8,
+    )?;
+    Ok(())
+}
+
+/// Copies `texture` to the CPU as opaque RGBA, blocking until the GPU is
+/// done. sRGB textures come back with their stored (encoded) values, as an
+/// image file expects.
+pub(crate) fn read_texture_rgba(
+    device: &Device,
+    queue: &Queue,
+    texture: &Texture,
+) -> Result<RgbaImage, ScreenshotError> {
+    let format = texture.format();
+    let swap_red_blue = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        _ => return Err(ScreenshotError::UnsupportedFormat(format)),
+    };
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+        return Err(ScreenshotError::NotCopyable);
+    }
+
+    let (width, height) = (texture.width(), texture.height());
+    let unpadded_bytes_per_row = 4 * width;
+    // Buffer rows must start on 256-byte boundaries
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Screenshot Buffer"),
+        size: u64::from(padded_bytes_per_row) * u64::from(height),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Screenshot Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .expect("map_async callback runs during poll")?;
+
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks_exact(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    // The frame is presented opaque regardless of what alpha ended up in it
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+
+    Ok(RgbaImage::from_raw(width, height, pixels).expect("pixel buffer matches the texture size"))
+}
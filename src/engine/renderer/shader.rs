@@ -0,0 +1,64 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use wgpu::{Device, ShaderModule};
+
+use super::shader_preprocessor::ShaderPreprocessor;
+
+#[derive(Debug)]
+pub enum ShaderError {
+    /// A shader or one of its includes couldn't be read.
+    Io(PathBuf, std::io::Error),
+    /// An `#include` line without a quoted path.
+    BadInclude { file: PathBuf, line: usize },
+    /// The files that include each other, ending with the one that closes
+    /// the loop.
+    IncludeCycle(Vec<PathBuf>),
+    /// wgpu rejected the preprocessed source.
+    Compile(String),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io(path, e) => write!(f, "failed to read {}: {e}", path.display()),
+            ShaderError::BadInclude { file, line } => {
+                write!(f, "malformed #include at {}:{line}", file.display())
+            }
+            ShaderError::IncludeCycle(chain) => {
+                let chain: Vec<_> = chain.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "include cycle: {}", chain.join(" -> "))
+            }
+            ShaderError::Compile(e) => write!(f, "failed to compile shader: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Shader modules built from WGSL files on disk.
+pub struct Shader;
+
+impl Shader {
+    /// Loads the WGSL file at `path`, expands its
+    /// `// #include "file.wgsl"` lines with files under `include_root` (see
+    /// [`ShaderPreprocessor`]) and compiles the result.
+    pub fn from_wgsl_file_with_includes(
+        device: &Device,
+        path: impl AsRef<Path>,
+        include_root: impl AsRef<Path>,
+    ) -> Result<ShaderModule, ShaderError> {
+        let path = path.as_ref();
+        let source = ShaderPreprocessor::new(include_root).process_file(path)?;
+
+        // Catch validation errors instead of letting wgpu panic on them
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: path.to_str(),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(e) => Err(ShaderError::Compile(e.to_string())),
+            None => Ok(module),
+        }
+    }
+}
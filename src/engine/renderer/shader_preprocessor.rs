@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::shader::ShaderError;
+
+/// Expands `#include` directives in WGSL, which has no way of its own to
+/// share code between files. A directive is a line comment of the form
+///
+/// ```wgsl
+/// // #include "common/lighting.wgsl"
+/// ```
+///
+/// and is replaced by the named file, resolved against the include root.
+/// Includes may include other files. Each file is pasted in at most once,
+/// so two files can share a dependency without redefining it; a file that
+/// ends up including itself is an error.
+pub struct ShaderPreprocessor {
+    include_root: PathBuf,
+}
+
+/// Files currently being expanded, outermost first, and every file pasted
+/// in so far.
+#[derive(Default)]
+struct Expansion {
+    stack: Vec<PathBuf>,
+    included: HashSet<PathBuf>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new(include_root: impl AsRef<Path>) -> Self {
+        Self {
+            include_root: include_root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads the shader at `path` and expands its includes.
+    pub fn process_file(&self, path: impl AsRef<Path>) -> Result<String, ShaderError> {
+        let mut expansion = Expansion::default();
+        let mut output = String::new();
+        self.expand_file(path.as_ref(), &mut expansion, &mut output)?;
+        Ok(output)
+    }
+
+    /// Expands the includes in `source`, which didn't come from a file.
+    pub fn process(&self, source: &str) -> Result<String, ShaderError> {
+        let mut expansion = Expansion::default();
+        let mut output = String::new();
+        self.expand(source, Path::new("<source>"), &mut expansion, &mut output)?;
+        Ok(output)
+    }
+
+    fn expand_file(
+        &self,
+        path: &Path,
+        expansion: &mut Expansion,
+        output: &mut String,
+    ) -> Result<(), ShaderError> {
+        let canonical =
+            std::fs::canonicalize(path).map_err(|e| ShaderError::Io(path.to_path_buf(), e))?;
+        if expansion.stack.contains(&canonical) {
+            let mut chain = expansion.stack.clone();
+            chain.push(canonical);
+            return Err(ShaderError::IncludeCycle(chain));
+        }
+        if !expansion.included.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let source =
+            std::fs::read_to_string(path).map_err(|e| ShaderError::Io(path.to_path_buf(), e))?;
+        expansion.stack.push(canonical);
+        self.expand(&source, path, expansion, output)?;
+        expansion.stack.pop();
+        Ok(())
+    }
+
+    fn expand(
+        &self,
+        source: &str,
+        file: &Path,
+        expansion: &mut Expansion,
+        output: &mut String,
+    ) -> Result<(), ShaderError> {
+        for (i, line) in source.lines().enumerate() {
+            match parse_include(line) {
+                None => {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+                Some(Some(include)) => {
+                    let path = self.include_root.join(include);
+                    self.expand_file(&path, expansion, output)?;
+                }
+                Some(None) => {
+                    return Err(ShaderError::BadInclude {
+                        file: file.to_path_buf(),
+                        line: i + 1,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `None` if `line` isn't a directive, `Some(None)` if it is but has no
+/// quoted path.
+fn parse_include(line: &str) -> Option<Option<&str>> {
+    let rest = line.trim().strip_prefix("//")?.trim_start();
+    let rest = rest.strip_prefix("#include")?.trim();
+    Some(
+        rest.strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+            .filter(|path| !path.is_empty()),
+    )
+}
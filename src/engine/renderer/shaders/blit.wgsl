@@ -0,0 +1,10 @@
+// Copies the bound texture unchanged. Runs as a post-process pass, so the
+// vertex stage comes from fullscreen.wgsl.
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+
+@fragment
+fn fs_main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, uv);
+}
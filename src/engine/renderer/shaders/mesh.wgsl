@@ -0,0 +1,95 @@
+// Meshes seen through the active Camera3D, lit unless their material says
+// otherwise. Compiled with the lighting snippet
+// (engine/lighting/lighting.wgsl) prepended.
+
+struct Camera {
+    view_projection: mat4x4<f32>,
+    position: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(0) var<uniform> lighting: Lighting;
+
+struct Material {
+    diffuse_color: vec4<f32>,
+    has_texture: u32,
+    lit: u32,
+};
+
+@group(2) @binding(0) var<uniform> material: Material;
+@group(2) @binding(1) var diffuse_texture: texture_2d<f32>;
+@group(2) @binding(2) var diffuse_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(3) model_0: vec4<f32>,
+    @location(4) model_1: vec4<f32>,
+    @location(5) model_2: vec4<f32>,
+    @location(6) model_3: vec4<f32>,
+    @location(7) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) uv: vec2<f32>,
+};
+
+const SHININESS: f32 = 32.0;
+
+@vertex
+fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    var out: VertexOutput;
+    let world_position = model * vec4<f32>(in.position, 1.0);
+    out.clip_position = camera.view_projection * world_position;
+    out.world_position = world_position.xyz;
+    // Fine for rotations and uniform scale, which covers typical use
+    out.normal = (model * vec4<f32>(in.normal, 0.0)).xyz;
+    out.color = instance.color;
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let view_dir = camera.position.xyz - in.world_position;
+    var light = vec3<f32>(1.0);
+    if material.lit != 0u {
+        light = phong_lighting(lighting, in.world_position, in.normal, view_dir, SHININESS);
+    }
+    // Sampled unconditionally: textureSample needs uniform control flow
+    let texel = textureSample(diffuse_texture, diffuse_sampler, in.uv);
+    let base = in.color * material.diffuse_color
+        * select(vec4<f32>(1.0), texel, material.has_texture != 0u);
+    return vec4<f32>(base.rgb * light, base.a);
+}
+
+// Debug views, swapped in for fs_main by mesh_renderer::set_debug_view
+
+@fragment
+fn fs_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    // World-space axes map to red, green and blue
+    return vec4<f32>(normalize(in.normal) * 0.5 + 0.5, 1.0);
+}
+
+@fragment
+fn fs_uvs(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(fract(in.uv), 0.0, 1.0);
+}
+
+@fragment
+fn fs_depth(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Reverse-Z with an infinite far plane crowds depth values near zero,
+    // so shade by distance instead: white up close, fading with distance
+    let distance = length(camera.position.xyz - in.world_position);
+    let shade = 1.0 / (1.0 + distance * 0.1);
+    return vec4<f32>(vec3<f32>(shade), 1.0);
+}
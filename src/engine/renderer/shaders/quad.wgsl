@@ -0,0 +1,39 @@
+// Screen-space textured quads. Positions are in pixels, origin top-left.
+
+struct Screen {
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> screen: Screen;
+@group(1) @binding(0) var quad_texture: texture_2d<f32>;
+@group(1) @binding(1) var quad_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let ndc = vec2<f32>(
+        in.position.x / screen.size.x * 2.0 - 1.0,
+        1.0 - in.position.y / screen.size.y * 2.0,
+    );
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(quad_texture, quad_sampler, in.uv) * in.color;
+}
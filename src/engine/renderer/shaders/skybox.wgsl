@@ -0,0 +1,38 @@
+// Cubemap background behind everything else, drawn as one triangle at the
+// far plane.
+
+struct Sky {
+    inverse_view_projection: mat4x4<f32>,
+    camera_position: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> sky: Sky;
+@group(1) @binding(0) var sky_texture: texture_cube<f32>;
+@group(1) @binding(1) var sky_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // (-1, -1), (3, -1), (-1, 3): the corners past the screen are clipped
+    let ndc = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    // Depth 0 is the far plane with reverse-Z
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Unproject onto the near plane (depth 1), which stays finite even with
+    // an infinite far plane
+    let near = sky.inverse_view_projection * vec4<f32>(in.ndc, 1.0, 1.0);
+    let dir = near.xyz / near.w - sky.camera_position.xyz;
+    // Cubemaps are left-handed; the world is right-handed
+    return textureSample(sky_texture, sky_sampler, vec3<f32>(dir.xy, -dir.z));
+}
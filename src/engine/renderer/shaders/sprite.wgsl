@@ -0,0 +1,62 @@
+// World-space sprites seen through the active Camera2D. Each instance is a
+// unit quad scaled to the sprite's size by its model matrix.
+
+struct Camera {
+    view_projection: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(0) var sprite_texture: texture_2d<f32>;
+@group(1) @binding(1) var sprite_sampler: sampler;
+
+struct InstanceInput {
+    @location(0) model_0: vec4<f32>,
+    @location(1) model_1: vec4<f32>,
+    @location(2) model_2: vec4<f32>,
+    @location(3) model_3: vec4<f32>,
+    @location(4) uv_rect: vec4<f32>,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index];
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_projection * model * vec4<f32>(corner - vec2<f32>(0.5), 0.0, 1.0);
+    // World y points up while texture v points down
+    out.uv = vec2<f32>(
+        mix(instance.uv_rect.x, instance.uv_rect.z, corner.x),
+        mix(instance.uv_rect.w, instance.uv_rect.y, corner.y),
+    );
+    out.color = instance.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(sprite_texture, sprite_sampler, in.uv) * in.color;
+}
+
+// For SpriteBlend::Multiply: transparent pixels become white, which leaves
+// what's behind unchanged.
+@fragment
+fn fs_multiply(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(sprite_texture, sprite_sampler, in.uv) * in.color;
+    return vec4<f32>(mix(vec3<f32>(1.0), color.rgb, color.a), color.a);
+}
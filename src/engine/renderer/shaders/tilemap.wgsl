@@ -0,0 +1,48 @@
+// Tilemap chunks seen through the active Camera2D. Each instance is one
+// tile, already placed in world space.
+
+struct Camera {
+    view_projection: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(0) var tile_texture: texture_2d<f32>;
+@group(1) @binding(1) var tile_sampler: sampler;
+
+struct InstanceInput {
+    @location(0) min: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) uv_rect: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_projection * vec4<f32>(instance.min + corner * instance.size, 0.0, 1.0);
+    // World y points up while texture v points down
+    out.uv = vec2<f32>(
+        mix(instance.uv_rect.x, instance.uv_rect.z, corner.x),
+        mix(instance.uv_rect.w, instance.uv_rect.y, corner.y),
+    );
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(tile_texture, tile_sampler, in.uv);
+}
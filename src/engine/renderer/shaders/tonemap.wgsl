@@ -0,0 +1,24 @@
+// Built-in tonemapping: scales the image by the exposure, maps it into
+// [0, 1] with the ACES filmic curve and applies an extra gamma. The surface
+// is usually sRGB and encodes gamma by itself, hence the default of 1.
+
+struct Tonemap {
+    exposure: f32,
+    gamma: f32,
+};
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var input_sampler: sampler;
+@group(1) @binding(0) var<uniform> params: Tonemap;
+
+// Krzysztof Narkowicz's fit of the ACES curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, uv);
+    let mapped = aces(color.rgb * params.exposure);
+    return vec4<f32>(pow(mapped, vec3<f32>(1.0 / params.gamma)), color.a);
+}
@@ -0,0 +1,22 @@
+// Built-in vignette: darkens the image towards its corners. Pixels closer
+// to the center than `radius` are untouched; past it the darkening ramps
+// up over `softness` until it reaches `strength`. Distances are scaled so
+// the corners are at 1.
+
+struct Vignette {
+    strength: f32,
+    radius: f32,
+    softness: f32,
+};
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var input_sampler: sampler;
+@group(1) @binding(0) var<uniform> params: Vignette;
+
+@fragment
+fn fs_main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, uv);
+    let from_center = length(uv - vec2<f32>(0.5)) * sqrt(2.0);
+    let falloff = smoothstep(params.radius, params.radius + params.softness, from_center);
+    return vec4<f32>(color.rgb * (1.0 - params.strength * falloff), color.a);
+}
@@ -0,0 +1,437 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use image::Rgba32FImage;
+use once_cell::sync::{Lazy, OnceCell};
+use std::f32::consts::{PI, TAU};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use wgpu::{BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
+
+use super::bind_group::BindGroupBuilder;
+use super::camera3d::Camera3D;
+use super::depth::DEPTH_FORMAT;
+use super::uniform::UniformBuffer;
+
+#[derive(Debug)]
+pub enum SkyboxError {
+    Io(std::io::Error),
+    ImageError(image::ImageError),
+    /// Faces must be square and all the same size.
+    FaceSize {
+        face: usize,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for SkyboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkyboxError::Io(e) => write!(f, "failed to read skybox image: {e}"),
+            SkyboxError::ImageError(e) => write!(f, "failed to decode skybox image: {e}"),
+            SkyboxError::FaceSize {
+                face,
+                width,
+                height,
+            } => write!(
+                f,
+                "skybox face {face} is {width}x{height}; faces must be square and the same size"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SkyboxError {}
+
+impl From<std::io::Error> for SkyboxError {
+    fn from(e: std::io::Error) -> Self {
+        SkyboxError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for SkyboxError {
+    fn from(e: image::ImageError) -> Self {
+        SkyboxError::ImageError(e)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SkyUniformData {
+    inverse_view_projection: [f32; 16],
+    camera_position: [f32; 4],
+}
+
+fn active_skybox() -> &'static Mutex<Option<Arc<SkyboxRenderer>>> {
+    static INSTANCE: Lazy<Mutex<Option<Arc<SkyboxRenderer>>>> = Lazy::new(|| Mutex::new(None));
+
+    &INSTANCE
+}
+
+/// A cubemap drawn behind the 3D scene, seen through the active
+/// [`Camera3D`]. Make it the background with [`SkyboxRenderer::set_active`].
+///
+/// Face images go in the order right (+x), left (-x), top (+y), bottom
+/// (-y), front, back, where front is what the default camera sees looking
+/// down -z.
+pub struct SkyboxRenderer {
+    uniform: UniformBuffer<SkyUniformData>,
+    uniform_layout: BindGroupLayout,
+    uniform_bind_group: BindGroup,
+    texture_layout: BindGroupLayout,
+    texture_bind_group: BindGroup,
+    /// Created on first use, once the surface format is known.
+    pipeline: OnceCell<RenderPipeline>,
+    face_size: u32,
+}
+
+impl SkyboxRenderer {
+    /// Decodes six face images (PNG, JPEG), square and all the same size.
+    pub fn from_faces(
+        device: &Device,
+        queue: &Queue,
+        faces: [&[u8]; 6],
+    ) -> Result<Self, SkyboxError> {
+        let mut size = None;
+        let mut pixels = Vec::new();
+        for (face, bytes) in faces.iter().enumerate() {
+            let image = image::load_from_memory(bytes)?.to_rgba8();
+            let (width, height) = image.dimensions();
+            if width != height || size.is_some_and(|size| size != width) {
+                return Err(SkyboxError::FaceSize {
+                    face,
+                    width,
+                    height,
+                });
+            }
+            size = Some(width);
+            pixels.extend_from_slice(&image);
+        }
+
+        let size = size.unwrap_or(0);
+        Ok(Self::new(
+            device,
+            queue,
+            size,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            &pixels,
+        ))
+    }
+
+    /// [`SkyboxRenderer::from_faces`] with the faces read from `paths`.
+    pub fn from_face_files<P: AsRef<Path>>(
+        device: &Device,
+        queue: &Queue,
+        paths: &[P; 6],
+    ) -> Result<Self, SkyboxError> {
+        let mut faces = Vec::with_capacity(6);
+        for path in paths {
+            faces.push(std::fs::read(path)?);
+        }
+        let faces: [&[u8]; 6] = std::array::from_fn(|i| faces[i].as_slice());
+        Self::from_faces(device, queue, faces)
+    }
+
+    /// [`SkyboxRenderer::from_equirectangular`] with the panorama read from
+    /// `path`.
+    pub fn from_equirectangular_file(
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, SkyboxError> {
+        Self::from_equirectangular(device, queue, &std::fs::read(path)?)
+    }
+
+    /// Converts a 2:1 equirectangular panorama (Radiance HDR, PNG, JPEG)
+    /// to a cubemap. HDR values above 1 are kept.
+    pub fn from_equirectangular(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+    ) -> Result<Self, SkyboxError> {
+        let panorama = image::load_from_memory(bytes)?.to_rgba32f();
+        // A quarter of the width keeps roughly the panorama's resolution
+        let size = (panorama.width() / 4).max(1);
+
+        let mut pixels = Vec::with_capacity(6 * (size * size) as usize * 8);
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+                    let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+                    let texel = sample_equirectangular(&panorama, face_direction(face, s, t));
+                    for channel in texel {
+                        pixels.extend_from_slice(&f16_bits(channel).to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        Ok(Self::new(
+            device,
+            queue,
+            size,
+            wgpu::TextureFormat::Rgba16Float,
+            &pixels,
+        ))
+    }
+
+    /// `pixels` holds the six faces one after another.
+    fn new(
+        device: &Device,
+        queue: &Queue,
+        size: u32,
+        format: TextureFormat,
+        pixels: &[u8],
+    ) -> Self {
+        let extent = wgpu::Extent3d {
+            width: size.max(1),
+            height: size.max(1),
+            depth_or_array_layers: 6,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Skybox Texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        if size > 0 {
+            let bytes_per_pixel = format.block_copy_size(None).unwrap_or(4);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                pixels,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_pixel * size),
+                    rows_per_image: Some(size),
+                },
+                extent,
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Skybox View"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Skybox Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform = UniformBuffer::new(device, Some("Skybox Buffer"), &SkyUniformData::zeroed());
+        let (uniform_layout, uniform_bind_group) = BindGroupBuilder::new(Some("Skybox Camera"))
+            .uniform(device, &uniform, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+
+        // BindGroupBuilder only knows 2D textures
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Skybox Texture"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Skybox Texture"),
+            layout: &texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            uniform,
+            uniform_layout,
+            uniform_bind_group,
+            texture_layout,
+            texture_bind_group,
+            pipeline: OnceCell::new(),
+            face_size: size,
+        }
+    }
+
+    /// Draws `skybox` behind the scene from the next frame on, or no sky
+    /// with `None` (the default).
+    pub fn set_active(skybox: Option<Arc<SkyboxRenderer>>) {
+        *active_skybox().lock().unwrap() = skybox;
+    }
+
+    pub fn active() -> Option<Arc<SkyboxRenderer>> {
+        active_skybox().lock().unwrap().clone()
+    }
+
+    fn create_pipeline(&self, device: &Device, format: TextureFormat) -> RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/skybox.wgsl").into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Pipeline Layout"),
+            bind_group_layouts: &[&self.uniform_layout, &self.texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Skybox Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            // Only fills pixels nothing has been drawn to yet. Reverse-Z puts
+            // the far plane at 0, so this is the usual LessEqual flipped.
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::GreaterEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+
+    /// Uploads the camera, creating the pipeline on first use.
+    pub(crate) fn prepare(&self, device: &Device, queue: &Queue, format: TextureFormat) {
+        self.pipeline
+            .get_or_init(|| self.create_pipeline(device, format));
+
+        let camera = Camera3D::get_instance().lock().unwrap();
+        let data = SkyUniformData {
+            inverse_view_projection: camera.view_projection().inverse().to_cols_array(),
+            camera_position: camera.position.extend(1.0).to_array(),
+        };
+        self.uniform.write(queue, &data);
+    }
+
+    /// Must come after [`SkyboxRenderer::prepare`] and after the opaque 3D
+    /// scene, so the depth test skips covered pixels.
+    pub(crate) fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        let Some(pipeline) = self.pipeline.get() else {
+            return;
+        };
+        rpass.set_pipeline(pipeline);
+        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        rpass.set_bind_group(1, &self.texture_bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+
+    //--Getters--//
+
+    /// Width and height of each face in pixels.
+    pub fn face_size(&self) -> u32 {
+        self.face_size
+    }
+}
+
+/// World-space direction through texel `(s, t)` (each -1..1, `t` down) of
+/// cubemap `face`, using the left-handed cubemap convention the shader
+/// compensates for.
+fn face_direction(face: usize, s: f32, t: f32) -> Vec3 {
+    let cube = match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    };
+    Vec3::new(cube.x, cube.y, -cube.z).normalize()
+}
+
+/// Bilinear sample of `panorama` in direction `dir`. The panorama's center
+/// column faces -z, the default camera's view.
+fn sample_equirectangular(panorama: &Rgba32FImage, dir: Vec3) -> [f32; 4] {
+    let longitude = dir.x.atan2(-dir.z);
+    let latitude = dir.y.clamp(-1.0, 1.0).asin();
+    let (width, height) = panorama.dimensions();
+    let u = (0.5 + longitude / TAU) * width as f32 - 0.5;
+    let v = (0.5 - latitude / PI) * height as f32 - 0.5;
+
+    let (x0, y0) = (u.floor(), v.floor());
+    let (fx, fy) = (u - x0, v - y0);
+    // Wraps around horizontally, clamps at the poles
+    let texel = |x: f32, y: f32| {
+        let x = (x as i64).rem_euclid(i64::from(width)) as u32;
+        let y = (y.max(0.0) as u32).min(height - 1);
+        panorama.get_pixel(x, y).0
+    };
+    let (a, b) = (texel(x0, y0), texel(x0 + 1.0, y0));
+    let (c, d) = (texel(x0, y0 + 1.0), texel(x0 + 1.0, y0 + 1.0));
+    std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        top + (bottom - top) * fy
+    })
+}
+
+/// Nearest IEEE half-float bit pattern, for uploading `Rgba16Float`.
+/// Values too large become infinity and tiny ones flush to zero, which is
+/// fine for colors.
+fn f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        return sign;
+    }
+    // Round to nearest; a carry into the exponent is still correct
+    let rounded = ((exponent as u32) << 10 | mantissa >> 13) + ((mantissa >> 12) & 1);
+    sign | rounded.min(0x7c00) as u16
+}
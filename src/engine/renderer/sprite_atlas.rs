@@ -0,0 +1,259 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::engine::math::rect::Rect;
+use crate::engine::renderer::texture::Texture2D;
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Json(serde_json::Error),
+    InvalidGrid { cols: u32, rows: u32 },
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Json(e) => write!(f, "failed to parse atlas JSON: {e}"),
+            AtlasError::InvalidGrid { cols, rows } => {
+                write!(f, "invalid atlas grid {cols}x{rows}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+impl From<serde_json::Error> for AtlasError {
+    fn from(e: serde_json::Error) -> Self {
+        AtlasError::Json(e)
+    }
+}
+
+/// A texture split into named sprites, measured in pixels.
+pub struct SpriteAtlas {
+    texture: Arc<Texture2D>,
+    sprites: HashMap<String, Rect>,
+    /// Sprite names in source order, for lookup by frame index.
+    order: Vec<String>,
+}
+
+impl SpriteAtlas {
+    /// Splits the texture into `cols * rows` equal cells named `"0"`,
+    /// `"1"`, ... in row-major order.
+    pub fn from_texture_and_grid(
+        texture: Arc<Texture2D>,
+        cols: u32,
+        rows: u32,
+    ) -> Result<Self, AtlasError> {
+        if cols == 0 || rows == 0 {
+            return Err(AtlasError::InvalidGrid { cols, rows });
+        }
+
+        let cell_w = texture.width() as f32 / cols as f32;
+        let cell_h = texture.height() as f32 / rows as f32;
+        let mut atlas = Self::empty(texture);
+        for row in 0..rows {
+            for col in 0..cols {
+                let rect = Rect::new(col as f32 * cell_w, row as f32 * cell_h, cell_w, cell_h);
+                atlas.insert((row * cols + col).to_string(), rect);
+            }
+        }
+        Ok(atlas)
+    }
+
+    /// Cuts the texture into `cell_width * cell_height` cells separated by
+    /// `spacing` pixels, inside a `margin` pixel border. Cells are named
+    /// `"0"`, `"1"`, ... in row-major order; partial cells at the right and
+    /// bottom edges are skipped, so any sheet size works.
+    pub fn from_grid(
+        texture: Arc<Texture2D>,
+        cell_width: u32,
+        cell_height: u32,
+        margin: u32,
+        spacing: u32,
+    ) -> Result<Self, AtlasError> {
+        let fit = |size: u32, cell: u32| {
+            if cell == 0 {
+                return 0;
+            }
+            (size.saturating_sub(2 * margin) + spacing) / (cell + spacing)
+        };
+        let cols = fit(texture.width(), cell_width);
+        let rows = fit(texture.height(), cell_height);
+        if cols == 0 || rows == 0 {
+            return Err(AtlasError::InvalidGrid { cols, rows });
+        }
+
+        let mut atlas = Self::empty(texture);
+        for row in 0..rows {
+            for col in 0..cols {
+                let rect = Rect::new(
+                    (margin + col * (cell_width + spacing)) as f32,
+                    (margin + row * (cell_height + spacing)) as f32,
+                    cell_width as f32,
+                    cell_height as f32,
+                );
+                atlas.insert((row * cols + col).to_string(), rect);
+            }
+        }
+        Ok(atlas)
+    }
+
+    /// Names sub-rectangles of the texture, in pixels, e.g. for a sheet
+    /// laid out by hand. Source order follows `regions`.
+    pub fn from_regions<S: Into<String>>(
+        texture: Arc<Texture2D>,
+        regions: impl IntoIterator<Item = (S, Rect)>,
+    ) -> Self {
+        let mut atlas = Self::empty(texture);
+        for (name, rect) in regions {
+            atlas.insert(name.into(), rect);
+        }
+        atlas
+    }
+
+    /// Reads the JSON written by TexturePacker or Aseprite, in either the
+    /// "hash" (`frames` is an object) or "array" layout. Rotated frames are
+    /// not supported and are read as if unrotated.
+    ///
+    /// A plain object mapping names to rectangles also works:
+    ///
+    /// ```json
+    /// { "player_idle_0": { "x": 0, "y": 0, "w": 24, "h": 32 } }
+    /// ```
+    pub fn from_json(texture: Arc<Texture2D>, json_bytes: &[u8]) -> Result<Self, AtlasError> {
+        let parsed: JsonDocument = serde_json::from_slice(json_bytes)?;
+
+        let mut atlas = Self::empty(texture);
+        let frames = match parsed {
+            JsonDocument::Packed(parsed) => parsed.frames,
+            JsonDocument::Plain(regions) => {
+                for (name, rect) in regions {
+                    let rect: JsonRect = serde_json::from_value(rect)?;
+                    atlas.insert(name, rect.into());
+                }
+                return Ok(atlas);
+            }
+        };
+        match frames {
+            JsonFrames::Hash(frames) => {
+                // Kept as a raw map so the file's frame order survives
+                for (name, frame) in frames {
+                    let frame: JsonFrame = serde_json::from_value(frame)?;
+                    atlas.insert(name, frame.frame.into());
+                }
+            }
+            JsonFrames::Array(frames) => {
+                for frame in frames {
+                    atlas.insert(frame.filename, frame.frame.into());
+                }
+            }
+        }
+        Ok(atlas)
+    }
+
+    fn empty(texture: Arc<Texture2D>) -> Self {
+        Self {
+            texture,
+            sprites: HashMap::new(),
+            order: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, name: String, rect: Rect) {
+        if self.sprites.insert(name.clone(), rect).is_none() {
+            self.order.push(name);
+        }
+    }
+
+    //--Getters--//
+
+    pub fn texture(&self) -> &Arc<Texture2D> {
+        &self.texture
+    }
+
+    /// Pixel rectangle of `name`.
+    pub fn rect(&self, name: &str) -> Option<Rect> {
+        self.sprites.get(name).copied()
+    }
+
+    /// `[u0, v0, u1, v1]` of `name`, normalized to the texture size.
+    pub fn uv_rect(&self, name: &str) -> Option<[f32; 4]> {
+        self.rect(name).map(|r| self.to_uv(r))
+    }
+
+    /// Pixel rectangle of the sprite at `index` in source order, e.g. frame
+    /// `N` of an animation.
+    pub fn rect_at(&self, index: usize) -> Option<Rect> {
+        self.name_at(index).and_then(|name| self.rect(name))
+    }
+
+    /// `[u0, v0, u1, v1]` of the sprite at `index` in source order.
+    pub fn uv_rect_at(&self, index: usize) -> Option<[f32; 4]> {
+        self.rect_at(index).map(|r| self.to_uv(r))
+    }
+
+    /// Name of the sprite at `index` in source order.
+    pub fn name_at(&self, index: usize) -> Option<&str> {
+        self.order.get(index).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn to_uv(&self, r: Rect) -> [f32; 4] {
+        let (w, h) = (self.texture.width() as f32, self.texture.height() as f32);
+        [r.x / w, r.y / h, (r.x + r.width) / w, (r.y + r.height) / h]
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonDocument {
+    Packed(JsonAtlas),
+    Plain(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+struct JsonAtlas {
+    frames: JsonFrames,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFrames {
+    Hash(serde_json::Map<String, serde_json::Value>),
+    Array(Vec<JsonNamedFrame>),
+}
+
+#[derive(Deserialize)]
+struct JsonFrame {
+    frame: JsonRect,
+}
+
+#[derive(Deserialize)]
+struct JsonNamedFrame {
+    filename: String,
+    frame: JsonRect,
+}
+
+#[derive(Deserialize)]
+struct JsonRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl From<JsonRect> for Rect {
+    fn from(r: JsonRect) -> Self {
+        Rect::new(r.x, r.y, r.w, r.h)
+    }
+}
@@ -0,0 +1,503 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec2};
+use once_cell::sync::Lazy;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use wgpu::{BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
+
+use super::animation::AnimationPlayer;
+use super::bind_group::{BindGroupBuilder, TextureBindGroups};
+use super::camera::Camera2D;
+use super::depth::overlay_depth_state;
+use super::sprite_atlas::SpriteAtlas;
+use super::texture::Texture2D;
+use super::uniform::UniformBuffer;
+use crate::engine::math::color::Color;
+use crate::engine::math::rect::Rect;
+use crate::engine::math::transform::Transform2D;
+
+const INITIAL_INSTANCE_CAPACITY: usize = 256;
+
+/// How a sprite's color combines with what's already drawn.
+///
+/// Sprites are drawn in the order they're queued, and a new batch starts
+/// wherever the texture or blending changes, so alternating modes costs a
+/// draw call each time; queue sprites of one mode together where the order
+/// allows. `Additive` and `Multiply` sprites give the same result in any
+/// order among themselves, while `Alpha` and `Opaque` ones cover whatever
+/// was drawn before them, since 2D content doesn't use the depth buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpriteBlend {
+    /// Covers what's behind by the sprite's alpha (the default).
+    #[default]
+    Alpha,
+    /// Adds the color, weighted by alpha, so overlapping sprites brighten
+    /// each other, e.g. for fire and sparks.
+    Additive,
+    /// Replaces what's behind, ignoring alpha, so transparent pixels come
+    /// out black. Meant for backgrounds and other fully opaque sprites.
+    Opaque,
+    /// Multiplies what's behind by the color, weighted by alpha, so it can
+    /// only darken, e.g. for shadows and tinted glass.
+    Multiply,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub(crate) struct SpriteInstance {
+    model: [f32; 16],
+    uv_rect: [f32; 4],
+    color: [f32; 4],
+}
+
+impl SpriteInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        0 => Float32x4, 1 => Float32x4, 2 => Float32x4, 3 => Float32x4,
+        4 => Float32x4, 5 => Float32x4
+    ];
+
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+impl SpriteInstance {
+    /// A `size` world units quad centered on `center`.
+    pub(crate) fn new(center: Vec2, size: Vec2, uv_rect: [f32; 4], color: [f32; 4]) -> Self {
+        let model = Mat4::from_scale_rotation_translation(
+            size.extend(1.0),
+            glam::Quat::IDENTITY,
+            center.extend(0.0),
+        );
+        Self {
+            model: model.to_cols_array(),
+            uv_rect,
+            color,
+        }
+    }
+}
+
+/// Sprites queued for this frame, in draw order, already grouped into
+/// batches.
+struct SpriteQueue {
+    batches: Vec<Batch>,
+    instances: Vec<SpriteInstance>,
+}
+
+impl SpriteQueue {
+    /// Appends `instances`, extending the last batch if it has the same
+    /// texture and blending.
+    fn push(
+        &mut self,
+        texture: &Arc<Texture2D>,
+        blend: SpriteBlend,
+        instances: impl IntoIterator<Item = SpriteInstance>,
+    ) {
+        let start = self.instances.len() as u32;
+        self.instances.extend(instances);
+        let end = self.instances.len() as u32;
+        if start == end {
+            return;
+        }
+        match self.batches.last_mut() {
+            Some(batch) if Arc::ptr_eq(&batch.texture, texture) && batch.blend == blend => {
+                batch.instances.end = end;
+            }
+            _ => self.batches.push(Batch {
+                texture: texture.clone(),
+                blend,
+                instances: start..end,
+            }),
+        }
+    }
+}
+
+fn sprite_queue() -> &'static Mutex<SpriteQueue> {
+    static INSTANCE: Lazy<Mutex<SpriteQueue>> = Lazy::new(|| {
+        Mutex::new(SpriteQueue {
+            batches: Vec::new(),
+            instances: Vec::new(),
+        })
+    });
+
+    &INSTANCE
+}
+
+/// Consecutive sprites sharing a texture and blending, drawn with one call.
+struct Batch {
+    texture: Arc<Texture2D>,
+    blend: SpriteBlend,
+    instances: Range<u32>,
+}
+
+/// Queues `instances` to draw this frame, taking the lock once, e.g. for
+/// every particle of an emitter.
+pub(crate) fn queue_instances(
+    texture: &Arc<Texture2D>,
+    blend: SpriteBlend,
+    instances: impl IntoIterator<Item = SpriteInstance>,
+) {
+    sprite_queue()
+        .lock()
+        .unwrap()
+        .push(texture, blend, instances);
+}
+
+/// Instanced pipeline for world-space sprites, drawn with the active
+/// [`Camera2D`] in the order they were queued.
+pub struct SpriteRenderer {
+    pipeline: RenderPipeline,
+    additive_pipeline: RenderPipeline,
+    opaque_pipeline: RenderPipeline,
+    multiply_pipeline: RenderPipeline,
+    camera: UniformBuffer<[f32; 16]>,
+    camera_bind_group: BindGroup,
+    bind_groups: TextureBindGroups,
+    instance_buffer: Buffer,
+    instance_capacity: usize,
+    instances: Vec<SpriteInstance>,
+    batches: Vec<Batch>,
+}
+
+impl SpriteRenderer {
+    /// Draws the atlas region `name` this frame, `transform` placing its
+    /// center. At scale 1 one texture pixel covers one world unit. Returns
+    /// false (drawing nothing) if the atlas has no such region.
+    pub fn draw_region(atlas: &SpriteAtlas, name: &str, transform: &Transform2D) -> bool {
+        Self::draw_region_tinted(atlas, name, transform, Color::WHITE)
+    }
+
+    /// [`SpriteRenderer::draw_region`] with the texture multiplied by `color`.
+    pub fn draw_region_tinted(
+        atlas: &SpriteAtlas,
+        name: &str,
+        transform: &Transform2D,
+        color: impl Into<Color>,
+    ) -> bool {
+        Self::draw_region_blended(atlas, name, transform, color, SpriteBlend::Alpha)
+    }
+
+    /// [`SpriteRenderer::draw_region_tinted`] combined with what's behind
+    /// as `blend` says.
+    pub fn draw_region_blended(
+        atlas: &SpriteAtlas,
+        name: &str,
+        transform: &Transform2D,
+        color: impl Into<Color>,
+        blend: SpriteBlend,
+    ) -> bool {
+        match (atlas.rect(name), atlas.uv_rect(name)) {
+            (Some(rect), Some(uv)) => {
+                Self::queue(atlas.texture(), rect, uv, transform, color.into(), blend);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Draws the region at `index` in the atlas's source order, e.g. frame
+    /// `N` of an animation.
+    pub fn draw_frame(atlas: &SpriteAtlas, index: usize, transform: &Transform2D) -> bool {
+        match (atlas.rect_at(index), atlas.uv_rect_at(index)) {
+            (Some(rect), Some(uv)) => {
+                Self::queue(
+                    atlas.texture(),
+                    rect,
+                    uv,
+                    transform,
+                    Color::WHITE,
+                    SpriteBlend::Alpha,
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Draws the current frame of `player`; call once per frame after
+    /// [`AnimationPlayer::update`]. Returns false if the frame's region is
+    /// missing from `atlas` or the animation has no frames.
+    pub fn draw_animation(
+        atlas: &SpriteAtlas,
+        player: &AnimationPlayer,
+        transform: &Transform2D,
+    ) -> bool {
+        player
+            .current_region()
+            .is_some_and(|region| Self::draw_region(atlas, region, transform))
+    }
+
+    fn queue(
+        texture: &Arc<Texture2D>,
+        rect: Rect,
+        uv_rect: [f32; 4],
+        transform: &Transform2D,
+        color: Color,
+        blend: SpriteBlend,
+    ) {
+        let size = Mat4::from_scale(Vec2::new(rect.width, rect.height).extend(1.0));
+        let instance = SpriteInstance {
+            model: (transform.local_matrix() * size).to_cols_array(),
+            uv_rect,
+            color: color.into(),
+        };
+        queue_instances(texture, blend, [instance]);
+    }
+
+    pub(crate) fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sprite Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/sprite.wgsl").into()),
+        });
+
+        let camera = UniformBuffer::new(
+            device,
+            Some("Sprite Camera Buffer"),
+            &Mat4::IDENTITY.to_cols_array(),
+        );
+        let (camera_layout, camera_bind_group) = BindGroupBuilder::new(Some("Sprite Camera"))
+            .uniform(device, &camera, wgpu::ShaderStages::VERTEX)
+            .build(device);
+
+        let textures = TextureBindGroups::new(device, "Sprite Texture");
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[&camera_layout, textures.layout()],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |blend, fragment_entry| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Sprite Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    compilation_options: Default::default(),
+                    buffers: &[SpriteInstance::layout()],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                // 2D content is layered by draw order, not depth
+                depth_stencil: Some(overlay_depth_state()),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: fragment_entry,
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(blend),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+        let pipeline = create_pipeline(wgpu::BlendState::ALPHA_BLENDING, "fs_main");
+        let additive_pipeline = create_pipeline(
+            wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            "fs_main",
+        );
+        let opaque_pipeline = create_pipeline(wgpu::BlendState::REPLACE, "fs_main");
+        // The shader fades the color towards white by its alpha, so the
+        // blend itself needs no alpha weighting
+        let multiply_pipeline = create_pipeline(
+            wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            "fs_multiply",
+        );
+
+        Self {
+            pipeline,
+            additive_pipeline,
+            opaque_pipeline,
+            multiply_pipeline,
+            camera,
+            camera_bind_group,
+            bind_groups: textures,
+            instance_buffer: Self::create_instance_buffer(device, INITIAL_INSTANCE_CAPACITY),
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            instances: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+
+    fn create_instance_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Instance Buffer"),
+            size: (capacity * std::mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Takes this frame's sprites, batched by texture and blending, and
+    /// uploads them.
+    pub(crate) fn prepare(&mut self, device: &Device, queue: &Queue, screen_size: Vec2) {
+        self.instances.clear();
+        self.batches.clear();
+        {
+            let mut q = sprite_queue().lock().unwrap();
+            std::mem::swap(&mut q.batches, &mut self.batches);
+            std::mem::swap(&mut q.instances, &mut self.instances);
+        }
+
+        let view_projection = Camera2D::get_instance()
+            .lock()
+            .unwrap()
+            .view_projection(screen_size);
+        self.camera.write(queue, &view_projection.to_cols_array());
+
+        self.bind_groups
+            .prepare(device, self.batches.iter().map(|batch| &batch.texture));
+
+        if self.instances.len() > self.instance_capacity {
+            self.instance_capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, self.instance_capacity);
+        }
+        if !self.instances.is_empty() {
+            queue.write_buffer(
+                &self.instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.instances),
+            );
+        }
+    }
+
+    pub(crate) fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        if self.batches.is_empty() {
+            return;
+        }
+        rpass.set_bind_group(0, &self.camera_bind_group, &[]);
+        rpass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        let mut bound = None;
+        for batch in &self.batches {
+            if bound != Some(batch.blend) {
+                rpass.set_pipeline(match batch.blend {
+                    SpriteBlend::Alpha => &self.pipeline,
+                    SpriteBlend::Additive => &self.additive_pipeline,
+                    SpriteBlend::Opaque => &self.opaque_pipeline,
+                    SpriteBlend::Multiply => &self.multiply_pipeline,
+                });
+                bound = Some(batch.blend);
+            }
+            rpass.set_bind_group(1, self.bind_groups.get(&batch.texture), &[]);
+            rpass.draw(0..6, batch.instances.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::context::test_device_or_skip;
+    use crate::engine::renderer::render_target::RenderTarget;
+    use crate::engine::renderer::screenshot::read_texture_rgba;
+
+    const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+    const BACKGROUND: wgpu::Color = wgpu::Color {
+        r: 0.5,
+        g: 0.25,
+        b: 0.0,
+        a: 1.0,
+    };
+
+    /// Draws a half-transparent grey sprite over [`BACKGROUND`] with
+    /// `blend` and reads back the pixel in the middle.
+    fn render(device: &Device, queue: &Queue, blend: SpriteBlend) -> [u8; 4] {
+        let texture = Arc::new(Texture2D::from_rgba8(
+            device,
+            queue,
+            &[255; 4],
+            1,
+            1,
+            Some("White"),
+        ));
+        let target = RenderTarget::new(device, 4, 4, FORMAT).with_depth(device);
+        let mut renderer = SpriteRenderer::new(device, FORMAT);
+
+        // Covers the whole target with the default camera
+        let sprite = SpriteInstance::new(
+            Vec2::ZERO,
+            Vec2::splat(64.0),
+            [0.0, 0.0, 1.0, 1.0],
+            [0.5, 0.5, 0.5, 0.5],
+        );
+        queue_instances(&texture, blend, [sprite]);
+        renderer.prepare(device, queue, Vec2::splat(4.0));
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target.texture_view(),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(BACKGROUND),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: target.depth_view().unwrap(),
+                    depth_ops: None,
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            renderer.draw(&mut rpass);
+        }
+        queue.submit(Some(encoder.finish()));
+
+        let image = read_texture_rgba(device, queue, target.texture()).unwrap();
+        image.get_pixel(2, 2).0
+    }
+
+    fn assert_rgb_near(actual: [u8; 4], expected: [u8; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(a.abs_diff(e) <= 2, "expected {expected:?}, got {actual:?}");
+        }
+    }
+
+    #[test]
+    fn additive_sprites_brighten_where_alpha_ones_cover() {
+        let (device, queue) = test_device_or_skip!();
+
+        // 0.5 * 0.5 of the sprite over half the background
+        let alpha = render(device, queue, SpriteBlend::Alpha);
+        assert_rgb_near(alpha, [128, 96, 64]);
+
+        // 0.5 * 0.5 of the sprite on top of all of the background
+        let additive = render(device, queue, SpriteBlend::Additive);
+        assert_rgb_near(additive, [191, 128, 64]);
+
+        assert!(additive[..3].iter().zip(&alpha[..3]).all(|(a, b)| a >= b));
+    }
+}
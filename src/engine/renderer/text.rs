@@ -0,0 +1,409 @@
+use glyph_brush::ab_glyph::{PxScale, Rect};
+use glyph_brush::{
+    BrushAction, BrushError, FontId, GlyphBrush, GlyphBrushBuilder, GlyphVertex, Rectangle,
+    Section, Text as GlyphText,
+};
+use wgpu::{BindGroup, Device, Queue, Sampler, Texture, TextureFormat, TextureView};
+
+use super::quad::{QuadPipeline, QuadVertex, quad_vertices};
+use crate::engine::math::color::Color;
+use crate::engine::text::{Font, Text};
+
+/// Starting size of the glyph cache texture; it doubles whenever a frame
+/// needs more room.
+const INITIAL_CACHE_SIZE: (u32, u32) = (512, 512);
+
+/// Screen-space text drawn through `glyph_brush`, on top of whatever is
+/// already in the frame. The window keeps one to draw what
+/// [`Text::draw`] queues; others can be made for text drawn by hand with
+/// [`TextRenderer::queue_text`].
+///
+/// Glyphs are rasterized into a cache texture the first time they're drawn
+/// at a given font, pixel size and subpixel offset, and reused from there on
+/// later frames. Newlines in queued text start a new line.
+pub struct TextRenderer {
+    brush: GlyphBrush<[QuadVertex; 6]>,
+    pipeline: QuadPipeline,
+    cache: Texture,
+    sampler: Sampler,
+    bind_group: BindGroup,
+    vertices: Vec<QuadVertex>,
+    screen_size: [f32; 2],
+    /// Every font handed to the brush so far.
+    fonts: Vec<(Font, FontId)>,
+    /// Font for [`TextRenderer::queue_text`].
+    font_id: FontId,
+}
+
+impl TextRenderer {
+    /// Creates a renderer using the bundled [`Font::default_font`].
+    pub fn new(device: &Device, queue: &Queue, surface_format: TextureFormat) -> Self {
+        let default_font = Font::default_font();
+        let brush = GlyphBrushBuilder::using_font(default_font.brush_font().clone())
+            .initial_cache_size(INITIAL_CACHE_SIZE)
+            .build();
+        let pipeline = QuadPipeline::new(device, surface_format);
+
+        let (width, height) = brush.texture_dimensions();
+        let cache = Self::create_cache(device, queue, width, height);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Cache Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = Self::create_bind_group(device, &pipeline, &cache, &sampler);
+
+        Self {
+            brush,
+            pipeline,
+            cache,
+            sampler,
+            bind_group,
+            vertices: Vec::new(),
+            screen_size: [1.0, 1.0],
+            fonts: vec![(default_font, FontId(0))],
+            font_id: FontId(0),
+        }
+    }
+
+    fn create_cache(device: &Device, queue: &Queue, width: u32, height: u32) -> Texture {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Cache"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        // Start fully transparent so stale texels never show up between glyphs.
+        queue.write_texture(
+            texture.as_image_copy(),
+            &vec![0; (4 * width * height) as usize],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+        texture
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        pipeline: &QuadPipeline,
+        cache: &Texture,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        let view = cache.create_view(&wgpu::TextureViewDescriptor::default());
+        pipeline.create_texture_bind_group(device, &view, sampler)
+    }
+
+    /// Switches all subsequently queued text to `font`.
+    pub fn set_font(&mut self, font: &Font) {
+        self.font_id = self.font_id(font);
+    }
+
+    /// The brush's id for `font`, adding it the first time it's seen.
+    fn font_id(&mut self, font: &Font) -> FontId {
+        if let Some((_, id)) = self.fonts.iter().find(|(f, _)| f.ptr_eq(font)) {
+            return *id;
+        }
+        let id = self.brush.add_font(font.brush_font().clone());
+        self.fonts.push((font.clone(), id));
+        id
+    }
+
+    /// Must be called whenever the surface is resized.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.screen_size = [width as f32, height as f32];
+    }
+
+    /// Queues `text` for the next [`TextRenderer::draw`] with its top-left
+    /// corner at `position` (pixels, origin top-left) and `scale` pixels tall.
+    pub fn queue_text(
+        &mut self,
+        text: &str,
+        position: [f32; 2],
+        scale: f32,
+        color: impl Into<Color>,
+    ) {
+        self.queue_with_font(text, position, scale, color.into().into(), self.font_id);
+    }
+
+    fn queue_with_font(
+        &mut self,
+        text: &str,
+        position: [f32; 2],
+        scale: f32,
+        color: [f32; 4],
+        font_id: FontId,
+    ) {
+        self.brush.queue(
+            Section::default()
+                .with_screen_position((position[0], position[1]))
+                .add_text(
+                    GlyphText::new(text)
+                        .with_scale(PxScale::from(scale))
+                        .with_color(color)
+                        .with_font_id(font_id),
+                ),
+        );
+    }
+
+    /// Uploads any new glyphs and vertices, then draws all queued text,
+    /// including this frame's [`Text::draw`] calls, onto `frame_view` in its
+    /// own render pass.
+    pub fn draw(&mut self, device: &Device, queue: &Queue, frame_view: &TextureView) {
+        self.prepare(device, queue, self.screen_size);
+        self.render(device, queue, frame_view);
+    }
+
+    /// Takes this frame's [`Text::draw`] calls, lays them out with the rest
+    /// of the queued text and uploads any new glyphs and vertices. The
+    /// result can be drawn with [`TextRenderer::render`] as often as
+    /// needed, e.g. again for a screenshot.
+    pub(crate) fn prepare(&mut self, device: &Device, queue: &Queue, screen_size: [f32; 2]) {
+        self.screen_size = screen_size;
+        let (font, commands) = Text::take_commands();
+        let font_id = self.font_id(&font);
+        for command in commands {
+            self.queue_with_font(
+                &command.text,
+                command.position,
+                command.size,
+                command.color,
+                font_id,
+            );
+        }
+
+        loop {
+            let cache = &self.cache;
+            let action = self.brush.process_queued(
+                |rect, data| Self::upload_glyph(queue, cache, rect, data),
+                to_quad,
+            );
+            match action {
+                Ok(BrushAction::Draw(quads)) => {
+                    self.vertices = quads.into_iter().flatten().collect();
+                    break;
+                }
+                Ok(BrushAction::ReDraw) => break,
+                Err(BrushError::TextureTooSmall { suggested }) => {
+                    let max = device.limits().max_texture_dimension_2d;
+                    let (width, height) = (suggested.0.min(max), suggested.1.min(max));
+                    if (width, height) == self.brush.texture_dimensions() {
+                        log::warn!("Glyph cache cannot grow past {width}x{height}; skipping text");
+                        self.vertices.clear();
+                        break;
+                    }
+                    self.cache = Self::create_cache(device, queue, width, height);
+                    self.bind_group =
+                        Self::create_bind_group(device, &self.pipeline, &self.cache, &self.sampler);
+                    self.brush.resize_texture(width, height);
+                }
+            }
+        }
+
+        self.pipeline
+            .prepare(device, queue, self.screen_size, &self.vertices);
+    }
+
+    /// Keeps the laid out text at the same pixel positions on a target of
+    /// `screen_size`, e.g. another window, until the next
+    /// [`TextRenderer::prepare`].
+    pub(crate) fn set_screen_size(&mut self, queue: &Queue, screen_size: [f32; 2]) {
+        self.screen_size = screen_size;
+        self.pipeline.set_screen_size(queue, screen_size);
+    }
+
+    /// Draws the text laid out by the last [`TextRenderer::prepare`] onto
+    /// `frame_view` in its own render pass.
+    pub(crate) fn render(&self, device: &Device, queue: &Queue, frame_view: &TextureView) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Text Encoder"),
+        });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Text Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: frame_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.pipeline.draw(&mut rpass, &self.bind_group);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+
+    /// Writes one glyph's coverage into the cache as white with alpha, so the
+    /// quad shader's `texture * color` tints it.
+    fn upload_glyph(queue: &Queue, cache: &Texture, rect: Rectangle<u32>, coverage: &[u8]) {
+        let rgba: Vec<u8> = coverage.iter().flat_map(|&a| [255, 255, 255, a]).collect();
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: cache,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.min[0],
+                    y: rect.min[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * rect.width()),
+                rows_per_image: Some(rect.height()),
+            },
+            wgpu::Extent3d {
+                width: rect.width(),
+                height: rect.height(),
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+/// Turns a positioned glyph into a quad, clipped to its section bounds.
+fn to_quad(vertex: GlyphVertex) -> [QuadVertex; 6] {
+    let GlyphVertex {
+        mut tex_coords,
+        mut pixel_coords,
+        bounds,
+        extra,
+    } = vertex;
+
+    if pixel_coords.max.x > bounds.max.x {
+        let old_width = pixel_coords.width();
+        pixel_coords.max.x = bounds.max.x;
+        tex_coords.max.x = tex_coords.min.x + tex_coords.width() * pixel_coords.width() / old_width;
+    }
+    if pixel_coords.min.x < bounds.min.x {
+        let old_width = pixel_coords.width();
+        pixel_coords.min.x = bounds.min.x;
+        tex_coords.min.x = tex_coords.max.x - tex_coords.width() * pixel_coords.width() / old_width;
+    }
+    if pixel_coords.max.y > bounds.max.y {
+        let old_height = pixel_coords.height();
+        pixel_coords.max.y = bounds.max.y;
+        tex_coords.max.y =
+            tex_coords.min.y + tex_coords.height() * pixel_coords.height() / old_height;
+    }
+    if pixel_coords.min.y < bounds.min.y {
+        let old_height = pixel_coords.height();
+        pixel_coords.min.y = bounds.min.y;
+        tex_coords.min.y =
+            tex_coords.max.y - tex_coords.height() * pixel_coords.height() / old_height;
+    }
+
+    quad_vertices(rect_array(pixel_coords), rect_array(tex_coords), extra.color)
+}
+
+fn rect_array(rect: Rect) -> [f32; 4] {
+    [rect.min.x, rect.min.y, rect.max.x, rect.max.y]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::context::test_device_or_skip;
+    use crate::engine::renderer::render_target::RenderTarget;
+    use crate::engine::renderer::screenshot::read_texture_rgba;
+
+    const SIZE: u32 = 64;
+    const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+    /// Held by tests drawing, as drawing takes the shared [`Text`] queue.
+    fn text_guard() -> std::sync::MutexGuard<'static, ()> {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn clear(device: &Device, queue: &Queue, view: &TextureView) {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        queue.submit(Some(encoder.finish()));
+    }
+
+    fn lit(device: &Device, queue: &Queue, target: &RenderTarget) -> usize {
+        let image = read_texture_rgba(device, queue, target.texture()).unwrap();
+        image.pixels().filter(|p| p[0] > 128).count()
+    }
+
+    #[test]
+    fn queued_text_is_drawn_once() {
+        let (device, queue) = test_device_or_skip!();
+        let _guard = text_guard();
+        let mut text = TextRenderer::new(device, queue, FORMAT);
+        text.resize(SIZE, SIZE);
+        let target = RenderTarget::new(device, SIZE, SIZE, FORMAT);
+
+        text.queue_text("Hi", [4.0, 4.0], 32.0, [1.0, 1.0, 1.0, 1.0]);
+        clear(device, queue, target.texture_view());
+        text.draw(device, queue, target.texture_view());
+        assert!(lit(device, queue, &target) > 0, "no text drawn");
+
+        // Drawing takes the queue, so the next frame starts empty
+        clear(device, queue, target.texture_view());
+        text.draw(device, queue, target.texture_view());
+        assert_eq!(lit(device, queue, &target), 0);
+    }
+
+    #[test]
+    fn text_from_text_draw_is_drawn_again_for_a_capture() {
+        let (device, queue) = test_device_or_skip!();
+        let _guard = text_guard();
+        let mut text = TextRenderer::new(device, queue, FORMAT);
+
+        // As the window does: lay the frame's text out once, draw it onto
+        // the presented frame, then again onto an offscreen capture
+        Text::draw("Hi", [4.0, 4.0], 32.0, [1.0, 1.0, 1.0, 1.0]);
+        text.prepare(device, queue, [SIZE as f32; 2]);
+
+        let mut images = Vec::new();
+        for _ in 0..2 {
+            let target = RenderTarget::new(device, SIZE, SIZE, FORMAT);
+            clear(device, queue, target.texture_view());
+            text.render(device, queue, target.texture_view());
+            images.push(read_texture_rgba(device, queue, target.texture()).unwrap());
+        }
+
+        let lit = |image: &image::RgbaImage| image.pixels().filter(|p| p[0] > 128).count();
+        assert!(lit(&images[0]) > 0, "no text on the frame");
+        assert_eq!(images[0], images[1], "capture lost the text");
+    }
+}
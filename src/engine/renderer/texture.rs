@@ -0,0 +1,144 @@
+use std::fmt;
+use std::path::Path;
+use wgpu::{Device, Queue, Sampler, Texture, TextureView};
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(std::io::Error),
+    Decode(image::ImageError),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Io(e) => write!(f, "failed to read texture: {e}"),
+            TextureError::Decode(e) => write!(f, "failed to decode texture: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+impl From<std::io::Error> for TextureError {
+    fn from(e: std::io::Error) -> Self {
+        TextureError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for TextureError {
+    fn from(e: image::ImageError) -> Self {
+        TextureError::Decode(e)
+    }
+}
+
+/// An sRGB color texture with a view and sampler ready for binding.
+pub struct Texture2D {
+    texture: Texture,
+    view: TextureView,
+    sampler: Sampler,
+    width: u32,
+    height: u32,
+}
+
+impl Texture2D {
+    /// Uploads tightly packed RGBA8 pixels.
+    pub fn from_rgba8(
+        device: &Device,
+        queue: &Queue,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        label: Option<&str>,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            width,
+            height,
+        }
+    }
+
+    /// Decodes an encoded image (PNG, JPEG) and uploads it.
+    pub fn from_bytes(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        label: Option<&str>,
+    ) -> Result<Self, TextureError> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        let (width, height) = image.dimensions();
+        Ok(Self::from_rgba8(device, queue, &image, width, height, label))
+    }
+
+    pub fn from_file(
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, TextureError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(device, queue, &bytes, path.to_str())
+    }
+
+    //--Getters--//
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
@@ -0,0 +1,587 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+use std::fmt;
+use std::sync::Arc;
+use wgpu::{Buffer, Device};
+
+use super::context::GpuContext;
+use super::mesh::{Mesh, MeshVertex};
+use super::sprite_atlas::SpriteAtlas;
+use super::tilemap_renderer::{ChunkDraw, queue_chunk};
+
+/// Width and height of a chunk in tiles. Each chunk is uploaded and drawn
+/// on its own, so editing one tile re-uploads at most this many squared.
+pub const CHUNK_SIZE: u32 = 16;
+
+const CHUNK_TILES: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+// Flip flags Tiled stores in the top bits of a tile id
+const CSV_FLIP_HORIZONTAL: u32 = 0x8000_0000;
+const CSV_FLIP_VERTICAL: u32 = 0x4000_0000;
+const CSV_FLAGS: u32 = 0xF000_0000;
+
+#[derive(Debug)]
+pub enum TilemapError {
+    /// A malformed cell, with its 1-based line and column.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A row with a different number of cells than the first one.
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for TilemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilemapError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "tilemap CSV {line}:{column}: {message}"),
+            TilemapError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "tilemap CSV line {line} has {found} tiles, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TilemapError {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub(crate) struct TileInstance {
+    /// World position of the tile's bottom-left corner.
+    min: [f32; 2],
+    size: [f32; 2],
+    uv_rect: [f32; 4],
+}
+
+impl TileInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+
+    pub(crate) fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TileInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Mirroring applied to a tile's image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileFlip {
+    pub horizontal: bool,
+    pub vertical: bool,
+}
+
+impl TileFlip {
+    pub const NONE: TileFlip = TileFlip {
+        horizontal: false,
+        vertical: false,
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    /// Index of the tile's region in the atlas, in source order (see
+    /// [`SpriteAtlas::name_at`]).
+    pub id: u32,
+    pub flip: TileFlip,
+}
+
+struct Chunk {
+    tiles: Vec<Option<Tile>>,
+    /// Created on the first upload of a non-empty chunk.
+    buffer: Option<Arc<Buffer>>,
+    instance_count: u32,
+    dirty: bool,
+}
+
+impl Chunk {
+    fn new() -> Self {
+        Self {
+            tiles: vec![None; CHUNK_TILES],
+            buffer: None,
+            instance_count: 0,
+            dirty: false,
+        }
+    }
+}
+
+struct TileLayer {
+    z: i32,
+    chunks: Vec<Chunk>,
+}
+
+/// A grid of atlas tiles in one or more layers, kept on the GPU in
+/// [`CHUNK_SIZE`] square chunks so only the chunks that changed are
+/// re-uploaded.
+///
+/// Tile `(0, 0)` is the top-left tile, with its top-left corner at
+/// [`Tilemap::origin`]; `x` grows right and `y` grows down, as in most map
+/// editors.
+pub struct Tilemap {
+    atlas: Arc<SpriteAtlas>,
+    width: u32,
+    height: u32,
+    tile_size: Vec2,
+    origin: Vec2,
+    chunks_x: u32,
+    chunks_y: u32,
+    layers: Vec<TileLayer>,
+    /// Changed since the last [`Tilemap::rebuild_if_dirty`].
+    mesh_dirty: bool,
+}
+
+impl Tilemap {
+    /// An empty `width * height` map with a single layer at z 0. `tile_size`
+    /// is in world units.
+    pub fn new(atlas: Arc<SpriteAtlas>, width: u32, height: u32, tile_size: Vec2) -> Self {
+        let mut tilemap = Self {
+            atlas,
+            width,
+            height,
+            tile_size,
+            origin: Vec2::ZERO,
+            chunks_x: width.div_ceil(CHUNK_SIZE),
+            chunks_y: height.div_ceil(CHUNK_SIZE),
+            layers: Vec::new(),
+            mesh_dirty: true,
+        };
+        tilemap.add_layer(0);
+        tilemap
+    }
+
+    /// A single-layer map from CSV, one row of tiles per line, as exported
+    /// by Tiled and most other map editors. Following Tiled, `0` (or any
+    /// negative number) is an empty cell and `n` is atlas tile `n - 1`;
+    /// Tiled's horizontal and vertical flip bits are honoured.
+    pub fn from_csv(
+        atlas: Arc<SpriteAtlas>,
+        csv: &str,
+        tile_size: Vec2,
+    ) -> Result<Self, TilemapError> {
+        let mut rows: Vec<Vec<Option<Tile>>> = Vec::new();
+        for (i, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            // Tiled ends every row but the last with a comma
+            let line = line.strip_suffix(',').unwrap_or(line);
+            let row = line
+                .split(',')
+                .enumerate()
+                .map(|(j, cell)| {
+                    parse_csv_tile(cell.trim()).map_err(|message| TilemapError::Parse {
+                        line: i + 1,
+                        column: j + 1,
+                        message,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(first) = rows.first()
+                && first.len() != row.len()
+            {
+                return Err(TilemapError::RaggedRow {
+                    line: i + 1,
+                    expected: first.len(),
+                    found: row.len(),
+                });
+            }
+            rows.push(row);
+        }
+
+        let width = rows.first().map_or(0, Vec::len) as u32;
+        let mut tilemap = Self::new(atlas, width, rows.len() as u32, tile_size);
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, tile) in row.into_iter().enumerate() {
+                tilemap.set(x as u32, y as u32, 0, tile);
+            }
+        }
+        Ok(tilemap)
+    }
+
+    /// Adds an empty layer and returns its index. Layers with a higher `z`
+    /// are drawn on top, across all tilemaps; equal `z` keeps draw order.
+    pub fn add_layer(&mut self, z: i32) -> usize {
+        let chunks = (self.chunks_x * self.chunks_y) as usize;
+        self.layers.push(TileLayer {
+            z,
+            chunks: (0..chunks).map(|_| Chunk::new()).collect(),
+        });
+        self.mesh_dirty = true;
+        self.layers.len() - 1
+    }
+
+    /// Places tile `tile_id` at `(x, y)` on `layer`. Returns false, changing
+    /// nothing, if the position or layer is out of range.
+    pub fn set_tile(&mut self, x: u32, y: u32, layer: usize, tile_id: u32) -> bool {
+        self.set(
+            x,
+            y,
+            layer,
+            Some(Tile {
+                id: tile_id,
+                flip: TileFlip::NONE,
+            }),
+        )
+    }
+
+    /// [`Tilemap::set_tile`] with the tile's image mirrored.
+    pub fn set_tile_flipped(
+        &mut self,
+        x: u32,
+        y: u32,
+        layer: usize,
+        tile_id: u32,
+        flip: TileFlip,
+    ) -> bool {
+        self.set(x, y, layer, Some(Tile { id: tile_id, flip }))
+    }
+
+    /// Empties `(x, y)` on `layer`.
+    pub fn clear_tile(&mut self, x: u32, y: u32, layer: usize) -> bool {
+        self.set(x, y, layer, None)
+    }
+
+    fn set(&mut self, x: u32, y: u32, layer: usize, tile: Option<Tile>) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let (chunk, index) = self.locate(x, y);
+        let Some(layer) = self.layers.get_mut(layer) else {
+            return false;
+        };
+        let chunk = &mut layer.chunks[chunk];
+        if chunk.tiles[index] != tile {
+            chunk.tiles[index] = tile;
+            chunk.dirty = true;
+            self.mesh_dirty = true;
+        }
+        true
+    }
+
+    /// Chunk index and tile index within the chunk of `(x, y)`.
+    fn locate(&self, x: u32, y: u32) -> (usize, usize) {
+        let chunk = (y / CHUNK_SIZE) * self.chunks_x + x / CHUNK_SIZE;
+        let index = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        (chunk as usize, index as usize)
+    }
+
+    /// Moves the map so its top-left corner is at `origin`. Every chunk is
+    /// re-uploaded.
+    pub fn set_origin(&mut self, origin: Vec2) {
+        if origin != self.origin {
+            self.origin = origin;
+            self.mark_all_dirty();
+        }
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.mesh_dirty = true;
+        for chunk in self.layers.iter_mut().flat_map(|l| l.chunks.iter_mut()) {
+            chunk.dirty = true;
+        }
+    }
+
+    /// Uploads any changed chunks and draws the map this frame with the
+    /// active [`Camera2D`](super::camera::Camera2D), skipping chunks outside
+    /// the view. Tilemaps are drawn before sprites. Does nothing until the
+    /// window has initialized the GPU.
+    pub fn draw(&mut self) {
+        let Some(context) = GpuContext::get() else {
+            return;
+        };
+
+        let chunk_extent = self.tile_size * CHUNK_SIZE as f32;
+        for layer in &mut self.layers {
+            for (i, chunk) in layer.chunks.iter_mut().enumerate() {
+                let (cx, cy) = (i as u32 % self.chunks_x, i as u32 / self.chunks_x);
+                if chunk.dirty {
+                    Self::upload_chunk(
+                        context,
+                        &self.atlas,
+                        chunk,
+                        (cx, cy),
+                        self.origin,
+                        self.tile_size,
+                    );
+                }
+                let Some(buffer) = chunk.buffer.as_ref().filter(|_| chunk.instance_count > 0)
+                else {
+                    continue;
+                };
+
+                let top_left = self.origin + Vec2::new(cx as f32, -(cy as f32)) * chunk_extent;
+                queue_chunk(ChunkDraw {
+                    z: layer.z,
+                    texture: self.atlas.texture().clone(),
+                    buffer: buffer.clone(),
+                    instance_count: chunk.instance_count,
+                    min: Vec2::new(top_left.x, top_left.y - chunk_extent.y),
+                    max: Vec2::new(top_left.x + chunk_extent.x, top_left.y),
+                });
+            }
+        }
+    }
+
+    fn upload_chunk(
+        context: &GpuContext,
+        atlas: &SpriteAtlas,
+        chunk: &mut Chunk,
+        (cx, cy): (u32, u32),
+        origin: Vec2,
+        tile_size: Vec2,
+    ) {
+        let mut instances = Vec::with_capacity(CHUNK_TILES);
+        for (i, tile) in chunk.tiles.iter().enumerate() {
+            // Ids missing from the atlas are left out
+            let Some((tile, uv)) = tile.and_then(|t| Some((t, atlas.uv_rect_at(t.id as usize)?)))
+            else {
+                continue;
+            };
+            let x = cx * CHUNK_SIZE + i as u32 % CHUNK_SIZE;
+            let y = cy * CHUNK_SIZE + i as u32 / CHUNK_SIZE;
+            let [mut u0, mut v0, mut u1, mut v1] = uv;
+            if tile.flip.horizontal {
+                std::mem::swap(&mut u0, &mut u1);
+            }
+            if tile.flip.vertical {
+                std::mem::swap(&mut v0, &mut v1);
+            }
+            instances.push(TileInstance {
+                min: [
+                    origin.x + x as f32 * tile_size.x,
+                    origin.y - (y + 1) as f32 * tile_size.y,
+                ],
+                size: tile_size.to_array(),
+                uv_rect: [u0, v0, u1, v1],
+            });
+        }
+
+        if !instances.is_empty() {
+            let buffer = chunk.buffer.get_or_insert_with(|| {
+                Arc::new(context.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Tilemap Chunk Buffer"),
+                    size: (CHUNK_TILES * std::mem::size_of::<TileInstance>())
+                        as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }))
+            });
+            context
+                .queue
+                .write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+        }
+        chunk.instance_count = instances.len() as u32;
+        chunk.dirty = false;
+    }
+
+    /// The whole map as one static [`Mesh`] of textured quads facing +z,
+    /// for drawing with a material that samples the atlas texture instead
+    /// of through [`Tilemap::draw`]. Each layer sits at its own z.
+    pub fn build_mesh(&self, device: &Device) -> Mesh {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for layer in &self.layers {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let (chunk, index) = self.locate(x, y);
+                    let Some((tile, uv)) = layer.chunks[chunk].tiles[index]
+                        .and_then(|t| Some((t, self.atlas.uv_rect_at(t.id as usize)?)))
+                    else {
+                        continue;
+                    };
+                    let [mut u0, mut v0, mut u1, mut v1] = uv;
+                    if tile.flip.horizontal {
+                        std::mem::swap(&mut u0, &mut u1);
+                    }
+                    if tile.flip.vertical {
+                        std::mem::swap(&mut v0, &mut v1);
+                    }
+
+                    let min = self.origin + Vec2::new(x as f32, -((y + 1) as f32)) * self.tile_size;
+                    let max = min + self.tile_size;
+                    let base = vertices.len() as u32;
+                    let z = layer.z as f32;
+                    for (position, uv) in [
+                        ([min.x, min.y, z], [u0, v1]),
+                        ([max.x, min.y, z], [u1, v1]),
+                        ([max.x, max.y, z], [u1, v0]),
+                        ([min.x, max.y, z], [u0, v0]),
+                    ] {
+                        vertices.push(MeshVertex {
+                            position,
+                            normal: [0.0, 0.0, 1.0],
+                            uv,
+                        });
+                    }
+                    indices.extend_from_slice(&[
+                        base,
+                        base + 1,
+                        base + 2,
+                        base,
+                        base + 2,
+                        base + 3,
+                    ]);
+                }
+            }
+        }
+        Mesh::new(device, &vertices, &indices)
+    }
+
+    /// A new [`Tilemap::build_mesh`] if any tile, layer or the origin has
+    /// changed since the last call, `None` otherwise.
+    pub fn rebuild_if_dirty(&mut self, device: &Device) -> Option<Mesh> {
+        if !std::mem::take(&mut self.mesh_dirty) {
+            return None;
+        }
+        Some(self.build_mesh(device))
+    }
+
+    //--Getters--//
+
+    pub fn atlas(&self) -> &Arc<SpriteAtlas> {
+        &self.atlas
+    }
+
+    /// The tile at `(x, y)` on `layer`, `None` if empty or out of range.
+    pub fn tile(&self, x: u32, y: u32, layer: usize) -> Option<Tile> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let (chunk, index) = self.locate(x, y);
+        self.layers.get(layer)?.chunks[chunk].tiles[index]
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn tile_size(&self) -> Vec2 {
+        self.tile_size
+    }
+
+    pub fn origin(&self) -> Vec2 {
+        self.origin
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+}
+
+/// One CSV cell as a tile, `None` for an empty one.
+fn parse_csv_tile(cell: &str) -> Result<Option<Tile>, String> {
+    let id: i64 = cell.parse().map_err(|_| format!("bad tile id {cell:?}"))?;
+    if id <= 0 {
+        return Ok(None);
+    }
+    let Ok(id) = u32::try_from(id) else {
+        return Err(format!("tile id {id} is out of range"));
+    };
+    let index = id & !CSV_FLAGS;
+    if index == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Tile {
+        id: index - 1,
+        flip: TileFlip {
+            horizontal: id & CSV_FLIP_HORIZONTAL != 0,
+            vertical: id & CSV_FLIP_VERTICAL != 0,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::context::test_device_or_skip;
+    use crate::engine::renderer::texture::Texture2D;
+
+    /// Four 4x4 tiles in a 2x2 grid.
+    fn atlas(device: &Device, queue: &wgpu::Queue) -> Arc<SpriteAtlas> {
+        let texture = Texture2D::from_rgba8(device, queue, &[255; 8 * 8 * 4], 8, 8, None);
+        Arc::new(SpriteAtlas::from_texture_and_grid(Arc::new(texture), 2, 2).unwrap())
+    }
+
+    #[test]
+    fn csv_cells_map_to_tiles() {
+        let (device, queue) = test_device_or_skip!();
+        // Tiled style: trailing commas, 0 for empty, flip bits on top
+        let csv = "1,0,3,\n2147483652,-1,1073741825\n";
+        let map = Tilemap::from_csv(atlas(device, queue), csv, Vec2::ONE).unwrap();
+
+        assert_eq!((map.width(), map.height()), (3, 2));
+        assert_eq!(map.tile(0, 0, 0).map(|t| t.id), Some(0));
+        assert_eq!(map.tile(1, 0, 0), None);
+        assert_eq!(map.tile(2, 0, 0).map(|t| t.id), Some(2));
+        assert_eq!(map.tile(1, 1, 0), None);
+
+        let flipped = map.tile(0, 1, 0).unwrap();
+        assert_eq!(flipped.id, 3);
+        assert!(flipped.flip.horizontal && !flipped.flip.vertical);
+        let flipped = map.tile(2, 1, 0).unwrap();
+        assert_eq!(flipped.id, 0);
+        assert!(!flipped.flip.horizontal && flipped.flip.vertical);
+    }
+
+    #[test]
+    fn bad_csv_reports_where() {
+        let (device, queue) = test_device_or_skip!();
+        let atlas = atlas(device, queue);
+
+        assert!(matches!(
+            Tilemap::from_csv(atlas.clone(), "1,2\n3,x\n", Vec2::ONE),
+            Err(TilemapError::Parse {
+                line: 2,
+                column: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Tilemap::from_csv(atlas, "1,2\n\n3\n", Vec2::ONE),
+            Err(TilemapError::RaggedRow {
+                line: 3,
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn mesh_has_a_quad_per_tile_and_rebuilds_only_when_dirty() {
+        let (device, queue) = test_device_or_skip!();
+        let mut map = Tilemap::from_csv(atlas(device, queue), "1,0\n2,3\n", Vec2::ONE).unwrap();
+
+        assert_eq!(map.build_mesh(device).index_count(), 3 * 6);
+        assert!(map.rebuild_if_dirty(device).is_some());
+        assert!(map.rebuild_if_dirty(device).is_none());
+
+        // Setting a tile to what it already is changes nothing
+        map.set_tile(0, 0, 0, 0);
+        assert!(map.rebuild_if_dirty(device).is_none());
+
+        map.set_tile(1, 0, 0, 3);
+        let mesh = map.rebuild_if_dirty(device).unwrap();
+        assert_eq!(mesh.index_count(), 4 * 6);
+    }
+}
@@ -0,0 +1,146 @@
+use glam::{Mat4, Vec2};
+use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex};
+use wgpu::{BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
+
+use super::bind_group::{BindGroupBuilder, TextureBindGroups};
+use super::camera::Camera2D;
+use super::depth::overlay_depth_state;
+use super::texture::Texture2D;
+use super::tilemap::TileInstance;
+use super::uniform::UniformBuffer;
+
+/// One non-empty chunk of one tilemap layer, queued by
+/// [`Tilemap::draw`](super::tilemap::Tilemap::draw).
+pub(crate) struct ChunkDraw {
+    pub z: i32,
+    pub texture: Arc<Texture2D>,
+    pub buffer: Arc<Buffer>,
+    pub instance_count: u32,
+    /// World bounds, for culling.
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+fn chunk_queue() -> &'static Mutex<Vec<ChunkDraw>> {
+    static INSTANCE: Lazy<Mutex<Vec<ChunkDraw>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+    &INSTANCE
+}
+
+pub(crate) fn queue_chunk(chunk: ChunkDraw) {
+    chunk_queue().lock().unwrap().push(chunk);
+}
+
+/// Draws the visible tilemap chunks queued this frame, one draw call each,
+/// ordered by layer z.
+pub(crate) struct TilemapRenderer {
+    pipeline: RenderPipeline,
+    camera: UniformBuffer<[f32; 16]>,
+    camera_bind_group: BindGroup,
+    bind_groups: TextureBindGroups,
+    chunks: Vec<ChunkDraw>,
+}
+
+impl TilemapRenderer {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Tilemap Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/tilemap.wgsl").into()),
+        });
+
+        let camera = UniformBuffer::new(
+            device,
+            Some("Tilemap Camera Buffer"),
+            &Mat4::IDENTITY.to_cols_array(),
+        );
+        let (camera_layout, camera_bind_group) = BindGroupBuilder::new(Some("Tilemap Camera"))
+            .uniform(device, &camera, wgpu::ShaderStages::VERTEX)
+            .build(device);
+        let textures = TextureBindGroups::new(device, "Tilemap Texture");
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Tilemap Pipeline Layout"),
+            bind_group_layouts: &[&camera_layout, textures.layout()],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tilemap Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[TileInstance::layout()],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            // Layers are ordered by z in `prepare`, not by depth
+            depth_stencil: Some(overlay_depth_state()),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            camera,
+            camera_bind_group,
+            bind_groups: textures,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Takes this frame's chunks, drops those outside the camera's view and
+    /// sorts the rest by z.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, screen_size: Vec2) {
+        self.chunks.clear();
+        std::mem::swap(&mut *chunk_queue().lock().unwrap(), &mut self.chunks);
+
+        let camera = *Camera2D::get_instance().lock().unwrap();
+        self.camera
+            .write(queue, &camera.view_projection(screen_size).to_cols_array());
+
+        // World bounds of the view; rotation makes it larger than the screen
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(screen_size.x, 0.0),
+            Vec2::new(0.0, screen_size.y),
+            screen_size,
+        ]
+        .map(|corner| camera.screen_to_world(corner, screen_size));
+        let view_min = corners.into_iter().reduce(Vec2::min).unwrap_or_default();
+        let view_max = corners.into_iter().reduce(Vec2::max).unwrap_or_default();
+
+        self.chunks
+            .retain(|chunk| chunk.min.cmple(view_max).all() && chunk.max.cmpge(view_min).all());
+        // Stable, so equal z keeps the order the chunks were queued in
+        self.chunks.sort_by_key(|chunk| chunk.z);
+
+        self.bind_groups
+            .prepare(device, self.chunks.iter().map(|chunk| &chunk.texture));
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        if self.chunks.is_empty() {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.camera_bind_group, &[]);
+        for chunk in &self.chunks {
+            let size = u64::from(chunk.instance_count) * std::mem::size_of::<TileInstance>() as u64;
+            rpass.set_bind_group(1, self.bind_groups.get(&chunk.texture), &[]);
+            rpass.set_vertex_buffer(0, chunk.buffer.slice(..size));
+            rpass.draw(0..6, 0..chunk.instance_count);
+        }
+    }
+}
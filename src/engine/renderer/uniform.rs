@@ -0,0 +1,118 @@
+use bytemuck::Pod;
+use std::marker::PhantomData;
+use wgpu::util::DeviceExt;
+use wgpu::{Buffer, Device, Queue};
+
+/// WGSL rounds uniform struct sizes up to 16 bytes.
+const UNIFORM_ALIGNMENT: u64 = 16;
+
+fn align_up(size: u64, alignment: u64) -> u64 {
+    size.div_ceil(alignment) * alignment
+}
+
+/// A uniform buffer holding one `T`, or with [`UniformBuffer::new_dynamic`]
+/// an array of `T` addressed through dynamic offsets.
+pub struct UniformBuffer<T: Pod> {
+    buffer: Buffer,
+    /// Distance between consecutive elements; a multiple of the device's
+    /// `min_uniform_buffer_offset_alignment` for dynamic buffers.
+    stride: u64,
+    len: u32,
+    dynamic: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> UniformBuffer<T> {
+    pub fn new(device: &Device, label: Option<&str>, value: &T) -> Self {
+        let stride = align_up(std::mem::size_of::<T>() as u64, UNIFORM_ALIGNMENT);
+        let mut contents = vec![0; stride as usize];
+        contents[..std::mem::size_of::<T>()].copy_from_slice(bytemuck::bytes_of(value));
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label,
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        Self {
+            buffer,
+            stride,
+            len: 1,
+            dynamic: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Room for `len` values, each bound on its own through
+    /// [`UniformBuffer::dynamic_offset`]. Elements are padded to the
+    /// device's offset alignment (typically 256 bytes).
+    pub fn new_dynamic(device: &Device, label: Option<&str>, len: u32) -> Self {
+        let alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+        let stride = align_up(
+            std::mem::size_of::<T>() as u64,
+            alignment.max(UNIFORM_ALIGNMENT),
+        );
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label,
+            size: stride * u64::from(len.max(1)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            stride,
+            len: len.max(1),
+            dynamic: true,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn write(&self, queue: &Queue, value: &T) {
+        self.write_at(queue, 0, value);
+    }
+
+    /// Writes element `index` of a dynamic buffer.
+    pub fn write_at(&self, queue: &Queue, index: u32, value: &T) {
+        assert!(
+            index < self.len,
+            "uniform index {index} out of bounds ({})",
+            self.len
+        );
+        queue.write_buffer(
+            &self.buffer,
+            u64::from(index) * self.stride,
+            bytemuck::bytes_of(value),
+        );
+    }
+
+    /// Offset to pass to `set_bind_group` to bind element `index`.
+    pub fn dynamic_offset(&self, index: u32) -> u32 {
+        (u64::from(index) * self.stride) as u32
+    }
+
+    /// Size of one bound element, as seen by the shader.
+    pub fn binding_size(&self) -> wgpu::BufferSize {
+        wgpu::BufferSize::new(align_up(std::mem::size_of::<T>() as u64, UNIFORM_ALIGNMENT))
+            .expect("uniform types are never zero-sized")
+    }
+
+    //--Getters--//
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// Number of elements; always 1 unless dynamic.
+    pub fn count(&self) -> u32 {
+        self.len
+    }
+
+    /// Whether this buffer is meant to be bound with dynamic offsets.
+    pub fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+}
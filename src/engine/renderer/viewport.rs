@@ -0,0 +1,164 @@
+use super::camera3d::Camera3D;
+
+/// A rectangle of the window the scene is drawn into, in physical pixels
+/// from the top-left corner, and the depth range it maps to. Set with
+/// [`Window::set_viewports`](crate::engine::window::Window::set_viewports).
+///
+/// ```ignore
+/// // Two players side by side, each with their own camera
+/// let [left, right] = Viewport::new(0, 0, width, height).split_columns();
+/// Window::set_viewports(vec![left.with_camera(player_one), right.with_camera(player_two)]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub min_depth: f32,
+    pub max_depth: f32,
+    /// The 3D camera drawn through this viewport, or the shared
+    /// [`Camera3D`] if `None`. Either way the aspect ratio follows the
+    /// viewport's.
+    pub camera: Option<Camera3D>,
+}
+
+impl Viewport {
+    /// Viewport over the full depth range, with the shared camera.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            min_depth: 0.0,
+            max_depth: 1.0,
+            camera: None,
+        }
+    }
+
+    /// The largest viewport with the given aspect ratio centered in a
+    /// window of `window_width` by `window_height`. The bars left over on
+    /// two sides show the clear color.
+    pub fn letterbox(window_width: u32, window_height: u32, aspect: f32) -> Self {
+        let (window_w, window_h) = (window_width as f32, window_height as f32);
+        let (width, height) = if window_w / window_h > aspect {
+            ((window_h * aspect).round() as u32, window_height)
+        } else {
+            (window_width, (window_w / aspect).round() as u32)
+        };
+        Self::new(
+            (window_width - width) / 2,
+            (window_height - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    pub fn with_camera(mut self, camera: Camera3D) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// Left and right halves, for two-player split-screen.
+    pub fn split_columns(&self) -> [Viewport; 2] {
+        let left = self.width / 2;
+        [
+            Viewport {
+                width: left,
+                ..*self
+            },
+            Viewport {
+                x: self.x + left,
+                width: self.width - left,
+                ..*self
+            },
+        ]
+    }
+
+    /// Top and bottom halves.
+    pub fn split_rows(&self) -> [Viewport; 2] {
+        let top = self.height / 2;
+        [
+            Viewport {
+                height: top,
+                ..*self
+            },
+            Viewport {
+                y: self.y + top,
+                height: self.height - top,
+                ..*self
+            },
+        ]
+    }
+
+    /// The part inside a target of `width` by `height`, which is all the
+    /// GPU accepts, or `None` if nothing is.
+    pub(crate) fn clipped(&self, width: u32, height: u32) -> Option<Viewport> {
+        let clipped = Viewport {
+            width: self.width.min(width.saturating_sub(self.x)),
+            height: self.height.min(height.saturating_sub(self.y)),
+            ..*self
+        };
+        (clipped.width > 0 && clipped.height > 0).then_some(clipped)
+    }
+
+    //--Getters--//
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_centers_the_largest_fitting_viewport() {
+        // Wider window: bars left and right
+        assert_eq!(
+            Viewport::letterbox(1000, 600, 4.0 / 3.0),
+            Viewport::new(100, 0, 800, 600)
+        );
+        // Taller window: bars top and bottom
+        assert_eq!(
+            Viewport::letterbox(800, 800, 16.0 / 9.0),
+            Viewport::new(0, 175, 800, 450)
+        );
+        // Same aspect: no bars
+        assert_eq!(
+            Viewport::letterbox(1280, 720, 16.0 / 9.0),
+            Viewport::new(0, 0, 1280, 720)
+        );
+    }
+
+    #[test]
+    fn splits_cover_the_viewport_without_gaps() {
+        let viewport = Viewport::new(10, 20, 101, 51);
+        let [left, right] = viewport.split_columns();
+        assert_eq!(left, Viewport::new(10, 20, 50, 51));
+        assert_eq!(right, Viewport::new(60, 20, 51, 51));
+
+        let [top, bottom] = viewport.split_rows();
+        assert_eq!(top, Viewport::new(10, 20, 101, 25));
+        assert_eq!(bottom, Viewport::new(10, 45, 101, 26));
+    }
+
+    #[test]
+    fn clipping_keeps_the_part_inside_the_target() {
+        let viewport = Viewport::new(50, 50, 100, 100);
+        assert_eq!(
+            viewport.clipped(120, 200),
+            Some(Viewport::new(50, 50, 70, 100))
+        );
+        assert_eq!(viewport.clipped(50, 200), None);
+        assert_eq!(viewport.clipped(200, 200), Some(viewport));
+    }
+
+    #[test]
+    fn aspect_survives_a_zero_height() {
+        assert_eq!(Viewport::new(0, 0, 300, 150).aspect(), 2.0);
+        assert_eq!(Viewport::new(0, 0, 300, 0).aspect(), 300.0);
+    }
+}
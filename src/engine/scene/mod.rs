@@ -0,0 +1,150 @@
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::engine::ecs::World;
+use crate::engine::physics2d::Physics2D;
+
+/// A level, menu or other self-contained part of the game, owning the
+/// entities that live in it.
+pub struct Scene {
+    name: String,
+    world: World,
+}
+
+impl Scene {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            world: World::new(),
+        }
+    }
+
+    //--Getters--//
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
+
+/// A stack of [`Scene`]s; the top one is active. The window runs
+/// [`Physics2D::detect`] on the active scene's world every frame, and
+/// update callbacks reach it through [`SceneManager::with_world`].
+///
+/// ```ignore
+/// SceneManager::switch(Scene::new("level 1"));
+/// SceneManager::with_world(|world| {
+///     let player = world.spawn();
+///     world.insert(player, Aabb::new(Vec2::ZERO, Vec2::splat(16.0)));
+/// });
+/// SceneManager::push(Scene::new("pause"));
+/// ```
+pub struct SceneManager {
+    stack: Vec<Scene>,
+}
+
+impl SceneManager {
+    pub fn get_instance() -> &'static Mutex<SceneManager> {
+        static INSTANCE: Lazy<Mutex<SceneManager>> =
+            Lazy::new(|| Mutex::new(SceneManager { stack: Vec::new() }));
+
+        &INSTANCE
+    }
+
+    /// Makes `scene` active on top of the current one, e.g. a pause menu
+    /// over a level.
+    pub fn push(scene: Scene) {
+        Self::get_instance().lock().unwrap().stack.push(scene);
+        Physics2D::clear();
+    }
+
+    /// Removes the active scene and returns it, making the one below
+    /// active again.
+    pub fn pop() -> Option<Scene> {
+        let scene = Self::get_instance().lock().unwrap().stack.pop();
+        if scene.is_some() {
+            Physics2D::clear();
+        }
+        scene
+    }
+
+    /// Replaces the active scene with `scene`, e.g. on changing levels,
+    /// and returns the old one.
+    pub fn switch(scene: Scene) -> Option<Scene> {
+        let mut manager = Self::get_instance().lock().unwrap();
+        let old = manager.stack.pop();
+        manager.stack.push(scene);
+        drop(manager);
+        Physics2D::clear();
+        old
+    }
+
+    /// Runs `f` on the active scene's world, or returns `None` without
+    /// one. `f` mustn't change scenes itself.
+    pub fn with_world<R>(f: impl FnOnce(&mut World) -> R) -> Option<R> {
+        let mut manager = Self::get_instance().lock().unwrap();
+        let scene = manager.stack.last_mut()?;
+        Some(f(&mut scene.world))
+    }
+
+    //--Getters--//
+
+    pub fn active_name() -> Option<String> {
+        let manager = Self::get_instance().lock().unwrap();
+        manager.stack.last().map(|scene| scene.name.clone())
+    }
+
+    /// Number of scenes on the stack.
+    pub fn len() -> usize {
+        Self::get_instance().lock().unwrap().stack.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::physics2d::{self, Aabb};
+    use glam::Vec2;
+
+    #[test]
+    fn each_scene_keeps_its_own_world() {
+        let _guard = physics2d::test_guard();
+        SceneManager::get_instance().lock().unwrap().stack.clear();
+        assert_eq!(SceneManager::with_world(|world| world.len()), None);
+
+        SceneManager::switch(Scene::new("level"));
+        SceneManager::with_world(|world| {
+            for x in [0.0, 5.0] {
+                let entity = world.spawn();
+                world.insert(
+                    entity,
+                    Aabb::new(Vec2::new(x, 0.0), Vec2::new(x + 10.0, 10.0)),
+                );
+            }
+            Physics2D::detect(world);
+        });
+        assert_eq!(Physics2D::pairs().len(), 1);
+
+        // The pause menu starts empty and the level's contacts are forgotten
+        SceneManager::push(Scene::new("pause"));
+        assert_eq!(SceneManager::active_name().as_deref(), Some("pause"));
+        assert_eq!(SceneManager::with_world(|world| world.len()), Some(0));
+        assert!(Physics2D::pairs().is_empty());
+
+        let pause = SceneManager::pop().unwrap();
+        assert_eq!(pause.name(), "pause");
+        assert_eq!(SceneManager::with_world(|world| world.len()), Some(2));
+
+        let level = SceneManager::switch(Scene::new("level 2")).unwrap();
+        assert_eq!(level.world().len(), 2);
+        assert_eq!(SceneManager::len(), 1);
+        assert_eq!(SceneManager::active_name().as_deref(), Some("level 2"));
+    }
+}
@@ -0,0 +1,208 @@
+use glyph_brush::ab_glyph::{Font as _, FontArc, InvalidFont, PxScale, ScaleFont};
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::engine::math::color::Color;
+
+/// DejaVu Sans Mono, used whenever no other font has been set.
+static DEFAULT_FONT_BYTES: &[u8] = include_bytes!("../../../assets/fonts/DejaVuSansMono.ttf");
+
+#[derive(Debug)]
+pub enum TextError {
+    Io(std::io::Error),
+    InvalidFont(InvalidFont),
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::Io(e) => write!(f, "failed to read font: {e}"),
+            TextError::InvalidFont(e) => write!(f, "failed to parse font: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl From<std::io::Error> for TextError {
+    fn from(e: std::io::Error) -> Self {
+        TextError::Io(e)
+    }
+}
+
+impl From<InvalidFont> for TextError {
+    fn from(e: InvalidFont) -> Self {
+        TextError::InvalidFont(e)
+    }
+}
+
+/// A TTF/OTF font, drawn by
+/// [`TextRenderer`](crate::engine::renderer::text::TextRenderer). Cheap to
+/// clone.
+#[derive(Clone)]
+pub struct Font {
+    /// Wrapped again so clones can be told apart from other fonts.
+    inner: Arc<FontArc>,
+}
+
+impl Font {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TextError> {
+        Ok(Self {
+            inner: Arc::new(FontArc::try_from_vec(bytes.to_vec())?),
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TextError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// The bundled DejaVu Sans Mono.
+    pub fn default_font() -> Font {
+        static DEFAULT: Lazy<Font> =
+            Lazy::new(|| Font::from_bytes(DEFAULT_FONT_BYTES).expect("bundled font is valid"));
+
+        DEFAULT.clone()
+    }
+
+    /// Distance between consecutive baselines at `size` pixels.
+    pub fn line_height(&self, size: f32) -> f32 {
+        let font = self.inner.as_scaled(PxScale::from(size));
+        font.height() + font.line_gap()
+    }
+
+    /// Size in pixels of `text` laid out at `size`, honoring `\n`. Matches
+    /// the layout [`Text::draw`] uses, kerning included.
+    pub fn measure(&self, text: &str, size: f32) -> (f32, f32) {
+        let font = self.inner.as_scaled(PxScale::from(size));
+        let width = text
+            .split('\n')
+            .map(|line| {
+                let mut previous = None;
+                line.chars()
+                    .map(|c| {
+                        let id = font.glyph_id(c);
+                        let kern = previous.map_or(0.0, |previous| font.kern(previous, id));
+                        previous = Some(id);
+                        kern + font.h_advance(id)
+                    })
+                    .sum::<f32>()
+            })
+            .fold(0.0, f32::max);
+        let lines = text.split('\n').count() as f32;
+        (width, lines * self.line_height(size))
+    }
+
+    /// Whether both handles share the same parsed font.
+    pub(crate) fn ptr_eq(&self, other: &Font) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    pub(crate) fn brush_font(&self) -> &FontArc {
+        &self.inner
+    }
+}
+
+pub(crate) struct TextCommand {
+    pub text: String,
+    pub position: [f32; 2],
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+/// Immediate-mode text queue, drained by the renderer every frame.
+pub struct Text {
+    font: Option<Font>,
+    commands: Vec<TextCommand>,
+}
+
+impl Text {
+    fn new() -> Self {
+        Self {
+            font: None,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<Text> {
+        static INSTANCE: Lazy<Mutex<Text>> = Lazy::new(|| Mutex::new(Text::new()));
+
+        &INSTANCE
+    }
+
+    /// Sets the font used by [`Text::draw`] and [`measure_text`]. Until this
+    /// is called the bundled [`Font::default_font`] is used.
+    pub fn set_font(font: Font) {
+        Self::get_instance().lock().unwrap().font = Some(font);
+    }
+
+    /// Queues `text` for this frame with its top-left corner at `position`
+    /// (pixels, origin top-left).
+    pub fn draw(text: &str, position: [f32; 2], size: f32, color: impl Into<Color>) {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .commands
+            .push(TextCommand {
+                text: text.to_owned(),
+                position,
+                size,
+                color: color.into().into(),
+            });
+    }
+
+    /// The font to draw with, along with this frame's commands.
+    pub(crate) fn take_commands() -> (Font, Vec<TextCommand>) {
+        let mut t = Self::get_instance().lock().unwrap();
+        let commands = std::mem::take(&mut t.commands);
+        let font = t.font.clone().unwrap_or_else(Font::default_font);
+        (font, commands)
+    }
+}
+
+/// Size in pixels of `text` at `size` using the current font.
+pub fn measure_text(text: &str, size: f32) -> (f32, f32) {
+    let font = Text::get_instance().lock().unwrap().font.clone();
+    font.unwrap_or_else(Font::default_font).measure(text, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newlines_stack_lines_and_keep_the_widest() {
+        let font = Font::default_font();
+        let (one_width, one_height) = font.measure("abc", 16.0);
+        let (width, height) = font.measure("abc\na", 16.0);
+
+        assert!(one_width > 0.0);
+        assert_eq!(width, one_width);
+        assert_eq!(one_height, font.line_height(16.0));
+        assert_eq!(height, 2.0 * one_height);
+    }
+
+    #[test]
+    fn measure_scales_with_size() {
+        let font = Font::default_font();
+        let (small, _) = font.measure("kreeda", 10.0);
+        let (large, _) = font.measure("kreeda", 20.0);
+
+        assert!((large - 2.0 * small).abs() < 0.01);
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_line() {
+        let font = Font::default_font();
+        assert_eq!(font.measure("", 12.0), (0.0, font.line_height(12.0)));
+    }
+
+    #[test]
+    fn invalid_font_bytes_are_an_error() {
+        assert!(matches!(
+            Font::from_bytes(b"not a font"),
+            Err(TextError::InvalidFont(_))
+        ));
+    }
+}
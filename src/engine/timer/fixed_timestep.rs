@@ -0,0 +1,129 @@
+use std::iter;
+
+/// Default physics rate: 60 steps per second.
+pub const DEFAULT_FIXED_DELTA: f64 = 1.0 / 60.0;
+
+/// Upper bound on steps per frame so a slow frame can't snowball into an
+/// ever-growing backlog (the "spiral of death").
+pub const DEFAULT_MAX_STEPS: u32 = 10;
+
+/// Splits variable frame deltas into a whole number of fixed-size steps.
+pub struct FixedTimestep {
+    accumulator: f64,
+    fixed_delta: f64,
+    max_steps: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(fixed_delta: f64) -> Self {
+        assert!(
+            fixed_delta > 0.0 && fixed_delta.is_finite(),
+            "fixed_delta must be positive and finite"
+        );
+        Self {
+            accumulator: 0.0,
+            fixed_delta,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// Feeds the elapsed frame time and yields one `fixed_delta` for every
+    /// physics step that should run this frame.
+    pub fn tick(&mut self, frame_delta: f64) -> impl Iterator<Item = f64> + use<> {
+        self.accumulator += frame_delta.max(0.0);
+
+        let mut steps = (self.accumulator / self.fixed_delta) as u32;
+        if steps > self.max_steps {
+            // Too far behind to catch up; drop the backlog instead.
+            steps = self.max_steps;
+            self.accumulator = 0.0;
+        } else {
+            self.accumulator -= f64::from(steps) * self.fixed_delta;
+        }
+
+        iter::repeat_n(self.fixed_delta, steps as usize)
+    }
+
+    /// Fraction of a step left in the accumulator, for interpolating
+    /// rendered state between the last two physics steps.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.fixed_delta
+    }
+
+    //--Getters--//
+
+    pub fn fixed_delta(&self) -> f64 {
+        self.fixed_delta
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(DEFAULT_FIXED_DELTA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A power-of-two step keeps the accumulator arithmetic exact
+    const STEP: f64 = 0.25;
+
+    fn steps(timestep: &mut FixedTimestep, frame_delta: f64) -> Vec<f64> {
+        timestep.tick(frame_delta).collect()
+    }
+
+    #[test]
+    fn whole_steps_run_and_the_remainder_carries_over() {
+        let mut timestep = FixedTimestep::new(STEP);
+
+        assert_eq!(steps(&mut timestep, 0.375), vec![STEP]);
+        assert_eq!(timestep.alpha(), 0.5);
+
+        // The leftover half step completes on the next short frame
+        assert_eq!(steps(&mut timestep, 0.125), vec![STEP]);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        assert_eq!(steps(&mut timestep, 0.625), vec![STEP, STEP]);
+        assert_eq!(timestep.alpha(), 0.5);
+    }
+
+    #[test]
+    fn short_frames_accumulate_until_a_step_is_due() {
+        let mut timestep = FixedTimestep::new(STEP);
+
+        for alpha in [0.25, 0.5, 0.75] {
+            assert!(steps(&mut timestep, 0.0625).is_empty());
+            assert_eq!(timestep.alpha(), alpha);
+        }
+        assert_eq!(steps(&mut timestep, 0.0625), vec![STEP]);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn a_long_frame_is_capped_and_the_backlog_dropped() {
+        let mut timestep = FixedTimestep::new(STEP).with_max_steps(3);
+
+        assert_eq!(steps(&mut timestep, 10.0).len(), 3);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(steps(&mut timestep, STEP).len(), 1);
+    }
+
+    #[test]
+    fn negative_deltas_are_ignored() {
+        let mut timestep = FixedTimestep::new(STEP);
+
+        assert!(steps(&mut timestep, -1.0).is_empty());
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+}
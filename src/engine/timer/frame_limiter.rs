@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+/// `thread::sleep` routinely overshoots by up to a millisecond, so the last
+/// stretch before the deadline is spun instead.
+const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+
+/// Caps the frame rate by waiting out whatever is left of each frame's
+/// budget.
+pub struct FrameRateLimiter {
+    target_fps: u32,
+    last_frame: Option<Instant>,
+}
+
+impl FrameRateLimiter {
+    /// `target_fps` of 0 means unlimited.
+    pub fn new(target_fps: u32) -> Self {
+        Self {
+            target_fps,
+            last_frame: None,
+        }
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        self.target_fps = target_fps;
+    }
+
+    /// Blocks until one frame budget has passed since the previous call,
+    /// then starts the next frame. Returns immediately when unlimited.
+    pub fn wait(&mut self) {
+        let Some(budget) = self.frame_budget() else {
+            self.last_frame = None;
+            return;
+        };
+        let Some(last_frame) = self.last_frame else {
+            self.last_frame = Some(Instant::now());
+            return;
+        };
+
+        let deadline = last_frame + budget;
+        let now = Instant::now();
+        if let Some(remaining) = deadline.checked_duration_since(now) {
+            if remaining > SPIN_THRESHOLD {
+                std::thread::sleep(remaining - SPIN_THRESHOLD);
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+        }
+
+        // Schedule from the deadline so small overshoots don't add up, but
+        // don't try to catch up after a long stall
+        let now = Instant::now();
+        self.last_frame = Some(if now.saturating_duration_since(deadline) > budget { now } else { deadline });
+    }
+
+    //--Getters--//
+
+    pub fn target_fps(&self) -> u32 {
+        self.target_fps
+    }
+
+    /// Time allotted to one frame, or `None` when unlimited.
+    pub fn frame_budget(&self) -> Option<Duration> {
+        (self.target_fps > 0).then(|| Duration::from_secs_f64(1.0 / f64::from(self.target_fps)))
+    }
+}
@@ -0,0 +1,5 @@
+pub mod fixed_timestep;
+pub mod frame_limiter;
+#[allow(clippy::module_inception)]
+pub mod timer;
+pub mod timers;
@@ -0,0 +1,249 @@
+/// Whether a [`Timer`] stops after firing once or starts over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+/// Counts down `duration` seconds of the delta times it's ticked with.
+///
+/// ```ignore
+/// let mut spawn = Timer::repeating(2.0);
+/// // every frame:
+/// if spawn.tick(dt) {
+///     spawn_enemy();
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Timer {
+    duration: f32,
+    elapsed: f32,
+    mode: TimerMode,
+    paused: bool,
+    catch_up: bool,
+    /// Times the last tick finished the timer.
+    finished_this_tick: u32,
+    /// A `Once` timer has fired and stopped.
+    finished: bool,
+}
+
+impl Timer {
+    /// Fires once after `duration` seconds. A `duration` of zero fires on
+    /// the first tick.
+    pub fn once(duration: f32) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    /// Fires every `interval` seconds, which must be positive.
+    pub fn repeating(interval: f32) -> Self {
+        assert!(
+            interval > 0.0,
+            "a repeating timer needs a positive interval"
+        );
+        Self::new(interval, TimerMode::Repeating)
+    }
+
+    fn new(duration: f32, mode: TimerMode) -> Self {
+        Self {
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            mode,
+            paused: false,
+            catch_up: false,
+            finished_this_tick: 0,
+            finished: false,
+        }
+    }
+
+    /// When a repeating timer is ticked with several intervals at once,
+    /// e.g. after a stall, fire once per interval instead of once and
+    /// dropping the rest. Off by default.
+    pub fn with_catch_up(mut self, catch_up: bool) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Advances by `delta` seconds and returns whether the timer fired.
+    /// [`Timer::times_finished_this_tick`] tells how often when catching up.
+    pub fn tick(&mut self, delta: f32) -> bool {
+        self.finished_this_tick = 0;
+        if self.paused || self.finished {
+            return false;
+        }
+        self.elapsed += delta.max(0.0);
+        if self.elapsed < self.duration {
+            return false;
+        }
+
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.finished = true;
+                self.finished_this_tick = 1;
+            }
+            TimerMode::Repeating => {
+                let times = (self.elapsed / self.duration) as u32;
+                self.elapsed %= self.duration;
+                self.finished_this_tick = if self.catch_up { times } else { 1 };
+            }
+        }
+        true
+    }
+
+    /// Stops the timer counting until [`Timer::resume`].
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Starts over from zero, also un-finishing a `Once` timer.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.finished_this_tick = 0;
+    }
+
+    //--Getters--//
+
+    /// Whether the last tick finished the timer.
+    pub fn just_finished(&self) -> bool {
+        self.finished_this_tick > 0
+    }
+
+    /// How many times the last tick finished the timer.
+    pub fn times_finished_this_tick(&self) -> u32 {
+        self.finished_this_tick
+    }
+
+    /// Whether a `Once` timer has fired. Repeating timers never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Seconds until the timer next fires.
+    pub fn remaining(&self) -> f32 {
+        if self.finished {
+            0.0
+        } else {
+            self.duration - self.elapsed
+        }
+    }
+
+    /// How far through the current duration the timer is, from 0 to 1.
+    pub fn percent(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+}
+
+/// A one-shot timer of one second, not yet ticked.
+impl Default for Timer {
+    fn default() -> Self {
+        Self::once(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn once_fires_a_single_time() {
+        let mut timer = Timer::once(1.0);
+        assert!(!timer.tick(0.75));
+        assert_eq!(timer.elapsed(), 0.75);
+        assert_eq!(timer.remaining(), 0.25);
+
+        assert!(timer.tick(0.5));
+        assert!(timer.is_finished());
+        assert_eq!(timer.elapsed(), 1.0);
+        assert_eq!(timer.remaining(), 0.0);
+        assert!(!timer.tick(5.0));
+        assert!(!timer.just_finished());
+    }
+
+    #[test]
+    fn repeating_fires_every_interval_and_keeps_the_remainder() {
+        let mut timer = Timer::repeating(0.5);
+        let fired = (0..8).filter(|_| timer.tick(0.25)).count();
+        assert_eq!(fired, 4);
+        assert!(!timer.is_finished());
+
+        assert!(timer.tick(0.75));
+        assert_eq!(timer.elapsed(), 0.25);
+        assert_eq!(timer.remaining(), 0.25);
+    }
+
+    #[test]
+    fn a_spike_fires_a_repeating_timer_once_unless_catching_up() {
+        let mut timer = Timer::repeating(1.0);
+        assert!(timer.tick(3.5));
+        assert_eq!(timer.times_finished_this_tick(), 1);
+        assert_eq!(timer.elapsed(), 0.5);
+
+        let mut timer = Timer::repeating(1.0).with_catch_up(true);
+        assert!(timer.tick(3.5));
+        assert_eq!(timer.times_finished_this_tick(), 3);
+        assert!(!timer.tick(0.25));
+        assert!(!timer.just_finished());
+    }
+
+    #[test]
+    fn reset_restarts_a_finished_timer() {
+        let mut timer = Timer::once(0.5);
+        timer.tick(1.0);
+        timer.reset();
+        assert!(!timer.is_finished());
+        assert_eq!(timer.elapsed(), 0.0);
+        assert_eq!(timer.remaining(), 0.5);
+        assert!(!timer.tick(0.25));
+        assert!(timer.tick(0.25));
+    }
+
+    #[test]
+    fn paused_timers_hold_their_progress() {
+        let mut timer = Timer::once(2.0);
+        timer.tick(0.5);
+        timer.pause();
+        assert!(!timer.tick(5.0));
+        assert_eq!(timer.remaining(), 1.5);
+        assert_eq!(timer.percent(), 0.25);
+
+        timer.resume();
+        assert!(timer.tick(1.5));
+        assert!(timer.is_finished());
+    }
+
+    #[test]
+    fn default_is_an_unstarted_one_second_one_shot() {
+        let timer = Timer::default();
+        assert_eq!(timer.mode(), TimerMode::Once);
+        assert_eq!(timer.duration(), 1.0);
+        assert_eq!(timer.elapsed(), 0.0);
+        assert_eq!(timer.remaining(), 1.0);
+        assert!(!timer.is_finished());
+        assert!(!timer.just_finished());
+    }
+}
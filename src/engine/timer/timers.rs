@@ -0,0 +1,280 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use super::timer::Timer;
+
+/// Handle to a callback scheduled with [`Timers::after`] or
+/// [`Timers::every`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+type TimerCallback = Box<dyn FnMut() + Send>;
+
+struct Scheduled {
+    id: TimerId,
+    timer: Timer,
+    /// Taken out while the callback runs, so it can schedule more.
+    callback: Option<TimerCallback>,
+}
+
+/// Runs callbacks after a delay or on an interval. The window ticks it
+/// once per frame, before the update callbacks, with the frame delta
+/// multiplied by [`Timers::time_scale`].
+///
+/// ```ignore
+/// Timers::every(2.0, spawn_enemy);
+/// let hint = Timers::after(3.0, || show_message(""));
+/// ```
+pub struct Timers {
+    scheduled: Vec<Scheduled>,
+    next_id: u64,
+    time_scale: f32,
+}
+
+impl Timers {
+    fn new() -> Self {
+        Self {
+            scheduled: Vec::new(),
+            next_id: 0,
+            time_scale: 1.0,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<Timers> {
+        static INSTANCE: Lazy<Mutex<Timers>> = Lazy::new(|| Mutex::new(Timers::new()));
+        &INSTANCE
+    }
+
+    #[cfg(test)]
+    pub(crate) fn reset() {
+        *Self::get_instance().lock().unwrap() = Self::new();
+    }
+
+    /// Runs `callback` once, `delay` seconds from now.
+    pub fn after(delay: f32, callback: impl FnMut() + Send + 'static) -> TimerId {
+        Self::schedule(Timer::once(delay), Box::new(callback))
+    }
+
+    /// Runs `callback` every `interval` seconds until cancelled. After a
+    /// frame longer than `interval` it runs once, not once per interval.
+    pub fn every(interval: f32, callback: impl FnMut() + Send + 'static) -> TimerId {
+        Self::schedule(Timer::repeating(interval), Box::new(callback))
+    }
+
+    fn schedule(timer: Timer, callback: TimerCallback) -> TimerId {
+        let mut timers = Self::get_instance().lock().unwrap();
+        let id = TimerId(timers.next_id);
+        timers.next_id += 1;
+        timers.scheduled.push(Scheduled {
+            id,
+            timer,
+            callback: Some(callback),
+        });
+        id
+    }
+
+    /// Drops a scheduled callback. Returns false if it already ran out or
+    /// was cancelled.
+    pub fn cancel(id: TimerId) -> bool {
+        let mut timers = Self::get_instance().lock().unwrap();
+        let len = timers.scheduled.len();
+        timers.scheduled.retain(|scheduled| scheduled.id != id);
+        timers.scheduled.len() != len
+    }
+
+    /// Holds a scheduled callback's countdown until [`Timers::resume`].
+    pub fn pause(id: TimerId) {
+        Self::with_timer(id, Timer::pause);
+    }
+
+    pub fn resume(id: TimerId) {
+        Self::with_timer(id, Timer::resume);
+    }
+
+    fn with_timer(id: TimerId, f: impl FnOnce(&mut Timer)) {
+        let mut timers = Self::get_instance().lock().unwrap();
+        if let Some(scheduled) = timers.scheduled.iter_mut().find(|s| s.id == id) {
+            f(&mut scheduled.timer);
+        }
+    }
+
+    /// Speeds up or slows down scheduled callbacks, e.g. 0.25 for slow
+    /// motion or 0 to freeze them. Clamped to zero or more.
+    ///
+    /// Timers you tick yourself aren't affected; multiply their delta by
+    /// [`Timers::time_scale`] to keep them in step.
+    pub fn set_time_scale(scale: f32) {
+        Self::get_instance().lock().unwrap().time_scale = scale.max(0.0);
+    }
+
+    /// Advances every scheduled callback by `delta` seconds, scaled, and
+    /// runs the ones that are due in the order they were scheduled.
+    pub(crate) fn tick(delta: f64) {
+        let due: Vec<_> = {
+            let mut timers = Self::get_instance().lock().unwrap();
+            let delta = delta as f32 * timers.time_scale;
+            timers
+                .scheduled
+                .iter_mut()
+                .filter_map(|scheduled| {
+                    if !scheduled.timer.tick(delta) {
+                        return None;
+                    }
+                    Some((scheduled.id, scheduled.callback.take()?))
+                })
+                .collect()
+        };
+
+        for (id, mut callback) in due {
+            callback();
+            let mut timers = Self::get_instance().lock().unwrap();
+            // Gone if the callback cancelled itself
+            if let Some(scheduled) = timers.scheduled.iter_mut().find(|s| s.id == id) {
+                scheduled.callback = Some(callback);
+            }
+        }
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .scheduled
+            .retain(|scheduled| !scheduled.timer.is_finished());
+    }
+
+    //--Getters--//
+
+    pub fn time_scale() -> f32 {
+        Self::get_instance().lock().unwrap().time_scale
+    }
+
+    /// Whether `id` is still scheduled.
+    pub fn is_scheduled(id: TimerId) -> bool {
+        let timers = Self::get_instance().lock().unwrap();
+        timers.scheduled.iter().any(|scheduled| scheduled.id == id)
+    }
+
+    /// Seconds of scaled time until `id` next runs, or `None` if it isn't
+    /// scheduled.
+    pub fn remaining(id: TimerId) -> Option<f32> {
+        let timers = Self::get_instance().lock().unwrap();
+        timers
+            .scheduled
+            .iter()
+            .find(|scheduled| scheduled.id == id)
+            .map(|scheduled| scheduled.timer.remaining())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Held by tests using the shared [`Timers`], starting from an empty one.
+    fn test_guard() -> std::sync::MutexGuard<'static, ()> {
+        static LOCK: Mutex<()> = Mutex::new(());
+        let guard = LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        Timers::reset();
+        guard
+    }
+
+    /// A callback counting its runs, and the count.
+    fn counter() -> (impl FnMut() + Send + 'static, Arc<AtomicU32>) {
+        let count = Arc::new(AtomicU32::new(0));
+        let runs = count.clone();
+        (
+            move || {
+                runs.fetch_add(1, Ordering::SeqCst);
+            },
+            count,
+        )
+    }
+
+    #[test]
+    fn delayed_callbacks_run_once_then_unschedule() {
+        let _guard = test_guard();
+        let (callback, runs) = counter();
+        let id = Timers::after(1.0, callback);
+
+        Timers::tick(0.5);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        assert_eq!(Timers::remaining(id), Some(0.5));
+
+        Timers::tick(0.5);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(!Timers::is_scheduled(id));
+        assert_eq!(Timers::remaining(id), None);
+
+        Timers::tick(5.0);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn interval_callbacks_repeat_until_cancelled() {
+        let _guard = test_guard();
+        let (callback, runs) = counter();
+        let id = Timers::every(1.0, callback);
+
+        for _ in 0..4 {
+            Timers::tick(0.5);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        // A stall runs it once, not once per interval
+        Timers::tick(10.0);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        assert!(Timers::cancel(id));
+        assert!(!Timers::cancel(id));
+        Timers::tick(1.0);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn pausing_and_time_scale_slow_callbacks_down() {
+        let _guard = test_guard();
+        let (callback, runs) = counter();
+        let id = Timers::after(1.0, callback);
+
+        Timers::pause(id);
+        Timers::tick(5.0);
+        assert_eq!(Timers::remaining(id), Some(1.0));
+        Timers::resume(id);
+
+        Timers::set_time_scale(0.5);
+        assert_eq!(Timers::time_scale(), 0.5);
+        Timers::tick(1.0);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        Timers::tick(1.0);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        Timers::set_time_scale(-1.0);
+        assert_eq!(Timers::time_scale(), 0.0);
+    }
+
+    #[test]
+    fn callbacks_can_schedule_and_cancel() {
+        let _guard = test_guard();
+        let (inner, runs) = counter();
+        let mut inner = Some(inner);
+        Timers::after(1.0, move || {
+            if let Some(inner) = inner.take() {
+                Timers::after(1.0, inner);
+            }
+        });
+        let slot = Arc::new(Mutex::new(None));
+        let own_id = slot.clone();
+        let id = Timers::every(1.0, move || {
+            Timers::cancel(own_id.lock().unwrap().unwrap());
+        });
+        *slot.lock().unwrap() = Some(id);
+
+        Timers::tick(1.0);
+        assert!(!Timers::is_scheduled(id));
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        Timers::tick(1.0);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+}
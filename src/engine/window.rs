@@ -1,23 +1,357 @@
-use once_cell::sync::OnceCell;
+use glam::Vec2;
+use image::RgbaImage;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fmt;
 use std::iter;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use wgpu::{Device, Dx12Compiler, Queue, Surface, SurfaceConfiguration, SurfaceError};
 use winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize,
-    event::WindowEvent,
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event::{DeviceEvent, DeviceId, WindowEvent},
     event_loop::{ActiveEventLoop, EventLoop},
-    window::Window as WinitWindow,
+    keyboard::{Key, NamedKey},
+    monitor::MonitorHandle,
+    window::{BadIcon, Icon, Window as WinitWindow},
 };
 
+use crate::{IntoLevelFilter, KreedaError};
+use crate::engine::animation::tween::TweenManager;
+use crate::engine::assets::resource_manager::ResourceManager;
+use crate::engine::debug::debug_draw::DebugDraw;
+use crate::engine::debug::metrics::PerformanceMetrics;
+use crate::engine::events::{CloseRequested, EventBus, FileDropped, FocusChanged, WindowResized};
+use crate::engine::debug::renderer::DebugRenderer;
+#[cfg(feature = "egui")]
+use crate::engine::debug::ui::{self, UiRenderer};
+use crate::engine::lighting::{LightingBuffer, LightingUniform};
+use crate::engine::math::color::Color;
+use crate::engine::physics2d::Physics2D;
+use crate::engine::scene::SceneManager;
+use crate::engine::renderer::camera3d::{Camera3D, CameraUniform, FlyController};
+use crate::engine::renderer::context::GpuContext;
+use crate::engine::renderer::depth::{DEPTH_CLEAR, create_depth_view};
+use crate::engine::renderer::globals::GlobalsUniform;
+use crate::engine::renderer::mesh_renderer::{self, MeshRenderer};
+use crate::engine::renderer::post_process::{self, PostProcessChain};
+use crate::engine::renderer::render_graph::{self, RenderGraph};
+use crate::engine::renderer::render_target::RenderTarget;
+use crate::engine::renderer::screenshot::{
+    ScreenshotError, read_texture_rgba, save_texture_png,
+};
+use crate::engine::renderer::skybox::SkyboxRenderer;
+use crate::engine::renderer::sprite_renderer::SpriteRenderer;
+use crate::engine::renderer::text::TextRenderer;
+use crate::engine::renderer::tilemap_renderer::TilemapRenderer;
+use crate::engine::renderer::viewport::Viewport;
+use crate::engine::timer::fixed_timestep::{DEFAULT_FIXED_DELTA, FixedTimestep};
+use crate::engine::timer::frame_limiter::FrameRateLimiter;
+use crate::engine::timer::timers::Timers;
+use crate::input::gamepad_listener::GamepadInput as gamepad;
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad_listener::GilrsBackend;
 use crate::input::mouse_listener::MouseInput as mouse;
 use crate::input::key_listener::KeyInput as key;
+use crate::input::recorder::{InputPlayer, InputRecorder};
+
+#[derive(Debug)]
+pub enum IconError {
+    Io(std::io::Error),
+    Decode(image::ImageError),
+    BadIcon(BadIcon),
+    /// The pixel data doesn't hold `width * height` RGBA pixels, or a side
+    /// is zero.
+    Size { width: u32, height: u32, len: usize },
+}
+
+impl fmt::Display for IconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IconError::Io(e) => write!(f, "failed to read icon: {e}"),
+            IconError::Decode(e) => write!(f, "failed to decode icon: {e}"),
+            IconError::BadIcon(e) => write!(f, "invalid icon: {e}"),
+            IconError::Size { width, height, len } => write!(
+                f,
+                "a {width}x{height} icon needs {} bytes of RGBA, got {len}",
+                u64::from(*width) * u64::from(*height) * 4
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IconError {}
+
+/// State the running event loop reads every frame. `run` copies the
+/// `Window` settings once when it starts, so anything game code changes at
+/// runtime goes through here instead.
+struct Shared {
+    icon: Option<Icon>,
+    icon_dirty: bool,
+    focused: bool,
+    pause_on_unfocus: bool,
+    minimized: bool,
+    maximized: bool,
+    occluded: bool,
+    /// Minimize, maximize or restore next, applied by the event loop.
+    state_request: Option<StateRequest>,
+    device_added: bool,
+    device_removed: bool,
+    target_fps: u32,
+    screenshot_requests: Vec<(PathBuf, ScreenshotCallback)>,
+    capture_requests: Vec<CaptureCallback>,
+    screenshot_key: Option<Key>,
+    debug_view_key: Option<Key>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+    close_policy: ClosePolicy,
+    exit_requested: bool,
+    quit_on_escape: bool,
+    /// Seconds of unpaused frames since the window opened.
+    elapsed: f64,
+    clear_color: wgpu::Color,
+    /// The adapter the GPU state was created on.
+    gpu_info: Option<wgpu::AdapterInfo>,
+    surface_format: Option<wgpu::TextureFormat>,
+    redraw_mode: RedrawMode,
+    /// Something changed that an [`RedrawMode::OnDemand`] window should
+    /// draw a frame for.
+    invalidated: bool,
+    monitors: Vec<MonitorInfo>,
+    /// Where to move the window next, applied by the event loop.
+    position_request: Option<PhysicalPosition<i32>>,
+    monitor_request: Option<usize>,
+    viewports: Vec<Viewport>,
+    /// Secondary windows to open and close, applied by the event loop.
+    open_requests: Vec<(WindowId, WindowConfig)>,
+    close_requests: Vec<WindowId>,
+    /// Secondary windows open or about to be.
+    secondary_windows: Vec<WindowId>,
+    next_window_id: u64,
+}
+
+impl Shared {
+    /// True while update and rendering should be skipped.
+    fn paused(&self) -> bool {
+        (self.pause_on_unfocus && !self.focused) || self.minimized || self.occluded
+    }
+}
+
+/// A change of window state asked for by game code.
+#[derive(Clone, Copy)]
+enum StateRequest {
+    Minimize,
+    Maximize,
+    Restore,
+}
+
+/// Receives the pixels of a frame requested with [`Window::capture_frame`].
+type CaptureCallback = Box<dyn FnOnce(Result<RgbaImage, ScreenshotError>) + Send>;
+
+/// Told how saving a screenshot from [`Window::save_screenshot`] went.
+type ScreenshotCallback = Box<dyn FnOnce(Result<(), ScreenshotError>) + Send>;
+
+fn shared() -> &'static Mutex<Shared> {
+    static INSTANCE: Lazy<Mutex<Shared>> = Lazy::new(|| {
+        Mutex::new(Shared {
+            icon: None,
+            icon_dirty: false,
+            focused: true,
+            pause_on_unfocus: false,
+            minimized: false,
+            maximized: false,
+            occluded: false,
+            state_request: None,
+            device_added: false,
+            device_removed: false,
+            target_fps: 0,
+            screenshot_requests: Vec::new(),
+            capture_requests: Vec::new(),
+            screenshot_key: None,
+            debug_view_key: None,
+            size: PhysicalSize::new(0, 0),
+            scale_factor: 1.0,
+            close_policy: ClosePolicy::Immediate,
+            exit_requested: false,
+            quit_on_escape: false,
+            elapsed: 0.0,
+            clear_color: wgpu::Color::WHITE,
+            gpu_info: None,
+            surface_format: None,
+            redraw_mode: RedrawMode::Continuous,
+            invalidated: false,
+            monitors: Vec::new(),
+            position_request: None,
+            monitor_request: None,
+            viewports: Vec::new(),
+            open_requests: Vec::new(),
+            close_requests: Vec::new(),
+            secondary_windows: Vec::new(),
+            next_window_id: 0,
+        })
+    });
+
+    &INSTANCE
+}
+
+/// What happens when the user closes the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClosePolicy {
+    /// Exit right away (the default).
+    #[default]
+    Immediate,
+    /// Keep running; the game exits with [`Window::exit`] when ready. A
+    /// [`CloseRequested`](crate::engine::events::CloseRequested) event is
+    /// sent either way.
+    Deferred,
+}
+
+/// When the window draws a new frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedrawMode {
+    /// As often as the display (or the frame rate cap) allows; the default,
+    /// for games.
+    #[default]
+    Continuous,
+    /// Only after input, a resize or [`Window::invalidate`], so a static
+    /// scene uses no power, e.g. in an editor. Running [`Tween`]s and
+    /// animations invalidate the window on their own while they play.
+    ///
+    /// [`Tween`]: crate::engine::animation::tween::Tween
+    OnDemand,
+}
+
+/// The window's new size, passed to [`Window::add_resize_callback`]
+/// callbacks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResizeEvent {
+    /// Size in device pixels, as the surface is rendered.
+    pub physical: PhysicalSize<u32>,
+    /// Size in scale-independent units, for laying out UI.
+    pub logical: LogicalSize<f64>,
+    pub scale_factor: f64,
+}
+
+/// Handle to a window opened with [`Window::open_secondary`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WindowId(u64);
+
+/// How a window opened with [`Window::open_secondary`] looks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Size in logical pixels.
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowConfig {
+    pub fn new(title: impl Into<String>, width: u32, height: u32) -> Self {
+        Self {
+            title: title.into(),
+            width,
+            height,
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self::new("Kreeda Engine", 800, 600)
+    }
+}
+
+/// A display, as listed by [`Window::available_monitors`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    /// Top-left corner on the desktop, in device pixels.
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    /// Current refresh rate in millihertz (60 Hz is 60000), if known.
+    pub refresh_rate_millihertz: Option<u32>,
+    pub scale_factor: f64,
+    pub primary: bool,
+    /// Modes the monitor supports, e.g. for exclusive fullscreen.
+    pub video_modes: Vec<VideoModeInfo>,
+}
+
+impl MonitorInfo {
+    fn new(handle: &MonitorHandle, primary: Option<&MonitorHandle>) -> Self {
+        Self {
+            name: handle.name(),
+            position: handle.position(),
+            size: handle.size(),
+            refresh_rate_millihertz: handle.refresh_rate_millihertz(),
+            scale_factor: handle.scale_factor(),
+            primary: primary == Some(handle),
+            video_modes: handle
+                .video_modes()
+                .map(|mode| VideoModeInfo {
+                    size: mode.size(),
+                    bit_depth: mode.bit_depth(),
+                    refresh_rate_millihertz: mode.refresh_rate_millihertz(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VideoModeInfo {
+    pub size: PhysicalSize<u32>,
+    pub bit_depth: u16,
+    pub refresh_rate_millihertz: u32,
+}
+
+/// How the GPU is picked and set up when the window opens.
+#[derive(Clone)]
+struct GpuConfig {
+    vsync: bool,
+    backends: wgpu::Backends,
+    power_preference: wgpu::PowerPreference,
+    required_features: wgpu::Features,
+    required_limits: wgpu::Limits,
+    surface_format: Option<wgpu::TextureFormat>,
+    view_formats: Vec<wgpu::TextureFormat>,
+}
 
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            backends: wgpu::Backends::PRIMARY,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            surface_format: None,
+            view_formats: Vec::new(),
+        }
+    }
+}
+
+/// Longest frame delta handed to update callbacks, so a stall (debugger,
+/// window drag) doesn't teleport gameplay forward.
+const MAX_FRAME_DELTA: f64 = 0.25;
+
+/// The engine's one window. Everything is set through associated
+/// functions, e.g. `Window::add_update(update)` then `Window::run()`, which
+/// lock it only for the call.
 pub struct Window {
-    width: u32,
-    height: u32,
-    title: String,
-    running: bool,
+    /// Size the window opens with, in logical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Title the window opens with.
+    pub title: String,
+    fixed_delta: f64,
+    gpu: GpuConfig,
+    update_callbacks: Vec<fn(f64)>,
+    physics_callbacks: Vec<fn(f64)>,
+    resize_callbacks: Vec<fn(ResizeEvent)>,
+    close_handler: Option<fn() -> bool>,
+    shutdown_hooks: Vec<fn()>,
 }
 
 impl Window {
@@ -26,31 +360,507 @@ impl Window {
             width: 800,
             height: 600,
             title: String::from("Kreeda Engine"),
-            running: true,
+            fixed_delta: DEFAULT_FIXED_DELTA,
+            gpu: GpuConfig::default(),
+            update_callbacks: Vec::new(),
+            physics_callbacks: Vec::new(),
+            resize_callbacks: Vec::new(),
+            close_handler: None,
+            shutdown_hooks: Vec::new(),
+        }
+    }
+
+    fn instance() -> &'static RwLock<Window> {
+        static INSTANCE: Lazy<RwLock<Window>> = Lazy::new(|| RwLock::new(Window::new()));
+        &INSTANCE
+    }
+
+    /// Runs `f` with the window locked for writing, e.g. to set the size
+    /// and title it opens with in one go. Calling another `Window`
+    /// function from `f` deadlocks.
+    ///
+    /// ```ignore
+    /// Window::with(|window| {
+    ///     window.width = 1280;
+    ///     window.height = 720;
+    ///     window.title = String::from("My Game");
+    /// });
+    /// ```
+    pub fn with<T, F: FnOnce(&mut Window) -> T>(f: F) -> T {
+        f(&mut Self::instance()
+            .write()
+            .expect("Failed to lock the Window instance"))
+    }
+
+    /// Shared asset cache. Load through it with the device from
+    /// [`GpuContext::get`] once the window is running.
+    pub fn get_resource_manager() -> &'static Mutex<ResourceManager> {
+        ResourceManager::get_instance()
+    }
+
+    /// Sets the title bar / taskbar icon from an image file (PNG or JPEG,
+    /// converted to RGBA; small square images such as 32x32 or 64x64 work
+    /// best). Can be called before `run` or while running. On error the
+    /// current icon is kept.
+    ///
+    /// Some platforms ignore per-window icons: macOS uses the application
+    /// bundle icon and Wayland takes it from the desktop entry.
+    pub fn set_icon(path: impl AsRef<Path>) -> Result<(), IconError> {
+        let bytes = std::fs::read(path).map_err(IconError::Io)?;
+        let image = image::load_from_memory(&bytes)
+            .map_err(IconError::Decode)?
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        Self::set_icon_from_bytes(&image, width, height)
+    }
+
+    /// [`Window::set_icon`] from an in-memory PNG, e.g. one embedded with
+    /// `include_bytes!`.
+    pub fn set_icon_from_png(bytes: &[u8]) -> Result<(), IconError> {
+        let image = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+            .map_err(IconError::Decode)?
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        Self::set_icon_from_bytes(&image, width, height)
+    }
+
+    /// [`Window::set_icon`] from tightly packed RGBA8 pixels, row by row
+    /// from the top-left.
+    pub fn set_icon_from_bytes(rgba: &[u8], width: u32, height: u32) -> Result<(), IconError> {
+        let expected = u64::from(width) * u64::from(height) * 4;
+        if width == 0 || height == 0 || rgba.len() as u64 != expected {
+            return Err(IconError::Size {
+                width,
+                height,
+                len: rgba.len(),
+            });
         }
+        let icon = Icon::from_rgba(rgba.to_vec(), width, height).map_err(IconError::BadIcon)?;
+
+        let mut shared = shared().lock().unwrap();
+        shared.icon = Some(icon);
+        shared.icon_dirty = true;
+        Ok(())
+    }
+
+    /// The connected monitors, as of when the window opened or was last
+    /// moved with [`Window::set_monitor`]. Empty before [`Window::run`].
+    pub fn available_monitors() -> Vec<MonitorInfo> {
+        shared().lock().unwrap().monitors.clone()
+    }
+
+    /// Moves the window's outer top-left corner, decorations included, to
+    /// `(x, y)` on the desktop in device pixels. Before [`Window::run`] it
+    /// sets where the window opens. Wayland doesn't let apps position
+    /// their windows, so it's ignored there.
+    pub fn set_position(x: i32, y: i32) {
+        shared().lock().unwrap().position_request = Some(PhysicalPosition::new(x, y));
+    }
+
+    /// Centers the window on monitor `index` of
+    /// [`Window::available_monitors`]. Before [`Window::run`] it picks the
+    /// monitor the window opens on. Ignored on Wayland, like
+    /// [`Window::set_position`].
+    pub fn set_monitor(index: usize) {
+        shared().lock().unwrap().monitor_request = Some(index);
+    }
+
+    /// Whether the window currently has keyboard focus.
+    pub fn is_focused() -> bool {
+        shared().lock().unwrap().focused
+    }
+
+    /// When enabled, update, physics and rendering stop while the window is
+    /// unfocused. Events (including close) are still processed.
+    pub fn set_pause_on_unfocus(pause: bool) {
+        shared().lock().unwrap().pause_on_unfocus = pause;
+    }
+
+    /// Whether the window is minimized or fully hidden. Rendering and
+    /// updates are suspended meanwhile, so games may also want to pause
+    /// audio.
+    pub fn is_minimized() -> bool {
+        let shared = shared().lock().unwrap();
+        shared.minimized || shared.occluded
+    }
+
+    /// Whether the window is maximized.
+    pub fn is_maximized() -> bool {
+        shared().lock().unwrap().maximized
+    }
+
+    /// Minimizes the window. Some platforms, like Wayland, can't restore a
+    /// window from code once it is minimized.
+    pub fn minimize() {
+        shared().lock().unwrap().state_request = Some(StateRequest::Minimize);
     }
 
-    pub fn get() -> MutexGuard<'static, Window> {
-        static INSTANCE: OnceCell<Mutex<Window>> = OnceCell::new();
-        INSTANCE
-            .get_or_init(|| Mutex::new(Window::new()))
+    /// Maximizes the window. Before [`Window::run`] the window opens
+    /// maximized.
+    pub fn maximize() {
+        shared().lock().unwrap().state_request = Some(StateRequest::Maximize);
+    }
+
+    /// Brings the window back from minimized or maximized.
+    pub fn restore() {
+        shared().lock().unwrap().state_request = Some(StateRequest::Restore);
+    }
+
+    /// True on the frame any input device was connected.
+    pub fn device_added() -> bool {
+        shared().lock().unwrap().device_added
+    }
+
+    /// True on the frame any input device was disconnected, e.g. to show a
+    /// "mouse disconnected" notice.
+    pub fn device_removed() -> bool {
+        shared().lock().unwrap().device_removed
+    }
+
+    /// Opens the window and runs the game until it exits. Fails if the
+    /// window or the GPU can't be set up, instead of panicking, so the app
+    /// can tell the user or fall back to something else.
+    ///
+    /// With the `log-tracing` feature this also installs a `tracing`
+    /// subscriber printing the engine's diagnostics to stderr, at the level
+    /// named by `KREEDA_LOG` (`warn` by default).
+    pub fn run() -> Result<(), KreedaError> {
+        #[cfg(feature = "log-tracing")]
+        crate::logging::init_tracing();
+        let (event_loop, mut app) = Self::init()?;
+        Self::r#loop(event_loop, &mut app)
+    }
+
+    /// Registers a callback run once per frame with the frame delta in
+    /// seconds, before the physics steps.
+    pub fn add_update(callback: fn(f64)) {
+        Self::with(|window| window.update_callbacks.push(callback));
+    }
+
+    /// Registers a callback run once per fixed physics step, before rendering.
+    pub fn add_physics_update(callback: fn(f64)) {
+        Self::with(|window| window.physics_callbacks.push(callback));
+    }
+
+    /// Saves the next rendered frame to `path` as a PNG, then calls
+    /// `callback` with the outcome. Like [`Window::capture_frame`]'s, it
+    /// runs on the event loop thread once the file is written.
+    pub fn save_screenshot(
+        path: impl Into<PathBuf>,
+        callback: impl FnOnce(Result<(), ScreenshotError>) + Send + 'static,
+    ) {
+        shared()
             .lock()
-            .expect("Failed to lock the Window instance")
+            .unwrap()
+            .screenshot_requests
+            .push((path.into(), Box::new(callback)));
     }
 
-    pub fn run(&mut self) {
-        let (event_loop, mut app) = self.init();
-        self.r#loop(event_loop, &mut app);
+    /// Calls `callback` with the pixels of the next rendered frame, e.g. to
+    /// compare against a reference image in a visual test. It runs on the
+    /// event loop thread once the frame has been read back.
+    pub fn capture_frame(
+        callback: impl FnOnce(Result<RgbaImage, ScreenshotError>) + Send + 'static,
+    ) {
+        shared()
+            .lock()
+            .unwrap()
+            .capture_requests
+            .push(Box::new(callback));
+    }
+
+    /// Binds `key` to save a screenshot named `screenshot-<unix millis>.png`
+    /// in the working directory, or removes the binding with `None`. No key
+    /// is bound by default; `Key::Named(NamedKey::F12)` is the usual choice.
+    pub fn set_screenshot_key(key: Option<Key>) {
+        shared().lock().unwrap().screenshot_key = key;
+    }
+
+    /// Binds `key` to cycle the meshes through the
+    /// [`DebugView`](crate::engine::renderer::mesh_renderer::DebugView)s,
+    /// or removes the binding with `None`. No key is bound by default;
+    /// `Key::Named(NamedKey::F3)` is the usual choice.
+    pub fn set_debug_view_key(key: Option<Key>) {
+        shared().lock().unwrap().debug_view_key = key;
+    }
+
+    /// Caps the frame rate at `fps` frames per second, or removes the cap
+    /// with 0 (the default). Can be changed while running. Only takes
+    /// effect with VSync off, since VSync already paces frames.
+    pub fn set_target_fps(fps: u32) {
+        shared().lock().unwrap().target_fps = fps;
     }
 
-    fn init(&self) -> (EventLoop<()>, App) {
-        let event_loop = EventLoop::new().expect("Failed to create event loop");
-        let app = App::new(self.width, self.height, self.title.clone());
-        (event_loop, app)
+    /// [`Window::set_target_fps`] with `None` for no cap.
+    ///
+    /// The cap only paces frames: physics still advances in steps of
+    /// [`Window::set_fixed_delta`], so a cap below `1 / fixed_delta` runs
+    /// several physics steps per frame rather than slowing the simulation,
+    /// and a cap above it runs some frames with no physics step at all.
+    pub fn set_fps_cap(cap: Option<u32>) {
+        Self::set_target_fps(cap.unwrap_or(0));
     }
 
-    fn r#loop(&self, event_loop: EventLoop<()>, app: &mut App) {
-        event_loop.run_app(app).expect("run_app failed");
+    /// The frame rate cap set by [`Window::set_fps_cap`], if any.
+    pub fn fps_cap() -> Option<u32> {
+        let fps = shared().lock().unwrap().target_fps;
+        (fps > 0).then_some(fps)
+    }
+
+    /// Whether presentation waits for the display's vertical blank (the
+    /// default). Must be set before `run`. Without VSync the engine renders
+    /// as fast as it can unless [`Window::set_target_fps`] caps it.
+    pub fn set_vsync(vsync: bool) {
+        Self::with(|window| window.gpu.vsync = vsync);
+    }
+
+    /// GPU features the device must have, e.g. `POLYGON_MODE_LINE` for
+    /// wireframe pipelines. None by default. Must be set before `run`,
+    /// which fails with [`KreedaError::UnsupportedFeatures`] if the
+    /// adapter lacks any of them.
+    pub fn set_required_features(features: wgpu::Features) {
+        Self::with(|window| window.gpu.required_features = features);
+    }
+
+    /// Limits the device must support, e.g. a larger
+    /// `max_texture_dimension_2d`. [`wgpu::Limits::default`] unless set.
+    /// Must be set before `run`, which fails with
+    /// [`KreedaError::UnsupportedLimit`] if the adapter can't meet them.
+    pub fn set_required_limits(limits: wgpu::Limits) {
+        Self::with(|window| window.gpu.required_limits = limits);
+    }
+
+    /// Graphics APIs the GPU may be driven through, e.g.
+    /// `Backends::VULKAN` to rule out DX12 while debugging. Defaults to
+    /// [`wgpu::Backends::PRIMARY`]. Must be set before `run`.
+    ///
+    /// The `KREEDA_BACKEND` environment variable overrides this with a
+    /// comma separated list such as `vulkan` or `dx12,gl`.
+    pub fn set_backends(backends: wgpu::Backends) {
+        Self::with(|window| window.gpu.backends = backends);
+    }
+
+    /// The format frames are presented in. By default the first sRGB format
+    /// the surface supports. Must be set before `run`, which fails with
+    /// [`KreedaError::UnsupportedSurfaceFormat`] if the surface can't use
+    /// it.
+    pub fn set_surface_format(format: wgpu::TextureFormat) {
+        Self::with(|window| window.gpu.surface_format = Some(format));
+    }
+
+    /// Other formats views of the frame may be created in, e.g. the
+    /// linear `Bgra8Unorm` of a `Bgra8UnormSrgb` surface. Only sRGB-ness
+    /// may differ from the surface format; `run` fails with
+    /// [`KreedaError::IncompatibleViewFormat`] otherwise. None by default.
+    /// Must be set before `run`.
+    pub fn set_surface_view_formats(formats: Vec<wgpu::TextureFormat>) {
+        Self::with(|window| window.gpu.view_formats = formats);
+    }
+
+    /// Which GPU to prefer when there are several: `LowPower` for an
+    /// integrated GPU that saves battery, `HighPerformance` (the default)
+    /// for a discrete one. Must be set before `run`.
+    pub fn set_power_preference(preference: wgpu::PowerPreference) {
+        Self::with(|window| window.gpu.power_preference = preference);
+    }
+
+    /// Calls `callback` whenever the window's size or scale factor changes,
+    /// and once when the window opens. Not called while minimized.
+    pub fn add_resize_callback(callback: fn(ResizeEvent)) {
+        Self::with(|window| window.resize_callbacks.push(callback));
+    }
+
+    /// The most verbose diagnostics logged, overriding `KREEDA_LOG` and
+    /// `RUST_LOG`. Takes a `log::LevelFilter` or `log::Level`, or with the
+    /// `log-tracing` feature a `tracing::Level`. Applies to whichever
+    /// logger is installed, since the engine logs through the `log` facade.
+    pub fn set_log_level(level: impl IntoLevelFilter) {
+        crate::logging::set_level(level.into_level_filter());
+    }
+
+    /// Whether frames are drawn continuously or only when something
+    /// changed. Can be switched at any time.
+    pub fn set_redraw_mode(mode: RedrawMode) {
+        let mut shared = shared().lock().unwrap();
+        shared.redraw_mode = mode;
+        shared.invalidated = true;
+    }
+
+    pub fn redraw_mode() -> RedrawMode {
+        shared().lock().unwrap().redraw_mode
+    }
+
+    /// Asks for another frame in [`RedrawMode::OnDemand`], e.g. after game
+    /// state changed outside an input event. Does nothing in
+    /// `Continuous` mode, which draws every frame anyway.
+    pub fn invalidate() {
+        shared().lock().unwrap().invalidated = true;
+    }
+
+    /// What closing the window does when no
+    /// [`Window::on_close_requested`] handler is set.
+    pub fn set_close_policy(policy: ClosePolicy) {
+        shared().lock().unwrap().close_policy = policy;
+    }
+
+    /// Lets `handler` decide what closing the window does, overriding the
+    /// [`ClosePolicy`]: returning true exits now, false keeps running, e.g.
+    /// to show a "save before quitting?" prompt that later calls
+    /// [`Window::exit`].
+    pub fn on_close_requested(handler: fn() -> bool) {
+        Self::with(|window| window.close_handler = Some(handler));
+    }
+
+    /// Runs `hook` once when the loop ends, however it ends, while the GPU
+    /// and every engine singleton are still alive. Hooks run in the order
+    /// they were added.
+    pub fn add_shutdown_hook(hook: fn()) {
+        Self::with(|window| window.shutdown_hooks.push(hook));
+    }
+
+    /// Closes the window and ends [`Window::run`] after the current frame.
+    pub fn exit() {
+        shared().lock().unwrap().exit_requested = true;
+    }
+
+    /// Makes pressing Escape call [`Window::exit`]. Off by default.
+    pub fn set_quit_on_escape(quit: bool) {
+        shared().lock().unwrap().quit_on_escape = quit;
+    }
+
+    /// Current size of the window's drawable area in device pixels; 0x0
+    /// before the window opens or while minimized.
+    pub fn size() -> PhysicalSize<u32> {
+        shared().lock().unwrap().size
+    }
+
+    /// Width of [`Window::size`].
+    pub fn width() -> u32 {
+        shared().lock().unwrap().size.width
+    }
+
+    /// Height of [`Window::size`].
+    pub fn height() -> u32 {
+        shared().lock().unwrap().size.height
+    }
+
+    /// [`Window::size`] divided by the scale factor.
+    pub fn logical_size() -> LogicalSize<f64> {
+        let shared = shared().lock().unwrap();
+        shared.size.to_logical(shared.scale_factor)
+    }
+
+    /// Device pixels per logical unit, e.g. 2.0 on most high-DPI displays.
+    pub fn scale_factor() -> f64 {
+        shared().lock().unwrap().scale_factor
+    }
+
+    /// The GPU the engine is rendering with: its name, backend, driver
+    /// and whether it's integrated or discrete. `None` until the window
+    /// has opened.
+    pub fn gpu_info() -> Option<wgpu::AdapterInfo> {
+        shared().lock().unwrap().gpu_info.clone()
+    }
+
+    /// The format frames are presented in, for pipelines that draw to the
+    /// window. `None` until the window has opened.
+    pub fn surface_format() -> Option<wgpu::TextureFormat> {
+        shared().lock().unwrap().surface_format
+    }
+
+    /// Seconds since the window opened, not counting time spent paused.
+    /// Shaders see the same value through
+    /// [`GlobalsUniform`](crate::engine::renderer::globals::GlobalsUniform).
+    pub fn elapsed_time() -> f64 {
+        shared().lock().unwrap().elapsed
+    }
+
+    /// Color the frame is cleared to before anything is drawn (default
+    /// white). Can be changed every frame.
+    pub fn set_clear_color(color: impl Into<Color>) {
+        shared().lock().unwrap().clear_color = color.into().into();
+    }
+
+    /// Draws the scene once into each of `viewports`, in order, e.g. for
+    /// split-screen or letterboxing, instead of over the whole window. 3D
+    /// content is drawn through each viewport's camera; 2D content is laid
+    /// out for the size of the first viewport and drawn the same in all of
+    /// them, and text goes over the whole window. Parts outside the window
+    /// are cut off. Can be changed every frame.
+    pub fn set_viewports(viewports: Vec<Viewport>) {
+        shared().lock().unwrap().viewports = viewports;
+    }
+
+    /// Draws over the whole window again.
+    pub fn reset_viewport() {
+        shared().lock().unwrap().viewports.clear();
+    }
+
+    /// The viewports set with [`Window::set_viewports`], empty when drawing
+    /// over the whole window.
+    pub fn viewports() -> Vec<Viewport> {
+        shared().lock().unwrap().viewports.clone()
+    }
+
+    /// Opens another window showing the scene, e.g. a preview next to an
+    /// editor. It draws after the main window each frame, through the
+    /// shared [`Camera3D`] at its own aspect ratio, with the same render
+    /// graph, post-processing and text; viewports and the debug UI stay on
+    /// the main window. Keyboard and mouse input over it counts like input
+    /// over the main window, with the cursor in its own coordinates. Before
+    /// [`Window::run`] it opens along with the main window.
+    pub fn open_secondary(config: WindowConfig) -> WindowId {
+        let mut shared = shared().lock().unwrap();
+        let id = WindowId(shared.next_window_id);
+        shared.next_window_id += 1;
+        shared.open_requests.push((id, config));
+        shared.secondary_windows.push(id);
+        id
+    }
+
+    /// Closes a window opened with [`Window::open_secondary`]. Closing it
+    /// from its title bar does the same.
+    pub fn close(id: WindowId) {
+        let mut shared = shared().lock().unwrap();
+        shared.secondary_windows.retain(|&open| open != id);
+        shared.close_requests.push(id);
+    }
+
+    /// Whether a window opened with [`Window::open_secondary`] is still
+    /// open.
+    pub fn is_open(id: WindowId) -> bool {
+        shared().lock().unwrap().secondary_windows.contains(&id)
+    }
+
+    /// Sets the physics step length in seconds (default 1/60).
+    ///
+    /// # Panics
+    ///
+    /// If `fixed_delta` isn't a positive, finite number of seconds.
+    pub fn set_fixed_delta(fixed_delta: f64) {
+        assert!(
+            fixed_delta > 0.0 && fixed_delta.is_finite(),
+            "fixed_delta must be positive and finite, got {fixed_delta}"
+        );
+        Self::with(|window| window.fixed_delta = fixed_delta);
+    }
+
+    fn init() -> Result<(EventLoop<()>, App), KreedaError> {
+        let event_loop = EventLoop::new()?;
+        let app = App::new(
+            &Self::instance()
+                .read()
+                .expect("Failed to lock the Window instance"),
+        );
+        Ok((event_loop, app))
+    }
+
+    fn r#loop(event_loop: EventLoop<()>, app: &mut App) -> Result<(), KreedaError> {
+        event_loop.run_app(app)?;
+        match app.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
     }
 }
 
@@ -61,36 +871,310 @@ struct App {
     desired_h: u32,
     title: String,
     state: Option<GpuState>,
+    /// Windows opened with [`Window::open_secondary`], drawn with the main
+    /// window's device.
+    secondaries: HashMap<winit::window::WindowId, SecondaryWindow>,
+    timestep: FixedTimestep,
+    update_callbacks: Vec<fn(f64)>,
+    physics_callbacks: Vec<fn(f64)>,
+    resize_callbacks: Vec<fn(ResizeEvent)>,
+    close_handler: Option<fn() -> bool>,
+    shutdown_hooks: Vec<fn()>,
+    last_frame: Instant,
+    gpu: GpuConfig,
+    limiter: FrameRateLimiter,
+    /// Whether the "limiter ignored under VSync" warning was already shown.
+    limiter_warned: bool,
+    /// Why the loop was stopped early, returned from [`Window::run`].
+    error: Option<KreedaError>,
+    /// A frame was drawn since input was last cleared.
+    drew_frame: bool,
+    /// No redraw was requested last iteration, so the next frame shouldn't
+    /// count the idle time as frame time.
+    idle: bool,
+    /// Indexed like [`Shared::monitors`]. Only the event loop can list
+    /// them, so they're refreshed when it's at hand.
+    monitors: Vec<MonitorHandle>,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<GilrsBackend>,
 }
 
 impl App {
-    fn new(w: u32, h: u32, title: String) -> Self {
+    fn new(window: &Window) -> Self {
         Self {
-            desired_w: w,
-            desired_h: h,
-            title,
+            desired_w: window.width,
+            desired_h: window.height,
+            title: window.title.clone(),
             state: None,
+            secondaries: HashMap::new(),
+            timestep: FixedTimestep::new(window.fixed_delta),
+            update_callbacks: window.update_callbacks.clone(),
+            physics_callbacks: window.physics_callbacks.clone(),
+            resize_callbacks: window.resize_callbacks.clone(),
+            close_handler: window.close_handler,
+            shutdown_hooks: window.shutdown_hooks.clone(),
+            last_frame: Instant::now(),
+            gpu: window.gpu.clone(),
+            limiter: FrameRateLimiter::new(0),
+            limiter_warned: false,
+            error: None,
+            drew_frame: false,
+            idle: false,
+            monitors: Vec::new(),
+            #[cfg(feature = "gamepad")]
+            gamepads: GilrsBackend::new(),
         }
     }
 }
 
+impl App {
+    /// Records the window's size and tells the resize callbacks about it.
+    fn notify_resize(&self, size: PhysicalSize<u32>, scale_factor: f64) {
+        {
+            let mut shared = shared().lock().unwrap();
+            shared.size = size;
+            shared.scale_factor = scale_factor;
+        }
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        EventBus::send(WindowResized {
+            width: size.width,
+            height: size.height,
+            scale_factor,
+        });
+        let event = ResizeEvent {
+            physical: size,
+            logical: size.to_logical(scale_factor),
+            scale_factor,
+        };
+        for callback in &self.resize_callbacks {
+            callback(event);
+        }
+    }
+
+    fn refresh_monitors(&mut self, event_loop: &ActiveEventLoop) {
+        let primary = event_loop.primary_monitor();
+        self.monitors = event_loop.available_monitors().collect();
+        let monitors = self
+            .monitors
+            .iter()
+            .map(|handle| MonitorInfo::new(handle, primary.as_ref()))
+            .collect();
+        shared().lock().unwrap().monitors = monitors;
+    }
+
+    /// Applies [`Window::set_monitor`] and then [`Window::set_position`].
+    fn apply_placement(&self, window: &WinitWindow) {
+        let (position, monitor) = {
+            let mut shared = shared().lock().unwrap();
+            (
+                shared.position_request.take(),
+                shared.monitor_request.take(),
+            )
+        };
+        if let Some(index) = monitor {
+            match self.monitors.get(index) {
+                Some(monitor) => {
+                    let (area, size) = (monitor.size(), window.outer_size());
+                    let offset = |area: u32, size: u32| (i64::from(area) - i64::from(size)) / 2;
+                    let origin = monitor.position();
+                    window.set_outer_position(PhysicalPosition::new(
+                        origin.x + offset(area.width, size.width) as i32,
+                        origin.y + offset(area.height, size.height) as i32,
+                    ));
+                }
+                None => log::warn!(
+                    "No monitor {index} to move the window to; {} available.",
+                    self.monitors.len()
+                ),
+            }
+        }
+        if let Some(position) = position {
+            window.set_outer_position(position);
+        }
+    }
+
+    /// Applies [`Window::minimize`], [`Window::maximize`] and
+    /// [`Window::restore`].
+    fn apply_state_request(window: &WinitWindow) {
+        let request = shared().lock().unwrap().state_request.take();
+        match request {
+            Some(StateRequest::Minimize) => window.set_minimized(true),
+            Some(StateRequest::Maximize) => window.set_maximized(true),
+            Some(StateRequest::Restore) => {
+                window.set_minimized(false);
+                window.set_maximized(false);
+            }
+            None => return,
+        }
+        window.request_redraw();
+    }
+
+    /// Applies [`Window::open_secondary`] and [`Window::close`]. Windows
+    /// that can't be opened are logged and dropped.
+    fn apply_window_requests(&mut self, event_loop: &ActiveEventLoop) {
+        // Kept until the main window is up to share its device
+        if self.state.is_none() {
+            return;
+        }
+        let (opens, closes) = {
+            let mut shared = shared().lock().unwrap();
+            (
+                std::mem::take(&mut shared.open_requests),
+                std::mem::take(&mut shared.close_requests),
+            )
+        };
+        self.secondaries
+            .retain(|_, secondary| !closes.contains(&secondary.id));
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        for (id, config) in opens {
+            // Closed again before it could open
+            if closes.contains(&id) {
+                continue;
+            }
+            match SecondaryWindow::new(event_loop, state, id, &config) {
+                Ok(secondary) => {
+                    self.secondaries.insert(secondary.window.id(), secondary);
+                }
+                Err(e) => {
+                    log::error!("Failed to open window \"{}\": {e}", config.title);
+                    let mut shared = shared().lock().unwrap();
+                    shared.secondary_windows.retain(|&open| open != id);
+                }
+            }
+        }
+    }
+
+    /// Forgets a secondary window, closing it.
+    fn remove_secondary(&mut self, window_id: winit::window::WindowId) {
+        if let Some(SecondaryWindow { id, .. }) = self.secondaries.remove(&window_id) {
+            let mut shared = shared().lock().unwrap();
+            shared.secondary_windows.retain(|&open| open != id);
+        }
+    }
+
+    /// Feeds a window event to the input listeners and the recorder.
+    fn handle_input(event: &WindowEvent) {
+        InputRecorder::record(event);
+        // A replay stands in for the real input
+        if !InputPlayer::is_playing() {
+            mouse::handle_event(event);
+            key::handle_event(event);
+        }
+    }
+
+    /// Handles an event for a secondary window. Its input goes to the same
+    /// listeners as the main window's; the rest of the frame is driven by
+    /// the main window, so only closing and resizing matter here.
+    fn secondary_window_event(&mut self, window_id: winit::window::WindowId, event: WindowEvent) {
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            Window::invalidate();
+        }
+        Self::handle_input(&event);
+
+        let (Some(state), Some(secondary)) =
+            (self.state.as_ref(), self.secondaries.get_mut(&window_id))
+        else {
+            return;
+        };
+        match event {
+            WindowEvent::CloseRequested | WindowEvent::Destroyed => {
+                self.remove_secondary(window_id);
+            }
+            WindowEvent::Resized(new_size) => {
+                secondary.resize(state, new_size);
+            }
+            // Same as for the main window: keep the logical size
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                mut inner_size_writer,
+            } => {
+                let size = PhysicalSize::new(secondary.config.width, secondary.config.height);
+                let new_size = rescale(size, secondary.scale_factor, scale_factor);
+                if inner_size_writer.request_inner_size(new_size).is_err() {
+                    log::warn!("Window ignored the size change for the new scale factor.");
+                }
+                secondary.scale_factor = scale_factor;
+                secondary.resize(state, new_size);
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens the window and sets up the GPU for it.
+    fn create_window(&mut self, event_loop: &ActiveEventLoop) -> Result<(), KreedaError> {
+        let attrs = {
+            let mut shared = shared().lock().unwrap();
+            shared.icon_dirty = false;
+            WinitWindow::default_attributes()
+                .with_title(self.title.clone())
+                .with_inner_size(winit::dpi::LogicalSize::new(
+                    f64::from(self.desired_w),
+                    f64::from(self.desired_h),
+                ))
+                .with_window_icon(shared.icon.clone())
+        };
+        let window = Arc::new(event_loop.create_window(attrs)?);
+        let size = window.inner_size();
+        log::info!("Created window {}x{}", size.width, size.height);
+        self.refresh_monitors(event_loop);
+        self.apply_placement(&window);
+        Self::apply_state_request(&window);
+        shared().lock().unwrap().maximized = window.is_maximized();
+
+        let state = pollster::block_on(GpuState::new_from_window(window.clone(), &self.gpu))?;
+        {
+            let mut shared = shared().lock().unwrap();
+            shared.gpu_info = Some(state.adapter_info().clone());
+            shared.surface_format = Some(state.config.format);
+        }
+        self.state = Some(state);
+        self.last_frame = Instant::now();
+        self.notify_resize(window.inner_size(), window.scale_factor());
+        Ok(())
+    }
+}
+
+/// Whether a window of `size` that the platform reports as `minimized`
+/// has anything to draw to. Minimizing reports a 0x0 size on most
+/// platforms, but X11 and Windows can keep the old size, so both count.
+fn is_drawable(size: PhysicalSize<u32>, minimized: Option<bool>) -> bool {
+    size.width > 0 && size.height > 0 && minimized != Some(true)
+}
+
+/// `config` resized to `size`, or `None` for a zero-sized window, whose
+/// surface is left configured as it was until the window is restored.
+fn resized_config(
+    config: &SurfaceConfiguration,
+    size: PhysicalSize<u32>,
+) -> Option<SurfaceConfiguration> {
+    if size.width == 0 || size.height == 0 {
+        return None;
+    }
+    Some(SurfaceConfiguration {
+        width: size.width,
+        height: size.height,
+        ..config.clone()
+    })
+}
+
+/// `size` at `old_scale` converted to the same logical size at `new_scale`.
+fn rescale(size: PhysicalSize<u32>, old_scale: f64, new_scale: f64) -> PhysicalSize<u32> {
+    size.to_logical::<f64>(old_scale).to_physical(new_scale)
+}
+
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        // Create the window (winit 0.30)
-        let attrs = WinitWindow::default_attributes()
-            .with_title(self.title.clone())
-            .with_inner_size(winit::dpi::LogicalSize::new(
-                f64::from(self.desired_w),
-                f64::from(self.desired_h),
-            ));
-        let window = event_loop
-            .create_window(attrs)
-            .expect("create_window failed");
-
-        let window = Arc::new(window);
-
-        let state = pollster::block_on(GpuState::new_from_window(window.clone()));
-        self.state = Some(state);
+        if let Err(e) = self.create_window(event_loop) {
+            self.error = Some(e);
+            event_loop.exit();
+            return;
+        }
+        // Secondary windows opened before run
+        self.apply_window_requests(event_loop);
     }
 
     fn window_event(
@@ -99,175 +1183,762 @@ impl ApplicationHandler for App {
         window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        if self.secondaries.contains_key(&window_id) {
+            self.secondary_window_event(window_id, event);
+            return;
+        }
         let Some(state) = self.state.as_mut() else {
             return;
         };
         if window_id != state.window.id() {
             return;
         }
-        
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            Window::invalidate();
+        }
+
+        // The debug UI sees input first, so it can capture what follows
+        #[cfg(feature = "egui")]
+        ui::handle_event(&event, state.window.scale_factor());
+
         //Initialize input handling
-        mouse::handle_event(&event);
-        key::handle_event(&event);
+        Self::handle_input(&event);
 
         match event {
             WindowEvent::CloseRequested => {
-                event_loop.exit();
+                EventBus::send(CloseRequested);
+                let exit = match self.close_handler {
+                    Some(handler) => handler(),
+                    None => shared().lock().unwrap().close_policy == ClosePolicy::Immediate,
+                };
+                if exit {
+                    event_loop.exit();
+                }
+            }
+            WindowEvent::DroppedFile(path) => {
+                EventBus::send(FileDropped(path));
+            }
+            WindowEvent::Focused(focused) => {
+                log::debug!("Window focused: {focused}");
+                EventBus::send(FocusChanged(focused));
+                shared().lock().unwrap().focused = focused;
+                if focused {
+                    // Don't count the time spent unfocused as frame time
+                    self.last_frame = Instant::now();
+                }
             }
             WindowEvent::Resized(new_size) => {
+                let minimized = !is_drawable(new_size, state.window.is_minimized());
+                log::debug!("Window resized to {}x{}", new_size.width, new_size.height);
+                let was_minimized = {
+                    let mut shared = shared().lock().unwrap();
+                    shared.maximized = state.window.is_maximized();
+                    std::mem::replace(&mut shared.minimized, minimized)
+                };
+                if was_minimized && !minimized {
+                    self.last_frame = Instant::now();
+                }
+
+                // Configures the surface again on restore, even when the
+                // size didn't change
                 state.resize(new_size);
                 state.window.request_redraw();
+                let scale_factor = state.window.scale_factor();
+                self.notify_resize(new_size, scale_factor);
+            }
+            WindowEvent::Occluded(occluded) => {
+                log::debug!("Window occluded: {occluded}");
+                shared().lock().unwrap().occluded = occluded;
+                if !occluded {
+                    self.last_frame = Instant::now();
+                    state.window.request_redraw();
+                }
             }
-            // In winit 0.30: ScaleFactorChanged has { scale_factor, inner_size_writer }
-            // We can ignore the writer and query the window size ourselves,
-            // or use the writer to set a custom size. Here we just reconfigure using current size.
-            WindowEvent::ScaleFactorChanged { .. } => {
-                let new_size = state.window.inner_size();
+            // Keep the same logical size at the new scale. The size has to go
+            // through the writer: inner_size() can still report the old one
+            // while this event is being handled.
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                mut inner_size_writer,
+            } => {
+                let old_scale = shared().lock().unwrap().scale_factor;
+                log::debug!("Scale factor changed from {old_scale} to {scale_factor}");
+                let new_size = rescale(state.size, old_scale, scale_factor);
+                if inner_size_writer.request_inner_size(new_size).is_err() {
+                    log::warn!("Window ignored the size change for the new scale factor.");
+                }
                 state.resize(new_size);
                 state.window.request_redraw();
+                self.notify_resize(new_size, scale_factor);
             }
             // Redraw is now a *window* event
             WindowEvent::RedrawRequested => {
+                if shared().lock().unwrap().paused() {
+                    return;
+                }
+                self.drew_frame = true;
+
+                let now = Instant::now();
+                let frame_delta = now
+                    .duration_since(self.last_frame)
+                    .as_secs_f64()
+                    .min(MAX_FRAME_DELTA);
+                self.last_frame = now;
+                shared().lock().unwrap().elapsed += frame_delta;
+                log::trace!("Frame delta {:.2} ms", frame_delta * 1000.0);
+
+                let screenshot_key = shared().lock().unwrap().screenshot_key.clone();
+                if screenshot_key.is_some_and(|k| key::key_just_pressed(&k)) {
+                    let millis = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_millis());
+                    let path = format!("screenshot-{millis}.png");
+                    Window::save_screenshot(path.clone(), move |result| match result {
+                        Ok(()) => log::info!("Saved screenshot {path}"),
+                        Err(e) => log::error!("Screenshot {path} failed: {e}"),
+                    });
+                }
+                let debug_view_key = shared().lock().unwrap().debug_view_key.clone();
+                if debug_view_key.is_some_and(|k| key::key_just_pressed(&k)) {
+                    let view = mesh_renderer::debug_view().next();
+                    log::info!("Debug view: {view:?}");
+                    mesh_renderer::set_debug_view(view);
+                }
+
+                // Events from the previous frame become readable now
+                EventBus::update();
+
+                #[cfg(feature = "egui")]
+                ui::begin_frame(state.size, state.window.scale_factor());
+
+                Timers::tick(frame_delta);
+                TweenManager::tick(frame_delta);
+                FlyController::tick(frame_delta);
+                for callback in &self.update_callbacks {
+                    callback(frame_delta);
+                }
+
+                // Physics runs at a fixed rate, independent of the frame rate
+                for dt in self.timestep.tick(frame_delta) {
+                    for callback in &self.physics_callbacks {
+                        callback(dt);
+                    }
+                }
+                // Collisions are found once everything has moved
+                SceneManager::with_world(|world| Physics2D::detect(world));
+
                 if let Err(e) = state.render() {
                     match e {
                         SurfaceError::Lost | SurfaceError::Outdated => {
-                            eprintln!("Surface error ({e:?}), reconfiguring surface.");
+                            log::warn!("Surface error ({e:?}), reconfiguring surface.");
                             let size = state.window.inner_size();
                             state.resize(size); // ← triggers reconfigure()
                         }
                         SurfaceError::OutOfMemory => {
-                            eprintln!("Surface out of memory, exiting.");
+                            log::error!("Surface out of memory, exiting.");
                             event_loop.exit();
                         }
                         SurfaceError::Timeout => {
-                            eprintln!("Surface timeout, skipping this frame.");
+                            log::warn!("Surface timeout, skipping this frame.");
                             // You might want to skip this frame, but not exit
                         }
                     }
 
                 }
+                for secondary in self.secondaries.values_mut() {
+                    match state.render_secondary(secondary) {
+                        Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                            secondary.reconfigure(state);
+                        }
+                        Err(e) => log::warn!("Skipping a frame of a secondary window: {e}"),
+                        Ok(()) => {}
+                    }
+                }
             }
             _ => {}
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        // Raw input feeds its own accumulators, so it never double counts
+        // with the window events above
+        mouse::handle_device_event(&event);
+        key::handle_device_event(&event);
+        // Raw mouse motion keeps coming while the cursor is elsewhere
+        if shared().lock().unwrap().focused {
+            Window::invalidate();
+        }
+
+        match event {
+            DeviceEvent::Added => shared().lock().unwrap().device_added = true,
+            DeviceEvent::Removed => shared().lock().unwrap().device_removed = true,
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Checked before the input is cleared below, and even while paused
+        let escape = Key::Named(NamedKey::Escape);
+        if shared().lock().unwrap().quit_on_escape && key::key_just_pressed(&escape) {
+            Window::exit();
+        }
+        if shared().lock().unwrap().exit_requested {
+            event_loop.exit();
+            return;
+        }
+        // Monitors come and go, so list them again before picking one
+        if self.state.is_some() && shared().lock().unwrap().monitor_request.is_some() {
+            self.refresh_monitors(event_loop);
+        }
+        self.apply_window_requests(event_loop);
         if let Some(state) = self.state.as_ref() {
-            state.window.request_redraw();
+            self.apply_placement(&state.window);
+            Self::apply_state_request(&state.window);
+            let mut shared = shared().lock().unwrap();
+            if shared.icon_dirty {
+                state.window.set_window_icon(shared.icon.clone());
+                shared.icon_dirty = false;
+            }
+            let paused = shared.paused();
+            let target_fps = shared.target_fps;
+            let redraw = match shared.redraw_mode {
+                RedrawMode::Continuous => true,
+                RedrawMode::OnDemand => std::mem::take(&mut shared.invalidated),
+            };
+            drop(shared);
+
+            // While paused, minimized or idle the loop sleeps until the next
+            // event
+            if !paused && redraw {
+                if std::mem::take(&mut self.idle) {
+                    self.last_frame = Instant::now();
+                }
+                if state.config.present_mode == wgpu::PresentMode::Fifo && target_fps > 0 {
+                    if !self.limiter_warned {
+                        log::warn!(
+                            "Frame rate limit of {target_fps} FPS ignored: VSync (PresentMode::Fifo) already paces frames."
+                        );
+                        self.limiter_warned = true;
+                    }
+                } else {
+                    self.limiter.set_target_fps(target_fps);
+                    self.limiter.wait();
+                }
+                PerformanceMetrics::get().record_frame();
+                state.window.request_redraw();
+            } else {
+                self.idle = true;
+                PerformanceMetrics::get().pause();
+            }
         }
 
-        // End of frame for input handling
-        mouse::end_frame();
-        key::end_frame();
+        DebugDraw::flush();
+        // End of frame for input handling. On demand, input waits for the
+        // frame it asked for instead of being cleared before it's seen.
+        let continuous = shared().lock().unwrap().redraw_mode == RedrawMode::Continuous;
+        if std::mem::take(&mut self.drew_frame) || continuous {
+            {
+                let mut shared = shared().lock().unwrap();
+                shared.device_added = false;
+                shared.device_removed = false;
+            }
+            mouse::end_frame();
+            key::end_frame();
+            gamepad::end_frame();
+            InputRecorder::end_frame();
+            InputPlayer::end_frame();
+        }
+
+        // Gamepad events land at the start of the next frame
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepads) = self.gamepads.as_mut() {
+            gamepads.poll();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        for hook in &self.shutdown_hooks {
+            hook();
+        }
+        // Release the GPU now rather than whenever the event loop drops us
+        self.secondaries.clear();
+        self.state = None;
     }
 }
 
 struct GpuState {
+    /// Kept for creating the surfaces of secondary windows.
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     surface: Surface<'static>, // now valid because window is 'static
     window: Arc<WinitWindow>,  // leaked window ref
     size: PhysicalSize<u32>,
-    device: Device,
-    queue: Queue,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
     config: SurfaceConfiguration,
     clear_color: wgpu::Color,
+    depth_view: wgpu::TextureView,
+    camera: &'static CameraUniform,
+    globals: &'static GlobalsUniform,
+    lighting: &'static LightingBuffer,
+    meshes: MeshRenderer,
+    /// The active skybox as of this frame.
+    skybox: Option<Arc<SkyboxRenderer>>,
+    /// `None` while no effects are registered, so frames go straight to
+    /// the surface.
+    post: Option<PostProcessChain>,
+    post_version: u64,
+    graph: Option<RenderGraph>,
+    graph_version: u64,
+    tilemaps: TilemapRenderer,
+    sprites: SpriteRenderer,
+    debug: DebugRenderer,
+    text: TextRenderer,
+    #[cfg(feature = "egui")]
+    ui: UiRenderer,
+    adapter_info: wgpu::AdapterInfo,
 }
 
 impl GpuState {
-    async fn new_from_window(window: Arc<WinitWindow>) -> Self {
+    async fn new_from_window(
+        window: Arc<WinitWindow>,
+        config: &GpuConfig,
+    ) -> Result<Self, KreedaError> {
+        let backends = match std::env::var("KREEDA_BACKEND") {
+            Ok(list) => match wgpu::util::parse_backends_from_comma_list(&list) {
+                backends if backends.is_empty() => {
+                    log::warn!("Ignoring KREEDA_BACKEND={list}, which names no known backend");
+                    config.backends
+                }
+                backends => {
+                    log::info!("KREEDA_BACKEND={list} overrides the backends with {backends:?}");
+                    backends
+                }
+            },
+            Err(_) => config.backends,
+        };
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
+            backends,
             dx12_shader_compiler: Dx12Compiler::default(),
             flags: wgpu::InstanceFlags::empty(),
             gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
         });
 
         // On native, it's safe to create a Surface from a &'static Window.
-        let surface = instance
-            .create_surface(window.clone()) // &WinitWindow
-            .expect("create_surface failed");
+        let surface = instance.create_surface(window.clone())?;
 
         // Adapter
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+                power_preference: config.power_preference,
                 compatible_surface: Some(&surface),
                 force_fallback_adapter: false,
             })
             .await
-            .expect("No suitable GPU adapters found");
+            .ok_or(KreedaError::NoAdapter)?;
+        let info = adapter.get_info();
+        log::info!(
+            "Rendering with {} ({:?}, {:?} GPU, asked for {:?})",
+            info.name,
+            info.backend,
+            info.device_type,
+            config.power_preference
+        );
+        log::info!("GPU driver: {} {}", info.driver, info.driver_info);
+
+        // Check up front so a missing feature is reported by name rather
+        // than as a generic device error
+        let missing = config.required_features - adapter.features();
+        if !missing.is_empty() {
+            return Err(KreedaError::UnsupportedFeatures(missing));
+        }
+        let mut unsupported = None;
+        config.required_limits.check_limits_with_fail_fn(
+            &adapter.limits(),
+            true,
+            |name, requested, allowed| unsupported = Some((name, requested, allowed)),
+        );
+        if let Some((name, requested, allowed)) = unsupported {
+            return Err(KreedaError::UnsupportedLimit {
+                name,
+                requested,
+                allowed,
+            });
+        }
 
         // Device + queue
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
+                    // Wireframe debug views need this, and it costs nothing
+                    required_features: config.required_features
+                        | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+                    required_limits: config.required_limits.clone(),
                 },
                 None,
             )
-            .await
-            .expect("request_device failed");
+            .await?;
+        let (device, queue) = (Arc::new(device), Arc::new(queue));
+        GpuContext::init(device.clone(), queue.clone());
 
         // Swapchain config (VSync = FIFO)
         let size = window.inner_size();
         let caps = surface.get_capabilities(&adapter);
-        let format = caps
-            .formats
+        let format = match config.surface_format {
+            Some(format) if !caps.formats.contains(&format) => {
+                return Err(KreedaError::UnsupportedSurfaceFormat {
+                    requested: format,
+                    supported: caps.formats,
+                });
+            }
+            Some(format) => format,
+            None => caps
+                .formats
+                .iter()
+                .copied()
+                .find(wgpu::TextureFormat::is_srgb)
+                .unwrap_or_else(|| {
+                    log::warn!(
+                        "No sRGB surface format available, falling back to {:?}; colors will look too dark.",
+                        caps.formats[0]
+                    );
+                    caps.formats[0]
+                }),
+        };
+        if let Some(&view) = config
+            .view_formats
             .iter()
-            .copied()
-            .find(wgpu::TextureFormat::is_srgb)
-            .unwrap_or(caps.formats[0]);
+            .find(|view| view.remove_srgb_suffix() != format.remove_srgb_suffix())
+        {
+            return Err(KreedaError::IncompatibleViewFormat {
+                surface: format,
+                view,
+            });
+        }
+
+        // FIFO is VSync and always supported; otherwise prefer tear-free mailbox
+        let present_mode = [wgpu::PresentMode::Mailbox, wgpu::PresentMode::Immediate]
+            .into_iter()
+            .find(|mode| !config.vsync && caps.present_modes.contains(mode))
+            .unwrap_or_else(|| {
+                if !config.vsync {
+                    log::warn!("VSync can't be turned off on this surface, using FIFO");
+                }
+                wgpu::PresentMode::Fifo
+            });
 
         let config = SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format,
             width: size.width.max(1),
             height: size.height.max(1),
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode,
             alpha_mode: caps.alpha_modes[0],
-            view_formats: vec![],
+            view_formats: config.view_formats.clone(),
             desired_maximum_frame_latency: 1,
         };
         surface.configure(&device, &config);
+        log::info!(
+            "Surface configured as {:?} with {:?} presentation",
+            config.format,
+            config.present_mode
+        );
 
-        let clear_color = wgpu::Color {
-            r: 1.0,
-            g: 1.0,
-            b: 1.0,
-            a: 1.0,
-        };
+        let depth_view = create_depth_view(&device, config.width, config.height);
+        Camera3D::get_instance()
+            .lock()
+            .unwrap()
+            .set_viewport(config.width, config.height);
+        let camera = CameraUniform::init(&device);
+        let globals = GlobalsUniform::init(&device);
+        let lighting = LightingBuffer::init(&device);
+        let meshes = MeshRenderer::new(&device, config.format, camera, lighting);
+        let tilemaps = TilemapRenderer::new(&device, config.format);
+        let sprites = SpriteRenderer::new(&device, config.format);
+        let debug = DebugRenderer::new(&device, config.format);
+        let text = TextRenderer::new(&device, &queue, config.format);
+        #[cfg(feature = "egui")]
+        let ui = UiRenderer::new(&device, config.format);
 
-        Self {
+        Ok(Self {
+            instance,
+            adapter,
             surface,
             window,
             size,
             device,
             queue,
             config,
-            clear_color,
-        }
+            clear_color: wgpu::Color::WHITE,
+            depth_view,
+            camera,
+            globals,
+            lighting,
+            meshes,
+            skybox: None,
+            post: None,
+            post_version: 0,
+            graph: None,
+            graph_version: 0,
+            tilemaps,
+            sprites,
+            debug,
+            text,
+            #[cfg(feature = "egui")]
+            ui,
+            adapter_info: info,
+        })
     }
 
-    fn resize(&mut self, new_size: PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-        }
+    fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
     }
 
-    fn reconfigure(&mut self) {
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        let Some(config) = resized_config(&self.config, new_size) else {
+            return;
+        };
+        self.size = new_size;
+        self.config = config;
         self.surface.configure(&self.device, &self.config);
+        self.depth_view = create_depth_view(&self.device, new_size.width, new_size.height);
+        Camera3D::get_instance()
+            .lock()
+            .unwrap()
+            .set_viewport(new_size.width, new_size.height);
+        if let Some(post) = &mut self.post {
+            post.resize(&self.device, new_size.width, new_size.height);
+        }
+        if let Some(graph) = &mut self.graph {
+            graph.resize(&self.device, new_size.width, new_size.height);
+        }
     }
 
     fn render(&mut self) -> Result<(), SurfaceError> {
+        // A minimized window has no surface to draw to
+        if !is_drawable(self.window.inner_size(), self.window.is_minimized()) {
+            return Ok(());
+        }
         let frame = self.surface.get_current_texture()?;
         let view = frame
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        let screen_size = [self.config.width as f32, self.config.height as f32];
+        let (elapsed, clear_color) = {
+            let shared = shared().lock().unwrap();
+            (shared.elapsed, shared.clear_color)
+        };
+        self.clear_color = clear_color;
+        self.globals.write(&self.queue, elapsed, screen_size);
+        self.camera
+            .write(&self.queue, &Camera3D::get_instance().lock().unwrap());
+        self.lighting
+            .write(&self.queue, &LightingUniform::get_instance().lock().unwrap());
+        self.meshes.prepare(&self.device, &self.queue);
+        self.skybox = SkyboxRenderer::active();
+        if let Some(skybox) = &self.skybox {
+            skybox.prepare(&self.device, &self.queue, self.config.format);
+        }
+        // 2D content is laid out once, for the first viewport
+        let scene_size = match self.viewports().as_deref() {
+            Some([first, ..]) => Vec2::new(first.width as f32, first.height as f32),
+            _ => screen_size.into(),
+        };
+        self.tilemaps.prepare(&self.device, &self.queue, scene_size);
+        self.sprites.prepare(&self.device, &self.queue, scene_size);
+        self.debug.prepare(&self.device, &self.queue, scene_size);
+        // Laid out once, as that takes the frame's text; screenshots draw
+        // the same text again
+        self.text.prepare(&self.device, &self.queue, screen_size);
+        self.update_post_process();
+        self.update_render_graph();
+
+        self.draw_frame(&view, self.viewports());
+
+        let (requests, captures) = {
+            let mut shared = shared().lock().unwrap();
+            (
+                std::mem::take(&mut shared.screenshot_requests),
+                std::mem::take(&mut shared.capture_requests),
+            )
+        };
+        for (path, callback) in requests {
+            callback(self.save_frame(&path));
+        }
+        for callback in captures {
+            callback(self.capture_frame());
+        }
+
+        // The debug UI goes over the finished frame, after any screenshot
+        #[cfg(feature = "egui")]
+        {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Debug UI Encoder"),
+                });
+            self.ui
+                .draw(&self.device, &self.queue, &mut encoder, &view, self.size);
+            self.queue.submit(iter::once(encoder.finish()));
+        }
+
+        frame.present();
+        Ok(())
+    }
+
+    /// Draws the scene again onto a secondary window, through the shared
+    /// camera at that window's aspect ratio, with the same render graph,
+    /// post-processing and text. Call after [`GpuState::render`], which
+    /// prepares the frame.
+    fn render_secondary(&mut self, secondary: &mut SecondaryWindow) -> Result<(), SurfaceError> {
+        if !is_drawable(secondary.window.inner_size(), secondary.window.is_minimized()) {
+            return Ok(());
+        }
+        let frame = secondary.surface.get_current_texture()?;
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let (width, height) = (secondary.config.width, secondary.config.height);
+        let mut camera = *Camera3D::get_instance().lock().unwrap();
+        camera.set_viewport(width, height);
+        self.camera.write(&self.queue, &camera);
+        self.text.set_screen_size(&self.queue, [width as f32, height as f32]);
+
+        secondary.swap_targets(self);
+        self.update_post_process();
+        self.update_render_graph();
+        self.draw_frame(&view, None);
+        secondary.swap_targets(self);
+
+        frame.present();
+        Ok(())
+    }
+
+    /// Picks up effects added or removed since the last frame.
+    fn update_post_process(&mut self) {
+        let Some((version, effects)) = post_process::effects_since(self.post_version) else {
+            return;
+        };
+        self.post_version = version;
+        if effects.is_empty() {
+            self.post = None;
+            return;
+        }
+        let post = self.post.get_or_insert_with(|| {
+            PostProcessChain::new(
+                &self.device,
+                self.config.width,
+                self.config.height,
+                self.config.format,
+            )
+        });
+        post.set_effects(effects);
+    }
+
+    /// Builds the render graph again if a new one was set.
+    fn update_render_graph(&mut self) {
+        let Some((version, build)) = render_graph::graph_since(self.graph_version) else {
+            return;
+        };
+        self.graph_version = version;
+        self.graph = build.and_then(|build| {
+            let mut graph = RenderGraph::new(
+                &self.device,
+                self.config.width,
+                self.config.height,
+                self.config.format,
+            );
+            build(&self.device, &mut graph);
+            match graph.compile() {
+                Ok(()) => Some(graph),
+                Err(e) => {
+                    log::error!("Render graph not used: {e}");
+                    None
+                }
+            }
+        });
+    }
+
+    /// Draws the scene onto `view`, split into `viewports` if given,
+    /// through the render graph and then the post-processing effects if
+    /// there are any.
+    fn draw_frame(&mut self, view: &wgpu::TextureView, viewports: Option<Vec<Viewport>>) {
+        if self.post.is_none() && self.graph.is_none() {
+            self.draw_scene(view, viewports);
+            return;
+        }
+        let post = self.post.take();
+        let mut graph = self.graph.take();
+        let target = post.as_ref().map_or(view, |post| post.input().texture_view());
+        match &graph {
+            Some(graph) => self.draw_scene(graph.scene().texture_view(), viewports),
+            None => self.draw_scene(target, viewports),
+        }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Post Process Encoder"),
+            });
+        if let Some(graph) = &mut graph {
+            // Compiled when it was built, so it can't fail here
+            let _ = graph.execute(&self.device, &self.queue, &mut encoder, target);
+        }
+        if let Some(post) = &post {
+            post.run(&self.device, &mut encoder, view);
+        }
+        self.queue.submit(iter::once(encoder.finish()));
+        self.post = post;
+        self.graph = graph;
+    }
+
+    /// The viewports set with [`Window::set_viewports`] cut to the surface,
+    /// or `None` to draw over all of it, as when none is on it.
+    fn viewports(&self) -> Option<Vec<Viewport>> {
+        let (width, height) = (self.config.width, self.config.height);
+        let viewports: Vec<_> = shared()
+            .lock()
+            .unwrap()
+            .viewports
+            .iter()
+            .filter_map(|v| v.clipped(width, height))
+            .collect();
+        (!viewports.is_empty()).then_some(viewports)
+    }
+
+    /// Draws everything prepared for this frame onto `view`, once per
+    /// viewport if there are any.
+    fn draw_scene(&mut self, view: &wgpu::TextureView, viewports: Option<Vec<Viewport>>) {
+        let passes = match viewports {
+            Some(viewports) => viewports.into_iter().map(Some).collect(),
+            None => vec![None],
+        };
+        let shared_camera = *Camera3D::get_instance().lock().unwrap();
+        for (i, viewport) in passes.iter().enumerate() {
+            if let Some(viewport) = viewport {
+                let mut camera = viewport.camera.unwrap_or(shared_camera);
+                camera.set_viewport(viewport.width, viewport.height);
+                // Lands between the previous pass's submission and this one's
+                self.camera.write(&self.queue, &camera);
+            }
+            self.draw_pass(view, viewport.as_ref(), i == 0);
+        }
+
+        // Text gets its own pass on top of everything else
+        self.text.render(&self.device, &self.queue, view);
+    }
+
+    /// Draws the scene onto `view`, limited to `viewport` if given.
+    /// Clearing only in the first pass keeps earlier viewports' pixels.
+    fn draw_pass(&mut self, view: &wgpu::TextureView, viewport: Option<&Viewport>, clear: bool) {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -275,25 +1946,191 @@ impl GpuState {
             });
 
         {
-            let _rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            let load = if clear {
+                wgpu::LoadOp::Clear(self.clear_color)
+            } else {
+                wgpu::LoadOp::Load
+            };
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Clear Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        load,
                         store: wgpu::StoreOp::Store,
                     },
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(DEPTH_CLEAR),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
+            if let Some(v) = viewport {
+                let (width, height) = (v.width as f32, v.height as f32);
+                rpass.set_viewport(v.x as f32, v.y as f32, width, height, v.min_depth, v.max_depth);
+                rpass.set_scissor_rect(v.x, v.y, v.width, v.height);
+            }
+
+            self.meshes.draw(&mut rpass, self.camera, self.lighting);
+            // After the meshes, so the depth test leaves only empty pixels
+            if let Some(skybox) = &self.skybox {
+                skybox.draw(&mut rpass);
+            }
+            self.tilemaps.draw(&mut rpass);
+            self.sprites.draw(&mut rpass);
+
+            // Debug lines are drawn last so they overlay the scene
+            self.debug.draw(&mut rpass);
         }
 
         self.queue.submit(iter::once(encoder.finish()));
-        frame.present();
-        Ok(())
+    }
+
+    /// Draws the current frame again into an offscreen texture. Going
+    /// offscreen means captures work even where the surface can't be
+    /// copied from.
+    fn draw_offscreen(&mut self) -> RenderTarget {
+        let target = RenderTarget::new(
+            &self.device,
+            self.config.width,
+            self.config.height,
+            self.config.format,
+        );
+        self.draw_frame(target.texture_view(), self.viewports());
+        target
+    }
+
+    /// Saves the current frame as a PNG at `path`. Blocks until the GPU is
+    /// done.
+    fn save_frame(&mut self, path: &Path) -> Result<(), ScreenshotError> {
+        let target = self.draw_offscreen();
+        save_texture_png(&self.device, &self.queue, target.texture(), path)
+    }
+
+    /// The current frame's pixels. Blocks until the GPU is done.
+    fn capture_frame(&mut self) -> Result<RgbaImage, ScreenshotError> {
+        let target = self.draw_offscreen();
+        read_texture_rgba(&self.device, &self.queue, target.texture())
+    }
+}
+
+/// A window opened with [`Window::open_secondary`]. It's drawn with the
+/// main window's device, as everything the engine uploads lives there, but
+/// keeps its own targets at its own size.
+struct SecondaryWindow {
+    id: WindowId,
+    // Declared before the window so it's dropped first
+    surface: Surface<'static>,
+    window: Arc<WinitWindow>,
+    config: SurfaceConfiguration,
+    scale_factor: f64,
+    depth_view: wgpu::TextureView,
+    post: Option<PostProcessChain>,
+    post_version: u64,
+    graph: Option<RenderGraph>,
+    graph_version: u64,
+}
+
+impl SecondaryWindow {
+    fn new(
+        event_loop: &ActiveEventLoop,
+        state: &GpuState,
+        id: WindowId,
+        config: &WindowConfig,
+    ) -> Result<Self, KreedaError> {
+        let attrs = WinitWindow::default_attributes()
+            .with_title(config.title.clone())
+            .with_inner_size(LogicalSize::new(
+                f64::from(config.width),
+                f64::from(config.height),
+            ))
+            .with_window_icon(shared().lock().unwrap().icon.clone());
+        let window = Arc::new(event_loop.create_window(attrs)?);
+        let surface = state.instance.create_surface(window.clone())?;
+
+        // The pipelines are built for the main surface's format
+        let caps = surface.get_capabilities(&state.adapter);
+        let format = state.config.format;
+        if !caps.formats.contains(&format) {
+            return Err(KreedaError::UnsupportedSurfaceFormat {
+                requested: format,
+                supported: caps.formats,
+            });
+        }
+        // The main window paces the frames, so don't wait twice for VSync
+        let present_mode = [wgpu::PresentMode::Mailbox, wgpu::PresentMode::Immediate]
+            .into_iter()
+            .find(|mode| caps.present_modes.contains(mode))
+            .unwrap_or(wgpu::PresentMode::Fifo);
+        let size = window.inner_size();
+        let surface_config = SurfaceConfiguration {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode,
+            alpha_mode: caps.alpha_modes[0],
+            ..state.config.clone()
+        };
+        surface.configure(&state.device, &surface_config);
+        let depth_view =
+            create_depth_view(&state.device, surface_config.width, surface_config.height);
+        log::info!(
+            "Opened window \"{}\" at {}x{}",
+            config.title,
+            size.width,
+            size.height
+        );
+
+        Ok(Self {
+            id,
+            surface,
+            scale_factor: window.scale_factor(),
+            window,
+            config: surface_config,
+            depth_view,
+            post: None,
+            post_version: 0,
+            graph: None,
+            graph_version: 0,
+        })
+    }
+
+    fn resize(&mut self, state: &GpuState, new_size: PhysicalSize<u32>) {
+        let Some(config) = resized_config(&self.config, new_size) else {
+            return;
+        };
+        let (width, height) = (new_size.width, new_size.height);
+        self.config = config;
+        self.reconfigure(state);
+        self.depth_view = create_depth_view(&state.device, width, height);
+        if let Some(post) = &mut self.post {
+            post.resize(&state.device, width, height);
+        }
+        if let Some(graph) = &mut self.graph {
+            graph.resize(&state.device, width, height);
+        }
+    }
+
+    /// Trades this window's targets with the main window's, so the main
+    /// window's drawing code draws onto this one. Calling it again swaps
+    /// them back.
+    fn swap_targets(&mut self, state: &mut GpuState) {
+        std::mem::swap(&mut self.config, &mut state.config);
+        std::mem::swap(&mut self.depth_view, &mut state.depth_view);
+        std::mem::swap(&mut self.post, &mut state.post);
+        std::mem::swap(&mut self.post_version, &mut state.post_version);
+        std::mem::swap(&mut self.graph, &mut state.graph);
+        std::mem::swap(&mut self.graph_version, &mut state.graph_version);
+    }
+
+    fn reconfigure(&self, state: &GpuState) {
+        self.surface.configure(&state.device, &self.config);
     }
 }
 
@@ -303,3 +2140,63 @@ impl Drop for GpuState {
         self.device.poll(wgpu::Maintain::Wait);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(width: u32, height: u32) -> SurfaceConfiguration {
+        SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn zero_sizes_leave_the_surface_configuration_alone() {
+        let current = config(800, 600);
+        assert!(resized_config(&current, PhysicalSize::new(0, 0)).is_none());
+        assert!(resized_config(&current, PhysicalSize::new(0, 600)).is_none());
+        assert!(resized_config(&current, PhysicalSize::new(800, 0)).is_none());
+
+        let restored = resized_config(&current, PhysicalSize::new(1024, 768)).unwrap();
+        assert_eq!((restored.width, restored.height), (1024, 768));
+        assert_eq!(restored.format, current.format);
+        assert_eq!(restored.present_mode, current.present_mode);
+    }
+
+    #[test]
+    fn minimized_or_empty_windows_are_not_drawn() {
+        let size = PhysicalSize::new(800, 600);
+        assert!(is_drawable(size, Some(false)));
+        // Platforms that can't tell
+        assert!(is_drawable(size, None));
+        assert!(!is_drawable(size, Some(true)));
+        assert!(!is_drawable(PhysicalSize::new(0, 0), None));
+        assert!(!is_drawable(PhysicalSize::new(800, 0), Some(false)));
+    }
+
+    #[test]
+    fn rescale_keeps_the_logical_size() {
+        let size = PhysicalSize::new(800, 600);
+
+        assert_eq!(rescale(size, 1.0, 2.0), PhysicalSize::new(1600, 1200));
+        assert_eq!(rescale(size, 2.0, 1.0), PhysicalSize::new(400, 300));
+        assert_eq!(rescale(size, 1.5, 1.5), size);
+    }
+
+    #[test]
+    fn rescale_rounds_to_whole_pixels() {
+        // 1001 / 1.5 = 667.33 logical, 834.17 physical at 1.25
+        assert_eq!(
+            rescale(PhysicalSize::new(1001, 1), 1.5, 1.25),
+            PhysicalSize::new(834, 1)
+        );
+    }
+}
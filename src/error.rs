@@ -0,0 +1,98 @@
+use std::fmt;
+
+/// Why [`Window::run`](crate::engine::window::Window::run) couldn't start
+/// or keep running.
+#[derive(Debug)]
+pub enum KreedaError {
+    /// No GPU adapter can draw to the window.
+    NoAdapter,
+    /// The adapter lacks these features asked for with
+    /// [`Window::set_required_features`](crate::engine::window::Window::set_required_features).
+    UnsupportedFeatures(wgpu::Features),
+    /// A limit asked for with
+    /// [`Window::set_required_limits`](crate::engine::window::Window::set_required_limits)
+    /// is beyond what the adapter allows.
+    UnsupportedLimit {
+        name: &'static str,
+        requested: u64,
+        allowed: u64,
+    },
+    /// The surface can't be presented in the format asked for with
+    /// [`Window::set_surface_format`](crate::engine::window::Window::set_surface_format).
+    UnsupportedSurfaceFormat {
+        requested: wgpu::TextureFormat,
+        supported: Vec<wgpu::TextureFormat>,
+    },
+    /// A view format asked for with
+    /// [`Window::set_surface_view_formats`](crate::engine::window::Window::set_surface_view_formats)
+    /// differs from the surface format by more than sRGB-ness.
+    IncompatibleViewFormat {
+        surface: wgpu::TextureFormat,
+        view: wgpu::TextureFormat,
+    },
+    DeviceRequestFailed(wgpu::RequestDeviceError),
+    SurfaceCreationFailed(wgpu::CreateSurfaceError),
+    /// The OS refused to open the window.
+    WindowCreationFailed(winit::error::OsError),
+    EventLoopError(winit::error::EventLoopError),
+}
+
+impl fmt::Display for KreedaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KreedaError::NoAdapter => write!(f, "no suitable GPU adapter found"),
+            KreedaError::UnsupportedFeatures(missing) => {
+                write!(f, "the GPU adapter doesn't support {missing:?}")
+            }
+            KreedaError::UnsupportedLimit {
+                name,
+                requested,
+                allowed,
+            } => write!(
+                f,
+                "the GPU adapter allows {name} up to {allowed}, but {requested} was requested"
+            ),
+            KreedaError::UnsupportedSurfaceFormat {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "the surface doesn't support {requested:?}, only {supported:?}"
+            ),
+            KreedaError::IncompatibleViewFormat { surface, view } => write!(
+                f,
+                "a {surface:?} surface can't be viewed as {view:?}, only with sRGB added or removed"
+            ),
+            KreedaError::DeviceRequestFailed(e) => write!(f, "failed to request a GPU device: {e}"),
+            KreedaError::SurfaceCreationFailed(e) => write!(f, "failed to create a surface: {e}"),
+            KreedaError::WindowCreationFailed(e) => write!(f, "failed to create the window: {e}"),
+            KreedaError::EventLoopError(e) => write!(f, "event loop error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for KreedaError {}
+
+impl From<wgpu::RequestDeviceError> for KreedaError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        KreedaError::DeviceRequestFailed(e)
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for KreedaError {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        KreedaError::SurfaceCreationFailed(e)
+    }
+}
+
+impl From<winit::error::OsError> for KreedaError {
+    fn from(e: winit::error::OsError) -> Self {
+        KreedaError::WindowCreationFailed(e)
+    }
+}
+
+impl From<winit::error::EventLoopError> for KreedaError {
+    fn from(e: winit::error::EventLoopError) -> Self {
+        KreedaError::EventLoopError(e)
+    }
+}
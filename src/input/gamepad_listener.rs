@@ -0,0 +1,219 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::input::input_axis::DEFAULT_DEAD_ZONE;
+use crate::input::input_device::{self, InputDevice};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// State of every connected gamepad merged into one virtual pad.
+pub struct GamepadInput {
+    axes: HashMap<GamepadAxis, f32>,
+    buttons_pressed: HashSet<GamepadButton>,
+    buttons_just_pressed: HashSet<GamepadButton>,
+    buttons_just_released: HashSet<GamepadButton>,
+}
+
+impl GamepadInput {
+    fn new() -> Self {
+        Self {
+            axes: HashMap::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_just_pressed: HashSet::new(),
+            buttons_just_released: HashSet::new(),
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<GamepadInput> {
+        static INSTANCE: Lazy<Mutex<GamepadInput>> = Lazy::new(|| Mutex::new(GamepadInput::new()));
+
+        &INSTANCE
+    }
+
+    #[cfg(test)]
+    pub(crate) fn reset() {
+        *Self::get_instance().lock().unwrap() = Self::new();
+    }
+
+    pub fn handle_axis(axis: GamepadAxis, value: f32) {
+        // Resting sticks drift a little; only deliberate movement counts
+        if value.abs() > DEFAULT_DEAD_ZONE {
+            input_device::note_input(InputDevice::Gamepad);
+        }
+        Self::get_instance().lock().unwrap().axes.insert(axis, value);
+    }
+
+    pub fn handle_button(button: GamepadButton, pressed: bool) {
+        if pressed {
+            input_device::note_input(InputDevice::Gamepad);
+        }
+        let mut input = Self::get_instance().lock().unwrap();
+        if pressed {
+            if input.buttons_pressed.insert(button) {
+                input.buttons_just_pressed.insert(button);
+            }
+        } else if input.buttons_pressed.remove(&button) {
+            input.buttons_just_released.insert(button);
+        }
+    }
+
+    pub fn end_frame() {
+        let mut input = Self::get_instance().lock().unwrap();
+        input.buttons_just_pressed.clear();
+        input.buttons_just_released.clear();
+    }
+
+    //--Getters--//
+
+    /// Raw axis value in `[-1, 1]` (`[0, 1]` for triggers).
+    pub fn axis(axis: GamepadAxis) -> f32 {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .axes
+            .get(&axis)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn button_down(button: GamepadButton) -> bool {
+        Self::get_instance().lock().unwrap().buttons_pressed.contains(&button)
+    }
+
+    pub fn button_just_pressed(button: GamepadButton) -> bool {
+        Self::get_instance().lock().unwrap().buttons_just_pressed.contains(&button)
+    }
+
+    /// Whether any button is held down, on any connected gamepad.
+    pub fn any_button_pressed() -> bool {
+        !Self::get_instance().lock().unwrap().buttons_pressed.is_empty()
+    }
+
+    /// Whether any button went down this frame, on any connected gamepad.
+    pub fn any_button_just_pressed() -> bool {
+        !Self::get_instance()
+            .lock()
+            .unwrap()
+            .buttons_just_pressed
+            .is_empty()
+    }
+
+    pub fn button_just_released(button: GamepadButton) -> bool {
+        Self::get_instance().lock().unwrap().buttons_just_released.contains(&button)
+    }
+}
+
+/// Polls gilrs and forwards its events into [`GamepadInput`].
+#[cfg(feature = "gamepad")]
+pub(crate) struct GilrsBackend {
+    gilrs: gilrs::Gilrs,
+}
+
+#[cfg(feature = "gamepad")]
+impl GilrsBackend {
+    pub(crate) fn new() -> Option<Self> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(Self { gilrs }),
+            Err(e) => {
+                log::warn!("Gamepad support unavailable: {e}");
+                None
+            }
+        }
+    }
+
+    pub(crate) fn poll(&mut self) {
+        use gilrs::EventType;
+
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = map_button(button) {
+                        GamepadInput::handle_button(button, true);
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = map_button(button) {
+                        GamepadInput::handle_button(button, false);
+                    }
+                }
+                // Analog triggers report through ButtonChanged
+                EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => {
+                    GamepadInput::handle_axis(GamepadAxis::LeftTrigger, value);
+                }
+                EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => {
+                    GamepadInput::handle_axis(GamepadAxis::RightTrigger, value);
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = map_axis(axis) {
+                        GamepadInput::handle_axis(axis, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn map_button(button: gilrs::Button) -> Option<GamepadButton> {
+    use gilrs::Button;
+
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "gamepad")]
+fn map_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+    use gilrs::Axis;
+
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        _ => return None,
+    })
+}
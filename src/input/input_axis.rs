@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use winit::keyboard::KeyCode;
+
+use crate::input::gamepad_listener::{GamepadAxis, GamepadInput};
+use crate::input::input_map::InputBinding;
+use crate::input::mouse_listener::MouseInput;
+
+/// Stick values this close to the center read as 0.
+pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+/// Mouse movement feeding an [`InputAxis`], in pixels this frame times
+/// `sensitivity`. Unlike the other sources it isn't limited to `[-1, 1]`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MouseAxis {
+    /// Positive is right.
+    X { sensitivity: f32 },
+    /// Positive is down, as in window coordinates; use a negative
+    /// `sensitivity` for up.
+    Y { sensitivity: f32 },
+}
+
+impl MouseAxis {
+    fn value(self) -> f32 {
+        match self {
+            Self::X { sensitivity } => MouseInput::get_dx() as f32 * sensitivity,
+            Self::Y { sensitivity } => MouseInput::get_dy() as f32 * sensitivity,
+        }
+    }
+}
+
+/// A 1D input from a pair of bindings and, optionally, a gamepad stick or
+/// trigger and mouse movement. Store one in the
+/// [`InputMap`](crate::input::input_map::InputMap) under a name and read
+/// it with [`InputMap::axis_value`](crate::input::input_map::InputMap::axis_value).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputAxis {
+    pub negative: InputBinding,
+    pub positive: InputBinding,
+    pub gamepad: Option<GamepadAxis>,
+    /// Stick values within this of the center read as 0; the rest is
+    /// rescaled so the output still spans the full range.
+    #[serde(default = "default_dead_zone")]
+    pub dead_zone: f32,
+    #[serde(default)]
+    pub mouse: Option<MouseAxis>,
+}
+
+fn default_dead_zone() -> f32 {
+    DEFAULT_DEAD_ZONE
+}
+
+impl InputAxis {
+    /// A/D by position, or the left stick. Left is -1, right +1.
+    pub const HORIZONTAL: InputAxis = InputAxis {
+        negative: InputBinding::KeyCode(KeyCode::KeyA),
+        positive: InputBinding::KeyCode(KeyCode::KeyD),
+        gamepad: Some(GamepadAxis::LeftStickX),
+        dead_zone: DEFAULT_DEAD_ZONE,
+        mouse: None,
+    };
+    /// S/W by position, or the left stick. Down is -1, up +1, as in world
+    /// space.
+    pub const VERTICAL: InputAxis = InputAxis {
+        negative: InputBinding::KeyCode(KeyCode::KeyS),
+        positive: InputBinding::KeyCode(KeyCode::KeyW),
+        gamepad: Some(GamepadAxis::LeftStickY),
+        dead_zone: DEFAULT_DEAD_ZONE,
+        mouse: None,
+    };
+
+    pub fn new(negative: InputBinding, positive: InputBinding) -> Self {
+        Self {
+            negative,
+            positive,
+            gamepad: None,
+            dead_zone: DEFAULT_DEAD_ZONE,
+            mouse: None,
+        }
+    }
+
+    pub fn with_gamepad(mut self, axis: GamepadAxis) -> Self {
+        self.gamepad = Some(axis);
+        self
+    }
+
+    /// Clamped to `0..=1`; 1 ignores the stick altogether.
+    pub fn with_dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_mouse(mut self, mouse: MouseAxis) -> Self {
+        self.mouse = Some(mouse);
+        self
+    }
+
+    //--Getters--//
+
+    /// Whichever source reads the largest magnitude: -1 while `negative`
+    /// is held, +1 while `positive` is held and 0 for both or neither; the
+    /// stick past the dead zone; or the mouse movement, which may exceed
+    /// `[-1, 1]`.
+    pub fn value(&self) -> f32 {
+        let buttons = match (self.negative.down(), self.positive.down()) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        let stick = self.gamepad.map_or(0.0, |axis| {
+            apply_dead_zone(GamepadInput::axis(axis), self.dead_zone)
+        });
+        let mouse = self.mouse.map_or(0.0, MouseAxis::value);
+
+        [stick, mouse].into_iter().fold(
+            buttons,
+            |best, v| if v.abs() > best.abs() { v } else { best },
+        )
+    }
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= dead_zone || dead_zone >= 1.0 {
+        return 0.0;
+    }
+    let scaled = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0);
+    scaled.copysign(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zone_is_rescaled_to_the_full_range() {
+        assert_eq!(apply_dead_zone(0.1, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(-0.2, 0.2), 0.0);
+        assert!((apply_dead_zone(0.6, 0.2) - 0.5).abs() < 1e-6);
+        assert!((apply_dead_zone(-0.6, 0.2) + 0.5).abs() < 1e-6);
+        assert_eq!(apply_dead_zone(1.0, 0.2), 1.0);
+        assert_eq!(apply_dead_zone(0.9, 1.0), 0.0);
+    }
+
+    #[test]
+    fn axes_saved_before_dead_zones_and_mouse_still_load() {
+        let json = r#"{
+            "negative": {"KeyCode": "KeyA"},
+            "positive": {"KeyCode": "KeyD"},
+            "gamepad": null
+        }"#;
+        let axis: InputAxis = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            axis,
+            InputAxis::new(
+                InputBinding::KeyCode(KeyCode::KeyA),
+                InputBinding::KeyCode(KeyCode::KeyD)
+            )
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::engine::events::{EventBus, InputDeviceChanged};
+
+/// Where the player's input comes from, e.g. to pick which button glyphs
+/// prompts show.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+fn last_device() -> &'static Mutex<InputDevice> {
+    static INSTANCE: Lazy<Mutex<InputDevice>> = Lazy::new(|| Mutex::new(InputDevice::default()));
+
+    &INSTANCE
+}
+
+#[cfg(test)]
+pub(crate) fn reset() {
+    *last_device().lock().unwrap() = InputDevice::default();
+}
+
+/// The device of the most recent key press, mouse press, cursor movement,
+/// scroll, gamepad button press or stick movement past the dead zone.
+/// Keyboard and mouse until anything else is used.
+pub fn last_input_device() -> InputDevice {
+    *last_device().lock().unwrap()
+}
+
+/// Called by the listeners on input from `device`. Sends
+/// [`InputDeviceChanged`] when it differs from the last one.
+pub(crate) fn note_input(device: InputDevice) {
+    let previous = std::mem::replace(&mut *last_device().lock().unwrap(), device);
+    if previous != device {
+        EventBus::send(InputDeviceChanged(device));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad_listener::{GamepadAxis, GamepadButton, GamepadInput};
+    use crate::input::key_listener::KeyInput;
+    use crate::input::mouse_listener::MouseInput;
+    use crate::input::test_guard;
+    use std::time::Instant;
+    use winit::event::{ElementState, MouseButton};
+    use winit::keyboard::{Key, KeyCode, PhysicalKey};
+
+    fn key(state: ElementState) {
+        KeyInput::handle_key(
+            &Key::Character("e".into()),
+            PhysicalKey::Code(KeyCode::KeyE),
+            state,
+        );
+    }
+
+    #[test]
+    fn the_last_device_follows_deliberate_input() {
+        let _guard = test_guard();
+        assert_eq!(last_input_device(), InputDevice::KeyboardMouse);
+
+        // Stick drift inside the dead zone doesn't count
+        GamepadInput::handle_axis(GamepadAxis::LeftStickX, 0.05);
+        assert_eq!(last_input_device(), InputDevice::KeyboardMouse);
+        GamepadInput::handle_axis(GamepadAxis::LeftStickX, 0.8);
+        assert_eq!(last_input_device(), InputDevice::Gamepad);
+
+        key(ElementState::Pressed);
+        assert_eq!(last_input_device(), InputDevice::KeyboardMouse);
+
+        GamepadInput::handle_button(GamepadButton::South, true);
+        assert_eq!(last_input_device(), InputDevice::Gamepad);
+        // Letting go of a key held from before isn't a switch
+        key(ElementState::Released);
+        assert_eq!(last_input_device(), InputDevice::Gamepad);
+
+        MouseInput::handle_cursor_moved(10.0, 10.0);
+        assert_eq!(last_input_device(), InputDevice::KeyboardMouse);
+    }
+
+    #[test]
+    fn any_input_helpers_last_one_frame() {
+        let _guard = test_guard();
+        assert!(!KeyInput::any_key_just_pressed());
+        assert!(!MouseInput::any_button_just_pressed());
+        assert!(!GamepadInput::any_button_just_pressed());
+
+        key(ElementState::Pressed);
+        MouseInput::handle_button(MouseButton::Right, ElementState::Pressed, Instant::now());
+        GamepadInput::handle_button(GamepadButton::Start, true);
+        assert!(KeyInput::any_key_just_pressed());
+        assert!(MouseInput::any_button_just_pressed());
+        assert!(GamepadInput::any_button_just_pressed());
+
+        KeyInput::end_frame();
+        MouseInput::end_frame();
+        GamepadInput::end_frame();
+        assert!(!KeyInput::any_key_just_pressed());
+        assert!(!MouseInput::any_button_just_pressed());
+        assert!(!GamepadInput::any_button_just_pressed());
+    }
+
+    #[test]
+    fn any_pressed_helpers_hold_until_release() {
+        let _guard = test_guard();
+        assert!(!KeyInput::any_key_pressed());
+        assert!(!MouseInput::any_button_pressed());
+        assert!(!GamepadInput::any_button_pressed());
+
+        key(ElementState::Pressed);
+        MouseInput::handle_button(MouseButton::Right, ElementState::Pressed, Instant::now());
+        GamepadInput::handle_button(GamepadButton::Start, true);
+        KeyInput::end_frame();
+        MouseInput::end_frame();
+        GamepadInput::end_frame();
+        assert!(KeyInput::any_key_pressed());
+        assert!(MouseInput::any_button_pressed());
+        assert!(GamepadInput::any_button_pressed());
+
+        key(ElementState::Released);
+        MouseInput::handle_button(MouseButton::Right, ElementState::Released, Instant::now());
+        GamepadInput::handle_button(GamepadButton::Start, false);
+        assert!(!KeyInput::any_key_pressed());
+        assert!(!MouseInput::any_button_pressed());
+        assert!(!GamepadInput::any_button_pressed());
+    }
+}
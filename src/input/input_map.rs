@@ -0,0 +1,641 @@
+use glam::Vec2;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use winit::keyboard::{Key, KeyCode};
+
+use crate::input::gamepad_listener::{GamepadButton, GamepadInput};
+use crate::input::input_axis::InputAxis;
+use crate::input::key_chord::KeyChord;
+use crate::input::key_listener::KeyInput;
+use crate::input::mouse_listener::MouseInput;
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingsError::Io(e) => write!(f, "failed to access bindings file: {e}"),
+            BindingsError::Json(e) => write!(f, "invalid bindings file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+impl From<std::io::Error> for BindingsError {
+    fn from(e: std::io::Error) -> Self {
+        BindingsError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BindingsError {
+    fn from(e: serde_json::Error) -> Self {
+        BindingsError::Json(e)
+    }
+}
+
+/// One physical input that can trigger an action.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputBinding {
+    /// A key by what it types in the current layout.
+    Key(Key),
+    /// A key by its position, e.g. WASD that stays in place on AZERTY.
+    KeyCode(KeyCode),
+    /// A key with modifiers, e.g. Ctrl+S.
+    Chord(KeyChord),
+    /// Mouse button index as used by [`MouseInput`]: 0 left, 1 right,
+    /// 2 middle.
+    MouseButton(usize),
+    GamepadButton(GamepadButton),
+}
+
+impl InputBinding {
+    pub(crate) fn down(&self) -> bool {
+        match self {
+            Self::Key(key) => KeyInput::key_down(key),
+            Self::KeyCode(code) => KeyInput::key_code_down(*code),
+            Self::Chord(chord) => KeyInput::chord_down(chord),
+            Self::MouseButton(button) => MouseInput::mouse_button_down(*button),
+            Self::GamepadButton(button) => GamepadInput::button_down(*button),
+        }
+    }
+
+    pub(crate) fn just_pressed(&self) -> bool {
+        match self {
+            Self::Key(key) => KeyInput::key_just_pressed(key),
+            Self::KeyCode(code) => KeyInput::key_code_just_pressed(*code),
+            Self::Chord(chord) => KeyInput::chord_just_pressed(chord),
+            Self::MouseButton(button) => MouseInput::button_just_pressed(*button),
+            Self::GamepadButton(button) => GamepadInput::button_just_pressed(*button),
+        }
+    }
+
+    pub(crate) fn just_released(&self) -> bool {
+        match self {
+            Self::Key(key) => KeyInput::key_just_released(key),
+            Self::KeyCode(code) => KeyInput::key_code_just_released(*code),
+            Self::Chord(chord) => KeyInput::chord_just_released(chord),
+            Self::MouseButton(button) => MouseInput::button_just_released(*button),
+            Self::GamepadButton(button) => GamepadInput::button_just_released(*button),
+        }
+    }
+}
+
+/// What an [`InputMap`] saves and loads.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Bindings {
+    #[serde(deserialize_with = "deserialize_actions")]
+    actions: HashMap<String, Vec<InputBinding>>,
+    #[serde(default = "default_axes")]
+    axes: HashMap<String, InputAxis>,
+}
+
+/// Reads files saved when an action had a single binding, too.
+fn deserialize_actions<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, Vec<InputBinding>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(InputBinding),
+        Many(Vec<InputBinding>),
+    }
+
+    let actions = HashMap::<String, OneOrMany>::deserialize(deserializer)?;
+    Ok(actions
+        .into_iter()
+        .map(|(action, bindings)| {
+            let bindings = match bindings {
+                OneOrMany::One(binding) => vec![binding],
+                OneOrMany::Many(bindings) => bindings,
+            };
+            (action, bindings)
+        })
+        .collect())
+}
+
+fn default_axes() -> HashMap<String, InputAxis> {
+    HashMap::from([
+        ("horizontal".to_owned(), InputAxis::HORIZONTAL),
+        ("vertical".to_owned(), InputAxis::VERTICAL),
+    ])
+}
+
+/// Named actions ("jump", "fire") bound to one or more keys, mouse buttons
+/// or gamepad buttons, and named [`InputAxis`]es, so controls can be rebound without
+/// touching game code. State is read from the input singletons on every
+/// query.
+///
+/// [`InputAxis::HORIZONTAL`] and [`InputAxis::VERTICAL`] are bound as
+/// "horizontal" and "vertical" from the start.
+///
+/// ```ignore
+/// InputMap::set_autosave_path(Some("bindings.json".into()));
+/// InputMap::load("bindings.json").ok();
+/// InputMap::bind("jump", InputBinding::Key(Key::Named(NamedKey::Space)));
+/// InputMap::bind("jump", InputBinding::GamepadButton(GamepadButton::South));
+/// if InputMap::just_pressed("jump") { /* ... */ }
+/// let speed = InputMap::axis_value("horizontal") * 200.0;
+/// ```
+pub struct InputMap {
+    bindings: Bindings,
+    /// Where changes are saved as they're made, if anywhere.
+    autosave_path: Option<PathBuf>,
+}
+
+impl InputMap {
+    fn new() -> Self {
+        Self {
+            bindings: Bindings {
+                actions: HashMap::new(),
+                axes: default_axes(),
+            },
+            autosave_path: None,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<InputMap> {
+        static INSTANCE: Lazy<Mutex<InputMap>> = Lazy::new(|| Mutex::new(InputMap::new()));
+
+        &INSTANCE
+    }
+
+    #[cfg(test)]
+    pub(crate) fn reset() {
+        *Self::get_instance().lock().unwrap() = Self::new();
+    }
+
+    /// Adds `binding` to those of `action`.
+    pub fn bind(action: &str, binding: InputBinding) {
+        let mut map = Self::get_instance().lock().unwrap();
+        let bindings = map.bindings.actions.entry(action.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+            map.autosave();
+        }
+    }
+
+    /// Makes `binding` the only binding of `action`, e.g. when the player
+    /// remaps a control.
+    pub fn rebind(action: &str, binding: InputBinding) {
+        let mut map = Self::get_instance().lock().unwrap();
+        let bindings = vec![binding];
+        if map.bindings.actions.get(action) != Some(&bindings) {
+            map.bindings.actions.insert(action.to_owned(), bindings);
+            map.autosave();
+        }
+    }
+
+    /// Adds `chord` to the bindings of `action`; short for
+    /// `bind(action, InputBinding::Chord(chord))`.
+    pub fn bind_chord(action: &str, chord: KeyChord) {
+        Self::bind(action, InputBinding::Chord(chord));
+    }
+
+    /// Removes `binding` from those of `action`.
+    pub fn unbind(action: &str, binding: &InputBinding) {
+        let mut map = Self::get_instance().lock().unwrap();
+        let Some(bindings) = map.bindings.actions.get_mut(action) else {
+            return;
+        };
+        let len = bindings.len();
+        bindings.retain(|b| b != binding);
+        if bindings.len() != len {
+            if bindings.is_empty() {
+                map.bindings.actions.remove(action);
+            }
+            map.autosave();
+        }
+    }
+
+    /// Removes every binding of `action`.
+    pub fn unbind_all(action: &str) {
+        let mut map = Self::get_instance().lock().unwrap();
+        if map.bindings.actions.remove(action).is_some() {
+            map.autosave();
+        }
+    }
+
+    /// Stores `axis` under `name`, replacing any axis of that name.
+    pub fn bind_axis(name: &str, axis: InputAxis) {
+        let mut map = Self::get_instance().lock().unwrap();
+        if map.bindings.axes.get(name) != Some(&axis) {
+            map.bindings.axes.insert(name.to_owned(), axis);
+            map.autosave();
+        }
+    }
+
+    /// Removes the axis `name`, built-in ones included.
+    pub fn unbind_axis(name: &str) {
+        let mut map = Self::get_instance().lock().unwrap();
+        if map.bindings.axes.remove(name).is_some() {
+            map.autosave();
+        }
+    }
+
+    /// Saves the bindings to `path` after every change from then on, e.g.
+    /// the file they were loaded from at startup. `None` stops saving.
+    pub fn set_autosave_path(path: Option<PathBuf>) {
+        Self::get_instance().lock().unwrap().autosave_path = path;
+    }
+
+    fn autosave(&self) {
+        let Some(path) = &self.autosave_path else {
+            return;
+        };
+        if let Err(e) = write_bindings(path, &self.bindings) {
+            log::warn!("Failed to save input bindings to {}: {e}", path.display());
+        }
+    }
+
+    /// Replaces all actions and axes with those saved by [`InputMap::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<(), BindingsError> {
+        let bytes = std::fs::read(path)?;
+        let bindings = serde_json::from_slice(&bytes)?;
+        Self::get_instance().lock().unwrap().bindings = bindings;
+        Ok(())
+    }
+
+    /// Writes all actions and axes to `path` as JSON.
+    pub fn save(path: impl AsRef<Path>) -> Result<(), BindingsError> {
+        write_bindings(
+            path.as_ref(),
+            &Self::get_instance().lock().unwrap().bindings,
+        )
+    }
+
+    //--Getters--//
+
+    /// Whether any binding of `action` is held. False for unknown actions.
+    pub fn is_pressed(action: &str) -> bool {
+        Self::bindings(action).iter().any(InputBinding::down)
+    }
+
+    /// Whether `action` went down this frame: a binding was pressed while
+    /// none of the others was already held.
+    pub fn just_pressed(action: &str) -> bool {
+        let bindings = Self::bindings(action);
+        bindings.iter().any(InputBinding::just_pressed)
+            && !bindings.iter().any(|b| b.down() && !b.just_pressed())
+    }
+
+    /// Whether `action` was let go this frame: a binding was released and
+    /// none is still held.
+    pub fn just_released(action: &str) -> bool {
+        let bindings = Self::bindings(action);
+        bindings.iter().any(InputBinding::just_released) && !bindings.iter().any(InputBinding::down)
+    }
+
+    /// [`InputAxis::value`] of the axis `name`, or 0 if there's none.
+    pub fn axis_value(name: &str) -> f32 {
+        // Cloned so the input singletons aren't locked while this one is
+        let axis = Self::axis(name);
+        axis.map_or(0.0, |axis| axis.value())
+    }
+
+    /// The axes `x` and `y` as a vector. While both are within `[-1, 1]`
+    /// it's clamped to unit length, so moving diagonally with the keys
+    /// isn't faster than moving straight; mouse movement past that is
+    /// left as is.
+    pub fn vec2(x: &str, y: &str) -> Vec2 {
+        let v = Vec2::new(Self::axis_value(x), Self::axis_value(y));
+        if v.x.abs() <= 1.0 && v.y.abs() <= 1.0 {
+            v.clamp_length_max(1.0)
+        } else {
+            v
+        }
+    }
+
+    /// The axis stored under `name`, if any.
+    pub fn axis(name: &str) -> Option<InputAxis> {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .bindings
+            .axes
+            .get(name)
+            .cloned()
+    }
+
+    /// The bindings of `action`, in the order they were added. Cloned so
+    /// the input singletons aren't locked while this one is.
+    pub fn bindings(action: &str) -> Vec<InputBinding> {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .bindings
+            .actions
+            .get(action)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn write_bindings(path: &Path, bindings: &Bindings) -> Result<(), BindingsError> {
+    let json = serde_json::to_string_pretty(bindings)?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad_listener::GamepadAxis;
+    use crate::input::input_axis::{DEFAULT_DEAD_ZONE, MouseAxis};
+    use crate::input::test_guard;
+    use winit::event::ElementState;
+    use winit::keyboard::{ModifiersState, NamedKey, PhysicalKey};
+
+    fn key(key: Key, code: KeyCode, state: ElementState) {
+        KeyInput::handle_key(&key, PhysicalKey::Code(code), state);
+    }
+
+    fn space() -> Key {
+        Key::Named(NamedKey::Space)
+    }
+
+    fn w() -> Key {
+        Key::Character("w".into())
+    }
+
+    #[test]
+    fn remapping_jump_follows_the_new_key() {
+        let _guard = test_guard();
+        InputMap::bind("jump", InputBinding::Key(space()));
+
+        key(space(), KeyCode::Space, ElementState::Pressed);
+        assert!(InputMap::is_pressed("jump"));
+        assert!(InputMap::just_pressed("jump"));
+
+        InputMap::rebind("jump", InputBinding::Key(w()));
+        assert_eq!(InputMap::bindings("jump"), [InputBinding::Key(w())]);
+        assert!(!InputMap::is_pressed("jump"));
+
+        key(w(), KeyCode::KeyW, ElementState::Pressed);
+        assert!(InputMap::is_pressed("jump"));
+
+        key(w(), KeyCode::KeyW, ElementState::Released);
+        assert!(!InputMap::is_pressed("jump"));
+        assert!(InputMap::just_released("jump"));
+    }
+
+    #[test]
+    fn key_codes_and_mouse_buttons_trigger_actions() {
+        let _guard = test_guard();
+        InputMap::bind("fire", InputBinding::KeyCode(KeyCode::KeyF));
+        InputMap::bind("aim", InputBinding::MouseButton(1));
+
+        key(
+            Key::Character("f".into()),
+            KeyCode::KeyF,
+            ElementState::Pressed,
+        );
+        assert!(InputMap::is_pressed("fire"));
+        assert!(!InputMap::is_pressed("aim"));
+
+        KeyInput::end_frame();
+        assert!(InputMap::is_pressed("fire"));
+        assert!(!InputMap::just_pressed("fire"));
+
+        InputMap::unbind("fire", &InputBinding::KeyCode(KeyCode::KeyF));
+        assert!(!InputMap::is_pressed("fire"));
+        assert!(!InputMap::is_pressed("unknown"));
+        assert!(InputMap::bindings("unknown").is_empty());
+    }
+
+    #[test]
+    fn an_action_can_have_several_bindings() {
+        let _guard = test_guard();
+        let pad = InputBinding::GamepadButton(GamepadButton::South);
+        InputMap::bind("jump", InputBinding::Key(space()));
+        InputMap::bind("jump", pad.clone());
+        InputMap::bind("jump", pad.clone());
+        assert_eq!(
+            InputMap::bindings("jump"),
+            [InputBinding::Key(space()), pad.clone()]
+        );
+
+        GamepadInput::handle_button(GamepadButton::South, true);
+        assert!(InputMap::is_pressed("jump"));
+        assert!(InputMap::just_pressed("jump"));
+
+        // Pressing the other one while held isn't a new press
+        GamepadInput::end_frame();
+        key(space(), KeyCode::Space, ElementState::Pressed);
+        assert!(!InputMap::just_pressed("jump"));
+
+        // Nor is letting go of one while the other is held a release
+        KeyInput::end_frame();
+        GamepadInput::handle_button(GamepadButton::South, false);
+        assert!(InputMap::is_pressed("jump"));
+        assert!(!InputMap::just_released("jump"));
+
+        GamepadInput::end_frame();
+        key(space(), KeyCode::Space, ElementState::Released);
+        assert!(InputMap::just_released("jump"));
+
+        InputMap::unbind("jump", &InputBinding::Key(space()));
+        assert_eq!(InputMap::bindings("jump"), [pad]);
+        InputMap::unbind_all("jump");
+        assert!(InputMap::bindings("jump").is_empty());
+    }
+
+    #[test]
+    fn axes_read_their_keys_or_the_stick() {
+        let _guard = test_guard();
+        assert_eq!(InputMap::axis_value("horizontal"), 0.0);
+
+        key(
+            Key::Character("a".into()),
+            KeyCode::KeyA,
+            ElementState::Pressed,
+        );
+        assert_eq!(InputMap::axis_value("horizontal"), -1.0);
+
+        key(
+            Key::Character("d".into()),
+            KeyCode::KeyD,
+            ElementState::Pressed,
+        );
+        assert_eq!(InputMap::axis_value("horizontal"), 0.0);
+
+        key(
+            Key::Character("a".into()),
+            KeyCode::KeyA,
+            ElementState::Released,
+        );
+        assert_eq!(InputMap::axis_value("horizontal"), 1.0);
+
+        // The key outweighs a half-tilted stick
+        GamepadInput::handle_axis(GamepadAxis::LeftStickX, -0.5);
+        assert_eq!(InputMap::axis_value("horizontal"), 1.0);
+        key(
+            Key::Character("d".into()),
+            KeyCode::KeyD,
+            ElementState::Released,
+        );
+        let rescaled = (0.5 - DEFAULT_DEAD_ZONE) / (1.0 - DEFAULT_DEAD_ZONE);
+        assert!((InputMap::axis_value("horizontal") + rescaled).abs() < 1e-6);
+        // A resting stick reads as centered
+        GamepadInput::handle_axis(GamepadAxis::LeftStickX, 0.1);
+        assert_eq!(InputMap::axis_value("horizontal"), 0.0);
+        assert_eq!(InputMap::axis_value("vertical"), 0.0);
+    }
+
+    #[test]
+    fn custom_axes_are_stored_by_name() {
+        let _guard = test_guard();
+        let zoom = InputAxis::new(InputBinding::MouseButton(1), InputBinding::MouseButton(0))
+            .with_gamepad(GamepadAxis::RightStickY)
+            .with_dead_zone(0.5);
+        InputMap::bind_axis("zoom", zoom.clone());
+        assert_eq!(InputMap::axis("zoom"), Some(zoom));
+
+        GamepadInput::handle_axis(GamepadAxis::RightStickY, 0.4);
+        assert_eq!(InputMap::axis_value("zoom"), 0.0);
+        GamepadInput::handle_axis(GamepadAxis::RightStickY, 0.75);
+        assert_eq!(InputMap::axis_value("zoom"), 0.5);
+
+        InputMap::unbind_axis("zoom");
+        assert_eq!(InputMap::axis_value("zoom"), 0.0);
+        assert_eq!(InputMap::axis_value("unknown"), 0.0);
+    }
+
+    #[test]
+    fn mouse_axes_scale_the_movement_and_may_exceed_one() {
+        let _guard = test_guard();
+        let look = InputAxis::new(
+            InputBinding::KeyCode(KeyCode::KeyQ),
+            InputBinding::KeyCode(KeyCode::KeyE),
+        )
+        .with_mouse(MouseAxis::X { sensitivity: 0.1 });
+        InputMap::bind_axis("look_x", look);
+
+        MouseInput::handle_cursor_moved(100.0, 100.0);
+        MouseInput::handle_cursor_moved(130.0, 90.0);
+        assert!((InputMap::axis_value("look_x") - 3.0).abs() < 1e-6);
+
+        // Small movements lose out to a held key
+        MouseInput::end_frame();
+        MouseInput::handle_cursor_moved(125.0, 90.0);
+        key(
+            Key::Character("e".into()),
+            KeyCode::KeyE,
+            ElementState::Pressed,
+        );
+        assert_eq!(InputMap::axis_value("look_x"), 1.0);
+    }
+
+    #[test]
+    fn vec2_keeps_diagonals_at_unit_length() {
+        let _guard = test_guard();
+        key(
+            Key::Character("d".into()),
+            KeyCode::KeyD,
+            ElementState::Pressed,
+        );
+        assert_eq!(InputMap::vec2("horizontal", "vertical"), Vec2::X);
+
+        key(
+            Key::Character("w".into()),
+            KeyCode::KeyW,
+            ElementState::Pressed,
+        );
+        let diagonal = InputMap::vec2("horizontal", "vertical");
+        assert!((diagonal.length() - 1.0).abs() < 1e-6);
+        assert!((diagonal.x - diagonal.y).abs() < 1e-6);
+
+        // Mouse look isn't held back
+        InputMap::bind_axis(
+            "look_x",
+            InputAxis::HORIZONTAL.with_mouse(MouseAxis::X { sensitivity: 1.0 }),
+        );
+        MouseInput::handle_cursor_moved(0.0, 0.0);
+        MouseInput::handle_cursor_moved(5.0, 0.0);
+        assert_eq!(InputMap::vec2("look_x", "vertical"), Vec2::new(5.0, 1.0));
+    }
+
+    #[test]
+    fn bindings_survive_save_and_load() {
+        let _guard = test_guard();
+        let path =
+            std::env::temp_dir().join(format!("kreeda-bindings-{}.json", std::process::id()));
+        InputMap::bind("jump", InputBinding::Key(space()));
+        InputMap::bind("jump", InputBinding::GamepadButton(GamepadButton::South));
+        InputMap::bind("crouch", InputBinding::GamepadButton(GamepadButton::East));
+        InputMap::bind(
+            "save",
+            InputBinding::Chord(KeyChord::new(
+                ModifiersState::CONTROL,
+                Key::Character("s".into()),
+            )),
+        );
+        InputMap::bind_axis(
+            "turn",
+            InputAxis::new(InputBinding::Key(w()), InputBinding::Key(space())),
+        );
+        InputMap::unbind_axis("vertical");
+        InputMap::save(&path).unwrap();
+
+        InputMap::reset();
+        assert!(InputMap::bindings("jump").is_empty());
+        InputMap::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            InputMap::bindings("jump"),
+            [
+                InputBinding::Key(space()),
+                InputBinding::GamepadButton(GamepadButton::South)
+            ]
+        );
+        assert_eq!(
+            InputMap::bindings("crouch"),
+            [InputBinding::GamepadButton(GamepadButton::East)]
+        );
+        assert!(matches!(
+            InputMap::bindings("save")[..],
+            [InputBinding::Chord(_)]
+        ));
+        assert!(InputMap::axis("turn").is_some());
+        assert_eq!(InputMap::axis("horizontal"), Some(InputAxis::HORIZONTAL));
+        assert_eq!(InputMap::axis("vertical"), None);
+    }
+
+    #[test]
+    fn changes_are_saved_to_the_autosave_path() {
+        let _guard = test_guard();
+        let path =
+            std::env::temp_dir().join(format!("kreeda-autosave-{}.json", std::process::id()));
+        InputMap::set_autosave_path(Some(path.clone()));
+        InputMap::bind("jump", InputBinding::Key(space()));
+        InputMap::set_autosave_path(None);
+
+        InputMap::reset();
+        InputMap::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(InputMap::bindings("jump"), [InputBinding::Key(space())]);
+    }
+
+    #[test]
+    fn files_with_one_binding_per_action_still_load() {
+        let _guard = test_guard();
+        let path =
+            std::env::temp_dir().join(format!("kreeda-old-bindings-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"actions": {"fire": {"MouseButton": 0}}}"#).unwrap();
+        InputMap::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(InputMap::bindings("fire"), [InputBinding::MouseButton(0)]);
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use winit::keyboard::Key;
+
+// Re-exported for `kchord!`, so games needn't depend on winit to use it
+pub use winit::keyboard::{ModifiersState, NamedKey};
+
+pub const CTRL: ModifiersState = ModifiersState::CONTROL;
+pub const SHIFT: ModifiersState = ModifiersState::SHIFT;
+pub const ALT: ModifiersState = ModifiersState::ALT;
+/// The Windows key, or Command on macOS.
+pub const SUPER: ModifiersState = ModifiersState::SUPER;
+
+/// A key pressed while exactly `modifiers` are held, e.g. Ctrl+S. Build
+/// one with [`kchord!`](crate::kchord).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyChord {
+    pub modifiers: ModifiersState,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: ModifiersState, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Chord on the key typing `character`.
+    pub fn character(modifiers: ModifiersState, character: &str) -> Self {
+        Self::new(modifiers, Key::Character(character.into()))
+    }
+
+    pub fn named(modifiers: ModifiersState, key: NamedKey) -> Self {
+        Self::new(modifiers, Key::Named(key))
+    }
+
+    /// Whether `key` is the chord's key. Letters match in either case, as
+    /// Shift turns the "s" of Ctrl+Shift+S into "S".
+    pub(crate) fn matches(&self, key: &Key) -> bool {
+        match (&self.key, key) {
+            (Key::Character(a), Key::Character(b)) => a.to_lowercase() == b.to_lowercase(),
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Builds a [`KeyChord`](crate::input::key_chord::KeyChord) from
+/// [`CTRL`](crate::input::key_chord::CTRL),
+/// [`SHIFT`](crate::input::key_chord::SHIFT),
+/// [`ALT`](crate::input::key_chord::ALT) and
+/// [`SUPER`](crate::input::key_chord::SUPER), then a character or a
+/// [`NamedKey`](winit::keyboard::NamedKey) variant.
+///
+/// ```ignore
+/// let save = kchord!(CTRL + "s");
+/// let save_as = kchord!(CTRL + SHIFT + "s");
+/// let fullscreen = kchord!(ALT + Enter);
+/// ```
+#[macro_export]
+macro_rules! kchord {
+    (@chord [$($modifier:ident)*] $key:literal) => {
+        $crate::input::key_chord::KeyChord::character(
+            $crate::kchord!(@modifiers $($modifier)*),
+            $key,
+        )
+    };
+    (@chord [$($modifier:ident)*] $key:ident) => {
+        $crate::input::key_chord::KeyChord::named(
+            $crate::kchord!(@modifiers $($modifier)*),
+            $crate::input::key_chord::NamedKey::$key,
+        )
+    };
+    (@chord [$($modifiers:ident)*] $modifier:ident + $($rest:tt)+) => {
+        $crate::kchord!(@chord [$($modifiers)* $modifier] $($rest)+)
+    };
+    (@modifiers $($modifier:ident)*) => {
+        $crate::input::key_chord::ModifiersState::empty()
+            $(| $crate::input::key_chord::$modifier)*
+    };
+    ($($chord:tt)+) => {
+        $crate::kchord!(@chord [] $($chord)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::input_map::InputMap;
+    use crate::input::key_listener::KeyInput;
+    use crate::input::test_guard;
+    use winit::event::ElementState;
+    use winit::keyboard::{KeyCode, PhysicalKey};
+
+    fn press(modifiers: ModifiersState, character: &str) {
+        KeyInput::handle_modifiers(modifiers);
+        KeyInput::handle_key(
+            &Key::Character(character.into()),
+            PhysicalKey::Code(KeyCode::KeyS),
+            ElementState::Pressed,
+        );
+    }
+
+    #[test]
+    fn kchord_builds_chords_from_modifiers_and_keys() {
+        assert_eq!(
+            kchord!(CTRL + "s"),
+            KeyChord::character(ModifiersState::CONTROL, "s")
+        );
+        assert_eq!(
+            kchord!(CTRL + SHIFT + "s"),
+            KeyChord::character(ModifiersState::CONTROL | ModifiersState::SHIFT, "s")
+        );
+        assert_eq!(
+            kchord!(ALT + Enter),
+            KeyChord::named(ModifiersState::ALT, NamedKey::Enter)
+        );
+        assert_eq!(
+            kchord!(F5),
+            KeyChord::named(ModifiersState::empty(), NamedKey::F5)
+        );
+    }
+
+    #[test]
+    fn chords_need_exactly_their_modifiers() {
+        let _guard = test_guard();
+        press(CTRL, "s");
+        assert!(KeyInput::chord_just_pressed(&kchord!(CTRL + "s")));
+        assert!(KeyInput::chord_down(&kchord!(CTRL + "s")));
+        assert!(!KeyInput::chord_just_pressed(&kchord!("s")));
+        assert!(!KeyInput::chord_just_pressed(&kchord!(CTRL + SHIFT + "s")));
+
+        KeyInput::end_frame();
+        assert!(!KeyInput::chord_just_pressed(&kchord!(CTRL + "s")));
+        assert!(KeyInput::chord_down(&kchord!(CTRL + "s")));
+    }
+
+    #[test]
+    fn shifted_letters_still_match() {
+        let _guard = test_guard();
+        press(CTRL | SHIFT, "S");
+        assert!(KeyInput::chord_just_pressed(&kchord!(CTRL + SHIFT + "s")));
+    }
+
+    #[test]
+    fn chords_bound_to_actions_trigger_them() {
+        let _guard = test_guard();
+        InputMap::bind_chord("save", kchord!(CTRL + "s"));
+
+        press(ModifiersState::empty(), "s");
+        assert!(!InputMap::just_pressed("save"));
+        KeyInput::end_frame();
+        KeyInput::handle_key(
+            &Key::Character("s".into()),
+            PhysicalKey::Code(KeyCode::KeyS),
+            ElementState::Released,
+        );
+
+        press(CTRL, "s");
+        assert!(InputMap::just_pressed("save"));
+        assert!(InputMap::is_pressed("save"));
+    }
+}
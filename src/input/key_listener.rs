@@ -1,13 +1,23 @@
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::collections::HashSet;
-use winit::keyboard::Key;
-use winit::event::{ElementState, WindowEvent};
+use winit::keyboard::{Key, KeyCode, ModifiersState, PhysicalKey};
+use winit::event::{DeviceEvent, ElementState, WindowEvent};
+
+use crate::input::input_device::{self, InputDevice};
+use crate::input::key_chord::KeyChord;
 
 pub struct KeyInput {
     keys_pressed: HashSet<Key>,
     keys_just_pressed: HashSet<Key>,
     keys_just_released: HashSet<Key>,
+    /// The same keys by position on the keyboard, regardless of layout.
+    codes_pressed: HashSet<KeyCode>,
+    codes_just_pressed: HashSet<KeyCode>,
+    codes_just_released: HashSet<KeyCode>,
+    raw_keys_pressed: HashSet<PhysicalKey>,
+    modifiers: ModifiersState,
+    captured: bool,
 }
 
 impl KeyInput {
@@ -16,6 +26,12 @@ impl KeyInput {
             keys_pressed: HashSet::new(),
             keys_just_pressed: HashSet::new(),
             keys_just_released: HashSet::new(),
+            codes_pressed: HashSet::new(),
+            codes_just_pressed: HashSet::new(),
+            codes_just_released: HashSet::new(),
+            raw_keys_pressed: HashSet::new(),
+            modifiers: ModifiersState::empty(),
+            captured: false,
         }
     }
     pub fn get_instance() -> &'static Mutex<KeyInput> {
@@ -24,20 +40,87 @@ impl KeyInput {
         &INSTANCE
     }
 
+    #[cfg(test)]
+    pub(crate) fn reset() {
+        *Self::get_instance().lock().unwrap() = Self::new();
+    }
+
+    /// While captured, e.g. by a focused text field, new key presses are
+    /// ignored. Keys already held still register their release.
+    pub fn set_captured(captured: bool) {
+        Self::get_instance().lock().unwrap().captured = captured;
+    }
+
     pub fn handle_event(event: &WindowEvent) {
-        if let WindowEvent::KeyboardInput { event, .. } = event {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                Self::handle_key(&event.logical_key, event.physical_key, event.state);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => Self::handle_modifiers(modifiers.state()),
+            _ => {}
+        }
+    }
+
+    /// Sets the held modifiers, as [`KeyInput::handle_event`] does for a
+    /// modifiers event.
+    pub(crate) fn handle_modifiers(modifiers: ModifiersState) {
+        Self::get_instance().lock().unwrap().modifiers = modifiers;
+    }
+
+    /// Applies a press or release of `key` at `physical`, as
+    /// [`KeyInput::handle_event`] does for a keyboard event. Used to replay
+    /// recorded input, since winit's key events can't be constructed outside
+    /// of winit.
+    pub(crate) fn handle_key(key: &Key, physical: PhysicalKey, state: ElementState) {
+        if state == ElementState::Pressed {
+            input_device::note_input(InputDevice::KeyboardMouse);
+        }
+        let mut input = Self::get_instance().lock().unwrap();
+        if input.captured && !input.keys_pressed.contains(key) {
+            return;
+        }
+
+        match state {
+            ElementState::Pressed => {
+                if !input.keys_pressed.contains(key) {
+                    input.keys_just_pressed.insert(key.clone());
+                }
+                input.keys_pressed.insert(key.clone());
+            }
+            ElementState::Released => {
+                input.keys_pressed.remove(key);
+                input.keys_just_released.insert(key.clone());
+            }
+        }
+
+        let PhysicalKey::Code(code) = physical else {
+            return;
+        };
+        match state {
+            ElementState::Pressed => {
+                if input.codes_pressed.insert(code) {
+                    input.codes_just_pressed.insert(code);
+                }
+            }
+            ElementState::Released => {
+                input.codes_pressed.remove(&code);
+                input.codes_just_released.insert(code);
+            }
+        }
+    }
+
+    /// Tracks raw key state by physical key, independent of the focused
+    /// window's layout-aware [`KeyInput::handle_event`] state.
+    pub fn handle_device_event(event: &DeviceEvent) {
+        if let DeviceEvent::Key(event) = event {
             let mut input = Self::get_instance().lock().unwrap();
 
             match event.state {
                 ElementState::Pressed => {
-                    if !input.keys_pressed.contains(&event.logical_key) {
-                        input.keys_just_pressed.insert(event.logical_key.clone());
-                    }
-                    input.keys_pressed.insert(event.logical_key.clone());
+                    input.raw_keys_pressed.insert(event.physical_key);
                 }
                 ElementState::Released => {
-                    input.keys_pressed.remove(&event.logical_key);
-                    input.keys_just_released.insert(event.logical_key.clone());
+                    input.raw_keys_pressed.remove(&event.physical_key);
                 }
             }
         }
@@ -47,10 +130,16 @@ impl KeyInput {
         let mut input = Self::get_instance().lock().unwrap();
         input.keys_just_pressed.clear();
         input.keys_just_released.clear();
+        input.codes_just_pressed.clear();
+        input.codes_just_released.clear();
     }
 
     //--Getters--//
 
+    pub fn is_captured() -> bool {
+        Self::get_instance().lock().unwrap().captured
+    }
+
     pub fn key_down(key: &Key) -> bool {
         Self::get_instance().lock().unwrap().keys_pressed.contains(key)
     }
@@ -59,8 +148,73 @@ impl KeyInput {
         Self::get_instance().lock().unwrap().keys_just_pressed.contains(key)
     }
 
+    /// Whether any key is held down.
+    pub fn any_key_pressed() -> bool {
+        !Self::get_instance().lock().unwrap().keys_pressed.is_empty()
+    }
+
+    /// Whether any key went down this frame, e.g. for "press any key".
+    pub fn any_key_just_pressed() -> bool {
+        !Self::get_instance()
+            .lock()
+            .unwrap()
+            .keys_just_pressed
+            .is_empty()
+    }
+
     pub fn key_just_released(key: &Key) -> bool {
         Self::get_instance().lock().unwrap().keys_just_released.contains(key)
     }
-}
 
+    /// Whether the key at `code`'s position is held, whatever the layout
+    /// maps it to; e.g. [`KeyCode::KeyW`] is Z on AZERTY keyboards.
+    pub fn key_code_down(code: KeyCode) -> bool {
+        Self::get_instance().lock().unwrap().codes_pressed.contains(&code)
+    }
+
+    pub fn key_code_just_pressed(code: KeyCode) -> bool {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .codes_just_pressed
+            .contains(&code)
+    }
+
+    pub fn key_code_just_released(code: KeyCode) -> bool {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .codes_just_released
+            .contains(&code)
+    }
+
+    /// Modifier keys held right now.
+    pub fn modifiers() -> ModifiersState {
+        Self::get_instance().lock().unwrap().modifiers
+    }
+
+    /// Whether `chord`'s key is held with exactly its modifiers.
+    pub fn chord_down(chord: &KeyChord) -> bool {
+        let input = Self::get_instance().lock().unwrap();
+        input.modifiers == chord.modifiers && input.keys_pressed.iter().any(|k| chord.matches(k))
+    }
+
+    /// Whether `chord`'s key went down this frame with exactly its
+    /// modifiers held, so Ctrl+S doesn't also fire Ctrl+Shift+S or plain S.
+    pub fn chord_just_pressed(chord: &KeyChord) -> bool {
+        let input = Self::get_instance().lock().unwrap();
+        input.modifiers == chord.modifiers
+            && input.keys_just_pressed.iter().any(|k| chord.matches(k))
+    }
+
+    /// Whether `chord`'s key was released this frame, whatever modifiers
+    /// are still held.
+    pub fn chord_just_released(chord: &KeyChord) -> bool {
+        let input = Self::get_instance().lock().unwrap();
+        input.keys_just_released.iter().any(|k| chord.matches(k))
+    }
+
+    pub fn raw_key_down(key: &PhysicalKey) -> bool {
+        Self::get_instance().lock().unwrap().raw_keys_pressed.contains(key)
+    }
+}
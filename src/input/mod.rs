@@ -1,2 +1,28 @@
+pub mod gamepad_listener;
+pub mod input_axis;
+pub mod input_device;
+pub mod input_map;
+pub mod key_chord;
 pub mod mouse_listener;
+pub mod recorder;
 pub mod key_listener;
+
+/// Held by tests that drive the input singletons, which every test in the
+/// crate shares, so they don't run at the same time. Starts from a clean
+/// [`KeyInput`](key_listener::KeyInput),
+/// [`MouseInput`](mouse_listener::MouseInput),
+/// [`GamepadInput`](gamepad_listener::GamepadInput),
+/// [`InputMap`](input_map::InputMap) and the last used device.
+#[cfg(test)]
+pub(crate) fn test_guard() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let guard = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    key_listener::KeyInput::reset();
+    mouse_listener::MouseInput::reset();
+    gamepad_listener::GamepadInput::reset();
+    input_device::reset();
+    input_map::InputMap::reset();
+    guard
+}
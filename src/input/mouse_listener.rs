@@ -1,17 +1,77 @@
+use glam::Vec2;
 use once_cell::sync::Lazy;
+use std::collections::HashSet;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use winit::dpi::PhysicalPosition;
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event::{ButtonId, DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
 
+use crate::input::input_device::{self, InputDevice};
+
+/// Default distance in pixels the cursor must travel from the press position
+/// before a held button counts as dragging; see
+/// [`MouseInput::set_drag_threshold`].
+pub const DRAG_THRESHOLD: f64 = 4.0;
+
+/// Thresholds deciding what counts as a click and a double-click.
+#[derive(Clone, Copy, Debug)]
+pub struct ClickConfig {
+    /// Longest press-to-release time that still counts as a click.
+    pub max_duration: Duration,
+    /// Furthest the cursor may travel (in pixels) while the button is held.
+    pub max_distance: f64,
+    /// A second press sooner than this after the first is a double-click.
+    pub double_click_threshold: Duration,
+}
+
+impl Default for ClickConfig {
+    fn default() -> Self {
+        Self {
+            max_duration: Duration::from_millis(300),
+            max_distance: 4.0,
+            double_click_threshold: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Mouse state for the current frame. Positions are physical pixels from the
+/// window's top-left corner: x grows to the right and y grows downwards.
+///
+/// Raw device input is tracked separately from the window's cursor events, so
+/// the same motion is never counted twice.
 pub struct MouseInput {
     scroll_x: f64,
     scroll_y: f64,
     x_pos: f64,
     y_pos: f64,
-    last_y: f64,
-    last_x: f64,
+    /// Whether a cursor position has been reported yet; until then there's
+    /// nothing to measure movement from.
+    has_position: bool,
+    dx: f64,
+    dy: f64,
     mouse_button_pressed: [bool; 3],
-    is_dragging: bool,
+    button_just_pressed: [bool; 3],
+    button_just_released: [bool; 3],
+    dragging: [bool; 3],
+    drag_threshold: f64,
+    click_config: ClickConfig,
+    press_time: [Option<Instant>; 3],
+    press_position: [(f64, f64); 3],
+    press_moved: [bool; 3],
+    /// When the press that could start a double-click happened.
+    last_click_time: [Option<Instant>; 3],
+    clicked: [bool; 3],
+    double_clicked: [bool; 3],
+    raw_dx: f64,
+    raw_dy: f64,
+    raw_scroll_x: f64,
+    raw_scroll_y: f64,
+    raw_buttons_pressed: HashSet<ButtonId>,
+    captured: bool,
+    scroll_smoothing: f64,
+    scroll_deadzone: f64,
+    /// Smoothed scroll up to the end of the last frame.
+    smoothed_scroll_y: f64,
 }
 
 impl MouseInput {
@@ -21,10 +81,30 @@ impl MouseInput {
             scroll_y: 0.0,
             x_pos: 0.0,
             y_pos: 0.0,
-            last_y: 0.0,
-            last_x: 0.0,
+            has_position: false,
+            dx: 0.0,
+            dy: 0.0,
             mouse_button_pressed: [false; 3],
-            is_dragging: false,
+            button_just_pressed: [false; 3],
+            button_just_released: [false; 3],
+            dragging: [false; 3],
+            drag_threshold: DRAG_THRESHOLD,
+            click_config: ClickConfig::default(),
+            press_time: [None; 3],
+            press_position: [(0.0, 0.0); 3],
+            press_moved: [false; 3],
+            last_click_time: [None; 3],
+            clicked: [false; 3],
+            double_clicked: [false; 3],
+            raw_dx: 0.0,
+            raw_dy: 0.0,
+            raw_scroll_x: 0.0,
+            raw_scroll_y: 0.0,
+            raw_buttons_pressed: HashSet::new(),
+            captured: false,
+            scroll_smoothing: 0.0,
+            scroll_deadzone: 0.0,
+            smoothed_scroll_y: 0.0,
         }
     }
     pub fn get_instance() -> &'static Mutex<MouseInput> {
@@ -33,58 +113,240 @@ impl MouseInput {
         &INSTANCE
     }
 
+    #[cfg(test)]
+    pub(crate) fn reset() {
+        *Self::get_instance().lock().unwrap() = Self::new();
+    }
+
+    pub fn set_click_config(config: ClickConfig) {
+        Self::get_instance().lock().unwrap().click_config = config;
+    }
+
+    /// Sets [`ClickConfig::double_click_threshold`], 300ms by default.
+    pub fn set_double_click_threshold(threshold: Duration) {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .click_config
+            .double_click_threshold = threshold;
+    }
+
+    /// Distance in pixels the cursor must move from where a button was
+    /// pressed before it counts as dragging, so jitter during a click
+    /// doesn't ([`DRAG_THRESHOLD`] by default). Drags in progress continue.
+    pub fn set_drag_threshold(threshold: f64) {
+        Self::get_instance().lock().unwrap().drag_threshold = threshold.max(0.0);
+    }
+
+    /// How much of [`MouseInput::get_scroll_y`] carries over from the
+    /// previous frame, from 0 (none, the default) up to 0.99. The
+    /// rest comes from this frame's scrolling, so higher values turn notchy
+    /// wheel steps into a gradual, longer scroll, e.g. for zooming.
+    pub fn set_scroll_smoothing(smoothing: f64) {
+        Self::get_instance().lock().unwrap().scroll_smoothing = smoothing.clamp(0.0, 0.99);
+    }
+
+    /// Smallest [`MouseInput::get_scroll_y`] magnitude reported; anything
+    /// less reads as 0, ignoring accidental touches of a touchpad and the
+    /// tail of a smoothed scroll. In the units of the scroll events: lines
+    /// for wheels, pixels for touchpads. 0 by default.
+    pub fn set_scroll_deadzone(deadzone: f64) {
+        Self::get_instance().lock().unwrap().scroll_deadzone = deadzone.abs();
+    }
+
+    /// While captured, e.g. by a UI under the cursor, new presses and scrolling
+    /// are ignored. Cursor movement and releases of buttons already held are
+    /// still tracked so nothing gets stuck down.
+    pub fn set_captured(captured: bool) {
+        Self::get_instance().lock().unwrap().captured = captured;
+    }
+
     pub fn handle_event(event: &WindowEvent) {
+        Self::handle_event_at(event, Instant::now());
+    }
+
+    /// Same as [`MouseInput::handle_event`] with an explicit timestamp, so
+    /// click timing can be driven by a replay or a test clock.
+    pub fn handle_event_at(event: &WindowEvent, now: Instant) {
         match event {
             WindowEvent::CursorMoved { position, .. } => {
-                let mut l = Self::get_instance().lock().unwrap();
-                l.last_x = l.x_pos;
-                l.last_y = l.y_pos;
-                l.x_pos = position.x;
-                l.y_pos = position.y;
-                l.is_dragging = l.mouse_button_pressed.iter().any(|&b| b);
+                Self::handle_cursor_moved(position.x, position.y);
             }
-
             WindowEvent::MouseInput { state, button, .. } => {
-                let mut l = Self::get_instance().lock().unwrap();
-                let index = match button {
-                    MouseButton::Left => 0,
-                    MouseButton::Right => 1,
-                    MouseButton::Middle => 2,
-                    _ => return,
-                };
-                match state {
-                    ElementState::Pressed => l.mouse_button_pressed[index] = true,
-                    ElementState::Released => {
-                        l.mouse_button_pressed[index] = false;
-                        l.is_dragging = false;
-                    }
-                }
+                Self::handle_button(*button, *state, now);
             }
+            WindowEvent::MouseWheel { delta, .. } => Self::handle_wheel(*delta),
+            _ => {}
+        }
+    }
+
+    /// The cursor moved to `(x, y)`. This and the other `handle_*`
+    /// functions take what [`MouseInput::handle_event_at`] reads from a
+    /// window event, so recorded input can be replayed without one.
+    pub(crate) fn handle_cursor_moved(x: f64, y: f64) {
+        input_device::note_input(InputDevice::KeyboardMouse);
+        let mut l = Self::get_instance().lock().unwrap();
+        // Several moves can arrive in one frame; keep their sum. The first
+        // one only tells where the cursor starts.
+        if l.has_position {
+            l.dx += x - l.x_pos;
+            l.dy += y - l.y_pos;
+        }
+        l.has_position = true;
+        l.x_pos = x;
+        l.y_pos = y;
+
+        for i in 0..l.mouse_button_pressed.len() {
+            if !l.mouse_button_pressed[i] {
+                continue;
+            }
+            let moved = distance(l.press_position[i], (l.x_pos, l.y_pos));
+            if moved > l.click_config.max_distance {
+                l.press_moved[i] = true;
+            }
+            if moved > l.drag_threshold {
+                l.dragging[i] = true;
+            }
+        }
+    }
 
-            WindowEvent::MouseWheel { delta, .. } => {
-                let mut l = Self::get_instance().lock().unwrap();
-                match delta {
-                    MouseScrollDelta::LineDelta(_, y) => l.scroll_y = f64::from(*y),
-                    MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => {
-                        l.scroll_y = f64::from(*y)
-                    }
+    pub(crate) fn handle_button(button: MouseButton, state: ElementState, now: Instant) {
+        if state == ElementState::Pressed {
+            input_device::note_input(InputDevice::KeyboardMouse);
+        }
+        let mut l = Self::get_instance().lock().unwrap();
+        let Some(index) = button_index(button) else {
+            return;
+        };
+        if l.captured && !l.mouse_button_pressed[index] {
+            return;
+        }
+        match state {
+            ElementState::Pressed => {
+                if !l.mouse_button_pressed[index] {
+                    l.button_just_pressed[index] = true;
+                    l.register_press(index, now);
                 }
+                l.mouse_button_pressed[index] = true;
+                l.press_time[index] = Some(now);
+                l.press_position[index] = (l.x_pos, l.y_pos);
+                l.press_moved[index] = false;
+            }
+            ElementState::Released => {
+                l.mouse_button_pressed[index] = false;
+                l.button_just_released[index] = true;
+                l.dragging[index] = false;
+                l.register_release(index, now);
             }
+        }
+    }
+
+    pub(crate) fn handle_wheel(delta: MouseScrollDelta) {
+        input_device::note_input(InputDevice::KeyboardMouse);
+        let mut l = Self::get_instance().lock().unwrap();
+        if l.captured {
+            return;
+        }
+        match delta {
+            MouseScrollDelta::LineDelta(_, y) => l.scroll_y = f64::from(y),
+            MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => l.scroll_y = y,
+        }
+    }
 
+    /// Feeds raw device input into the `raw_*` accumulators. Unlike window
+    /// events, this keeps arriving when the cursor is grabbed or at the edge
+    /// of the screen.
+    pub fn handle_device_event(event: &DeviceEvent) {
+        let mut l = Self::get_instance().lock().unwrap();
+        match event {
+            DeviceEvent::MouseMotion { delta } => {
+                l.raw_dx += delta.0;
+                l.raw_dy += delta.1;
+            }
+            DeviceEvent::MouseWheel { delta } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => {
+                    l.raw_scroll_x += f64::from(*x);
+                    l.raw_scroll_y += f64::from(*y);
+                }
+                MouseScrollDelta::PixelDelta(PhysicalPosition { x, y }) => {
+                    l.raw_scroll_x += x;
+                    l.raw_scroll_y += y;
+                }
+            },
+            DeviceEvent::Button { button, state } => match state {
+                ElementState::Pressed => {
+                    l.raw_buttons_pressed.insert(*button);
+                }
+                ElementState::Released => {
+                    l.raw_buttons_pressed.remove(button);
+                }
+            },
             _ => {}
         }
     }
 
+    /// Detects double-clicks. Runs before the press position is updated,
+    /// so it still holds the previous press.
+    fn register_press(&mut self, index: usize, now: Instant) {
+        let config = self.click_config;
+        let position = (self.x_pos, self.y_pos);
+        match self.last_click_time[index] {
+            Some(at)
+                if now.duration_since(at) < config.double_click_threshold
+                    && distance(self.press_position[index], position) <= config.max_distance =>
+            {
+                self.double_clicked[index] = true;
+                // A third press starts a new pair rather than double-clicking again
+                self.last_click_time[index] = None;
+            }
+            _ => self.last_click_time[index] = Some(now),
+        }
+    }
+
+    fn register_release(&mut self, index: usize, now: Instant) {
+        let Some(pressed_at) = self.press_time[index].take() else {
+            return;
+        };
+        let config = self.click_config;
+        if !self.press_moved[index] && now.duration_since(pressed_at) <= config.max_duration {
+            self.clicked[index] = true;
+        }
+    }
+
+    fn filtered_scroll_y(&self) -> f64 {
+        let scroll_y = self.scroll_smoothing * self.smoothed_scroll_y
+            + (1.0 - self.scroll_smoothing) * self.scroll_y;
+        if scroll_y.abs() < self.scroll_deadzone {
+            0.0
+        } else {
+            scroll_y
+        }
+    }
+
     pub fn end_frame() {
         let mut listener = Self::get_instance().lock().unwrap();
+        listener.smoothed_scroll_y = listener.scroll_smoothing * listener.smoothed_scroll_y
+            + (1.0 - listener.scroll_smoothing) * listener.scroll_y;
         listener.scroll_x = 0.0;
         listener.scroll_y = 0.0;
-        listener.last_x = listener.x_pos;
-        listener.last_y = listener.y_pos;
+        listener.dx = 0.0;
+        listener.dy = 0.0;
+        listener.button_just_pressed = [false; 3];
+        listener.button_just_released = [false; 3];
+        listener.clicked = [false; 3];
+        listener.double_clicked = [false; 3];
+        listener.raw_dx = 0.0;
+        listener.raw_dy = 0.0;
+        listener.raw_scroll_x = 0.0;
+        listener.raw_scroll_y = 0.0;
     }
 
     //--Getters--//
 
+    pub fn is_captured() -> bool {
+        Self::get_instance().lock().unwrap().captured
+    }
+
     pub fn get_x() -> f64 {
         Self::get_instance().lock().unwrap().x_pos
     }
@@ -93,22 +355,120 @@ impl MouseInput {
         Self::get_instance().lock().unwrap().y_pos
     }
 
+    /// Horizontal cursor movement this frame in pixels; positive is right.
     pub fn get_dx() -> f64 {
-        let l = Self::get_instance().lock().unwrap();
-        l.last_x - l.x_pos
+        Self::get_instance().lock().unwrap().dx
     }
 
+    /// Vertical cursor movement this frame in pixels. Window coordinates
+    /// have their origin at the top-left, so positive is down.
     pub fn get_dy() -> f64 {
+        Self::get_instance().lock().unwrap().dy
+    }
+
+    /// Cursor position as a vector; see [`MouseInput::get_x`] and
+    /// [`MouseInput::get_y`].
+    pub fn position() -> Vec2 {
+        let l = Self::get_instance().lock().unwrap();
+        Vec2::new(l.x_pos as f32, l.y_pos as f32)
+    }
+
+    /// Cursor movement this frame as a vector; see [`MouseInput::get_dx`]
+    /// and [`MouseInput::get_dy`].
+    pub fn delta() -> Vec2 {
+        let l = Self::get_instance().lock().unwrap();
+        Vec2::new(l.dx as f32, l.dy as f32)
+    }
+
+    /// Raw device motion this frame as a vector; see
+    /// [`MouseInput::get_raw_dx`].
+    pub fn raw_delta() -> Vec2 {
         let l = Self::get_instance().lock().unwrap();
-        l.last_y - l.y_pos
+        Vec2::new(l.raw_dx as f32, l.raw_dy as f32)
     }
 
+    /// Vertical scroll this frame, smoothed and with the dead zone applied;
+    /// see [`MouseInput::set_scroll_smoothing`] and
+    /// [`MouseInput::set_scroll_deadzone`].
     pub fn get_scroll_y() -> f64 {
+        Self::get_instance().lock().unwrap().filtered_scroll_y()
+    }
+
+    /// Vertical scroll this frame exactly as the window reported it.
+    pub fn get_unfiltered_scroll_y() -> f64 {
         Self::get_instance().lock().unwrap().scroll_y
     }
 
-    pub fn is_dragging() -> bool {
-        Self::get_instance().lock().unwrap().is_dragging
+    /// Unaccelerated horizontal motion reported by the device this frame, in
+    /// device units rather than pixels.
+    pub fn get_raw_dx() -> f64 {
+        Self::get_instance().lock().unwrap().raw_dx
+    }
+
+    /// Unaccelerated vertical motion reported by the device this frame; like
+    /// [`MouseInput::get_dy`], positive is down.
+    pub fn get_raw_dy() -> f64 {
+        Self::get_instance().lock().unwrap().raw_dy
+    }
+
+    pub fn get_raw_scroll_x() -> f64 {
+        Self::get_instance().lock().unwrap().raw_scroll_x
+    }
+
+    pub fn get_raw_scroll_y() -> f64 {
+        Self::get_instance().lock().unwrap().raw_scroll_y
+    }
+
+    /// Whether the platform-specific raw button `button` is held.
+    pub fn raw_button_down(button: ButtonId) -> bool {
+        Self::get_instance()
+            .lock()
+            .unwrap()
+            .raw_buttons_pressed
+            .contains(&button)
+    }
+
+    /// True once `button` is held and the cursor has moved more than the
+    /// drag threshold from where it was pressed; see
+    /// [`MouseInput::set_drag_threshold`].
+    pub fn is_dragging(button: usize) -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.dragging.get(button).copied().unwrap_or(false)
+    }
+
+    /// Cursor position when `button` was pressed, while it's still held.
+    pub fn drag_start(button: usize) -> Option<(f64, f64)> {
+        let l = Self::get_instance().lock().unwrap();
+        match l.mouse_button_pressed.get(button) {
+            Some(true) => Some(l.press_position[button]),
+            _ => None,
+        }
+    }
+
+    /// Where the drag with `button` started, i.e. where it was pressed, or
+    /// `None` unless [`MouseInput::is_dragging`].
+    pub fn drag_start_position(button: usize) -> Option<Vec2> {
+        let l = Self::get_instance().lock().unwrap();
+        match l.dragging.get(button) {
+            Some(true) => {
+                let (x, y) = l.press_position[button];
+                Some(Vec2::new(x as f32, y as f32))
+            }
+            _ => None,
+        }
+    }
+
+    /// Total cursor movement since `button` was pressed, or `(0, 0)` if it
+    /// isn't held.
+    pub fn drag_delta(button: usize) -> (f64, f64) {
+        let l = Self::get_instance().lock().unwrap();
+        match l.mouse_button_pressed.get(button) {
+            Some(true) => {
+                let (x, y) = l.press_position[button];
+                (l.x_pos - x, l.y_pos - y)
+            }
+            _ => (0.0, 0.0),
+        }
     }
 
     pub fn mouse_button_down(button: usize) -> bool {
@@ -119,4 +479,351 @@ impl MouseInput {
             false
         }
     }
+
+    /// True on the frame `button` went down.
+    pub fn button_just_pressed(button: usize) -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.button_just_pressed.get(button).copied().unwrap_or(false)
+    }
+
+    /// Whether any button is held down.
+    pub fn any_button_pressed() -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.mouse_button_pressed.contains(&true)
+    }
+
+    /// Whether any button went down this frame.
+    pub fn any_button_just_pressed() -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.button_just_pressed.contains(&true)
+    }
+
+    /// True on the frame `button` was released.
+    pub fn button_just_released(button: usize) -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.button_just_released.get(button).copied().unwrap_or(false)
+    }
+
+    /// True on the frame `button` was released after a short, stationary press.
+    pub fn button_clicked(button: usize) -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.clicked.get(button).copied().unwrap_or(false)
+    }
+
+    /// True on the frame the second of two quick clicks on `button` landed.
+    pub fn button_double_clicked(button: usize) -> bool {
+        let l = Self::get_instance().lock().unwrap();
+        l.double_clicked.get(button).copied().unwrap_or(false)
+    }
+}
+
+fn button_index(button: MouseButton) -> Option<usize> {
+    match button {
+        MouseButton::Left => Some(0),
+        MouseButton::Right => Some(1),
+        MouseButton::Middle => Some(2),
+        _ => None,
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::test_guard;
+    use winit::event::DeviceId;
+
+    /// Feeds a button event `ms` milliseconds after `start`.
+    fn button(start: Instant, ms: u64, button: MouseButton, state: ElementState) {
+        let event = WindowEvent::MouseInput {
+            device_id: DeviceId::dummy(),
+            state,
+            button,
+        };
+        MouseInput::handle_event_at(&event, start + Duration::from_millis(ms));
+    }
+
+    fn move_to(x: f64, y: f64) {
+        let event = WindowEvent::CursorMoved {
+            device_id: DeviceId::dummy(),
+            position: PhysicalPosition::new(x, y),
+        };
+        MouseInput::handle_event(&event);
+    }
+
+    /// Presses at `down_ms` and releases at `up_ms` without moving.
+    fn click(start: Instant, down_ms: u64, up_ms: u64) {
+        button(start, down_ms, MouseButton::Left, ElementState::Pressed);
+        button(start, up_ms, MouseButton::Left, ElementState::Released);
+    }
+
+    #[test]
+    fn moves_in_one_frame_sum_into_one_delta() {
+        let _guard = test_guard();
+        move_to(100.0, 100.0);
+        MouseInput::end_frame();
+
+        move_to(103.0, 98.0);
+        move_to(110.0, 95.0);
+        assert_eq!((MouseInput::get_dx(), MouseInput::get_dy()), (10.0, -5.0));
+        assert_eq!(MouseInput::delta(), Vec2::new(10.0, -5.0));
+        assert_eq!(MouseInput::position(), Vec2::new(110.0, 95.0));
+
+        MouseInput::end_frame();
+        assert_eq!((MouseInput::get_dx(), MouseInput::get_dy()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn first_move_does_not_jump_from_the_origin() {
+        let _guard = test_guard();
+        move_to(640.0, 360.0);
+        assert_eq!((MouseInput::get_dx(), MouseInput::get_dy()), (0.0, 0.0));
+
+        move_to(650.0, 360.0);
+        assert_eq!((MouseInput::get_dx(), MouseInput::get_dy()), (10.0, 0.0));
+    }
+
+    #[test]
+    fn vector_getters_match_the_scalar_ones() {
+        let _guard = test_guard();
+        move_to(20.0, 30.0);
+        move_to(25.5, 28.0);
+
+        assert_eq!(
+            MouseInput::position(),
+            Vec2::new(MouseInput::get_x() as f32, MouseInput::get_y() as f32)
+        );
+        assert_eq!(MouseInput::position(), Vec2::new(25.5, 28.0));
+        assert_eq!(
+            MouseInput::delta(),
+            Vec2::new(MouseInput::get_dx() as f32, MouseInput::get_dy() as f32)
+        );
+        assert_eq!(MouseInput::delta(), Vec2::new(5.5, -2.0));
+    }
+
+    #[test]
+    fn raw_delta_sums_device_motion() {
+        let _guard = test_guard();
+        MouseInput::handle_device_event(&DeviceEvent::MouseMotion { delta: (3.0, -1.0) });
+        MouseInput::handle_device_event(&DeviceEvent::MouseMotion { delta: (2.0, 4.0) });
+
+        assert_eq!(MouseInput::raw_delta(), Vec2::new(5.0, 3.0));
+        assert_eq!(MouseInput::delta(), Vec2::ZERO);
+
+        MouseInput::end_frame();
+        assert_eq!(MouseInput::raw_delta(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn quick_stationary_press_is_a_click() {
+        let _guard = test_guard();
+        let start = Instant::now();
+        move_to(10.0, 10.0);
+
+        click(start, 0, 100);
+        assert!(MouseInput::button_clicked(0));
+        assert!(!MouseInput::button_clicked(1));
+        assert!(!MouseInput::button_double_clicked(0));
+
+        MouseInput::end_frame();
+        assert!(!MouseInput::button_clicked(0));
+    }
+
+    #[test]
+    fn long_press_is_not_a_click() {
+        let _guard = test_guard();
+        let start = Instant::now();
+
+        click(start, 0, 301);
+        assert!(MouseInput::button_just_released(0));
+        assert!(!MouseInput::button_clicked(0));
+    }
+
+    #[test]
+    fn moving_past_the_distance_is_not_a_click() {
+        let _guard = test_guard();
+        let start = Instant::now();
+        move_to(10.0, 10.0);
+
+        button(start, 0, MouseButton::Left, ElementState::Pressed);
+        move_to(15.0, 10.0);
+        move_to(10.0, 10.0);
+        button(start, 50, MouseButton::Left, ElementState::Released);
+        assert!(!MouseInput::button_clicked(0));
+    }
+
+    #[test]
+    fn small_movement_is_still_a_click() {
+        let _guard = test_guard();
+        let start = Instant::now();
+        move_to(10.0, 10.0);
+
+        button(start, 0, MouseButton::Left, ElementState::Pressed);
+        move_to(13.0, 10.0);
+        button(start, 50, MouseButton::Left, ElementState::Released);
+        assert!(MouseInput::button_clicked(0));
+    }
+
+    #[test]
+    fn two_quick_clicks_double_click_on_the_second_press() {
+        let _guard = test_guard();
+        let start = Instant::now();
+
+        click(start, 0, 50);
+        MouseInput::end_frame();
+        button(start, 200, MouseButton::Left, ElementState::Pressed);
+        assert!(MouseInput::button_double_clicked(0));
+
+        MouseInput::end_frame();
+        button(start, 250, MouseButton::Left, ElementState::Released);
+        assert!(MouseInput::button_clicked(0));
+        assert!(!MouseInput::button_double_clicked(0));
+
+        // A third quick press starts a new pair
+        button(start, 400, MouseButton::Left, ElementState::Pressed);
+        assert!(!MouseInput::button_double_clicked(0));
+    }
+
+    #[test]
+    fn slow_clicks_do_not_double_click() {
+        let _guard = test_guard();
+        let start = Instant::now();
+
+        click(start, 0, 50);
+        click(start, 500, 550);
+        assert!(MouseInput::button_clicked(0));
+        assert!(!MouseInput::button_double_clicked(0));
+    }
+
+    #[test]
+    fn the_second_press_must_come_before_the_threshold() {
+        let _guard = test_guard();
+        let start = Instant::now();
+
+        click(start, 0, 50);
+        click(start, 300, 350);
+        assert!(!MouseInput::button_double_clicked(0));
+
+        MouseInput::end_frame();
+        click(start, 599, 620);
+        assert!(MouseInput::button_double_clicked(0));
+    }
+
+    #[test]
+    fn click_config_changes_the_thresholds() {
+        let _guard = test_guard();
+        let start = Instant::now();
+        MouseInput::set_click_config(ClickConfig {
+            max_duration: Duration::from_millis(50),
+            max_distance: 4.0,
+            double_click_threshold: Duration::from_millis(100),
+        });
+
+        click(start, 0, 100);
+        assert!(!MouseInput::button_clicked(0));
+
+        click(start, 150, 170);
+        assert!(MouseInput::button_clicked(0));
+        assert!(!MouseInput::button_double_clicked(0));
+
+        MouseInput::set_double_click_threshold(Duration::from_millis(1000));
+        click(start, 900, 920);
+        assert!(MouseInput::button_double_clicked(0));
+    }
+
+    #[test]
+    fn small_moves_do_not_start_a_drag() {
+        let _guard = test_guard();
+        let start = Instant::now();
+        move_to(100.0, 100.0);
+        button(start, 0, MouseButton::Left, ElementState::Pressed);
+
+        move_to(102.0, 100.0);
+        assert!(!MouseInput::is_dragging(0));
+        assert_eq!(MouseInput::drag_start_position(0), None);
+        assert_eq!(MouseInput::drag_delta(0), (2.0, 0.0));
+
+        move_to(110.0, 100.0);
+        assert!(MouseInput::is_dragging(0));
+        assert_eq!(
+            MouseInput::drag_start_position(0),
+            Some(Vec2::new(100.0, 100.0))
+        );
+        assert_eq!(MouseInput::drag_delta(0), (10.0, 0.0));
+
+        // Coming back within the threshold doesn't end the drag
+        move_to(101.0, 100.0);
+        assert!(MouseInput::is_dragging(0));
+
+        button(start, 500, MouseButton::Left, ElementState::Released);
+        assert!(!MouseInput::is_dragging(0));
+        assert_eq!(MouseInput::drag_delta(0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn the_drag_threshold_is_configurable() {
+        let _guard = test_guard();
+        let start = Instant::now();
+        MouseInput::set_drag_threshold(20.0);
+        move_to(0.0, 0.0);
+        button(start, 0, MouseButton::Right, ElementState::Pressed);
+
+        move_to(10.0, 0.0);
+        assert!(!MouseInput::is_dragging(1));
+        move_to(0.0, 25.0);
+        assert!(MouseInput::is_dragging(1));
+        assert!(!MouseInput::is_dragging(0));
+    }
+
+    fn scroll(y: f32) {
+        MouseInput::handle_wheel(MouseScrollDelta::LineDelta(0.0, y));
+    }
+
+    #[test]
+    fn scrolling_inside_the_dead_zone_is_dropped() {
+        let _guard = test_guard();
+        MouseInput::set_scroll_deadzone(0.5);
+
+        scroll(0.25);
+        assert_eq!(MouseInput::get_scroll_y(), 0.0);
+        assert_eq!(MouseInput::get_unfiltered_scroll_y(), 0.25);
+        MouseInput::end_frame();
+
+        scroll(-0.4);
+        assert_eq!(MouseInput::get_scroll_y(), 0.0);
+        MouseInput::end_frame();
+
+        scroll(-1.0);
+        assert_eq!(MouseInput::get_scroll_y(), -1.0);
+    }
+
+    #[test]
+    fn smoothed_scrolling_converges_to_the_raw_value() {
+        let _guard = test_guard();
+        MouseInput::set_scroll_smoothing(0.5);
+
+        scroll(1.0);
+        assert_eq!(MouseInput::get_scroll_y(), 0.5);
+        MouseInput::end_frame();
+        scroll(1.0);
+        assert_eq!(MouseInput::get_scroll_y(), 0.75);
+
+        for _ in 0..30 {
+            MouseInput::end_frame();
+            scroll(1.0);
+        }
+        assert!((MouseInput::get_scroll_y() - 1.0).abs() < 1e-6);
+
+        // Once the wheel stops the scroll dies out, cut off by the dead zone
+        MouseInput::set_scroll_deadzone(0.1);
+        MouseInput::end_frame();
+        assert!((MouseInput::get_scroll_y() - 0.5).abs() < 1e-6);
+        for _ in 0..3 {
+            MouseInput::end_frame();
+        }
+        assert_eq!(MouseInput::get_scroll_y(), 0.0);
+    }
 }
@@ -0,0 +1,384 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, ModifiersState, PhysicalKey};
+
+use crate::input::key_listener::KeyInput;
+use crate::input::mouse_listener::MouseInput;
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    /// A line of a recording isn't a valid event. Lines count from 1.
+    Json {
+        line: usize,
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(e) => write!(f, "failed to access input recording: {e}"),
+            RecordingError::Json { line, error } => {
+                write!(f, "invalid input recording at line {line}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(e: std::io::Error) -> Self {
+        RecordingError::Io(e)
+    }
+}
+
+/// The part of a window event that [`KeyInput`] and [`MouseInput`] read.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        key: Key,
+        physical: PhysicalKey,
+        state: ElementState,
+    },
+    Modifiers(ModifiersState),
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    MouseButton {
+        button: MouseButton,
+        state: ElementState,
+    },
+    MouseWheel(MouseScrollDelta),
+}
+
+impl InputEvent {
+    /// The input in `event`, or `None` for events the input singletons
+    /// ignore.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => Some(InputEvent::Key {
+                key: event.logical_key.clone(),
+                physical: event.physical_key,
+                state: event.state,
+            }),
+            WindowEvent::ModifiersChanged(modifiers) => {
+                Some(InputEvent::Modifiers(modifiers.state()))
+            }
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved {
+                x: position.x,
+                y: position.y,
+            }),
+            WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::MouseButton {
+                button: *button,
+                state: *state,
+            }),
+            WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::MouseWheel(*delta)),
+            _ => None,
+        }
+    }
+
+    /// Feeds the event to [`KeyInput`] or [`MouseInput`] as if it had just
+    /// arrived, `now` timing clicks.
+    pub fn apply(&self, now: Instant) {
+        match self {
+            InputEvent::Key {
+                key,
+                physical,
+                state,
+            } => KeyInput::handle_key(key, *physical, *state),
+            InputEvent::Modifiers(modifiers) => KeyInput::handle_modifiers(*modifiers),
+            InputEvent::CursorMoved { x, y } => MouseInput::handle_cursor_moved(*x, *y),
+            InputEvent::MouseButton { button, state } => {
+                MouseInput::handle_button(*button, *state, now);
+            }
+            InputEvent::MouseWheel(delta) => MouseInput::handle_wheel(*delta),
+        }
+    }
+}
+
+/// An event and when it arrived: the frame, counted from the start of the
+/// recording, and the seconds since then, which click detection depends on.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub frame: u64,
+    pub time: f64,
+    pub event: InputEvent,
+}
+
+/// Reads a recording written by [`InputRecorder`]: one JSON event per line.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>, RecordingError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line)
+            .map_err(|error| RecordingError::Json { line: i + 1, error })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Writes `events` in the format [`load`] reads.
+pub fn save(path: impl AsRef<Path>, events: &[RecordedEvent]) -> Result<(), RecordingError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for event in events {
+        write_event(&mut writer, event)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_event(writer: &mut impl Write, event: &RecordedEvent) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *writer, event)?;
+    writeln!(writer)
+}
+
+/// Writes the input the window receives to a file as it arrives, so a
+/// crash still leaves everything up to the last frame on disk.
+pub struct InputRecorder {
+    writer: Option<BufWriter<File>>,
+    frame: u64,
+    started: Instant,
+}
+
+impl InputRecorder {
+    pub fn get_instance() -> &'static Mutex<InputRecorder> {
+        static INSTANCE: Lazy<Mutex<InputRecorder>> = Lazy::new(|| {
+            Mutex::new(InputRecorder {
+                writer: None,
+                frame: 0,
+                started: Instant::now(),
+            })
+        });
+
+        &INSTANCE
+    }
+
+    /// Starts recording to `path`, replacing the file and any recording in
+    /// progress. Start before [`Window::run`] to capture the whole session.
+    ///
+    /// [`Window::run`]: crate::engine::window::Window::run
+    pub fn start(path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let writer = BufWriter::new(File::create(path)?);
+        let mut recorder = Self::get_instance().lock().unwrap();
+        if let Some(mut old) = recorder.writer.replace(writer) {
+            old.flush()?;
+        }
+        recorder.frame = 0;
+        recorder.started = Instant::now();
+        Ok(())
+    }
+
+    /// Stops recording and flushes the file.
+    pub fn stop() -> Result<(), RecordingError> {
+        if let Some(mut writer) = Self::get_instance().lock().unwrap().writer.take() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Called by the window with every event it receives.
+    pub(crate) fn record(event: &WindowEvent) {
+        let mut recorder = Self::get_instance().lock().unwrap();
+        let (frame, time) = (recorder.frame, recorder.started.elapsed().as_secs_f64());
+        let (Some(writer), Some(event)) =
+            (&mut recorder.writer, InputEvent::from_window_event(event))
+        else {
+            return;
+        };
+        let event = RecordedEvent { frame, time, event };
+        if let Err(e) = write_event(writer, &event) {
+            log::error!("Stopped recording input: {e}");
+            recorder.writer = None;
+        }
+    }
+
+    /// Called by the window when it clears input at the end of a frame.
+    pub(crate) fn end_frame() {
+        let mut guard = Self::get_instance().lock().unwrap();
+        let recorder = &mut *guard;
+        if let Some(writer) = &mut recorder.writer {
+            recorder.frame += 1;
+            if let Err(e) = writer.flush() {
+                log::error!("Stopped recording input: {e}");
+                recorder.writer = None;
+            }
+        }
+    }
+
+    //--Getters--//
+
+    pub fn is_recording() -> bool {
+        Self::get_instance().lock().unwrap().writer.is_some()
+    }
+}
+
+/// Replays a recording by feeding its events to [`KeyInput`] and
+/// [`MouseInput`] at the frames they arrived on. The window's own input is
+/// ignored meanwhile. Together with the fixed timestep this repeats a run
+/// exactly, as far as the game depends on input and physics steps.
+pub struct InputPlayer {
+    events: Vec<RecordedEvent>,
+    /// Index of the next event to feed.
+    next: usize,
+    frame: u64,
+    started: Instant,
+}
+
+impl InputPlayer {
+    pub fn get_instance() -> &'static Mutex<InputPlayer> {
+        static INSTANCE: Lazy<Mutex<InputPlayer>> = Lazy::new(|| {
+            Mutex::new(InputPlayer {
+                events: Vec::new(),
+                next: 0,
+                frame: 0,
+                started: Instant::now(),
+            })
+        });
+
+        &INSTANCE
+    }
+
+    /// Loads and plays the recording at `path`.
+    pub fn play_file(path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        Self::play(load(path)?);
+        Ok(())
+    }
+
+    /// Plays `events`, replacing any playback in progress. Frame 0's events
+    /// are fed right away; start at the same point the recording was
+    /// started, e.g. before [`Window::run`].
+    ///
+    /// [`Window::run`]: crate::engine::window::Window::run
+    pub fn play(events: Vec<RecordedEvent>) {
+        let mut player = Self::get_instance().lock().unwrap();
+        player.events = events;
+        player.next = 0;
+        player.frame = 0;
+        player.started = Instant::now();
+        player.feed();
+    }
+
+    pub fn stop() {
+        let mut player = Self::get_instance().lock().unwrap();
+        player.next = player.events.len();
+    }
+
+    /// Called by the window when it clears input at the end of a frame,
+    /// feeding the next frame's events.
+    pub(crate) fn end_frame() {
+        let mut player = Self::get_instance().lock().unwrap();
+        if player.next < player.events.len() {
+            player.frame += 1;
+            player.feed();
+        }
+    }
+
+    fn feed(&mut self) {
+        while let Some(recorded) = self.events.get(self.next) {
+            if recorded.frame > self.frame {
+                break;
+            }
+            let at = self.started + Duration::from_secs_f64(recorded.time.max(0.0));
+            recorded.event.apply(at);
+            self.next += 1;
+        }
+    }
+
+    //--Getters--//
+
+    /// True until every event has been fed or playback is stopped.
+    pub fn is_playing() -> bool {
+        let player = Self::get_instance().lock().unwrap();
+        player.next < player.events.len()
+    }
+
+    /// Frames since playback started.
+    pub fn frame() -> u64 {
+        Self::get_instance().lock().unwrap().frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::test_guard;
+    use winit::dpi::PhysicalPosition;
+    use winit::event::DeviceId;
+
+    #[test]
+    fn a_recording_saved_and_loaded_replays_the_same_input() {
+        let _guard = test_guard();
+        let path = std::env::temp_dir().join(format!("kreeda-rec-{}.jsonl", std::process::id()));
+        let copy = path.with_extension("copy.jsonl");
+
+        InputRecorder::start(&path).unwrap();
+        assert!(InputRecorder::is_recording());
+        InputRecorder::record(&WindowEvent::CursorMoved {
+            device_id: DeviceId::dummy(),
+            position: PhysicalPosition::new(10.0, 20.0),
+        });
+        InputRecorder::record(&WindowEvent::MouseInput {
+            device_id: DeviceId::dummy(),
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+        });
+        // Not input, so not recorded
+        InputRecorder::record(&WindowEvent::Focused(true));
+        InputRecorder::end_frame();
+        InputRecorder::record(&WindowEvent::MouseInput {
+            device_id: DeviceId::dummy(),
+            state: ElementState::Released,
+            button: MouseButton::Left,
+        });
+        InputRecorder::stop().unwrap();
+        assert!(!InputRecorder::is_recording());
+
+        let events = load(&path).unwrap();
+        let frames: Vec<u64> = events.iter().map(|e| e.frame).collect();
+        assert_eq!(frames, [0, 0, 1]);
+        save(&copy, &events).unwrap();
+        assert_eq!(load(&copy).unwrap(), events);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&copy).unwrap();
+
+        MouseInput::reset();
+        InputPlayer::play(events);
+        assert!(InputPlayer::is_playing());
+        assert_eq!((MouseInput::get_x(), MouseInput::get_y()), (10.0, 20.0));
+        assert!(MouseInput::mouse_button_down(0));
+
+        MouseInput::end_frame();
+        InputPlayer::end_frame();
+        assert_eq!(InputPlayer::frame(), 1);
+        assert!(!MouseInput::mouse_button_down(0));
+        assert!(MouseInput::button_just_released(0));
+        assert!(!InputPlayer::is_playing());
+    }
+
+    #[test]
+    fn invalid_lines_report_their_line_number() {
+        let path = std::env::temp_dir().join(format!("kreeda-badrec-{}.jsonl", std::process::id()));
+        std::fs::write(
+            &path,
+            "\n{\"frame\":0,\"time\":0.0,\"event\":{\"MouseWheel\":1}}\n",
+        )
+        .unwrap();
+        let result = load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(RecordingError::Json { line: 2, .. })));
+    }
+}
@@ -0,0 +1,8 @@
+pub mod engine;
+mod error;
+pub mod input;
+mod logging;
+
+pub use engine::math;
+pub use error::KreedaError;
+pub use logging::{IntoLevelFilter, init_logging};
@@ -0,0 +1,163 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+/// Writes records to stderr as `[LEVEL target] message`.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{:<5} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Level set with [`set_level`], which beats the environment.
+fn level_override() -> &'static Mutex<Option<LevelFilter>> {
+    static INSTANCE: Lazy<Mutex<Option<LevelFilter>>> = Lazy::new(|| Mutex::new(None));
+
+    &INSTANCE
+}
+
+/// The override if there is one, else the level named by the first of
+/// `vars` that holds a plain level such as `debug`, else `default`.
+fn configured_level(vars: &[&str], default: LevelFilter) -> LevelFilter {
+    if let Some(level) = *level_override().lock().unwrap() {
+        return level;
+    }
+    vars.iter()
+        .filter_map(|var| std::env::var(var).ok()?.parse().ok())
+        .next()
+        .unwrap_or(default)
+}
+
+/// Prints the crate's diagnostics (and anything else logged through the
+/// `log` facade) to stderr. The level comes from `RUST_LOG` when it holds
+/// a plain level such as `debug`, and defaults to `info`.
+///
+/// Apps with a logger of their own, e.g. `env_logger` or a `tracing`
+/// subscriber, should set that up instead. If a logger is already
+/// installed this does nothing.
+pub fn init_logging() {
+    static LOGGER: StderrLogger = StderrLogger;
+
+    let level = configured_level(&["RUST_LOG"], LevelFilter::Info);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// A level for [`Window::set_log_level`](crate::engine::window::Window::set_log_level):
+/// a `log` level or level filter, or with the `log-tracing` feature a
+/// `tracing` level.
+pub trait IntoLevelFilter {
+    fn into_level_filter(self) -> LevelFilter;
+}
+
+impl IntoLevelFilter for LevelFilter {
+    fn into_level_filter(self) -> LevelFilter {
+        self
+    }
+}
+
+impl IntoLevelFilter for log::Level {
+    fn into_level_filter(self) -> LevelFilter {
+        self.to_level_filter()
+    }
+}
+
+#[cfg(feature = "log-tracing")]
+impl IntoLevelFilter for tracing::Level {
+    fn into_level_filter(self) -> LevelFilter {
+        to_log_level(self).to_level_filter()
+    }
+}
+
+/// The `log` level a `tracing` level is filtered as.
+#[cfg(feature = "log-tracing")]
+fn to_log_level(level: tracing::Level) -> log::Level {
+    match level {
+        tracing::Level::ERROR => log::Level::Error,
+        tracing::Level::WARN => log::Level::Warn,
+        tracing::Level::INFO => log::Level::Info,
+        tracing::Level::DEBUG => log::Level::Debug,
+        tracing::Level::TRACE => log::Level::Trace,
+    }
+}
+
+/// Changes the most verbose level logged, overriding the environment.
+pub(crate) fn set_level(level: LevelFilter) {
+    *level_override().lock().unwrap() = Some(level);
+    log::set_max_level(level);
+}
+
+/// Installs a `tracing` subscriber printing to stderr, which also receives
+/// everything logged through the `log` facade. The level comes from
+/// `KREEDA_LOG` and defaults to `warn`. Does nothing if the app already
+/// installed a logger or subscriber.
+#[cfg(feature = "log-tracing")]
+pub(crate) fn init_tracing() {
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let level = configured_level(&["KREEDA_LOG"], LevelFilter::Warn);
+    // Filtered against the log facade's level, so `set_level` covers
+    // `tracing` events from dependencies too
+    let filter = tracing_subscriber::filter::filter_fn(|metadata| {
+        to_log_level(*metadata.level()) <= log::max_level()
+    });
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_writer(std::io::stderr)
+        .finish()
+        .with(filter);
+    if subscriber.try_init().is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_comes_from_the_override_then_the_environment() {
+        const VAR: &str = "KREEDA_TEST_LOG_LEVEL";
+        const UNSET: &str = "KREEDA_TEST_LOG_LEVEL_UNSET";
+        // SAFETY: no other test reads these variables
+        unsafe {
+            std::env::set_var(VAR, "error");
+            std::env::remove_var(UNSET);
+        }
+
+        assert_eq!(configured_level(&[UNSET], LevelFilter::Warn), LevelFilter::Warn);
+        assert_eq!(
+            configured_level(&[UNSET, VAR], LevelFilter::Warn),
+            LevelFilter::Error
+        );
+
+        // Filter directives like `kreeda=debug` aren't plain levels
+        unsafe { std::env::set_var(VAR, "kreeda=debug") };
+        assert_eq!(configured_level(&[VAR], LevelFilter::Warn), LevelFilter::Warn);
+
+        set_level(LevelFilter::Trace);
+        assert_eq!(configured_level(&[VAR], LevelFilter::Warn), LevelFilter::Trace);
+        assert_eq!(log::max_level(), LevelFilter::Trace);
+        *level_override().lock().unwrap() = None;
+    }
+
+    #[cfg(feature = "log-tracing")]
+    #[test]
+    fn tracing_levels_map_to_the_matching_filter() {
+        assert_eq!(tracing::Level::ERROR.into_level_filter(), LevelFilter::Error);
+        assert_eq!(tracing::Level::INFO.into_level_filter(), LevelFilter::Info);
+        assert_eq!(tracing::Level::TRACE.into_level_filter(), LevelFilter::Trace);
+    }
+}
@@ -1,8 +1,6 @@
-mod engine;
-mod input;
-use crate::engine::window::Window;
+use kreeda::KreedaError;
+use kreeda::engine::window::Window;
 
-fn main() {
-    let mut window = Window::get();
-    window.run();
+fn main() -> Result<(), KreedaError> {
+    Window::run()
 }